@@ -1,22 +1,38 @@
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-use clap::{ArgGroup, Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 
-use ddnnf_lib::ddnnf::anomalies::t_wise_sampling::save_sample_to_file;
+#[cfg(feature = "sampling")]
+use ddnnf_lib::ddnnf::anomalies::t_wise_sampling::save_sample_to_file_ordered;
+use ddnnf_lib::ddnnf::anomalies::variable_order::VariableOrder;
+use ddnnf_lib::ddnnf::counting::bounded::CountMode;
+use ddnnf_lib::ddnnf::counting::certificate::Certificate;
 use ddnnf_lib::parser::util::format_vec;
 use itertools::Itertools;
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
-use std::time::Instant;
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use ddnnf_lib::ddnnf::Ddnnf;
 use ddnnf_lib::parser::{
     self as dparser,
-    persisting::{write_as_mermaid_md, write_ddnnf_to_file},
+    from_cnf::read_cnf,
+    persisting::{
+        write_as_dot, write_as_json_graph, write_as_mermaid_md, write_ddnnf_as,
+        write_ddnnf_to_file, CountScale, Format,
+    },
 };
+use ddnnf_lib::profiling::{reset_profiler, set_profiler, FoldedStacks};
+use ddnnf_lib::settings::Settings;
+
+// The number of sampled models that check the determinism of Or nodes via --integrity
+const INTEGRITY_PROBES: usize = 1_000;
 
 #[derive(Parser)]
 #[command(author, version, about, arg_required_else_help(true),
@@ -65,6 +81,61 @@ struct Cli {
     /// Provides information about the type of nodes, their connection, and the different paths.
     #[arg(long, verbatim_doc_comment)]
     heuristics: bool,
+
+    /// Reduces the size of the d-DNNF before performing any operation by merging isomorphic subgraphs
+    /// and factoring out common children of And nodes below Or nodes. The count stays the same.
+    #[arg(long, verbatim_doc_comment)]
+    minimize: bool,
+
+    /// Lists the dead features of the model before performing any operation.
+    /// With 'remove', the dead features are additionally projected out and the remaining features
+    /// get renumbered to close the gaps. The renumbering is listed as well.
+    /// In the stream mode, only the renumbering gets listed, on stderr. The queries have to use the new numbers.
+    #[arg(long, value_enum, verbatim_doc_comment)]
+    dead_features: Option<DeadFeatures>,
+
+    /// Checks the parsed d-DNNF for structures that hint at a malformed file: variables beyond the
+    /// declared number of variables, And nodes whose children share variables, and Or nodes whose
+    /// children share models (detected by sampling). The warnings and repair suggestions are written to stderr.
+    /// With 'repair', the number of variables additionally gets raised to the largest occurring variable.
+    #[arg(long, value_enum, verbatim_doc_comment)]
+    integrity: Option<Integrity>,
+
+    /// Path to a TOML file that contains defaults (threads, compiler, cache_dir, numeric, limits.samples, and limits.max_digits).
+    /// If not set, 'ddnnife.toml' in the working directory is used if it exists.
+    /// Command line arguments always take precedence over the values of the file.
+    #[arg(long, verbatim_doc_comment)]
+    config: Option<String>,
+
+    /// Measures the time spent in the phases (parse, build-graph, literal-diffs, count, query, sample)
+    /// and writes it as folded stacks into the file, e.g. as input for inferno-flamegraph.
+    #[arg(long, verbatim_doc_comment)]
+    profile: Option<String>,
+
+    /// Path to a CNF whose comments of the form 'c <variable> <name>' name the features.
+    /// Explanations refer to the features by these names.
+    /// If not set and the input is a CNF, its comments are used.
+    #[arg(long, verbatim_doc_comment)]
+    feature_names: Option<String>,
+
+    /// Path to a file that lists variable numbers separated by whitespaces, e.g. in the hierarchy
+    /// order of the feature model. The literals of sampled configurations and of the configurations
+    /// that the stream mode answers are written in that order.
+    /// Variables that are not listed follow in ascending order. The default is the ascending order.
+    #[arg(long, verbatim_doc_comment)]
+    variable_order: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Integrity {
+    Warn,
+    Repair,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DeadFeatures {
+    Report,
+    Remove,
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -78,6 +149,40 @@ enum Operation {
         /// The default is no assumption. The Output gets displayed on the terminal.
         #[arg(num_args = 0.., allow_negative_numbers = true, verbatim_doc_comment)]
         features: Option<Vec<i32>>,
+        /// Saves a certificate for the count in the given file. The certificate contains the count
+        /// of each node and can be checked with 'verify-certificate'.
+        #[arg(long, verbatim_doc_comment)]
+        certificate: Option<String>,
+        /// Counts of the query with more digits are reported in log10 form (10^x).
+        /// Smaller counts, including zero, stay exact.
+        #[arg(long, verbatim_doc_comment)]
+        max_digits: Option<usize>,
+        /// Computes the count with floats in log10 form (10^x) regardless of its size,
+        /// which is faster than exact counting for models with astronomically large counts but only approximate.
+        #[arg(long, conflicts_with = "max_digits", verbatim_doc_comment)]
+        log10: bool,
+        /// Additionally prints the count modulo the given prime,
+        /// which is a cheap fingerprint of the models, e.g. for comparisons in CI pipelines.
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..), verbatim_doc_comment)]
+        modulo: Option<u64>,
+    },
+    /// Checks a certificate created by 'count --certificate' against the d-DNNF.
+    /// Exits with an error if the certificate is not valid.
+    #[clap(verbatim_doc_comment)]
+    VerifyCertificate {
+        /// Path to the certificate
+        #[arg(verbatim_doc_comment)]
+        certificate_file: String,
+    },
+    /// Checks whether the d-DNNF represents the CNF it was supposedly compiled from.
+    /// Each model of the d-DNNF has to satisfy each clause. For CNFs with at most 25 variables,
+    /// the counts are compared as well. Larger CNFs are only checked for the first property,
+    /// hence missing models go unnoticed. Exits with an error if there are discrepancies.
+    #[clap(verbatim_doc_comment)]
+    Verify {
+        /// Path to the CNF in the DIMACS format
+        #[arg(verbatim_doc_comment)]
+        cnf_file: String,
     },
     /// Computes the cardinality of a single feature for all features. Is single threaded.
     #[clap(short_flag = 'c')]
@@ -100,9 +205,9 @@ enum Operation {
         #[arg(verbatim_doc_comment)]
         custom_output_file: Option<String>,
         /// Specify how many threads should be used.
-        /// Possible values are between 1 and 32.
-        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=32), default_value_t = 4, verbatim_doc_comment)]
-        jobs: u16,
+        /// Possible values are between 1 and 32. The default is 4.
+        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=32), verbatim_doc_comment)]
+        jobs: Option<u16>,
     },
     /// Computes multiple SAT queries.
     Sat {
@@ -115,17 +220,17 @@ enum Operation {
         #[arg(verbatim_doc_comment)]
         custom_output_file: Option<String>,
         /// Specify how many threads should be used.
-        /// Possible values are between 1 and 32.
-        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=32), default_value_t = 4, verbatim_doc_comment)]
-        jobs: u16,
+        /// Possible values are between 1 and 32. The default is 4.
+        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=32), verbatim_doc_comment)]
+        jobs: Option<u16>,
     },
 
     /// Starts ddnnife in stream mode.
     Stream {
         /// Specify how many threads should be used.
-        /// Possible values are between 1 and 32.
-        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=32), default_value_t = 1, verbatim_doc_comment)]
-        jobs: u16,
+        /// Possible values are between 1 and 32. The default is 1.
+        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=32), verbatim_doc_comment)]
+        jobs: Option<u16>,
     },
     /// Evaluates multiple queries of the stream format from a file.
     StreamQueries {
@@ -138,7 +243,30 @@ enum Operation {
         #[arg(verbatim_doc_comment)]
         custom_output_file: Option<String>,
     },
+    /// Starts a daemon that serves the stream mode to multiple local clients via a UNIX domain socket.
+    /// Each client has its own session. Besides the messages of the stream mode, sessions understand
+    /// 'models', 'use <name>', and 'load <name> <path> [total-features]' to switch between multiple
+    /// loaded models. Editing a model requires its exclusive lock via 'lock' and 'unlock'.
+    /// The model given to ddnnife is loaded under the chosen name.
+    #[cfg(unix)]
+    #[clap(verbatim_doc_comment)]
+    Daemon {
+        /// The path of the socket, which must not exist yet.
+        #[arg(verbatim_doc_comment)]
+        socket: String,
+        /// The name of the initially loaded model.
+        #[arg(short, long, default_value = "default", verbatim_doc_comment)]
+        name: String,
+        /// Prevents the clients from locking, editing, and replacing the initially loaded model.
+        #[arg(long, verbatim_doc_comment)]
+        read_only: bool,
+        /// Verifies the loaded models every given number of seconds and reports
+        /// corrupted states on stderr. Models that got edited start over with a new snapshot.
+        #[arg(long, value_name = "SECONDS", verbatim_doc_comment)]
+        self_check: Option<u64>,
+    },
     /// Computes t-wise samples
+    #[cfg(feature = "sampling")]
     TWise {
         /// The default ouput file is '{FILE_NAME}-t-wise.csv'.
         #[arg(verbatim_doc_comment)]
@@ -151,6 +279,20 @@ enum Operation {
         /// but also the larger the number of test cases required.
         #[clap(short, verbatim_doc_comment, default_value_t = 2)]
         t: usize,
+        /// The numbers of the features that should be included or excluded in each configuration
+        /// (positive number to include, negative to exclude). The sample covers the interactions
+        /// of the remaining model. The default is no assumption.
+        #[clap(short, long, allow_negative_numbers = true, num_args = 0.., verbatim_doc_comment)]
+        assumptions: Vec<i32>,
+        /// Samples the independent components below the root separately (in parallel)
+        /// and merges their samples afterwards.
+        #[clap(long, verbatim_doc_comment)]
+        hierarchical: bool,
+        /// Path to the previous version of the model. The sample only covers the interactions
+        /// that are valid in the model but not in the previous version. The previous version
+        /// gets loaded with the same 'total_features'. Can not be combined with 'hierarchical'.
+        #[clap(long, conflicts_with = "hierarchical", verbatim_doc_comment)]
+        since: Option<String>,
     },
     /// Computes core, dead, false-optional features, and atomic sets.
     Anomalies {
@@ -182,6 +324,7 @@ enum Operation {
         cross: bool,
     },
     /// Generates uniform random sample
+    #[cfg(feature = "sampling")]
     Urs {
         /// The default ouput file is '{FILE_NAME}-urs.csv'.
         #[arg(verbatim_doc_comment)]
@@ -196,11 +339,19 @@ enum Operation {
         /// Reusing the same seed yields the same urs.
         #[clap(short, long, default_value_t = 42)]
         seed: u64,
-        /// The amount of samples ddnnife should generate.
-        #[clap(short, long, default_value_t = 1000)]
-        number: usize,
+        /// The amount of samples ddnnife should generate. The default is 1000.
+        #[clap(short, long)]
+        number: Option<usize>,
+    },
+    /// Clusters the variables by the decisions they depend on, i.e. the variables of a cluster
+    /// are always below the same Or nodes. Each line of the output contains one cluster.
+    #[clap(verbatim_doc_comment)]
+    Clusters {
+        /// The default ouput file is '{FILE_NAME}-clusters.csv'.
+        #[arg(verbatim_doc_comment)]
+        custom_output_file: Option<String>,
     },
-    /// Computes the core and dead features.
+    /// Computes the core and dead features (i.e. the backbone) by the counts of their literals.
     #[clap(verbatim_doc_comment)]
     Core {
         /// An leading '-' indicates that the feature is dead.
@@ -209,6 +360,72 @@ enum Operation {
         #[arg(verbatim_doc_comment)]
         custom_output_file: Option<String>,
     },
+    /// Explains a partial configuration in sentences: why it is impossible or, if it is possible,
+    /// which decisions it forces. The explanation is printed on the terminal.
+    #[clap(verbatim_doc_comment)]
+    Explain {
+        /// The numbers of the features that should be included or excluded
+        /// (positive number to include, negative to exclude). The default is no assumption.
+        #[arg(num_args = 0.., allow_negative_numbers = true, verbatim_doc_comment)]
+        features: Vec<i32>,
+        /// Prints the explanation as JSON object instead of a sentence.
+        #[arg(long, verbatim_doc_comment)]
+        json: bool,
+    },
+    /// Converts the smooth d-DNNF into another format while keeping the variable numbers.
+    /// A CNF is created via the Tseitin transformation and contains additional variables for the inner nodes.
+    #[clap(verbatim_doc_comment)]
+    Convert {
+        /// The file the converted model is written to.
+        #[arg(verbatim_doc_comment)]
+        output_file: String,
+        /// The target format: c2d, d4, cnf, or binary.
+        /// If not set, the format is derived from the extension of the output file
+        /// (.nnf for c2d, .cnf or .dimacs for cnf, and .bin for binary).
+        #[arg(short, long, value_parser = Format::from_str, verbatim_doc_comment)]
+        format: Option<Format>,
+    },
+    /// Writes an anonymized copy of the d-DNNF that can be shared without leaking details of the model.
+    /// The variables get renamed, the nodes get shuffled, and annotations are dropped. The counts stay the same.
+    #[clap(verbatim_doc_comment)]
+    Anonymize {
+        /// The file the anonymized model is written to.
+        #[arg(verbatim_doc_comment)]
+        output_file: String,
+        /// The target format: c2d, d4, cnf, or binary.
+        /// If not set, the format is derived from the extension of the output file.
+        #[arg(short, long, value_parser = Format::from_str, verbatim_doc_comment)]
+        format: Option<Format>,
+        /// Reusing the same seed yields the same copy.
+        #[clap(short, long, default_value_t = 42)]
+        seed: u64,
+        /// Writes the renaming into this file. Each line contains an old variable and its new number.
+        /// Keep this file private, because it reverses the renaming.
+        #[arg(long, verbatim_doc_comment)]
+        renaming: Option<String>,
+    },
+    /// Exports the d-DNNF as graph in the DOT language or as JSON together with the count of each node.
+    /// The nodes are colored by their share of the configurations.
+    #[clap(verbatim_doc_comment)]
+    Graph {
+        /// Default output file is '{FILE_NAME}-graph.dot' or '{FILE_NAME}-graph.json'.
+        /// Alternatively, you can choose a name. The ending is added automatically.
+        #[arg(verbatim_doc_comment)]
+        custom_output_file: Option<String>,
+        /// Writes JSON instead of DOT.
+        #[arg(long, verbatim_doc_comment)]
+        json: bool,
+        /// States the counts as decadic logarithms.
+        #[arg(long, verbatim_doc_comment)]
+        log_scale: bool,
+        /// The numbers of the features that should be included or excluded
+        /// (positive number to include, negative to exclude).
+        /// If there are any, only the nodes that contribute to the count
+        /// under the assumptions are exported together with their counts
+        /// under the assumptions. The default is no assumption.
+        #[clap(short, long, allow_negative_numbers = true, num_args = 0.., verbatim_doc_comment)]
+        assumptions: Vec<i32>,
+    },
     /// Transforms the smooth d-DNNF into the mermaid.md format.
     #[clap(verbatim_doc_comment)]
     Mermaid {
@@ -231,6 +448,30 @@ enum Operation {
 fn main() {
     let cli = Cli::parse();
 
+    let settings = match Settings::load(cli.config.as_deref().map(Path::new)) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!(
+                "\x1b[1;38;5;196mERROR: Unable to load the settings: {}\x1b[0m",
+                err
+            );
+            process::exit(1);
+        }
+    };
+    let variable_order = match cli.variable_order.as_deref() {
+        Some(path) => VariableOrder::from_file(path).unwrap_or_else(|err| {
+            eprintln!("\x1b[1;38;5;196mERROR: Unable to load the variable order: {err}\x1b[0m");
+            process::exit(1);
+        }),
+        None => VariableOrder::model(),
+    };
+
+    let profiler = cli.profile.as_ref().map(|_| {
+        let stacks = Arc::new(FoldedStacks::default());
+        set_profiler(stacks.clone());
+        stacks
+    });
+
     // create the ddnnf based of the input file that is required
     let time = Instant::now();
     let mut ddnnf: Ddnnf;
@@ -248,7 +489,23 @@ fn main() {
         ddnnf = dparser::distribute_building(input, cli.total_features, None);
     } else {
         let ddnnf_path = &cli.file_path.clone().unwrap();
-        ddnnf = dparser::build_ddnnf(ddnnf_path, cli.total_features)
+        ddnnf = dparser::build_ddnnf_with_settings(ddnnf_path, cli.total_features, &settings)
+    }
+
+    ddnnf.variable_order = variable_order.clone();
+
+    let feature_names = cli.feature_names.clone().or_else(|| {
+        cli.file_path.clone().filter(|path| {
+            Path::new(path)
+                .extension()
+                .is_some_and(|extension| extension == "cnf" || extension == "dimacs")
+        })
+    });
+    if let Some(path) = feature_names {
+        if let Err(err) = ddnnf.load_feature_names(&path) {
+            eprintln!("\x1b[1;38;5;196mERROR: Unable to load the feature names: {err}\x1b[0m");
+            process::exit(1);
+        }
     }
 
     // file path without last extension
@@ -272,6 +529,49 @@ fn main() {
         )
     };
 
+    if let Some(mode) = cli.integrity {
+        for warning in ddnnf.check_integrity(INTEGRITY_PROBES) {
+            // Bold, Yellow, Foreground Color (see https://gist.github.com/fnky/458719343aabd01cfb17a3a4f7296797)
+            eprintln!(
+                "\x1b[1;38;5;226mWARNING: {warning}. {}\x1b[0m",
+                warning.suggestion()
+            );
+        }
+        if mode == Integrity::Repair && ddnnf.repair_variable_count() {
+            eprintln!(
+                "Raised the number of variables to {}.",
+                ddnnf.number_of_variables
+            );
+        }
+    }
+
+    if cli.minimize {
+        let stats = ddnnf.minimize();
+        if !matches!(cli.operation, Some(Operation::Stream { .. })) {
+            println!("Minimized the d-DNNF: {}", stats);
+        }
+    }
+
+    if let Some(mode) = cli.dead_features {
+        let verbose = !matches!(cli.operation, Some(Operation::Stream { .. }));
+        let dead = ddnnf.dead_features();
+        if verbose && dead.is_empty() {
+            println!("The model has no dead features.");
+        } else if verbose {
+            println!("Dead features: {}", format_vec(dead.iter()));
+        }
+        if mode == DeadFeatures::Remove && !dead.is_empty() {
+            let (quarantined, log) = ddnnf.without_dead_features();
+            ddnnf = quarantined;
+            if verbose {
+                print!("{log}");
+            } else {
+                // stdout belongs to the answers of the stream, but the renumbering changes the meaning of the queries
+                eprint!("{log}");
+            }
+        }
+    }
+
     // print additional output, iff we are not in the stream mode
     match &cli.operation {
         Some(Operation::Stream { .. }) => (),
@@ -283,6 +583,16 @@ fn main() {
                 ddnnf.rc(),
                 elapsed_time
             );
+            if let Some(stats) = ddnnf.compile_stats().first() {
+                println!(
+                    "Compiling the CNF took {:.3}s with a peak memory of {} and resulted in a d-DNNF of {} bytes.",
+                    stats.runtime.as_secs_f32(),
+                    stats
+                        .peak_memory_kib
+                        .map_or(String::from("unknown size"), |kib| format!("{kib} KiB")),
+                    stats.output_size
+                );
+            }
         }
     }
 
@@ -294,8 +604,11 @@ fn main() {
 
         // change the number of threads used for cardinality of features and partial configurations
         match operation {
-            CountQueries { jobs, .. } | Stream { jobs } | Sat { jobs, .. } => {
-                ddnnf.max_worker = jobs;
+            CountQueries { jobs, .. } | Sat { jobs, .. } => {
+                ddnnf.max_worker = jobs.or(settings.threads).unwrap_or(4);
+            }
+            Stream { jobs } => {
+                ddnnf.max_worker = jobs.or(settings.threads).unwrap_or(1);
             }
             _ => (),
         }
@@ -314,9 +627,11 @@ fn main() {
             StreamQueries {
                 custom_output_file, ..
             } => construct_ouput_path(custom_output_file, "stream", "csv"),
+            #[cfg(feature = "sampling")]
             TWise {
                 custom_output_file,
                 t,
+                ..
             } => construct_ouput_path(custom_output_file, format!("{}-wise", t).as_str(), "csv"),
             Anomalies { custom_output_file } => {
                 construct_ouput_path(custom_output_file, "anomalies", "txt")
@@ -324,13 +639,26 @@ fn main() {
             AtomicSets {
                 custom_output_file, ..
             } => construct_ouput_path(custom_output_file, "atomic", "csv"),
+            #[cfg(feature = "sampling")]
             Urs {
                 custom_output_file, ..
             } => construct_ouput_path(custom_output_file, "urs", "csv"),
+            Clusters { custom_output_file } => {
+                construct_ouput_path(custom_output_file, "clusters", "csv")
+            }
             Core { custom_output_file } => construct_ouput_path(custom_output_file, "core", "csv"),
             Mermaid {
                 custom_output_file, ..
             } => construct_ouput_path(custom_output_file, "mermaid", "md"),
+            Graph {
+                custom_output_file,
+                json,
+                ..
+            } => construct_ouput_path(
+                custom_output_file,
+                "graph",
+                if *json { "json" } else { "dot" },
+            ),
             _ => String::new(),
         };
 
@@ -354,16 +682,18 @@ fn main() {
                     output_file_path
                 );
             }
+            #[cfg(feature = "sampling")]
             Urs {
                 assumptions,
                 seed,
                 number,
                 custom_output_file: _,
             } => {
+                let number = number.or(settings.limits.samples).unwrap_or(1000);
                 let mut wtr =
                     BufWriter::new(File::create(&output_file_path).expect("Unable to create file"));
                 for sample in ddnnf
-                    .uniform_random_sampling(assumptions, *number, *seed)
+                    .uniform_random_sampling_ordered(assumptions, number, *seed, &variable_order)
                     .unwrap()
                 {
                     wtr.write_all(format_vec(sample.iter()).as_bytes()).unwrap();
@@ -375,25 +705,71 @@ fn main() {
                     number, output_file_path
                 );
             }
+            #[cfg(feature = "sampling")]
             TWise {
                 t,
                 custom_output_file: _,
+                assumptions,
+                hierarchical,
+                since,
             } => {
-                let sample_result = ddnnf.sample_t_wise(*t);
-                save_sample_to_file(&sample_result, &output_file_path).unwrap();
+                let sample_result = if let Some(path) = since {
+                    let previous =
+                        dparser::build_ddnnf_with_settings(path, cli.total_features, &settings);
+                    ddnnf
+                        .condition(assumptions)
+                        .sample_t_wise_differential(&previous, *t)
+                } else if *hierarchical {
+                    ddnnf.condition(assumptions).sample_t_wise_hierarchical(*t)
+                } else {
+                    ddnnf.sample_t_wise_with_assumptions(*t, assumptions)
+                };
+                save_sample_to_file_ordered(&sample_result, &output_file_path, &variable_order)
+                    .unwrap();
                 println!(
                     "\nComputed {}-wise samples and saved the results in {}.",
                     t, output_file_path
                 );
             }
             // computes the cardinality for the partial configuration that can be mentioned with parameters
-            Count { features } => {
+            Count {
+                features,
+                certificate,
+                max_digits,
+                log10,
+                modulo,
+            } => {
                 let features = features.clone().unwrap_or(vec![]);
+                if let Some(path) = certificate {
+                    let certificate = ddnnf.certified_count(&features);
+                    std::fs::write(path, certificate.to_string()).unwrap();
+                    println!(
+                        "\nSaved the certificate for the count {} in {}.",
+                        certificate.count(),
+                        path
+                    );
+                }
+                ddnnf.max_digits = max_digits.or(settings.limits.max_digits);
+                let mode = if *log10 {
+                    CountMode::Log10
+                } else if max_digits.is_some() {
+                    CountMode::Bounded
+                } else {
+                    settings.numeric.unwrap_or(CountMode::Bounded)
+                };
                 println!(
-                    "\nDdnnf count for query {:?} is: {:?}",
+                    "\nDdnnf count for query {:?} is: {}",
                     &features,
-                    ddnnf.execute_query(&features)
+                    ddnnf.count_with_mode(&features, mode)
                 );
+                if let Some(prime) = modulo {
+                    println!(
+                        "Ddnnf count for query {:?} modulo {} is: {}",
+                        &features,
+                        prime,
+                        ddnnf.condition(&features).count_mod(*prime)
+                    );
+                }
                 let marked_nodes = ddnnf.get_marked_nodes_clone(&features);
                 println!("While computing the cardinality of the partial configuration {} out of the {} nodes were marked. \
                     That are {:.2}%", marked_nodes.len(), ddnnf.nodes.len(), marked_nodes.len() as f64 / ddnnf.nodes.len() as f64 * 100.0);
@@ -463,6 +839,32 @@ fn main() {
             Stream { .. } => {
                 ddnnf.init_stream();
             }
+            // serve multiple clients and models until the daemon gets killed
+            #[cfg(unix)]
+            Daemon {
+                socket,
+                name,
+                read_only,
+                self_check,
+            } => {
+                let daemon = Arc::new(ddnnf_lib::ddnnf::daemon::Daemon::default());
+                if *read_only {
+                    daemon.insert_read_only(name.as_str(), ddnnf);
+                } else {
+                    daemon.insert(name.as_str(), ddnnf);
+                }
+                if let Some(seconds) = self_check {
+                    daemon
+                        .clone()
+                        .spawn_self_check(Duration::from_secs(*seconds));
+                }
+                println!("\nServing the stream mode on {socket}.");
+                if let Err(err) = daemon.serve(socket.as_str()) {
+                    eprintln!("\x1b[1;38;5;196mERROR: Unable to listen on {socket}: {err}\x1b[0m");
+                    process::exit(1);
+                }
+                return;
+            }
             // writes the anomalies of the d-DNNF to file
             // anomalies are: core, dead, false-optional features and atomic sets
             Anomalies {
@@ -471,10 +873,34 @@ fn main() {
                 ddnnf.write_anomalies(&output_file_path).unwrap();
                 println!("\nThe anomalies of the d-DNNF (i.e. core, dead, false-optional features, and atomic sets) are written into {}.", output_file_path);
             }
+            Clusters {
+                custom_output_file: _,
+            } => {
+                let clusters = ddnnf.variable_clusters();
+                let mut wtr =
+                    BufWriter::new(File::create(&output_file_path).expect("Unable to create file"));
+                for cluster in clusters.iter() {
+                    wtr.write_all(format_vec(cluster.iter()).as_bytes())
+                        .unwrap();
+                    wtr.write_all("\n".as_bytes()).unwrap();
+                }
+                wtr.flush().unwrap();
+                println!(
+                    "\nComputed {} clusters of variables and saved the results in {}.",
+                    clusters.len(),
+                    output_file_path
+                );
+            }
             Core {
                 custom_output_file: _,
             } => {
-                let mut core = ddnnf.core.clone().into_iter().collect_vec();
+                let backbone = ddnnf.backbone();
+                let mut core = backbone
+                    .core
+                    .iter()
+                    .map(|&f| f as i32)
+                    .chain(backbone.dead.iter().map(|&f| -(f as i32)))
+                    .collect_vec();
                 core.sort_unstable_by_key(|k| k.abs());
 
                 let mut wtr =
@@ -486,6 +912,101 @@ fn main() {
                     output_file_path
                 );
             }
+            Explain { features, json } => match (ddnnf.explain_void(features, true), *json) {
+                (Some(explanation), true) => {
+                    println!("{}", ddnnf.void_explanation_json(&explanation))
+                }
+                (Some(explanation), false) => {
+                    println!("\n{}", ddnnf.describe_void(&explanation))
+                }
+                (None, true) => println!("{}", ddnnf.propagation_json(features)),
+                (None, false) => println!("\n{}", ddnnf.describe_propagation(features)),
+            },
+            Convert {
+                output_file,
+                format,
+            } => {
+                let format = match format.or_else(|| Format::from_path(output_file)) {
+                    Some(format) => format,
+                    None => {
+                        eprintln!(
+                            "\x1b[1;38;5;196mERROR: Unable to derive the format from {}. Please specify it with --format.\x1b[0m",
+                            output_file
+                        );
+                        process::exit(1);
+                    }
+                };
+                write_ddnnf_as(&ddnnf, format, output_file).unwrap();
+                println!(
+                    "\nThe smooth d-DNNF was converted into the {} format and was written in {}.",
+                    format, output_file
+                );
+            }
+            Anonymize {
+                output_file,
+                format,
+                seed,
+                renaming,
+            } => {
+                let format = match format.or_else(|| Format::from_path(output_file)) {
+                    Some(format) => format,
+                    None => {
+                        eprintln!(
+                            "\x1b[1;38;5;196mERROR: Unable to derive the format from {}. Please specify it with --format.\x1b[0m",
+                            output_file
+                        );
+                        process::exit(1);
+                    }
+                };
+                let (anonymized, mapping) = match ddnnf.anonymize(*seed) {
+                    Ok(anonymized) => anonymized,
+                    Err(err) => {
+                        eprintln!("\x1b[1;38;5;196mERROR: {err}. Try --integrity repair.\x1b[0m");
+                        process::exit(1);
+                    }
+                };
+                write_ddnnf_as(&anonymized, format, output_file).unwrap();
+                if let Some(path) = renaming {
+                    let mut wtr =
+                        BufWriter::new(File::create(path).expect("Unable to create file"));
+                    for (old, new) in mapping.iter().enumerate().skip(1) {
+                        writeln!(wtr, "{old},{new}").unwrap();
+                    }
+                    wtr.flush().unwrap();
+                }
+                println!(
+                    "\nThe anonymized d-DNNF was written in the {} format in {}.",
+                    format, output_file
+                );
+            }
+            Graph {
+                custom_output_file: _,
+                json,
+                log_scale,
+                assumptions,
+            } => {
+                let scale = if *log_scale {
+                    CountScale::Log10
+                } else {
+                    CountScale::Exact
+                };
+                let subgraph;
+                let graph = if assumptions.is_empty() {
+                    &ddnnf
+                } else {
+                    subgraph = ddnnf.contributing_subgraph(assumptions).0;
+                    &subgraph
+                };
+                if *json {
+                    write_as_json_graph(graph, scale, &output_file_path).unwrap();
+                } else {
+                    write_as_dot(graph, scale, &output_file_path).unwrap();
+                }
+                println!(
+                    "\nThe d-DNNF was exported as graph with the counts of its nodes and was written in {}.",
+                    output_file_path
+                );
+            }
             Mermaid {
                 custom_output_file: _,
                 assumptions,
@@ -493,6 +1014,44 @@ fn main() {
                 write_as_mermaid_md(&mut ddnnf, assumptions, &output_file_path).unwrap();
                 println!("The smooth d-DNNF was transformed into mermaid markdown format and was written in {}.", output_file_path);
             }
+            VerifyCertificate { certificate_file } => {
+                let certificate: Certificate = std::fs::read_to_string(certificate_file)
+                    .map_err(|err| err.to_string())
+                    .and_then(|content| content.parse())
+                    .unwrap_or_else(|err| {
+                        eprintln!("Unable to read the certificate {certificate_file}: {err}");
+                        process::exit(1);
+                    });
+                match certificate.verify(&ddnnf) {
+                    Ok(count) => println!(
+                        "\nThe certificate is valid. The count for the query {:?} is {}.",
+                        certificate.query, count
+                    ),
+                    Err(err) => {
+                        eprintln!("The certificate is invalid: {err}");
+                        process::exit(1);
+                    }
+                }
+            }
+            Verify { cnf_file } => {
+                let (variables, clauses) = read_cnf(cnf_file).unwrap_or_else(|err| {
+                    eprintln!("Unable to read the CNF {cnf_file}: {err}");
+                    process::exit(1);
+                });
+                let verification = ddnnf.verify_against_cnf(variables, &clauses);
+                println!("\n{verification}");
+                match verification.is_valid() {
+                    Some(true) => println!("The d-DNNF is consistent with the CNF {cnf_file}."),
+                    Some(false) => {
+                        eprintln!("The d-DNNF does not represent the CNF {cnf_file}.");
+                        process::exit(1);
+                    }
+                    None => println!(
+                        "Each model of the d-DNNF satisfies the CNF {cnf_file}, \
+                        but it is unknown whether the d-DNNF contains all models of the CNF."
+                    ),
+                }
+            }
         }
     }
 
@@ -510,6 +1069,12 @@ fn main() {
     if cli.heuristics {
         ddnnf.print_all_heuristics();
     }
+
+    if let (Some(path), Some(stacks)) = (&cli.profile, profiler) {
+        reset_profiler();
+        fs::write(path, stacks.folded()).unwrap();
+        println!("\nThe time spent in each phase was written into {path}.");
+    }
 }
 
 fn compute_queries<T: ToString + Ord + Send + 'static>(
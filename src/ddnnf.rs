@@ -1,20 +1,68 @@
 pub mod anomalies;
 pub mod clause_cache;
+pub mod cnf_verification;
+pub mod conditioning;
+pub mod configurator;
 pub mod counting;
+#[cfg(unix)]
+pub mod daemon;
+#[cfg(all(feature = "sampling", feature = "editing"))]
+pub mod edit_properties;
+#[cfg(feature = "sampling")]
+pub mod edit_verification;
+pub mod equivalence;
+pub mod events;
+pub mod explanation;
+#[cfg(feature = "sampling")]
+pub mod generator;
 pub mod heuristics;
+pub mod integrity;
+pub mod metrics;
+pub mod minimization;
 pub mod multiple_queries;
 pub mod node;
+pub mod numeric;
+pub mod obfuscation;
+pub mod quantified;
+pub mod relations;
+pub mod sensitivity;
+pub mod sentences;
 pub mod stream;
+pub mod synthesis;
 
 use std::collections::{BTreeSet, HashMap, HashSet};
 
 use itertools::Either;
+use once_cell::sync::OnceCell;
 use rug::Integer;
 
-use self::{clause_cache::ClauseCache, node::Node};
+use crate::parser::CompileStats;
+use crate::profiling::{self, Phase};
+use crate::settings::Settings;
+
+use self::{
+    anomalies::{config_creation::EnumerationCursors, variable_order::VariableOrder},
+    clause_cache::{normalize_clauses, ClauseCache, ClauseOrigin},
+    events::{Listeners, ModelEvent},
+    node::{Node, NodeId, NodeType::*},
+    numeric::NumericFeature,
+};
 
 #[derive(Clone, Debug)]
 /// A Ddnnf holds all the nodes as a vector, also includes meta data and further information that is used for optimations
+///
+/// # Thread safety
+///
+/// A Ddnnf is Send and Sync. Operations that take &self, such as [Ddnnf::rc], [Ddnnf::count_at],
+/// [Ddnnf::log10_count], [Ddnnf::count_mod], and [Ddnnf::condition], only read the d-DNNF
+/// and can run concurrently on a shared d-DNNF, e.g. behind an Arc. [Ddnnf::count_at] with the root
+/// answers the same counting queries as [Ddnnf::execute_query].
+///
+/// Operations that take &mut self, such as [Ddnnf::execute_query], [Ddnnf::sat], and the clause updates,
+/// use the scratch space of the nodes (temp, marker, and partial_derivative) or change the d-DNNF.
+/// The borrow checker ensures that they have exclusive access. Threads that need them work on their own
+/// clone or share the d-DNNF via a Mutex or RwLock. Listeners are Send and Sync as well and get
+/// called on the thread that changes the d-DNNF.
 pub struct Ddnnf {
     /// The actual nodes of the d-DNNF in postorder
     pub nodes: Vec<Node>,
@@ -30,12 +78,41 @@ pub struct Ddnnf {
     pub number_of_variables: u32,
     /// The number of threads
     pub max_worker: u16,
+    /// The maximum number of decimal digits of exact counts computed by [Ddnnf::bounded_count].
+    /// Larger counts are computed in log10 form. If None, all counts are exact.
+    pub max_digits: Option<usize>,
+    /// User defined metadata of nodes (e.g. names, costs, comments). Annotations survive clause updates
+    /// for each node whose subgraph stays the same. Only the binary format persists them.
+    pub annotations: HashMap<NodeId, String>,
+    /// The names of the features, e.g. from the comments of a CNF (see [Ddnnf::load_feature_names]).
+    /// Explanations refer to features without a name by their number.
+    pub feature_names: HashMap<u32, String>,
+    /// The numeric features that are encoded as one-hot groups of variables, see [Ddnnf::add_numeric_feature]
+    pub numeric_features: Vec<NumericFeature>,
+    /// The order of the literals within the configurations that the stream mode answers, e.g. for 'enum'
+    pub variable_order: VariableOrder,
+    /// The listeners that get notified about changes of the d-DNNF
+    listeners: Listeners,
+    /// The index at which the next enumeration continues for each combination of assumptions and order.
+    /// Clones share the cursors, hence the workers of the stream mode continue each other's enumerations.
+    enumeration_cursors: EnumerationCursors,
+    /// The smallest index of each structural id, see [Ddnnf::node_index]. Built on demand and reset
+    /// whenever the nodes get replaced.
+    node_indices: OnceCell<HashMap<NodeId, usize>>,
 }
 
+// The d-DNNF has to stay shareable between threads, e.g. for the stream and the parallel queries
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Ddnnf>();
+    assert_send_sync::<Node>();
+};
+
 impl Default for Ddnnf {
+    /// The empty d-DNNF, which has a single False node as root and hence no models
     fn default() -> Self {
         Ddnnf {
-            nodes: Vec::new(),
+            nodes: vec![Node::new_bool(false)],
             cached_state: None,
             literals: HashMap::new(),
             true_nodes: Vec::new(),
@@ -43,19 +120,31 @@ impl Default for Ddnnf {
             md: Vec::new(),
             number_of_variables: 0,
             max_worker: 4,
+            max_digits: None,
+            annotations: HashMap::new(),
+            feature_names: HashMap::new(),
+            numeric_features: Vec::new(),
+            variable_order: VariableOrder::model(),
+            listeners: Listeners::default(),
+            enumeration_cursors: EnumerationCursors::default(),
+            node_indices: OnceCell::new(),
         }
     }
 }
 
 impl Ddnnf {
-    /// Creates a new ddnnf including dead and core features
+    /// Creates a new ddnnf including dead and core features.
+    /// Without any node, the ddnnf consists of a single False node and has no models.
     pub fn new(
-        nodes: Vec<Node>,
+        mut nodes: Vec<Node>,
         literals: HashMap<i32, usize>,
         true_nodes: Vec<usize>,
         number_of_variables: u32,
         clauses: Option<BTreeSet<BTreeSet<i32>>>,
     ) -> Ddnnf {
+        if nodes.is_empty() {
+            nodes.push(Node::new_bool(false));
+        }
         let mut ddnnf = Ddnnf {
             nodes,
             cached_state: None,
@@ -65,7 +154,16 @@ impl Ddnnf {
             md: Vec::new(),
             number_of_variables,
             max_worker: 4,
+            max_digits: None,
+            annotations: HashMap::new(),
+            feature_names: HashMap::new(),
+            numeric_features: Vec::new(),
+            variable_order: VariableOrder::model(),
+            listeners: Listeners::default(),
+            enumeration_cursors: EnumerationCursors::default(),
+            node_indices: OnceCell::new(),
         };
+        ddnnf.collect_garbage();
         ddnnf.get_core();
         if let Some(c) = clauses {
             ddnnf.update_cached_state(Either::Right(c), Some(number_of_variables));
@@ -80,7 +178,8 @@ impl Ddnnf {
     }
 
     /// Either initialises the ClauseCache by saving the clauses and its corresponding clauses
-    /// or updates the state accordingly.
+    /// or updates the state accordingly. All clauses get normalized beforehand: duplicate literals
+    /// are removed and tautologies are skipped. Clauses with variables beyond total_features let the update fail.
     pub fn update_cached_state(
         &mut self,
         clause_info: Either<(Vec<BTreeSet<i32>>, Vec<BTreeSet<i32>>), BTreeSet<BTreeSet<i32>>>, // Left(edit operation) or Right(clauses)
@@ -89,9 +188,23 @@ impl Ddnnf {
         match self.cached_state.as_mut() {
             Some(state) => match clause_info.left() {
                 Some((add, rmv)) => {
-                    if total_features.is_none()
-                        || !state.apply_edits_and_replace(add, rmv, total_features.unwrap())
-                    {
+                    let total_features = match total_features {
+                        Some(total_features) => total_features,
+                        None => return false,
+                    };
+                    // tautologies are skipped and invalid clauses abort the update
+                    let (add, rmv) = match (
+                        normalize_clauses(&add, total_features),
+                        normalize_clauses(&rmv, total_features),
+                    ) {
+                        (Ok(add), Ok(rmv)) => (add, rmv),
+                        _ => return false,
+                    };
+                    let event = ModelEvent::ClausesEdited {
+                        added: add.clone(),
+                        removed: rmv.clone(),
+                    };
+                    if !state.apply_edits_and_replace(add, rmv, total_features) {
                         return false;
                     }
                     // The old d-DNNF got replaced by the new one.
@@ -99,13 +212,19 @@ impl Ddnnf {
                     // We swap their field data to keep the order without needing to deal with recursivly building up
                     // obselete d-DNNFs that trash the RAM.
                     self.swap();
+                    self.notify(event);
                 }
                 None => return false,
             },
             None => match clause_info.right() {
                 Some(clauses) => {
+                    let total_features = total_features.unwrap();
+                    let clauses = match normalize_clauses(&clauses, total_features) {
+                        Ok(clauses) => clauses.into_iter().collect(),
+                        Err(_) => return false,
+                    };
                     let mut state = ClauseCache::default();
-                    state.initialize(clauses, total_features.unwrap());
+                    state.initialize(clauses, total_features);
                     self.cached_state = Some(state);
                 }
                 None => return false,
@@ -114,6 +233,36 @@ impl Ddnnf {
         true
     }
 
+    /// The measurements of the compilation of the CNF and of the recompilations caused by clause
+    /// updates in chronological order. Empty if the d-DNNF was not compiled from a CNF.
+    pub fn compile_stats(&self) -> &[CompileStats] {
+        self.cached_state
+            .as_ref()
+            .map_or(&[], |state| state.compilations())
+    }
+
+    /// Lists the clauses of the current CNF that mention the feature and whether the clause stems
+    /// from the initial CNF or got added by a clause update. These are the clauses that can
+    /// explain, e.g., why a feature became dead. Empty if the d-DNNF was not compiled from a CNF.
+    pub fn constraints_on(&self, feature: u32) -> Vec<(&BTreeSet<i32>, ClauseOrigin)> {
+        self.cached_state
+            .as_ref()
+            .map_or(Vec::new(), |state| state.clauses_with(feature))
+    }
+
+    pub(crate) fn record_compilation(&mut self, stats: CompileStats) {
+        if let Some(state) = self.cached_state.as_mut() {
+            state.record_compilation(stats);
+        }
+    }
+
+    // Remembers the settings the CNF got compiled with, hence clause updates recompile the same way
+    pub(crate) fn record_settings(&mut self, settings: &Settings) {
+        if let Some(state) = self.cached_state.as_mut() {
+            state.set_settings(settings.clone());
+        }
+    }
+
     fn swap(&mut self) {
         if let Some(cached_state) = self.cached_state.as_mut() {
             if let Some(save_state) = cached_state.old_state.as_mut() {
@@ -127,6 +276,15 @@ impl Ddnnf {
                     &mut save_state.number_of_variables,
                 );
                 std::mem::swap(&mut self.max_worker, &mut save_state.max_worker);
+                // the enumeration indices refer to the order of the nodes
+                std::mem::swap(
+                    &mut self.enumeration_cursors,
+                    &mut save_state.enumeration_cursors,
+                );
+                std::mem::swap(&mut self.node_indices, &mut save_state.node_indices);
+                // The annotations stay, because they are keyed by structural ids and hence remain valid
+                // for each node that both versions share. The listeners belong to the subscribers of this
+                // d-DNNF and not to one of its versions.
             }
         }
     }
@@ -139,6 +297,7 @@ impl Ddnnf {
             Some(state) => {
                 state.setup_for_undo();
                 self.swap();
+                self.notify(ModelEvent::Undone);
                 //std::mem::swap(self, &mut state.to_owned().get_old_state().unwrap());
                 true
             }
@@ -146,16 +305,191 @@ impl Ddnnf {
         }
     }
 
+    /// Removes all nodes that are not reachable from the root and returns the number of removed nodes.
+    /// Leftover nodes can originate from the input file and would falsify the core and dead features,
+    /// because a literal node would count as existent even though it does not contribute to any model.
+    /// Each newly created d-DNNF (including the ones resulting from clause updates) gets cleaned up automatically.
+    pub fn collect_garbage(&mut self) -> usize {
+        if self.nodes.is_empty() {
+            return 0;
+        }
+
+        // the nodes are in postorder, hence parents get visited before their children
+        let mut reachable = vec![false; self.nodes.len()];
+        *reachable.last_mut().unwrap() = true;
+        for index in (0..self.nodes.len()).rev() {
+            if !reachable[index] {
+                continue;
+            }
+            if let And { children } | Or { children } = &self.nodes[index].ntype {
+                for &child in children {
+                    reachable[child] = true;
+                }
+            }
+        }
+
+        let removed = reachable.iter().filter(|&&r| !r).count();
+        if removed == 0 {
+            return 0;
+        }
+
+        // removing nodes keeps the relative order, so the new indices still form a postorder
+        let mut new_index = vec![usize::MAX; self.nodes.len()];
+        for (next, (index, _)) in reachable.iter().enumerate().filter(|(_, &r)| r).enumerate() {
+            new_index[index] = next;
+        }
+
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .zip(reachable.iter())
+            .filter(|(_, &r)| r)
+            .map(|(mut node, _)| {
+                if let And { children } | Or { children } = &mut node.ntype {
+                    children.iter_mut().for_each(|c| *c = new_index[*c]);
+                }
+                node.parents = node
+                    .parents
+                    .iter()
+                    .filter(|&&p| reachable[p])
+                    .map(|&p| new_index[p])
+                    .collect();
+                node
+            })
+            .collect();
+
+        self.literals.retain(|_, index| reachable[*index]);
+        self.literals
+            .values_mut()
+            .for_each(|index| *index = new_index[*index]);
+        self.true_nodes = self
+            .true_nodes
+            .iter()
+            .filter(|&&index| reachable[index])
+            .map(|&index| new_index[index])
+            .collect();
+        self.md.clear();
+        self.node_indices = OnceCell::new();
+
+        removed
+    }
+
+    /// Computes the structural id of each node. The i-th entry belongs to the i-th node.
+    /// In contrast to the indices, the ids stay valid across rebuilds for each node whose subgraph survives.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let ids = ddnnf.node_ids();
+    ///
+    /// assert_eq!(ddnnf.nodes.len(), ids.len());
+    /// assert_eq!(Some(ddnnf.nodes.len() - 1), ddnnf.node_index(ids[ids.len() - 1]));
+    /// ```
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        let mut ids = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            ids.push(NodeId::of(&node.ntype, |child| ids[child]));
+        }
+        ids
+    }
+
+    /// Determines the current index of the node with that id.
+    /// If multiple nodes are structurally identical, we return the smallest index.
+    /// The first lookup builds a table of all ids that gets reused until the d-DNNF replaces its nodes
+    /// (e.g. due to a clause update). Changing the public nodes directly does not reset that table.
+    pub fn node_index(&self, id: NodeId) -> Option<usize> {
+        self.node_indices
+            .get_or_init(|| {
+                let mut indices = HashMap::with_capacity(self.nodes.len());
+                for (index, node_id) in self.node_ids().into_iter().enumerate() {
+                    indices.entry(node_id).or_insert(index);
+                }
+                indices
+            })
+            .get(&id)
+            .copied()
+    }
+
+    /// Computes the structural id of a single node by only looking at its subgraph
+    pub fn node_id(&self, index: usize) -> NodeId {
+        let mut ids: HashMap<usize, NodeId> = HashMap::new();
+        let mut stack = vec![index];
+        while let Some(&current) = stack.last() {
+            if ids.contains_key(&current) {
+                stack.pop();
+                continue;
+            }
+            // the id of a node requires the ids of all its children
+            let missing: Vec<usize> = match &self.nodes[current].ntype {
+                And { children } | Or { children } => children
+                    .iter()
+                    .copied()
+                    .filter(|child| !ids.contains_key(child))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if missing.is_empty() {
+                let id = NodeId::of(&self.nodes[current].ntype, |child| ids[&child]);
+                ids.insert(current, id);
+                stack.pop();
+            } else {
+                stack.extend(missing);
+            }
+        }
+        ids[&index]
+    }
+
+    /// Attaches the annotation to the node with the given index and returns the previous annotation.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let root = ddnnf.nodes.len() - 1;
+    /// ddnnf.annotate(root, "root");
+    ///
+    /// assert_eq!(Some("root"), ddnnf.annotation(root));
+    /// assert_eq!(None, ddnnf.annotation(0));
+    /// ```
+    pub fn annotate(&mut self, index: usize, annotation: impl Into<String>) -> Option<String> {
+        let id = self.node_id(index);
+        self.annotations.insert(id, annotation.into())
+    }
+
+    /// Returns the annotation of the node with the given index
+    pub fn annotation(&self, index: usize) -> Option<&str> {
+        self.annotations
+            .get(&self.node_id(index))
+            .map(String::as_str)
+    }
+
+    /// Removes and returns the annotation of the node with the given index
+    pub fn remove_annotation(&mut self, index: usize) -> Option<String> {
+        let id = self.node_id(index);
+        self.annotations.remove(&id)
+    }
+
     // Returns the current count of the root node in the ddnnf.
-    // That value is the same during all computations
+    // That value is the same during all computations. Without nodes, there is no model.
     pub fn rc(&self) -> Integer {
-        self.nodes[self.nodes.len() - 1].count.clone()
+        self.nodes
+            .last()
+            .map_or(Integer::ZERO, |root| root.count.clone())
     }
 
     // Returns the current temp count of the root node in the ddnnf.
     // That value is changed during computations
     fn rt(&self) -> Integer {
-        self.nodes[self.nodes.len() - 1].temp.clone()
+        self.nodes
+            .last()
+            .map_or(Integer::ZERO, |root| root.temp.clone())
     }
 
     /// Determines the positions of the inverted featueres
@@ -171,6 +505,8 @@ impl Ddnnf {
 
     /// Executes a query.
     /// We use the in our opinion best type of query depending on the amount of features.
+    /// Up to 20 features, we only recount the nodes that the features mark. More features touch
+    /// most of the d-DNNF anyway, hence we recount all nodes via [Ddnnf::count_adaptive].
     ///
     /// # Example
     /// ```
@@ -186,20 +522,72 @@ impl Ddnnf {
     /// assert_eq!(1, ddnnf.execute_query(&vec![3,4]));
     /// assert_eq!(2, ddnnf.execute_query(&vec![3]));
     pub fn execute_query(&mut self, features: &[i32]) -> Integer {
+        let _querying = profiling::phase(Phase::Query);
         match features.len() {
             0 => self.rc(),
             1 => self.card_of_feature_with_marker(features[0]),
             2..=20 => {
                 self.operate_on_partial_config_marker(features, Ddnnf::calc_count_marked_node)
             }
-            _ => self.operate_on_partial_config_default(features, Ddnnf::calc_count),
+            _ => {
+                if self.query_is_not_sat(features) {
+                    Integer::ZERO
+                } else {
+                    self.count_adaptive(features)
+                }
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::parser::build_ddnnf;
+    use std::{
+        collections::{BTreeSet, HashSet},
+        sync::Arc,
+        thread,
+    };
+
+    use super::anomalies::config_creation::EnumOptions;
+    use super::clause_cache::ClauseOrigin;
+    use super::{node::NodeType::False, Ddnnf};
+
+    use crate::parser::{
+        build_ddnnf, distribute_building,
+        persisting::{write_binary_ddnnf_to_file, write_ddnnf_to_file},
+    };
+
+    #[test]
+    fn shared_between_threads() {
+        let mut vp9 = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let expected: Vec<_> = (1..=42)
+            .map(|f| vp9.execute_query(&[f, -(f % 7 + 1)]))
+            .collect();
+
+        let shared = Arc::new(vp9);
+        let root = shared.nodes.len() - 1;
+        let counts: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = (1..=42)
+                .map(|f| {
+                    let ddnnf = Arc::clone(&shared);
+                    scope.spawn(move || ddnnf.count_at(root, &[f, -(f % 7 + 1)]))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(expected, counts);
+
+        // exclusive operations need their own copy
+        let mut copies: Vec<_> = (0..4).map(|_| (*shared).clone()).collect();
+        thread::scope(|scope| {
+            for (i, copy) in copies.iter_mut().enumerate() {
+                let expected = &expected;
+                let f = i as i32 + 1;
+                scope
+                    .spawn(move || assert_eq!(expected[i], copy.execute_query(&[f, -(f % 7 + 1)])));
+            }
+        });
+    }
 
     #[test]
     fn features_opposing_indexes() {
@@ -214,4 +602,173 @@ mod test {
             ddnnf.map_features_opposing_indexes(&[-1, -2, -3, -4])
         );
     }
+
+    #[test]
+    fn unreachable_nodes_get_removed() {
+        // the literal -1 is not part of the d-DNNF that starts at the root
+        let lines = ["nnf 5 4 2", "L 1", "L -1", "L 2", "O 1 2 0 1", "A 2 0 2"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let mut ddnnf = distribute_building(lines, None, None);
+
+        assert_eq!(3, ddnnf.nodes.len());
+        assert_eq!(None, ddnnf.literals.get(&-1));
+        assert_eq!(HashSet::from([1, 2]), ddnnf.core);
+        assert_eq!(vec![2], ddnnf.nodes[0].parents);
+        assert_eq!(1, ddnnf.rc());
+        assert_eq!(0, ddnnf.execute_query(&[-1]));
+        assert_eq!(0, ddnnf.collect_garbage());
+
+        let mut vp9 = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let nodes = vp9.nodes.len();
+        assert_eq!(0, vp9.collect_garbage());
+        assert_eq!(nodes, vp9.nodes.len());
+    }
+
+    #[test]
+    fn node_ids_survive_rebuilds() {
+        let vp9 = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let ids = vp9.node_ids();
+
+        // the d4 file gets rebuilt from its c2d representation
+        let path = "tests/data/node_ids_vp9.nnf";
+        write_ddnnf_to_file(&vp9, path).unwrap();
+        let rebuilt = build_ddnnf(path, Some(42));
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(ids, rebuilt.node_ids());
+        for (index, &id) in ids.iter().enumerate() {
+            let rebuilt_index = rebuilt.node_index(id).unwrap();
+            assert_eq!(vp9.nodes[index].count, rebuilt.nodes[rebuilt_index].count);
+        }
+
+        // leftover nodes get removed, but the remaining ones keep their ids
+        let lines = ["nnf 4 2 2", "L -1", "L 1", "L 2", "A 2 1 2"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let cleaned = distribute_building(lines, None, None);
+        let lines = ["nnf 3 2 2", "L 2", "L 1", "A 2 1 0"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            cleaned.node_ids().into_iter().collect::<HashSet<_>>(),
+            distribute_building(lines, None, None)
+                .node_ids()
+                .into_iter()
+                .collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn annotations() {
+        let mut vp9 = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let ids = vp9.node_ids();
+        for index in [0, 17, vp9.nodes.len() - 1] {
+            assert_eq!(ids[index], vp9.node_id(index));
+        }
+
+        let root = vp9.nodes.len() - 1;
+        let literal = vp9.literals[&-3];
+        assert_eq!(None, vp9.annotate(root, "root"));
+        assert_eq!(None, vp9.annotate(literal, "deselected feature 3"));
+        assert_eq!(Some(String::from("root")), vp9.annotate(root, "the root"));
+        assert_eq!(Some("the root"), vp9.annotation(root));
+
+        // annotations are part of the binary format
+        let path = "tests/data/annotated_vp9.bin";
+        write_binary_ddnnf_to_file(&vp9, path).unwrap();
+        let mut rebuilt = build_ddnnf(path, None);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(vp9.annotations, rebuilt.annotations);
+        assert_eq!(
+            Some("deselected feature 3"),
+            rebuilt.annotation(rebuilt.literals[&-3])
+        );
+        assert_eq!(
+            Some(String::from("the root")),
+            rebuilt.remove_annotation(root)
+        );
+        assert_eq!(None, rebuilt.annotation(root));
+    }
+
+    #[test]
+    fn constraints_on_features() {
+        let lines = std::fs::read_to_string("tests/data/small_ex_c2d.nnf")
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+        let clauses = BTreeSet::from([BTreeSet::from([1, 2]), BTreeSet::from([-2, 3, 4])]);
+        let ddnnf = distribute_building(lines, None, Some(clauses));
+
+        assert_eq!(
+            vec![(&BTreeSet::from([1, 2]), ClauseOrigin::Original)],
+            ddnnf.constraints_on(1)
+        );
+        assert_eq!(2, ddnnf.constraints_on(2).len());
+        assert!(build_ddnnf("tests/data/small_ex_c2d.nnf", None)
+            .constraints_on(1)
+            .is_empty());
+    }
+
+    #[test]
+    fn degenerate_models() {
+        let lines = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect();
+        let tautology = distribute_building(lines(&["t 1 0"]), Some(2), None);
+        let contradictions = [
+            Ddnnf::default(),
+            distribute_building(Vec::new(), Some(2), None),
+            distribute_building(lines(&["f 1 0"]), Some(3), None),
+            // the False node falsifies the And node and with it the whole d-DNNF
+            distribute_building(
+                lines(&["a 1 0", "f 2 0", "t 3 0", "1 2 0", "1 3 1 0"]),
+                Some(3),
+                None,
+            ),
+            distribute_building(lines(&["nnf 1 0 2", "O 0 0"]), None, None),
+        ];
+
+        let mut tautology = tautology;
+        assert_eq!(4, tautology.rc());
+        assert_eq!(2, tautology.execute_query(&[1]));
+        assert!(tautology.core.is_empty());
+        assert_eq!(
+            4,
+            tautology
+                .enumerate(&[], EnumOptions::with_limit(10))
+                .unwrap()
+                .count()
+        );
+
+        for mut ddnnf in contradictions {
+            assert_eq!(
+                vec![False],
+                ddnnf
+                    .nodes
+                    .iter()
+                    .map(|n| n.ntype.clone())
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(0, ddnnf.rc());
+            assert_eq!(0, ddnnf.execute_query(&[]));
+            assert!(!ddnnf.sat(&[]));
+            assert!(ddnnf.enumerate(&[], EnumOptions::with_limit(10)).is_none());
+            assert_eq!(None, ddnnf.propagate(&[]));
+            assert_eq!(0, ddnnf.condition(&[1]).rc());
+            #[cfg(feature = "sampling")]
+            {
+                assert_eq!(None, ddnnf.uniform_random_sampling(&[], 3, 42));
+                assert_eq!(
+                    crate::ddnnf::anomalies::t_wise_sampling::SamplingResult::Void,
+                    ddnnf.sample_t_wise(2)
+                );
+            }
+            ddnnf.minimize();
+            assert_eq!(0, ddnnf.rc());
+        }
+    }
 }
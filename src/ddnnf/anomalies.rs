@@ -1,9 +1,22 @@
 pub mod atomic_sets;
 pub mod config_creation;
 pub mod core;
+pub mod corrections;
+pub mod cross_constraints;
+pub mod dead_features;
+pub mod decision_propagation;
 pub mod false_optional;
+pub mod minimum_cardinality;
+pub mod optimization;
+pub mod packed_configs;
+pub mod prime_implicants;
 pub mod sat;
+pub mod sharded_enumeration;
+#[cfg(feature = "sampling")]
 pub mod t_wise_sampling;
+pub mod top_k;
+pub mod variable_clusters;
+pub mod variable_order;
 
 use std::{
     fs::File,
@@ -201,12 +201,19 @@ impl Ddnnf {
     /// Computes the signs of the features in multiple uniform random samples.
     /// Each of the features is represented by an BitArray holds as many entries as random samples
     /// with a 0 indicating that the feature occurs negated and a 1 indicating the feature occurs affirmed.
+    /// Without the sampling feature, there are no samples and each pair of candidates gets checked.
+    #[cfg_attr(not(feature = "sampling"), allow(unused_variables))]
     fn get_signed_excludes(&mut self, assumptions: &[i32]) -> Vec<BitArray<[u64; 8]>> {
         const SAMPLE_AMOUNT: usize = 512;
 
         let mut signed_excludes = Vec::with_capacity(self.number_of_variables as usize);
 
-        let samples = match self.uniform_random_sampling(assumptions, SAMPLE_AMOUNT, 10) {
+        #[cfg(feature = "sampling")]
+        let samples = self.uniform_random_sampling(assumptions, SAMPLE_AMOUNT, 10);
+        #[cfg(not(feature = "sampling"))]
+        let samples: Option<Vec<Vec<i32>>> = None;
+
+        let samples = match samples {
             Some(x) => x,
             None => {
                 // If the assumptions make the query unsat, then we get no samples.
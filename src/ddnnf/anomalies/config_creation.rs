@@ -1,99 +1,222 @@
 use std::{
-    cmp::min,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
-use itertools::Itertools;
-use once_cell::sync::Lazy;
-use rand::seq::SliceRandom;
-use rand::SeedableRng;
+#[cfg(feature = "sampling")]
+use rand::{seq::SliceRandom, SeedableRng};
+#[cfg(feature = "sampling")]
 use rand_distr::{Binomial, Distribution, WeightedAliasIndex};
+#[cfg(feature = "sampling")]
 use rand_pcg::{Lcg64Xsh32, Pcg32};
 
-use rug::{Assign, Integer, Rational};
+#[cfg(feature = "sampling")]
+use rug::{Assign, Rational};
+use rug::{Complete, Integer};
 
+use super::{packed_configs::PackedConfigs, variable_order::VariableOrder};
+#[cfg(feature = "sampling")]
+use crate::profiling::{self, Phase};
 use crate::Ddnnf;
 use crate::NodeType::*;
 
-#[allow(clippy::type_complexity)]
-static ENUMERATION_CACHE: Lazy<Arc<Mutex<HashMap<Vec<i32>, usize>>>> =
-    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+// The index at which the next enumeration continues for each combination of assumptions and order
+pub(crate) type EnumerationCursors = Arc<Mutex<HashMap<(Vec<i32>, EnumOrder), Integer>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// The order in which the configurations are enumerated
+pub enum EnumOrder {
+    /// The order given by the structure of the d-DNNF
+    #[default]
+    Forward,
+    /// The reversed order of [EnumOrder::Forward]
+    Backward,
+    /// The lexicographic order of the configurations by the variables in ascending order, where the deselection
+    /// of a variable precedes its selection. The order only depends on the configurations, hence d-DNNFs of the same
+    /// model enumerate the same sequence regardless of the compiler and the order of their nodes.
+    /// Each configuration updates the counts once or twice per variable, which makes this order slower than the others.
+    Canonical,
+}
 
-impl Ddnnf {
-    /// Creates satisfiable complete configurations for a ddnnf and given assumptions
-    /// If the ddnnf on itself or in combination with the assumption is unsatisfiable,
-    /// then we can not create any satisfiable configuration and simply return None.
-    pub fn enumerate(
-        &mut self,
-        assumptions: &mut Vec<i32>,
-        amount: usize,
-    ) -> Option<Vec<Vec<i32>>> {
-        if amount == 0 {
-            return Some(Vec::new());
-        }
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Controls which configurations are produced by [Ddnnf::enumerate]
+pub struct EnumOptions {
+    /// The index of the first configuration.
+    /// If None, we continue after the last configuration that this d-DNNF enumerated for the same assumptions and order.
+    /// After all configurations were enumerated, the cycle starts again at index 0.
+    pub start: Option<usize>,
+    /// The maximum number of configurations. If None, all remaining configurations are enumerated.
+    pub limit: Option<usize>,
+    /// If set, each configuration only contains the literals of these variables and
+    /// duplicates that arise from the projection are skipped within one enumeration.
+    /// The start index still refers to the complete configurations.
+    pub projection: Option<Vec<u32>>,
+    /// The order in which the configurations are produced
+    pub order: EnumOrder,
+    /// The order of the literals within each configuration
+    pub variable_order: VariableOrder,
+}
 
-        if !self.preprocess_config_creation(assumptions) {
-            return None;
+impl EnumOptions {
+    /// Enumerates up to limit configurations while continuing after the last enumeration
+    pub fn with_limit(limit: usize) -> Self {
+        EnumOptions {
+            limit: Some(limit),
+            ..Default::default()
         }
-        assumptions.sort_unstable_by_key(|f| f.abs());
+    }
+}
 
-        if self.execute_query(assumptions) > 0 {
-            let last_stop = match ENUMERATION_CACHE.lock().unwrap().get(assumptions) {
-                Some(&x) => x,
-                None => 0,
+/// A lazy enumeration of satisfiable configurations that is created by [Ddnnf::enumerate].
+/// Each configuration is sorted by the variable order of the [EnumOptions].
+pub struct Enumeration<'a> {
+    ddnnf: &'a mut Ddnnf,
+    assumptions: Vec<i32>,
+    cache_key: Option<(Vec<i32>, EnumOrder)>,
+    total: Integer,
+    position: Integer,
+    remaining: Option<usize>,
+    projection: Option<Vec<u32>>,
+    seen: HashSet<Vec<i32>>,
+    order: EnumOrder,
+    variable_order: VariableOrder,
+}
+
+impl Iterator for Enumeration<'_> {
+    type Item = Vec<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining != Some(0) && self.position < self.total {
+            let index = match self.order {
+                EnumOrder::Forward | EnumOrder::Canonical => self.position.clone(),
+                EnumOrder::Backward => (&self.total - &self.position).complete() - 1,
             };
+            self.position += 1;
+
+            let mut config = Vec::new();
+            if self.order == EnumOrder::Canonical {
+                self.ddnnf
+                    .unrank_canonical(index, &self.assumptions, &mut config);
+            } else {
+                self.ddnnf
+                    .unrank_node(index, self.ddnnf.nodes.len() - 1, &mut config);
+            }
 
-            let mut sample_list = self.enumerate_node(
-                (
-                    &Integer::from(last_stop),
-                    &min(self.rt(), Integer::from(last_stop + amount)),
-                ),
-                self.nodes.len() - 1,
-            );
-            for sample in sample_list.iter_mut() {
-                sample.sort_unstable_by_key(|f| f.abs());
+            if let Some(projection) = &self.projection {
+                config.retain(|f| projection.contains(&f.unsigned_abs()));
+                if !self.seen.insert(config.clone()) {
+                    continue;
+                }
             }
+            self.variable_order.sort(&mut config);
 
-            ENUMERATION_CACHE.lock().unwrap().insert(
-                assumptions.to_vec(),
-                (min(self.rt(), Integer::from(last_stop + amount)) % self.rt()).to_usize_wrapping(),
-            );
-            return Some(sample_list);
+            if let Some(remaining) = self.remaining.as_mut() {
+                *remaining -= 1;
+            }
+            return Some(config);
         }
         None
     }
+}
 
-    /// Generates amount many uniform random samples under a given set of assumptions and a seed.
-    /// Each sample is sorted by the number of the features. Each sample is a complete configuration with #SAT of 1.
-    /// If the ddnnf itself or in combination with the assumptions is unsatisfiable, None is returned.
-    pub fn uniform_random_sampling(
+impl Enumeration<'_> {
+    /// Collects the remaining configurations as bit-packed rows in the variable order of the options
+    pub fn packed(mut self) -> PackedConfigs {
+        let mut packed = PackedConfigs::new(self.ddnnf.number_of_variables, &self.variable_order);
+        for config in &mut self {
+            packed.push(&config);
+        }
+        packed
+    }
+}
+
+impl Drop for Enumeration<'_> {
+    // remembers where to continue for the next enumeration with the same assumptions
+    fn drop(&mut self) {
+        if let Some(key) = self.cache_key.take() {
+            let next_start = (&self.position % &self.total).complete();
+            self.ddnnf
+                .enumeration_cursors
+                .lock()
+                .unwrap()
+                .insert(key, next_start);
+        }
+    }
+}
+
+impl Ddnnf {
+    /// Creates satisfiable complete configurations for a ddnnf and given assumptions.
+    /// The options determine the first configuration, the limit, the projection, and the order.
+    /// If the ddnnf on itself or in combination with the assumption is unsatisfiable,
+    /// then we can not create any satisfiable configuration and simply return None.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::anomalies::config_creation::EnumOptions;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let options = EnumOptions { start: Some(0), projection: Some(vec![2, 3]), ..Default::default() };
+    /// let configs: Vec<Vec<i32>> = ddnnf.enumerate(&[4], options).unwrap().collect();
+    /// assert_eq!(2, configs.len());
+    /// assert!(configs.contains(&vec![2, -3]) && configs.contains(&vec![-2, 3]));
+    /// ```
+    pub fn enumerate(
         &mut self,
         assumptions: &[i32],
-        amount: usize,
-        seed: u64,
-    ) -> Option<Vec<Vec<i32>>> {
-        if !self.preprocess_config_creation(assumptions) {
+        options: EnumOptions,
+    ) -> Option<Enumeration<'_>> {
+        // if any of the assumptions isn't valid by being in the range of +-#variables, then we return None
+        if assumptions
+            .iter()
+            .any(|f| f.abs() > self.number_of_variables as i32)
+        {
             return None;
         }
 
-        if self.execute_query(assumptions) > 0 {
-            let mut sample_list = self.sample_node(
-                amount,
-                self.nodes.len() - 1,
-                &mut Pcg32::seed_from_u64(seed),
-            );
-            for sample in sample_list.iter_mut() {
-                sample.sort_unstable_by_key(|f| f.abs());
-            }
-            return Some(sample_list);
+        // Afterwards, the temp value of each node is its count under the assumptions
+        let total = self.operate_on_partial_config_default(assumptions, Ddnnf::calc_count);
+        if total == 0 {
+            return None;
         }
-        None
+
+        let mut key = assumptions.to_vec();
+        key.sort_unstable_by_key(|f| f.abs());
+        key.dedup();
+        let cache_key = (key, options.order);
+
+        let position = match options.start {
+            Some(start) => Integer::from(start),
+            None => self
+                .enumeration_cursors
+                .lock()
+                .unwrap()
+                .get(&cache_key)
+                .cloned()
+                .unwrap_or_default(),
+        };
+
+        Some(Enumeration {
+            ddnnf: self,
+            assumptions: assumptions.to_vec(),
+            cache_key: options.start.is_none().then_some(cache_key),
+            total,
+            position,
+            remaining: options.limit,
+            projection: options.projection,
+            seen: HashSet::new(),
+            order: options.order,
+            variable_order: options.variable_order,
+        })
     }
 
     // resets the temp count of each node to the cached count,
     // computes the count under the assumptions to set some of the temp values,
     // and handle the literals properly.
+    #[cfg(feature = "sampling")]
     fn preprocess_config_creation(&mut self, assumptions: &[i32]) -> bool {
         // if any of the assumptions isn't valid by being in the range of +-#variables, then we return false
         if assumptions
@@ -121,172 +244,375 @@ impl Ddnnf {
         true
     }
 
-    // Handles a node appropiate depending on its kind to produce complete
-    // satisfiable configurations
-    fn enumerate_node(&self, range: (&Integer, &Integer), index: usize) -> Vec<Vec<i32>> {
-        let _range2 = (range.0.to_usize_wrapping(), range.1.to_usize_wrapping());
-        let mut enumeration_list = Vec::new();
-        if *range.1 == 0 || self.nodes[index].temp == 0 {
-            return enumeration_list;
+    // Computes the configuration with the given index in the canonical order. The variables get decided
+    // in ascending order: the index either falls among the configurations that deselect the variable,
+    // or we skip them and select it. Variables that do not occur in the d-DNNF are not part of the configuration.
+    // The temp values hold the counts under the decisions so far. Each decision only updates the ancestors
+    // of the literal that it excludes.
+    fn unrank_canonical(&mut self, mut index: Integer, assumptions: &[i32], config: &mut Vec<i32>) {
+        for i in 0..self.nodes.len() {
+            match self.nodes[i].ntype {
+                Literal { literal } if assumptions.contains(&-literal) => {
+                    self.nodes[i].temp = Integer::ZERO
+                }
+                _ => self.calc_count(i),
+            }
         }
 
-        match &self.nodes[index].ntype {
-            And { children } => {
-                let mut acc_amount = Integer::from(1);
-                let mut enumeration_child_lists = Vec::new();
-
-                for &child in children {
-                    // skip the true nodes
-                    if self.true_nodes.contains(&child) {
-                        continue;
-                    }
+        let mut decisions = assumptions.to_vec();
+        for var in 1..=self.number_of_variables as i32 {
+            let selected = self.literals.get(&var).copied();
+            let deselected = self.literals.get(&-var).copied();
+            if selected.is_none() && deselected.is_none()
+                || decisions.iter().any(|literal| literal.abs() == var)
+            {
+                continue;
+            }
 
-                    if &acc_amount < range.1 {
-                        let change = (&Integer::ZERO, min(range.1, &self.nodes[child].temp));
-                        enumeration_child_lists.push(self.enumerate_node(change, child));
-                        acc_amount *= change.1;
-                    } else {
-                        // restrict the creation of any more configs
-                        enumeration_child_lists.push(vec![self
-                            .enumerate_node((&Integer::ZERO, &Integer::from(1)), child)[0]
-                            .clone()]);
-                    }
+            let root = self.nodes.len() - 1;
+            let affected = selected.map_or(Vec::new(), |node| self.exclude_literal(node));
+            let count = match affected.last() {
+                Some(&node) if node == root => self.nodes[root].partial_derivative.clone(),
+                _ => self.nodes[root].temp.clone(),
+            };
+            if index < count {
+                decisions.push(-var);
+                self.commit(&affected);
+            } else {
+                index -= count;
+                decisions.push(var);
+                if let Some(node) = deselected {
+                    let affected = self.exclude_literal(node);
+                    self.commit(&affected);
                 }
+            }
+        }
+        decisions.sort_unstable_by_key(|literal| literal.abs());
+        decisions.dedup();
+        config.extend(decisions);
+    }
 
-                // cartesian product of  all combinations of children
-                // example:
-                //      enumeration_child_lists: Vec<Vec<Vec<i32>>>
-                //          [[[1,2,-3],[3]],[[4,5,-5]]]
-                //      enumeration_list: Vec<Vec<i32>>
-                //          [[1,2,-3,4],[1,2,-3,5],[1,2,-3,-5],[3,4],[3,5],[3,-5]]
-                //
-                // reverse is important to ensure a total order with additions of configs at the end
-                enumeration_child_lists.reverse();
-                enumeration_list = enumeration_child_lists
-                    .into_iter()
-                    .multi_cartesian_product()
-                    .map(|elem| elem.into_iter().flatten().collect())
-                    .skip(range.0.to_usize_wrapping())
-                    .take(range.1.to_usize_wrapping() - range.0.to_usize_wrapping()) // stop after we got our required amount of configs
-                    .collect();
+    // Computes the counts of the literal node and its ancestors under the assumption that the literal
+    // gets excluded in addition to the temp values. Returns the affected nodes in ascending order.
+    // Their new counts are saved as partial derivatives, which serve as scratch space.
+    fn exclude_literal(&mut self, literal_node: usize) -> Vec<usize> {
+        let mut affected = vec![literal_node];
+        self.nodes[literal_node].marker = true;
+        let mut stack = vec![literal_node];
+        while let Some(node) = stack.pop() {
+            for parent in self.nodes[node].parents.clone() {
+                if !self.nodes[parent].marker {
+                    self.nodes[parent].marker = true;
+                    affected.push(parent);
+                    stack.push(parent);
+                }
             }
-            Or { children } => {
-                let mut acc_amount = Integer::ZERO;
+        }
+        // the nodes are in postorder, hence children get updated before their parents
+        affected.sort_unstable();
 
-                for &child in children {
-                    if self.nodes[child].temp == Integer::ZERO {
-                        continue;
-                    }
+        for &node in affected.iter() {
+            let count = |child: usize| match self.nodes[child].marker {
+                true => &self.nodes[child].partial_derivative,
+                false => &self.nodes[child].temp,
+            };
+            let value = match &self.nodes[node].ntype {
+                And { children } => Integer::product(children.iter().map(|&c| count(c))).complete(),
+                Or { children } => Integer::sum(children.iter().map(|&c| count(c))).complete(),
+                _ => Integer::ZERO,
+            };
+            self.nodes[node].partial_derivative = value;
+        }
+        for &node in affected.iter() {
+            self.nodes[node].marker = false;
+        }
+        affected
+    }
+
+    // Applies the counts that Ddnnf::exclude_literal computed for the affected nodes
+    fn commit(&mut self, affected: &[usize]) {
+        for &node in affected {
+            let node = &mut self.nodes[node];
+            std::mem::swap(&mut node.temp, &mut node.partial_derivative);
+        }
+    }
 
-                    if &acc_amount < range.1 {
-                        let change = (&Integer::ZERO, min(range.1, &self.nodes[child].temp));
-                        enumeration_list.append(&mut self.enumerate_node(change, child));
-                        acc_amount += change.1;
-                    } else {
-                        break;
+    // Computes the configuration with the given index among the configurations of the node.
+    // The temp values have to contain the counts under the assumptions.
+    // For AND nodes, the index is split into one digit per child with the child's count as base.
+    fn unrank_node(&self, index: Integer, node: usize, config: &mut Vec<i32>) {
+        // the children are visited in their order with an explicit stack
+        let mut stack = vec![(node, index)];
+        while let Some((node, mut index)) = stack.pop() {
+            match &self.nodes[node].ntype {
+                And { children } => {
+                    let mut digits = Vec::with_capacity(children.len());
+                    for &child in children {
+                        let (quotient, remainder) = index.div_rem(self.nodes[child].temp.clone());
+                        digits.push((child, remainder));
+                        index = quotient;
                     }
+                    stack.extend(digits.into_iter().rev());
                 }
+                Or { children } => {
+                    for &child in children {
+                        if index < self.nodes[child].temp {
+                            stack.push((child, index));
+                            break;
+                        }
+                        index -= &self.nodes[child].temp;
+                    }
+                }
+                Literal { literal } => config.push(*literal),
+                _ => (),
             }
-            Literal { literal } => {
-                enumeration_list.push(vec![*literal]);
+        }
+    }
+}
+
+#[cfg(feature = "sampling")]
+impl Ddnnf {
+    /// Generates amount many uniform random samples under a given set of assumptions and a seed.
+    /// Each sample is sorted by the number of the features. Each sample is a complete configuration with #SAT of 1.
+    /// If the ddnnf itself or in combination with the assumptions is unsatisfiable, None is returned.
+    pub fn uniform_random_sampling(
+        &mut self,
+        assumptions: &[i32],
+        amount: usize,
+        seed: u64,
+    ) -> Option<Vec<Vec<i32>>> {
+        self.uniform_random_sampling_ordered(assumptions, amount, seed, &VariableOrder::model())
+    }
+
+    /// The same as [Ddnnf::uniform_random_sampling], but each sample is sorted by the variable order.
+    pub fn uniform_random_sampling_ordered(
+        &mut self,
+        assumptions: &[i32],
+        amount: usize,
+        seed: u64,
+        variable_order: &VariableOrder,
+    ) -> Option<Vec<Vec<i32>>> {
+        let _sampling = profiling::phase(Phase::Sample);
+        if !self.preprocess_config_creation(assumptions) {
+            return None;
+        }
+
+        if self.execute_query(assumptions) > 0 {
+            let mut sample_list = self.sample_node(
+                amount,
+                self.nodes.len() - 1,
+                &mut Pcg32::seed_from_u64(seed),
+            );
+            for sample in sample_list.iter_mut() {
+                variable_order.sort(sample);
             }
-            _ => (),
+            return Some(sample_list);
         }
-        enumeration_list
+        None
     }
 
     // Performs the operations needed to generate random samples.
     // The algorithm is based upon KUS's uniform random sampling algorithm.
+    // The nodes are processed with an explicit stack of frames. The sample lists of finished
+    // nodes are put on the result stack, where their parent picks them up.
     fn sample_node(&self, amount: usize, index: usize, rng: &mut Lcg64Xsh32) -> Vec<Vec<i32>> {
-        let mut sample_list = Vec::new();
-        if amount == 0 {
-            return sample_list;
-        }
-        match &self.nodes[index].ntype {
-            And { children } => {
-                for _ in 0..amount {
-                    sample_list.push(Vec::new());
-                }
-                for &child in children {
-                    let mut child_sample_list = self.sample_node(amount, child, rng);
-                    // shuffle operation from KUS algorithm
-                    child_sample_list.shuffle(rng);
-
-                    // stitch operation
-                    for (index, sample) in child_sample_list.iter_mut().enumerate() {
-                        sample_list[index].append(sample);
+        let mut frames = vec![SampleFrame::Visit { amount, index }];
+        let mut results: Vec<Vec<Vec<i32>>> = Vec::new();
+
+        while let Some(frame) = frames.pop() {
+            match frame {
+                SampleFrame::Visit { amount, index } => {
+                    if amount == 0 {
+                        results.push(Vec::new());
+                        continue;
                     }
-                }
-            }
-            Or { children } => {
-                let mut pick_amount = vec![0; children.len()];
-                let mut choices = Vec::new();
-                let mut weights = Vec::new();
-
-                // compute the probability of getting a sample of a child node
-                let parent_count_as_float = Rational::from((&self.nodes[index].temp, 1));
-                #[allow(clippy::needless_range_loop)]
-                for child_index in 0..children.len() {
-                    let child_count_as_float =
-                        Rational::from((&self.nodes[children[child_index]].temp, 1));
-
-                    // can't get a sample of a children with no more valid configuration
-                    if child_count_as_float != 0 {
-                        let child_amount = (child_count_as_float / &parent_count_as_float).to_f64()
-                            * amount as f64;
-                        choices.push(child_index);
-                        weights.push(child_amount);
+                    match &self.nodes[index].ntype {
+                        And { .. } => frames.push(SampleFrame::And {
+                            index,
+                            amount,
+                            next_child: 0,
+                            sample_list: vec![Vec::new(); amount],
+                        }),
+                        Or { children } => {
+                            let (choices, pick_amount) =
+                                self.pick_amounts(amount, index, children, rng);
+                            frames.push(SampleFrame::Or {
+                                index,
+                                amount,
+                                choices,
+                                pick_amount,
+                                next_choice: 0,
+                                sample_list: Vec::new(),
+                            });
+                        }
+                        Literal { literal } => results.push(vec![vec![*literal]; amount]),
+                        _ => results.push(Vec::new()),
                     }
                 }
-
-                // choice some sort of weighted distribution depending on the number of children with count > 0
-                match weights.len() {
-                    1 => pick_amount[choices[0]] += amount,
-                    2 => {
-                        let binomial_dist =
-                            Binomial::new(amount as u64, weights[0] / (weights[0] + weights[1]))
-                                .unwrap();
-                        pick_amount[choices[0]] += binomial_dist.sample(rng) as usize;
-                        pick_amount[choices[1]] = amount - pick_amount[choices[0]];
+                SampleFrame::And {
+                    index,
+                    amount,
+                    next_child,
+                    mut sample_list,
+                } => {
+                    let children = match &self.nodes[index].ntype {
+                        And { children } => children,
+                        _ => unreachable!("the frame belongs to an And node"),
+                    };
+                    if next_child > 0 {
+                        let mut child_sample_list = results.pop().unwrap();
+                        // shuffle operation from KUS algorithm
+                        child_sample_list.shuffle(rng);
+
+                        // stitch operation
+                        for (index, sample) in child_sample_list.iter_mut().enumerate() {
+                            sample_list[index].append(sample);
+                        }
                     }
-                    _ => {
-                        let weighted_dist = WeightedAliasIndex::new(weights).unwrap();
-                        for _ in 0..amount {
-                            pick_amount[choices[weighted_dist.sample(rng)]] += 1;
+                    match children.get(next_child) {
+                        Some(&child) => {
+                            frames.push(SampleFrame::And {
+                                index,
+                                amount,
+                                next_child: next_child + 1,
+                                sample_list,
+                            });
+                            frames.push(SampleFrame::Visit {
+                                amount,
+                                index: child,
+                            });
                         }
+                        None => results.push(sample_list),
                     }
                 }
-
-                for &choice in choices.iter() {
-                    sample_list.append(&mut self.sample_node(
-                        pick_amount[choice],
-                        children[choice],
-                        rng,
-                    ));
+                SampleFrame::Or {
+                    index,
+                    amount,
+                    choices,
+                    pick_amount,
+                    next_choice,
+                    mut sample_list,
+                } => {
+                    let children = match &self.nodes[index].ntype {
+                        Or { children } => children,
+                        _ => unreachable!("the frame belongs to an Or node"),
+                    };
+                    if next_choice > 0 {
+                        sample_list.append(&mut results.pop().unwrap());
+                    }
+                    match choices.get(next_choice) {
+                        Some(&choice) => {
+                            let child = SampleFrame::Visit {
+                                amount: pick_amount[choice],
+                                index: children[choice],
+                            };
+                            frames.push(SampleFrame::Or {
+                                index,
+                                amount,
+                                choices,
+                                pick_amount,
+                                next_choice: next_choice + 1,
+                                sample_list,
+                            });
+                            frames.push(child);
+                        }
+                        None => {
+                            // add empty lists for child nodes that have a count of zero
+                            while sample_list.len() != amount {
+                                sample_list.push(Vec::new());
+                            }
+
+                            sample_list.shuffle(rng);
+                            results.push(sample_list);
+                        }
+                    }
                 }
+            }
+        }
+        results.pop().unwrap_or_default()
+    }
 
-                // add empty lists for child nodes that have a count of zero
-                while sample_list.len() != amount {
-                    sample_list.push(Vec::new());
-                }
+    // Distributes the amount of samples of an Or node among its children with a count above zero.
+    // Returns the children that get samples and the amount of samples for each child.
+    fn pick_amounts(
+        &self,
+        amount: usize,
+        index: usize,
+        children: &[usize],
+        rng: &mut Lcg64Xsh32,
+    ) -> (Vec<usize>, Vec<usize>) {
+        let mut pick_amount = vec![0; children.len()];
+        let mut choices = Vec::new();
+        let mut weights = Vec::new();
+
+        // compute the probability of getting a sample of a child node
+        let parent_count_as_float = Rational::from((&self.nodes[index].temp, 1));
+        #[allow(clippy::needless_range_loop)]
+        for child_index in 0..children.len() {
+            let child_count_as_float = Rational::from((&self.nodes[children[child_index]].temp, 1));
+
+            // can't get a sample of a children with no more valid configuration
+            if child_count_as_float != 0 {
+                let child_amount =
+                    (child_count_as_float / &parent_count_as_float).to_f64() * amount as f64;
+                choices.push(child_index);
+                weights.push(child_amount);
+            }
+        }
 
-                sample_list.shuffle(rng);
+        // choice some sort of weighted distribution depending on the number of children with count > 0
+        match weights.len() {
+            1 => pick_amount[choices[0]] += amount,
+            2 => {
+                let binomial_dist =
+                    Binomial::new(amount as u64, weights[0] / (weights[0] + weights[1])).unwrap();
+                pick_amount[choices[0]] += binomial_dist.sample(rng) as usize;
+                pick_amount[choices[1]] = amount - pick_amount[choices[0]];
             }
-            Literal { literal } => {
+            _ => {
+                let weighted_dist = WeightedAliasIndex::new(weights).unwrap();
                 for _ in 0..amount {
-                    sample_list.push(vec![*literal]);
+                    pick_amount[choices[weighted_dist.sample(rng)]] += 1;
                 }
             }
-            _ => (),
         }
-        sample_list
+        (choices, pick_amount)
     }
 }
 
+// The state of a node during the sampling of Ddnnf::sample_node
+#[cfg(feature = "sampling")]
+enum SampleFrame {
+    // the samples of the node have to be created
+    Visit {
+        amount: usize,
+        index: usize,
+    },
+    // the samples of the children before next_child were stitched into the sample list
+    And {
+        index: usize,
+        amount: usize,
+        next_child: usize,
+        sample_list: Vec<Vec<i32>>,
+    },
+    // the samples of the chosen children before next_choice were appended to the sample list
+    Or {
+        index: usize,
+        amount: usize,
+        choices: Vec<usize>,
+        pick_amount: Vec<usize>,
+        next_choice: usize,
+        sample_list: Vec<Vec<i32>>,
+    },
+}
+
 #[cfg(test)]
 mod test {
+    use std::cmp::min;
     use std::collections::HashSet;
 
+    use itertools::Itertools;
+    #[cfg(feature = "sampling")]
     use rand::thread_rng;
 
     use super::*;
@@ -299,10 +625,13 @@ mod test {
         let mut res_all = HashSet::new();
         let mut res_assumptions = HashSet::new();
 
-        let mut assumptions = vec![
+        let assumptions = vec![
             1, 2, 3, -4, -5, 6, 7, -8, -9, 10, 11, -12, -13, -14, 15, 16, -17, -18, 19, 20, 27,
         ];
-        let inter_res_assumptions_1 = vp9.enumerate(&mut assumptions, 40).unwrap();
+        let inter_res_assumptions_1 = vp9
+            .enumerate(&assumptions, EnumOptions::with_limit(40))
+            .unwrap()
+            .collect_vec();
         for inter in inter_res_assumptions_1 {
             assert!(vp9.sat(&inter));
             assert_eq!(
@@ -319,14 +648,20 @@ mod test {
         );
 
         for i in 1..=4 {
-            let inter_res_all = vp9.enumerate(&mut vec![], 50000).unwrap();
+            let inter_res_all = vp9
+                .enumerate(&[], EnumOptions::with_limit(50000))
+                .unwrap()
+                .collect_vec();
             assert_eq!(50000, inter_res_all.len());
             for inter in inter_res_all {
                 res_all.insert(inter);
             }
             assert_eq!(i * 50000, res_all.len(), "there are duplicates");
         }
-        let inter_res = vp9.enumerate(&mut vec![], 50000).unwrap();
+        let inter_res = vp9
+            .enumerate(&[], EnumOptions::with_limit(50000))
+            .unwrap()
+            .collect_vec();
         assert_eq!(16000, inter_res.len(), "there are only 16000 configs left");
         for inter in inter_res {
             res_all.insert(inter);
@@ -334,14 +669,20 @@ mod test {
         assert_eq!(vp9.rt(), res_all.len(), "there are duplicates");
 
         assert_eq!(80, vp9.execute_query(&assumptions));
-        let inter_res_assumptions_2 = vp9.enumerate(&mut assumptions, 40).unwrap();
+        let inter_res_assumptions_2 = vp9
+            .enumerate(&assumptions, EnumOptions::with_limit(40))
+            .unwrap()
+            .collect_vec();
         for inter in inter_res_assumptions_2.clone() {
             res_assumptions.insert(inter);
         }
         assert_eq!(40, inter_res_assumptions_2.len());
 
         // the cycle for that set of assumptions starts again
-        let inter_res_assumptions_3 = vp9.enumerate(&mut assumptions, 40).unwrap();
+        let inter_res_assumptions_3 = vp9
+            .enumerate(&assumptions, EnumOptions::with_limit(40))
+            .unwrap()
+            .collect_vec();
         for inter in inter_res_assumptions_3.clone() {
             res_assumptions.insert(inter);
         }
@@ -359,19 +700,24 @@ mod test {
         let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
 
         let mut res_all = HashSet::new();
+        #[cfg_attr(not(feature = "sampling"), allow(unused_mut))]
         let mut assumptions = vec![
             1, -2, -3, 4, -5, 6, 7, 8, -9, -10, 11, -12, -13, 100, -101, 102,
         ];
 
         for i in (1_000..=10_000).step_by(1_000) {
-            let configs = auto1.enumerate(&mut assumptions, 1_000).unwrap();
+            let configs = auto1
+                .enumerate(&assumptions, EnumOptions::with_limit(1_000))
+                .unwrap()
+                .collect_vec();
             for inter in configs {
                 res_all.insert(inter);
             }
             assert_eq!(i, res_all.len(), "there are duplicates");
 
             // shuffeling the assumptions should have no effect on the caching of the number of configs that we already looked at
-            assumptions.shuffle(&mut thread_rng())
+            #[cfg(feature = "sampling")]
+            assumptions.shuffle(&mut thread_rng());
         }
     }
 
@@ -383,7 +729,10 @@ mod test {
         let mut assumptions = vec![-35, 42];
 
         for i in 1..=1_000 {
-            let configs = vp9.enumerate(&mut assumptions, 1).unwrap();
+            let configs = vp9
+                .enumerate(&assumptions, EnumOptions::with_limit(1))
+                .unwrap()
+                .collect_vec();
             for inter in configs {
                 assert!(vp9.sat(&inter));
                 assert_eq!(
@@ -406,7 +755,10 @@ mod test {
 
         // vp9.rt() under the assumptions is 86400. Hence, we should never get more than 86400 different configs
         for i in (1_000..=100_000).step_by(2_000) {
-            let configs = vp9.enumerate(&mut assumptions, 2_000).unwrap();
+            let configs = vp9
+                .enumerate(&assumptions, EnumOptions::with_limit(2_000))
+                .unwrap()
+                .collect_vec();
             for inter in configs {
                 assert!(vp9.sat(&inter));
                 assert_eq!(
@@ -429,24 +781,177 @@ mod test {
         }
     }
 
+    #[test]
+    fn enumeration_options() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let all_options = EnumOptions {
+            start: Some(0),
+            ..Default::default()
+        };
+
+        let forward = ddnnf
+            .enumerate(&[], all_options.clone())
+            .unwrap()
+            .collect_vec();
+        let mut expected = ddnnf.brute_force_enumerate().unwrap();
+        let mut sorted = forward.clone();
+        expected.sort();
+        sorted.sort();
+        assert_eq!(expected, sorted, "not all models were enumerated");
+
+        let backward = EnumOptions {
+            order: EnumOrder::Backward,
+            ..all_options.clone()
+        };
+        let mut reversed = ddnnf.enumerate(&[], backward).unwrap().collect_vec();
+        reversed.reverse();
+        assert_eq!(forward, reversed);
+
+        let window = EnumOptions {
+            start: Some(1),
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            forward[1..3].to_vec(),
+            ddnnf.enumerate(&[], window).unwrap().collect_vec()
+        );
+
+        let projected = EnumOptions {
+            projection: Some(vec![1, 4]),
+            ..all_options
+        };
+        let projected_configs = ddnnf.enumerate(&[], projected).unwrap().collect_vec();
+        assert_eq!(2, projected_configs.len());
+        assert!(
+            projected_configs.contains(&vec![1, 4]) && projected_configs.contains(&vec![1, -4])
+        );
+
+        let reordered = EnumOptions {
+            start: Some(0),
+            variable_order: VariableOrder::custom(vec![4, 2]).unwrap(),
+            ..Default::default()
+        };
+        let reordered_configs = ddnnf.enumerate(&[], reordered).unwrap().collect_vec();
+        assert_eq!(forward.len(), reordered_configs.len());
+        for (config, reordered) in forward.iter().zip(reordered_configs.iter()) {
+            assert_eq!(vec![config[3], config[1], config[0], config[2]], *reordered);
+        }
+
+        // continuing without a start index cycles through the models
+        let first = ddnnf
+            .enumerate(&[2], EnumOptions::with_limit(1))
+            .unwrap()
+            .collect_vec();
+        let second = ddnnf
+            .enumerate(&[2], EnumOptions::with_limit(5))
+            .unwrap()
+            .collect_vec();
+        let third = ddnnf
+            .enumerate(&[2], EnumOptions::with_limit(1))
+            .unwrap()
+            .collect_vec();
+        assert_eq!(1, second.len());
+        assert_ne!(first, second);
+        assert_eq!(first, third);
+
+        // another d-DNNF starts at the beginning, while clones continue the same enumeration
+        let mut other: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let mut clone = ddnnf.clone();
+        let next = |ddnnf: &mut Ddnnf| {
+            ddnnf
+                .enumerate(&[2], EnumOptions::with_limit(1))
+                .unwrap()
+                .collect_vec()
+        };
+        assert_eq!(first, next(&mut other));
+        assert_eq!(second, next(&mut clone));
+        assert_eq!(first, next(&mut ddnnf));
+    }
+
+    #[test]
+    fn enumeration_canonical_order() {
+        let canonical = |start: usize, limit: Option<usize>| EnumOptions {
+            start: Some(start),
+            limit,
+            order: EnumOrder::Canonical,
+            ..Default::default()
+        };
+
+        // the configurations are sorted lexicographically, where -f precedes f
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let assumptions = [3, 7, 11, 16, 20];
+        let mut expected = vp9
+            .enumerate(&assumptions, EnumOptions::with_limit(400))
+            .unwrap()
+            .collect_vec();
+        expected.sort();
+        assert_eq!(400, expected.len());
+        let configs = vp9
+            .enumerate(&assumptions, canonical(0, None))
+            .unwrap()
+            .collect_vec();
+        assert_eq!(expected, configs);
+        assert_eq!(
+            expected[123..130].to_vec(),
+            vp9.enumerate(&assumptions, canonical(123, Some(7)))
+                .unwrap()
+                .collect_vec()
+        );
+
+        // c2d and d4 order their nodes differently, but the canonical order is the same
+        for (c2d, d4, variables, start) in [
+            ("small_ex_c2d.nnf", "small_ex_d4.nnf", 4, 0),
+            ("auto1_c2d.nnf", "auto1_d4.nnf", 2513, 1_000_000_007),
+        ] {
+            let mut c2d = build_ddnnf(&format!("tests/data/{c2d}"), Some(variables));
+            let mut d4 = build_ddnnf(&format!("tests/data/{d4}"), Some(variables));
+            let limit = Some(4);
+            let default = EnumOptions {
+                start: Some(start),
+                limit,
+                ..Default::default()
+            };
+            assert_ne!(
+                c2d.enumerate(&[], default.clone()).unwrap().collect_vec(),
+                d4.enumerate(&[], default).unwrap().collect_vec()
+            );
+            assert_eq!(
+                c2d.enumerate(&[], canonical(start, limit))
+                    .unwrap()
+                    .collect_vec(),
+                d4.enumerate(&[], canonical(start, limit))
+                    .unwrap()
+                    .collect_vec()
+            );
+        }
+    }
+
     #[test]
     fn enumeration_is_not_possible() {
         let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
         let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
 
-        assert!(vp9.enumerate(&mut vec![1, -1], 1).is_none());
         assert!(vp9
-            .enumerate(&mut vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 1)
+            .enumerate(&[1, -1], EnumOptions::with_limit(1))
+            .is_none());
+        assert!(vp9
+            .enumerate(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], EnumOptions::with_limit(1))
             .is_none());
-        assert!(vp9.enumerate(&mut vec![100], 1).is_none());
+        assert!(vp9.enumerate(&[100], EnumOptions::with_limit(1)).is_none());
 
-        assert!(auto1.enumerate(&mut vec![1, -1], 1).is_none());
         assert!(auto1
-            .enumerate(&mut vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 1)
+            .enumerate(&[1, -1], EnumOptions::with_limit(1))
+            .is_none());
+        assert!(auto1
+            .enumerate(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], EnumOptions::with_limit(1))
+            .is_none());
+        assert!(auto1
+            .enumerate(&[-10_000], EnumOptions::with_limit(1))
             .is_none());
-        assert!(auto1.enumerate(&mut vec![-10_000], 1).is_none());
     }
 
+    #[cfg(feature = "sampling")]
     #[test]
     fn sampling_validity() {
         let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
@@ -474,6 +979,7 @@ mod test {
         }
     }
 
+    #[cfg(feature = "sampling")]
     #[test]
     fn sampling_seeding() {
         let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
@@ -515,6 +1021,22 @@ mod test {
         );
     }
 
+    #[cfg(feature = "sampling")]
+    #[test]
+    fn sampling_with_variable_order() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let reversed = VariableOrder::custom((1..=42).rev().collect()).unwrap();
+
+        let samples = vp9.uniform_random_sampling(&[4], 10, 42).unwrap();
+        let reordered = vp9
+            .uniform_random_sampling_ordered(&[4], 10, 42, &reversed)
+            .unwrap();
+        for (sample, reordered) in samples.into_iter().zip(reordered) {
+            assert_eq!(sample.into_iter().rev().collect_vec(), reordered);
+        }
+    }
+
+    #[cfg(feature = "sampling")]
     #[test]
     fn sampling_is_not_possible() {
         let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
@@ -2,7 +2,48 @@ use std::collections::HashSet;
 
 use crate::Ddnnf;
 
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The features that have the same value in each configuration, see [Ddnnf::backbone]
+pub struct Backbone {
+    /// The features that are selected in each configuration in ascending order
+    pub core: Vec<u32>,
+    /// The features that are deselected in each configuration in ascending order
+    pub dead: Vec<u32>,
+}
+
 impl Ddnnf {
+    /// Computes the core and dead features by their counts. In contrast to the core set that
+    /// gets derived from the occurrences of the literals, the counts also reveal literals that only
+    /// occur in parts of the d-DNNF without any model.
+    /// All counts result from a single pass over the partial derivatives.
+    /// If the d-DNNF has no configuration at all, there is no backbone.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let backbone = ddnnf.backbone();
+    /// assert_eq!(vec![1], backbone.core);
+    /// assert!(backbone.dead.is_empty());
+    /// ```
+    pub fn backbone(&mut self) -> Backbone {
+        let mut backbone = Backbone::default();
+        if self.rc() == 0 {
+            return backbone;
+        }
+        for (feature, (selected, deselected)) in (1..).zip(self.literal_distribution()) {
+            if deselected == 0 {
+                backbone.core.push(feature);
+            } else if selected == 0 {
+                backbone.dead.push(feature);
+            }
+        }
+        backbone
+    }
+
     /// Computes all dead and core features.
     /// A feature is a core feature iff there exists only the positiv occurence of that feature.
     /// A feature is a dead feature iff there exists only the negativ occurence of that feature.
@@ -46,3 +87,58 @@ impl Ddnnf {
         features.iter().any(|f| self.makes_query_unsat(f))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{build_ddnnf, distribute_building};
+
+    use super::*;
+
+    #[test]
+    fn backbone_matches_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert_eq!(
+            Backbone {
+                core: vec![1, 2, 6, 10, 15, 19, 25, 31, 40],
+                dead: vec![]
+            },
+            vp9.backbone()
+        );
+
+        let mut conditioned = vp9.condition(&[3, -7]);
+        let backbone = conditioned.backbone();
+        for feature in 1..=42 {
+            let core = conditioned.execute_query(&[-(feature as i32)]) == 0;
+            let dead = conditioned.execute_query(&[feature as i32]) == 0;
+            assert_eq!(core, backbone.core.contains(&feature));
+            assert_eq!(dead, backbone.dead.contains(&feature));
+        }
+        assert_eq!(conditioned.dead_features(), backbone.dead);
+
+        assert_eq!(Backbone::default(), vp9.condition(&[3, -3]).backbone());
+    }
+
+    #[test]
+    fn literals_without_models() {
+        // 1 ∧ (2 ∧ false ∨ ¬2), hence 2 occurs but is dead
+        let lines = [
+            "nnf 7 6 2",
+            "L 1",
+            "L 2",
+            "O 0 0",
+            "A 2 1 2",
+            "L -2",
+            "O 2 2 3 4",
+            "A 2 0 5",
+        ];
+        let mut ddnnf = distribute_building(lines.map(String::from).to_vec(), None, None);
+        assert!(!ddnnf.core.contains(&-2));
+        assert_eq!(
+            Backbone {
+                core: vec![1],
+                dead: vec![2]
+            },
+            ddnnf.backbone()
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::cmp::Reverse;
+
+use itertools::Itertools;
+use rug::Integer;
+
+use crate::Ddnnf;
+
+/// A repair suggestion for an invalid partial configuration, see [Ddnnf::correct]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correction {
+    /// The literals of the configuration that have to be dropped. Without them, the configuration is
+    /// satisfiable, but keeping any of them would still make it unsatisfiable.
+    pub literals: Vec<i32>,
+    /// Whether the configuration is satisfiable as well if we flip the literals instead of dropping them
+    pub flippable: bool,
+    /// The number of valid configurations that remain after dropping the literals
+    pub remaining: Integer,
+}
+
+impl Ddnnf {
+    /// Computes the smallest correction sets of the partial configuration, i.e. the sets with the fewest
+    /// literals whose removal makes the rest of the configuration satisfiable. Because no smaller set works,
+    /// each of them is minimal as well. Returns the corrections ranked as repair suggestions: the ones that
+    /// still work if the user flips the literals instead of dropping them come first, followed by the ones
+    /// that leave more valid configurations. A valid configuration needs a single empty correction, while
+    /// an unsatisfiable model can not be corrected at all.
+    ///
+    /// We try the sets of literals by increasing size and stop at the first size that corrects the
+    /// configuration. Hence, the effort grows exponentially with the number of literals that have to
+    /// change, which suits partial configurations with few conflicts. To bound the effort, we try sets
+    /// of at most max_size literals and return an error if none of them corrects the configuration.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let corrections = ddnnf.correct(&[-1, 2, 3, 4], 3).unwrap();
+    /// let literals: Vec<Vec<i32>> = corrections.iter().map(|c| c.literals.clone()).collect();
+    /// assert_eq!(vec![vec![-1, 2], vec![-1, 3]], literals);
+    /// assert!(corrections.iter().all(|c| c.flippable && c.remaining == 1));
+    ///
+    /// // a valid configuration needs no change
+    /// let corrections = ddnnf.correct(&[1, 2], 0).unwrap();
+    /// assert_eq!(1, corrections.len());
+    /// assert!(corrections[0].literals.is_empty());
+    ///
+    /// // the configuration needs at least two changes
+    /// assert!(ddnnf.correct(&[-1, 2, 3, 4], 1).is_err());
+    /// ```
+    pub fn correct(&self, config: &[i32], max_size: usize) -> Result<Vec<Correction>, String> {
+        let literals: Vec<i32> = config.iter().copied().unique().collect();
+        let mut found: Vec<Vec<usize>> = Vec::new();
+
+        if self.is_interaction_valid(&[]) {
+            for size in 0..=literals.len().min(max_size) {
+                for candidate in (0..literals.len()).combinations(size) {
+                    let rest: Vec<i32> = (0..literals.len())
+                        .filter(|i| !candidate.contains(i))
+                        .map(|i| literals[i])
+                        .collect();
+                    if self.is_interaction_valid(&rest) {
+                        found.push(candidate);
+                    }
+                }
+                if !found.is_empty() {
+                    break;
+                }
+            }
+            if found.is_empty() {
+                return Err(format!(
+                    "Correcting the configuration needs more than {max_size} changes"
+                ));
+            }
+        }
+
+        let mut corrections: Vec<Correction> = found
+            .into_iter()
+            .map(|correction| {
+                let rest: Vec<i32> = (0..literals.len())
+                    .filter(|i| !correction.contains(i))
+                    .map(|i| literals[i])
+                    .collect();
+                let flipped: Vec<i32> = rest
+                    .iter()
+                    .copied()
+                    .chain(correction.iter().map(|&i| -literals[i]))
+                    .collect();
+                Correction {
+                    literals: correction.iter().map(|&i| literals[i]).collect(),
+                    flippable: self.is_interaction_valid(&flipped),
+                    remaining: self.count_adaptive(&rest),
+                }
+            })
+            .collect();
+        corrections.sort_by_key(|correction| {
+            (!correction.flippable, Reverse(correction.remaining.clone()))
+        });
+        Ok(corrections)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use crate::ddnnf::anomalies::optimization::Direction;
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn corrections_are_minimal() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+
+        // a valid configuration with a few flipped literals
+        let (_, mut config) = vp9
+            .optimize(&Default::default(), Direction::Minimize)
+            .unwrap();
+        for index in [2, 7, 20] {
+            config[index] = -config[index];
+        }
+        config.truncate(25);
+
+        let corrections = vp9.correct(&config, 3).unwrap();
+        assert!(vp9
+            .correct(&config, corrections[0].literals.len() - 1)
+            .is_err());
+        assert!(!corrections.is_empty());
+        for (index, correction) in corrections.iter().enumerate() {
+            let rest: Vec<i32> = config
+                .iter()
+                .copied()
+                .filter(|l| !correction.literals.contains(l))
+                .collect();
+            assert!(vp9.is_interaction_valid(&rest));
+            assert_eq!(vp9.count_adaptive(&rest), correction.remaining);
+            for literal in correction.literals.iter() {
+                let mut kept = rest.clone();
+                kept.push(*literal);
+                assert!(!vp9.is_interaction_valid(&kept));
+            }
+
+            // all corrections have the same size and the flippable ones come first
+            assert_eq!(corrections[0].literals.len(), correction.literals.len());
+            if index > 0 {
+                let previous = &corrections[index - 1];
+                assert!(previous.flippable || !correction.flippable);
+                if previous.flippable == correction.flippable {
+                    assert!(previous.remaining >= correction.remaining);
+                }
+            }
+        }
+        assert!(corrections.iter().any(|correction| correction.flippable));
+        assert!(corrections[0].literals.len() <= 3);
+
+        // no set of fewer literals corrects the configuration
+        let size = corrections[0].literals.len();
+        for smaller in config.iter().copied().combinations(size - 1) {
+            let rest: Vec<i32> = config
+                .iter()
+                .copied()
+                .filter(|l| !smaller.contains(l))
+                .collect();
+            assert!(!vp9.is_interaction_valid(&rest));
+        }
+    }
+
+    #[test]
+    fn corrections_of_special_configurations() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+
+        let valid = ddnnf.correct(&[1, -2], 0).unwrap();
+        assert_eq!(1, valid.len());
+        assert!(valid[0].literals.is_empty() && valid[0].flippable);
+        assert_eq!(1, ddnnf.correct(&[], 0).unwrap().len());
+
+        // a contradiction is resolved by dropping either of its literals
+        let corrections = ddnnf.correct(&[4, -4, 4], 1).unwrap();
+        assert_eq!(2, corrections.len());
+        assert!(corrections.iter().all(|c| c.literals.len() == 1));
+
+        // flipping 2 is not enough, because 1 is core
+        let corrections = ddnnf.correct(&[-1, 2, 3], 3).unwrap();
+        assert_eq!(vec![-1, 2], corrections[0].literals);
+        assert!(corrections[0].flippable);
+        assert!(corrections.iter().all(|c| c.literals.contains(&-1)));
+
+        let unsat = ddnnf.condition(&[2, 3]);
+        assert!(unsat.correct(&[1], 0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{ddnnf::relations::Relation, Ddnnf};
+
+impl Ddnnf {
+    /// Computes the implications and exclusions that hold between the features of two sets,
+    /// e.g. between hardware and software features. The result contains Requires(a, b) and
+    /// Requires(b, a) for each implication in one of the two directions and Excludes(a, b) for
+    /// each pair that can not be selected together, where a is from set_a and b from set_b.
+    ///
+    /// Core and dead features are skipped, because they trivially take part in such a relation
+    /// with every other feature. For the remaining features, we do not check each pair on its own:
+    /// Propagating the selection of a feature yields all features it requires or excludes at once
+    /// (see [Ddnnf::propagate]). Hence, we need one pass per feature instead of one per pair.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::ddnnf::relations::Relation;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(vec![Relation::Excludes(2, 3)], ddnnf.cross_constraints(&[2], &[3, 4]));
+    /// // 1 is core and hence skipped
+    /// assert!(ddnnf.cross_constraints(&[1], &[2, 3, 4]).is_empty());
+    /// ```
+    pub fn cross_constraints(&self, set_a: &[u32], set_b: &[u32]) -> Vec<Relation> {
+        let decided = match self.propagate(&[]) {
+            Some(decided) => decided,
+            None => return Vec::new(),
+        };
+        let relevant = |set: &[u32]| -> Vec<u32> {
+            let mut features: Vec<u32> = set
+                .iter()
+                .copied()
+                .filter(|&f| !decided.contains(&(f as i32)) && !decided.contains(&-(f as i32)))
+                .collect();
+            features.sort_unstable();
+            features.dedup();
+            features
+        };
+        let (set_a, set_b) = (relevant(set_a), relevant(set_b));
+
+        let mut relations = Vec::new();
+        for &a in set_a.iter() {
+            let implied = self.propagate(&[a as i32]).unwrap_or_default();
+            for &b in set_b.iter().filter(|&&b| b != a) {
+                if implied.contains(&(b as i32)) {
+                    relations.push(Relation::Requires(a, b));
+                } else if implied.contains(&-(b as i32)) {
+                    relations.push(Relation::Excludes(a, b));
+                }
+            }
+        }
+        for &b in set_b.iter() {
+            let implied = self.propagate(&[b as i32]).unwrap_or_default();
+            for &a in set_a.iter().filter(|&&a| a != b) {
+                if implied.contains(&(a as i32)) {
+                    relations.push(Relation::Requires(b, a));
+                }
+            }
+        }
+        relations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn cross_constraints_match_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let set_a: Vec<u32> = (1..=20).collect();
+        let set_b: Vec<u32> = (21..=42).collect();
+        let relations = vp9.cross_constraints(&set_a, &set_b);
+        assert!(!relations.is_empty());
+
+        let core = vp9.core.clone();
+        for &a in set_a.iter() {
+            for &b in set_b.iter() {
+                let (a, b) = (a as i32, b as i32);
+                // core and dead features are skipped
+                if [a, -a, b, -b].iter().any(|l| core.contains(l)) {
+                    continue;
+                }
+                let requires = |from: i32, to: i32| Relation::Requires(from as u32, to as u32);
+                assert_eq!(
+                    vp9.execute_query(&[a, -b]) == 0,
+                    relations.contains(&requires(a, b))
+                );
+                assert_eq!(
+                    vp9.execute_query(&[b, -a]) == 0,
+                    relations.contains(&requires(b, a))
+                );
+                assert_eq!(
+                    vp9.execute_query(&[a, b]) == 0,
+                    relations.contains(&Relation::Excludes(a as u32, b as u32))
+                );
+            }
+        }
+
+        // a set has no relation to the empty set
+        assert!(vp9.cross_constraints(&set_a, &[]).is_empty());
+    }
+}
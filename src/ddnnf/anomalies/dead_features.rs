@@ -0,0 +1,197 @@
+use std::fmt;
+
+use crate::{Ddnnf, NodeType, NodeType::*};
+
+/// The changelog of [Ddnnf::without_dead_features]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeadFeatureLog {
+    /// The removed dead features in ascending order
+    pub removed: Vec<u32>,
+    /// The new number of each remaining feature of the original d-DNNF as pairs (old, new).
+    /// Features that keep their number are not listed.
+    pub renumbered: Vec<(u32, u32)>,
+}
+
+impl fmt::Display for DeadFeatureLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for feature in self.removed.iter() {
+            writeln!(f, "removed the dead feature {feature}")?;
+        }
+        for (old, new) in self.renumbered.iter() {
+            writeln!(f, "renumbered the feature {old} to {new}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Ddnnf {
+    /// The features that are deselected in each configuration in ascending order
+    pub fn dead_features(&self) -> Vec<u32> {
+        let mut dead: Vec<u32> = self
+            .core
+            .iter()
+            .filter(|feature| feature.is_negative())
+            .map(|feature| feature.unsigned_abs())
+            .collect();
+        dead.sort_unstable();
+        dead
+    }
+
+    /// Creates a copy of the d-DNNF without the dead features. Each dead feature gets projected out
+    /// and the remaining features get renumbered to close the gaps, which is recorded in the returned log.
+    /// The count stays the same, because each configuration deselects the dead features anyway.
+    ///
+    /// Like a conditioned d-DNNF, the copy can not be edited via clause updates. Annotations are not
+    /// carried over, because the renumbering changes the identity of the nodes. Feature names and
+    /// numeric features follow the renumbering.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// // 2 becomes dead, hence 3 and 4 move up
+    /// let conditioned = ddnnf.condition(&[-2]);
+    /// let (mut quarantined, log) = conditioned.without_dead_features();
+    ///
+    /// assert_eq!(vec![2], log.removed);
+    /// assert_eq!(vec![(3, 2), (4, 3)], log.renumbered);
+    /// assert_eq!(3, quarantined.number_of_variables);
+    /// assert_eq!(conditioned.rc(), quarantined.rc());
+    /// ```
+    pub fn without_dead_features(&self) -> (Ddnnf, DeadFeatureLog) {
+        let removed = self.dead_features();
+        let mut renumbering: Vec<Option<u32>> = vec![None; self.number_of_variables as usize + 1];
+        let mut log = DeadFeatureLog {
+            removed,
+            ..Default::default()
+        };
+        let mut next = 1;
+        for feature in 1..=self.number_of_variables {
+            if log.removed.binary_search(&feature).is_err() {
+                renumbering[feature as usize] = Some(next);
+                if feature != next {
+                    log.renumbered.push((feature, next));
+                }
+                next += 1;
+            }
+        }
+
+        let mut ntypes: Vec<NodeType> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let ntype = match &node.ntype {
+                Literal { literal } => match renumbering[literal.unsigned_abs() as usize] {
+                    Some(feature) => Literal {
+                        literal: feature as i32 * literal.signum(),
+                    },
+                    // dead features only occur negated and their deselection holds anyway
+                    None if literal.is_negative() => True,
+                    None => False,
+                },
+                ntype => ntype.clone(),
+            };
+            ntypes.push(ntype);
+        }
+
+        let mut quarantined = Ddnnf {
+            max_worker: self.max_worker,
+            max_digits: self.max_digits,
+            number_of_variables: next - 1,
+            ..Default::default()
+        };
+        // the values of dead variables are impossible anyway
+        quarantined.numeric_features = self
+            .numeric_features
+            .iter()
+            .filter_map(|feature| feature.renumbered(|var| renumbering[var as usize]))
+            .collect();
+        quarantined.feature_names = self
+            .feature_names
+            .iter()
+            .filter_map(|(&feature, name)| {
+                let renumbered = renumbering.get(feature as usize).copied().flatten();
+                renumbered.map(|feature| (feature, name.clone()))
+            })
+            .collect();
+        quarantined.replace_nodes(ntypes);
+        (quarantined, log)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ddnnf::numeric::NumericFeature;
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn removes_dead_features() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert!(vp9.dead_features().is_empty());
+        let (same, log) = vp9.without_dead_features();
+        assert_eq!(DeadFeatureLog::default(), log);
+        assert_eq!(42, same.number_of_variables);
+        assert_eq!(vp9.rc(), same.rc());
+
+        let mut conditioned = vp9.condition(&[-5, -41, 42]);
+        assert!(conditioned.rc() > 0);
+        let dead = conditioned.dead_features();
+        assert!(dead.contains(&5) && dead.contains(&41) && !dead.contains(&42));
+
+        conditioned.feature_names.insert(5, String::from("dead"));
+        conditioned.feature_names.insert(42, String::from("last"));
+        assert!(conditioned.add_numeric_feature(NumericFeature {
+            name: String::from("level"),
+            values: vec![(1, 5), (2, 42), (3, 41)],
+        }));
+        let (mut quarantined, log) = conditioned.without_dead_features();
+        assert_eq!(dead, log.removed);
+        assert_eq!(42 - dead.len() as u32, quarantined.number_of_variables);
+        assert!(quarantined.dead_features().is_empty());
+        assert_eq!(conditioned.rc(), quarantined.rc());
+        assert_eq!(
+            "removed the dead feature 5",
+            log.to_string().lines().next().unwrap()
+        );
+
+        // the cardinalities of the remaining features stay the same
+        for (old, new) in (1..=42).filter_map(|old| {
+            let new = log
+                .renumbered
+                .iter()
+                .find(|(renumbered, _)| *renumbered == old)
+                .map_or(old, |(_, new)| *new);
+            (!dead.contains(&old)).then_some((old as i32, new as i32))
+        }) {
+            assert_eq!(
+                conditioned.execute_query(&[old]),
+                quarantined.execute_query(&[new])
+            );
+            assert_eq!(
+                conditioned.execute_query(&[-old]),
+                quarantined.execute_query(&[-new])
+            );
+        }
+
+        assert_eq!(1, quarantined.feature_names.len());
+        assert_eq!(
+            "last",
+            quarantined.feature_name(quarantined.number_of_variables)
+        );
+
+        // the numeric features lose the values of dead variables and follow the renumbering
+        let level = quarantined.numeric_feature("level").unwrap();
+        assert_eq!(vec![(2, quarantined.number_of_variables)], level.values);
+        assert_eq!(
+            conditioned.value_counts("level", &[]),
+            quarantined.value_counts("level", &[]).map(|mut counts| {
+                counts.extend([(1, 0.into()), (3, 0.into())]);
+                counts.sort();
+                counts
+            })
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{Ddnnf, NodeType::*};
+
+impl Ddnnf {
+    /// Computes the decisions that the partial configuration implies, i.e. the literals that each
+    /// valid configuration with the partial configuration contains. Positive literals are implied
+    /// selections and negative ones implied deselections. The literals of the partial configuration
+    /// itself are left out and the result is sorted by the number of the features.
+    /// Returns None if the partial configuration is unsatisfiable.
+    ///
+    /// Instead of two queries per feature, we need a single pass over the nodes in each direction:
+    /// A literal occurs in a valid configuration iff its node contributes to the count under the
+    /// partial configuration (see [Ddnnf::contributing_nodes]). Because the d-DNNF is smooth, each
+    /// variable occurs in each configuration. Hence, a feature is decided iff only one of its literals
+    /// contributes.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(Some(vec![1]), ddnnf.propagate(&[]));
+    /// assert_eq!(Some(vec![1, -3]), ddnnf.propagate(&[2, 4]));
+    /// assert_eq!(None, ddnnf.propagate(&[2, 3]));
+    /// ```
+    pub fn propagate(&self, partial_config: &[i32]) -> Option<Vec<i32>> {
+        let contributing = self.contributing_nodes(partial_config);
+        if contributing.is_empty() {
+            return None;
+        }
+
+        let mut possible = vec![(false, false); self.number_of_variables as usize + 1];
+        for &index in contributing.iter() {
+            if let Literal { literal } = self.nodes[index].ntype {
+                let (selectable, deselectable) = &mut possible[literal.unsigned_abs() as usize];
+                if literal.is_positive() {
+                    *selectable = true;
+                } else {
+                    *deselectable = true;
+                }
+            }
+        }
+
+        let implied = (1..=self.number_of_variables as i32)
+            .filter_map(|feature| match possible[feature as usize] {
+                (true, false) => Some(feature),
+                (false, true) => Some(-feature),
+                _ => None,
+            })
+            .filter(|literal| !partial_config.contains(literal))
+            .collect();
+        Some(implied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn propagation_matches_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+
+        let mut satisfiable = 0;
+        for (ddnnf, partial_configs) in [
+            (
+                &mut vp9,
+                vec![vec![], vec![4], vec![-4, 9], vec![3, -7, 20]],
+            ),
+            (&mut auto1, vec![vec![], vec![-1], vec![1, 10, -200]]),
+        ] {
+            for partial_config in partial_configs {
+                let implied = match ddnnf.propagate(&partial_config) {
+                    Some(implied) => implied,
+                    None => {
+                        assert_eq!(0, ddnnf.execute_query(&partial_config));
+                        continue;
+                    }
+                };
+                satisfiable += 1;
+                // the first features suffice to keep the queries for auto1 fast
+                for feature in 1..=ddnnf.number_of_variables.min(300) as i32 {
+                    if partial_config.contains(&feature) || partial_config.contains(&-feature) {
+                        continue;
+                    }
+                    let mut selected = partial_config.clone();
+                    selected.push(feature);
+                    let mut deselected = partial_config.clone();
+                    deselected.push(-feature);
+                    let (with, without) = (
+                        ddnnf.execute_query(&selected),
+                        ddnnf.execute_query(&deselected),
+                    );
+                    assert_eq!(without == 0, implied.contains(&feature));
+                    assert_eq!(with == 0, implied.contains(&-feature));
+                }
+            }
+        }
+
+        assert!(satisfiable >= 5);
+        assert_eq!(None, vp9.propagate(&[4, -4]));
+        assert_eq!(None, vp9.propagate(&[-1]));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+
+use rug::Integer;
+
+use crate::{Ddnnf, NodeType::*};
+
+impl Ddnnf {
+    /// Computes the smallest number of selected features of a valid configuration under the assumptions,
+    /// i.e. the size of the smallest valid product, together with the number of configurations of that size.
+    /// Returns None if there is no valid configuration.
+    ///
+    /// Each node gets evaluated once in a hybrid of the (min, +) semiring and counting: And nodes add the
+    /// minima and multiply the counts of their children, while Or nodes take the smallest minimum and
+    /// add the counts of the children that reach it.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let (size, count) = ddnnf.min_cardinality(&[]).unwrap();
+    /// assert_eq!((2, 2), (size, count.to_u64().unwrap()));
+    /// let (size, count) = ddnnf.min_cardinality(&[4, -3]).unwrap();
+    /// assert_eq!((3, 1), (size, count.to_u64().unwrap()));
+    /// assert!(ddnnf.min_cardinality(&[2, 3]).is_none());
+    /// ```
+    pub fn min_cardinality(&self, assumptions: &[i32]) -> Option<(usize, Integer)> {
+        self.min_cardinality_values(assumptions).pop().flatten()
+    }
+
+    /// Enumerates up to limit valid configurations under the assumptions that select the fewest
+    /// features (see [Ddnnf::min_cardinality]). Each configuration is sorted by the number of the features.
+    ///
+    /// Each node keeps up to limit of its smallest partial configurations: Or nodes concatenate
+    /// the lists of the children that reach the minimum and And nodes combine the lists of their children.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let mut smallest = ddnnf.min_cardinality_configs(&[], 10);
+    /// smallest.sort();
+    /// assert_eq!(vec![vec![1, -2, 3, -4], vec![1, 2, -3, -4]], smallest);
+    /// assert_eq!(1, ddnnf.min_cardinality_configs(&[], 1).len());
+    /// ```
+    pub fn min_cardinality_configs(&self, assumptions: &[i32], limit: usize) -> Vec<Vec<i32>> {
+        let values = self.min_cardinality_values(assumptions);
+        if limit == 0 || !matches!(values.last(), Some(Some(_))) {
+            return Vec::new();
+        }
+
+        // the lists of a node are dropped after its last parent combined them
+        let last_use: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|node| node.parents.iter().copied().max().unwrap_or(0))
+            .collect();
+
+        let mut smallest: Vec<Vec<Vec<i32>>> = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.iter().enumerate() {
+            let configs = match (&values[index], &node.ntype) {
+                (None, _) => Vec::new(),
+                (Some(_), And { children }) => {
+                    children.iter().fold(vec![Vec::new()], |configs, &child| {
+                        let mut combined = Vec::new();
+                        'outer: for config in configs.iter() {
+                            for other in smallest[child].iter() {
+                                if combined.len() == limit {
+                                    break 'outer;
+                                }
+                                let mut merged = config.clone();
+                                merged.extend_from_slice(other);
+                                combined.push(merged);
+                            }
+                        }
+                        combined
+                    })
+                }
+                (Some((minimum, _)), Or { children }) => children
+                    .iter()
+                    .filter(|&&child| matches!(&values[child], Some((m, _)) if m == minimum))
+                    .flat_map(|&child| smallest[child].iter().cloned())
+                    .take(limit)
+                    .collect(),
+                (Some(_), Literal { literal }) => vec![vec![*literal]],
+                (Some(_), _) => vec![Vec::new()],
+            };
+            if let And { children } | Or { children } = &node.ntype {
+                for &child in children.iter().filter(|&&c| last_use[c] == index) {
+                    smallest[child] = Vec::new();
+                }
+            }
+            smallest.push(configs);
+        }
+
+        let mut configs = smallest.pop().unwrap();
+        for config in configs.iter_mut() {
+            config.sort_unstable_by_key(|literal| literal.abs());
+        }
+        configs
+    }
+
+    // Computes the smallest number of selected features of each node and how many of its
+    // configurations reach it. None marks nodes without configurations under the assumptions.
+    fn min_cardinality_values(&self, assumptions: &[i32]) -> Vec<Option<(usize, Integer)>> {
+        let excluded: HashSet<i32> = assumptions.iter().map(|literal| -literal).collect();
+
+        let mut values: Vec<Option<(usize, Integer)>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let value = match &node.ntype {
+                And { children } => {
+                    children
+                        .iter()
+                        .try_fold((0, Integer::from(1)), |(minimum, count), &child| {
+                            values[child]
+                                .as_ref()
+                                .map(|(m, c)| (minimum + m, count * c))
+                        })
+                }
+                Or { children } => {
+                    let mut best: Option<(usize, Integer)> = None;
+                    for (m, c) in children.iter().filter_map(|&child| values[child].as_ref()) {
+                        match best.as_mut() {
+                            Some((minimum, count)) if *m == *minimum => *count += c,
+                            Some((minimum, _)) if *m > *minimum => (),
+                            _ => best = Some((*m, c.clone())),
+                        }
+                    }
+                    best
+                }
+                Literal { literal } if excluded.contains(literal) => None,
+                Literal { literal } => Some((literal.is_positive() as usize, Integer::from(1))),
+                True => Some((0, Integer::from(1))),
+                False => None,
+            };
+            values.push(value);
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn min_cardinality_matches_brute_force() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+        let models = ddnnf.brute_force_enumerate().unwrap();
+        let selected = |model: &Vec<i32>| model.iter().filter(|l| l.is_positive()).count();
+
+        for assumptions in [vec![], vec![4], vec![-2, 3], vec![2, -2]] {
+            let valid: Vec<&Vec<i32>> = models
+                .iter()
+                .filter(|model| assumptions.iter().all(|l| model.contains(l)))
+                .collect();
+            let minimum = valid.iter().map(|model| selected(model)).min();
+            let smallest: Vec<Vec<i32>> = valid
+                .iter()
+                .filter(|model| Some(selected(model)) == minimum)
+                .map(|model| model.to_vec())
+                .collect();
+
+            assert_eq!(
+                minimum.map(|m| (m, Integer::from(smallest.len()))),
+                ddnnf.min_cardinality(&assumptions)
+            );
+            let mut configs = ddnnf.min_cardinality_configs(&assumptions, 100);
+            configs.sort();
+            let mut expected = smallest.clone();
+            expected.sort();
+            assert_eq!(expected, configs);
+        }
+    }
+
+    #[test]
+    fn min_cardinality_of_a_bigger_model() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let (size, count) = vp9.min_cardinality(&[]).unwrap();
+        assert_eq!(count, vp9.count_with_selection_size(&[], size..=size));
+        assert_eq!(0, vp9.count_with_selection_size(&[], ..size));
+
+        let configs = vp9.min_cardinality_configs(&[], usize::MAX);
+        assert_eq!(count, configs.len());
+        for config in configs.iter() {
+            assert_eq!(42, config.len());
+            assert_eq!(size, config.iter().filter(|l| l.is_positive()).count());
+            assert!(vp9.is_interaction_valid(config));
+        }
+
+        assert_eq!(
+            count.to_usize().unwrap().min(5),
+            vp9.min_cardinality_configs(&[], 5).len()
+        );
+        assert!(vp9.min_cardinality_configs(&[-1], 5).is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::{Ddnnf, NodeType::*};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Whether [Ddnnf::optimize] searches for the lowest or the highest objective value
+pub enum Direction {
+    Minimize,
+    Maximize,
+}
+
+impl Ddnnf {
+    /// Computes a valid configuration that minimizes or maximizes a linear objective over literals,
+    /// e.g. the sum of the costs of the selected features. Literals without a coefficient contribute 0.
+    /// Returns the optimal objective value and the configuration sorted by the number of the features,
+    /// or None if there is no valid configuration at all.
+    ///
+    /// The objective gets evaluated once per node in the max-plus semiring: And nodes add the values
+    /// of their children and Or nodes take the best child, which we remember for the traceback
+    /// from the root. If several configurations are optimal, the first child of each Or node wins.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::HashMap;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::anomalies::optimization::Direction;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let costs = HashMap::from([(2, 3.0), (3, 2.0), (4, 1.5)]);
+    /// assert_eq!(Some((2.0, vec![1, -2, 3, -4])), ddnnf.optimize(&costs, Direction::Minimize));
+    /// assert_eq!(Some((4.5, vec![1, 2, -3, 4])), ddnnf.optimize(&costs, Direction::Maximize));
+    /// ```
+    pub fn optimize(
+        &self,
+        objective: &HashMap<i32, f64>,
+        direction: Direction,
+    ) -> Option<(f64, Vec<i32>)> {
+        // we always maximize and negate the coefficients for minimization
+        let sign = match direction {
+            Direction::Minimize => -1.0,
+            Direction::Maximize => 1.0,
+        };
+
+        // the best value of each node (None if the node has no configuration) and the best child of Or nodes
+        let mut values: Vec<Option<f64>> = Vec::with_capacity(self.nodes.len());
+        let mut best_child = vec![0; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            let value = if node.count == 0 {
+                None
+            } else {
+                match &node.ntype {
+                    And { children } => children
+                        .iter()
+                        .try_fold(0.0, |sum, &child| values[child].map(|value| sum + value)),
+                    Or { children } => {
+                        let mut best: Option<f64> = None;
+                        for &child in children {
+                            if let Some(value) = values[child] {
+                                if !best.is_some_and(|best| value <= best) {
+                                    best = Some(value);
+                                    best_child[index] = child;
+                                }
+                            }
+                        }
+                        best
+                    }
+                    Literal { literal } => {
+                        Some(sign * objective.get(literal).copied().unwrap_or(0.0))
+                    }
+                    True => Some(0.0),
+                    False => None,
+                }
+            };
+            values.push(value);
+        }
+
+        let root = self.nodes.len().checked_sub(1)?;
+        let value = values[root]?;
+
+        let mut config = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            match &self.nodes[node].ntype {
+                And { children } => stack.extend(children),
+                Or { .. } => stack.push(best_child[node]),
+                Literal { literal } => config.push(*literal),
+                _ => (),
+            }
+        }
+        config.sort_unstable_by_key(|literal| literal.abs());
+        Some((sign * value, config))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn optima_match_brute_force() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+        let objective: HashMap<i32, f64> = (1..=4)
+            .flat_map(|f| [(f, (f * 7 % 5) as f64 - 2.0), (-f, (f * 3 % 4) as f64)])
+            .collect();
+        let value = |config: &Vec<i32>| config.iter().map(|l| objective[l]).sum::<f64>();
+
+        let models = ddnnf.brute_force_enumerate().unwrap();
+        let minimum = models.iter().map(value).min_by(f64::total_cmp).unwrap();
+        let maximum = models.iter().map(value).max_by(f64::total_cmp).unwrap();
+
+        for (direction, expected) in [
+            (Direction::Minimize, minimum),
+            (Direction::Maximize, maximum),
+        ] {
+            let (optimum, config) = ddnnf.optimize(&objective, direction).unwrap();
+            assert_eq!(expected, optimum);
+            assert_eq!(expected, value(&config));
+            assert!(models.contains(&config));
+        }
+    }
+
+    #[test]
+    fn optima_of_a_bigger_model() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let objective: HashMap<i32, f64> = (1..=42)
+            .flat_map(|f| [(f, (f * 7 % 11) as f64), (-f, (f * 5 % 13) as f64 / 2.0)])
+            .collect();
+
+        // the best configuration of top_k is optimal as well
+        let (maximum, config) = vp9.optimize(&objective, Direction::Maximize).unwrap();
+        assert_eq!(vp9.top_k(1, &objective)[0].0, maximum);
+        assert_eq!(42, config.len());
+        assert!(vp9.is_interaction_valid(&config));
+
+        let negated: HashMap<i32, f64> = objective.iter().map(|(&l, &c)| (l, -c)).collect();
+        let (minimum, config) = vp9.optimize(&objective, Direction::Minimize).unwrap();
+        assert_eq!(-vp9.top_k(1, &negated)[0].0, minimum);
+        assert_eq!(minimum, config.iter().map(|l| objective[l]).sum::<f64>());
+        assert!(minimum < maximum);
+
+        // without an objective, each valid configuration is optimal
+        let (optimum, config) = vp9.optimize(&HashMap::new(), Direction::Minimize).unwrap();
+        assert_eq!(0.0, optimum);
+        assert!(vp9.is_interaction_valid(&config));
+    }
+}
@@ -0,0 +1,281 @@
+use std::io::{self, BufRead, Read, Write};
+
+use super::variable_order::VariableOrder;
+
+/// Complete configurations stored as bit-packed rows. Bit i of a row is set iff the i-th variable
+/// of the variable order is selected. Each row consists of the same number of u64 words.
+/// Compared to lists of literals, this needs one bit instead of 32 bits per variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedConfigs {
+    variables: Vec<u32>,
+    // the position of each variable in the variable order (usize::MAX for missing ones)
+    positions: Vec<usize>,
+    words_per_config: usize,
+    words: Vec<u64>,
+}
+
+impl PackedConfigs {
+    /// Creates an empty set of rows for configurations over the variables 1 to number_of_variables.
+    /// The variables are arranged like the variable order determines.
+    pub fn new(number_of_variables: u32, variable_order: &VariableOrder) -> Self {
+        let mut variables: Vec<i32> = (1..=number_of_variables as i32).collect();
+        variable_order.sort(&mut variables);
+        Self::with_variables(variables.into_iter().map(|v| v as u32).collect())
+    }
+
+    fn with_variables(variables: Vec<u32>) -> Self {
+        let max_variable = variables.iter().copied().max().unwrap_or(0) as usize;
+        let mut positions = vec![usize::MAX; max_variable + 1];
+        for (position, &variable) in variables.iter().enumerate() {
+            positions[variable as usize] = position;
+        }
+        Self {
+            words_per_config: variables.len().div_ceil(64),
+            variables,
+            positions,
+            words: Vec::new(),
+        }
+    }
+
+    /// Packs the configurations. Literals of variables that are not part of the rows are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::ddnnf::anomalies::packed_configs::PackedConfigs;
+    /// use ddnnf_lib::ddnnf::anomalies::variable_order::VariableOrder;
+    ///
+    /// let configs = vec![vec![1, -2, 3, -4], vec![-1, 2, -3, 4]];
+    /// let packed = PackedConfigs::from_configs(4, &VariableOrder::model(), configs.iter());
+    ///
+    /// assert_eq!(configs.len(), packed.len());
+    /// assert_eq!(configs, packed.iter().collect::<Vec<Vec<i32>>>());
+    /// ```
+    pub fn from_configs<'a>(
+        number_of_variables: u32,
+        variable_order: &VariableOrder,
+        configs: impl IntoIterator<Item = &'a Vec<i32>>,
+    ) -> Self {
+        let mut packed = Self::new(number_of_variables, variable_order);
+        for config in configs {
+            packed.push(config);
+        }
+        packed
+    }
+
+    /// Appends a configuration as new row. Unset variables are treated as deselected.
+    pub fn push(&mut self, config: &[i32]) {
+        let offset = self.words.len();
+        self.words.resize(offset + self.words_per_config, 0);
+        for &literal in config.iter().filter(|&&literal| literal > 0) {
+            if let Some(&position) = self.positions.get(literal as usize) {
+                if position != usize::MAX {
+                    self.words[offset + position / 64] |= 1 << (position % 64);
+                }
+            }
+        }
+    }
+
+    /// The variables in the order of the bits of each row
+    pub fn variables(&self) -> &[u32] {
+        &self.variables
+    }
+
+    /// The number of configurations
+    pub fn len(&self) -> usize {
+        self.words
+            .len()
+            .checked_div(self.words_per_config)
+            .unwrap_or(0)
+    }
+
+    /// Checks whether there is no configuration
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The words of the row with the index
+    pub fn row(&self, index: usize) -> &[u64] {
+        &self.words[index * self.words_per_config..(index + 1) * self.words_per_config]
+    }
+
+    /// Unpacks the row with the index to a list of literals in the variable order
+    pub fn unpack(&self, index: usize) -> Vec<i32> {
+        let row = self.row(index);
+        self.variables
+            .iter()
+            .enumerate()
+            .map(|(position, &variable)| {
+                if row[position / 64] & (1 << (position % 64)) != 0 {
+                    variable as i32
+                } else {
+                    -(variable as i32)
+                }
+            })
+            .collect()
+    }
+
+    /// Iterates over the unpacked configurations
+    pub fn iter(&self) -> impl Iterator<Item = Vec<i32>> + '_ {
+        (0..self.len()).map(|index| self.unpack(index))
+    }
+
+    /// Writes the rows in a binary format. A text header states the number of variables and
+    /// configurations ('p VARIABLES CONFIGURATIONS') followed by the variable order
+    /// ('o VARIABLES 0'). Afterwards, the words of the rows follow in little endian.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "p {} {}", self.variables.len(), self.len())?;
+        write!(writer, "o")?;
+        for variable in self.variables.iter() {
+            write!(writer, " {variable}")?;
+        }
+        writeln!(writer, " 0")?;
+        for word in self.words.iter() {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads rows that were written by [PackedConfigs::write_to]
+    pub fn read_from(reader: &mut impl BufRead) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut line = String::new();
+
+        reader.read_line(&mut line)?;
+        let header: Vec<usize> = match line.trim_end().strip_prefix("p ") {
+            Some(numbers) => numbers
+                .split_whitespace()
+                .map(|number| number.parse().map_err(|_| invalid("invalid header")))
+                .collect::<io::Result<_>>()?,
+            None => return Err(invalid("missing header")),
+        };
+        let [number_of_variables, number_of_configs] = header[..] else {
+            return Err(invalid("invalid header"));
+        };
+
+        line.clear();
+        reader.read_line(&mut line)?;
+        let mut variables: Vec<u32> = match line.trim_end().strip_prefix("o ") {
+            Some(numbers) => numbers
+                .split_whitespace()
+                .map(|number| {
+                    number
+                        .parse()
+                        .map_err(|_| invalid("invalid variable order"))
+                })
+                .collect::<io::Result<_>>()?,
+            None => return Err(invalid("missing variable order")),
+        };
+        if variables.pop() != Some(0) || variables.len() != number_of_variables {
+            return Err(invalid("invalid variable order"));
+        }
+        // the order is a permutation of the variables 1 to number_of_variables
+        let mut seen = vec![false; number_of_variables + 1];
+        for &variable in variables.iter() {
+            let variable = variable as usize;
+            if variable == 0 || variable > number_of_variables || seen[variable] {
+                return Err(invalid("invalid variable order"));
+            }
+            seen[variable] = true;
+        }
+
+        let mut packed = Self::with_variables(variables);
+        let number_of_bytes = packed
+            .words_per_config
+            .checked_mul(number_of_configs)
+            .and_then(|words| words.checked_mul(8))
+            .ok_or_else(|| invalid("too many configurations"))?;
+        // the header is untrusted, hence the rows only take the memory of the data that is actually there
+        let mut bytes = Vec::new();
+        reader
+            .take(number_of_bytes as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != number_of_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the rows are incomplete",
+            ));
+        }
+        packed.words = bytes
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        Ok(packed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{ddnnf::anomalies::config_creation::EnumOptions, parser::build_ddnnf, Ddnnf};
+
+    #[test]
+    fn packing_roundtrip() {
+        let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+        let configs = auto1
+            .enumerate(&[], EnumOptions::with_limit(20))
+            .unwrap()
+            .collect_vec();
+        let packed = PackedConfigs::from_configs(2513, &VariableOrder::model(), configs.iter());
+        assert_eq!(20, packed.len());
+        assert_eq!(2513_usize.div_ceil(64), packed.row(0).len());
+        assert_eq!(configs, packed.iter().collect_vec());
+
+        let mut bytes = Vec::new();
+        packed.write_to(&mut bytes).unwrap();
+        let read = PackedConfigs::read_from(&mut BufReader::new(&bytes[..])).unwrap();
+        assert_eq!(packed, read);
+
+        assert!(PackedConfigs::read_from(&mut BufReader::new(&bytes[..bytes.len() - 1])).is_err());
+        assert!(PackedConfigs::read_from(&mut BufReader::new("p 1\no 1 0\n".as_bytes())).is_err());
+    }
+
+    #[test]
+    fn corrupted_headers() {
+        let read = |input: &str| PackedConfigs::read_from(&mut BufReader::new(input.as_bytes()));
+        let kind = |input: &str| read(input).unwrap_err().kind();
+
+        // neither huge variables nor huge amounts of configurations get allocated up front
+        assert_eq!(io::ErrorKind::InvalidData, kind("p 1 0\no 4294967295 0\n"));
+        assert_eq!(
+            io::ErrorKind::UnexpectedEof,
+            kind(&format!("p 1 {}\no 1 0\n", usize::MAX / 8))
+        );
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            kind(&format!(
+                "p 65 {}\no {} 0\n",
+                usize::MAX,
+                (1..=65).join(" ")
+            ))
+        );
+
+        // the order has to be a permutation of the variables
+        assert_eq!(io::ErrorKind::InvalidData, kind("p 2 0\no 1 1 0\n"));
+        assert_eq!(io::ErrorKind::InvalidData, kind("p 2 0\no 1 3 0\n"));
+        assert_eq!(io::ErrorKind::InvalidData, kind("p 1 0\no 0 0\n"));
+        assert_eq!(&[2, 1], read("p 2 0\no 2 1 0\n").unwrap().variables());
+    }
+
+    #[test]
+    fn packed_enumeration() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let options = EnumOptions {
+            variable_order: VariableOrder::custom(vec![3, 1]).unwrap(),
+            ..Default::default()
+        };
+        let configs = ddnnf.enumerate(&[], options.clone()).unwrap().collect_vec();
+        let packed = ddnnf.enumerate(&[], options).unwrap().packed();
+
+        assert_eq!(&[3, 1, 2, 4], packed.variables());
+        assert_eq!(configs, packed.iter().collect_vec());
+
+        let empty = PackedConfigs::new(0, &VariableOrder::model());
+        assert!(empty.is_empty());
+        assert_eq!(0, empty.len());
+    }
+}
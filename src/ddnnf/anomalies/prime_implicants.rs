@@ -0,0 +1,209 @@
+use rug::Integer;
+
+use super::config_creation::EnumOptions;
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Checks whether the partial configuration guarantees validity, i.e. each of its completions is
+    /// a valid configuration. That is the case iff its count equals the number of its completions.
+    /// Contradicting partial configurations are no implicants.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert!(ddnnf.is_implicant(&[1, 2, -3]));
+    /// assert!(ddnnf.is_implicant(&[1, 2, -3, 4]));
+    /// assert!(!ddnnf.is_implicant(&[1, 2]));
+    /// assert!(!ddnnf.is_implicant(&[1, 2, -3, -1]));
+    /// ```
+    pub fn is_implicant(&self, literals: &[i32]) -> bool {
+        let mut variables: Vec<u32> = literals.iter().map(|l| l.unsigned_abs()).collect();
+        variables.sort_unstable();
+        variables.dedup();
+        let mut distinct = literals.to_vec();
+        distinct.sort_unstable();
+        distinct.dedup();
+        if distinct.len() != variables.len()
+            || variables.first() == Some(&0)
+            || variables
+                .last()
+                .is_some_and(|&v| v > self.number_of_variables)
+        {
+            return false;
+        }
+
+        let completions = Integer::from(1) << (self.number_of_variables as usize - variables.len());
+        self.count_adaptive(literals) == completions
+    }
+
+    /// Checks whether the partial configuration is a prime implicant, i.e. an implicant
+    /// (see [Ddnnf::is_implicant]) that stops to be one if we remove any of its literals.
+    pub fn is_prime_implicant(&self, literals: &[i32]) -> bool {
+        self.is_implicant(literals)
+            && (0..literals.len()).all(|index| {
+                let mut reduced = literals.to_vec();
+                reduced.remove(index);
+                !self.is_implicant(&reduced)
+            })
+    }
+
+    /// Reduces an implicant, e.g. a valid complete configuration, to a prime implicant by removing
+    /// literals as long as the remaining literals still guarantee validity. The literals get
+    /// tried from the last to the first, hence earlier literals tend to stay.
+    /// Returns None if the literals are no implicant in the first place.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(Some(vec![1, -2, 3]), ddnnf.prime_implicant(&[1, -2, 3, -4]));
+    /// assert_eq!(None, ddnnf.prime_implicant(&[1, 2, 3, -4]));
+    /// ```
+    pub fn prime_implicant(&self, implicant: &[i32]) -> Option<Vec<i32>> {
+        if !self.is_implicant(implicant) {
+            return None;
+        }
+
+        let mut prime = implicant.to_vec();
+        for index in (0..prime.len()).rev() {
+            let literal = prime.remove(index);
+            if !self.is_implicant(&prime) {
+                prime.insert(index, literal);
+            }
+        }
+        Some(prime)
+    }
+
+    /// Computes prime implicants, i.e. minimal partial configurations that guarantee validity
+    /// (see [Ddnnf::is_prime_implicant]), and stops after the first limit ones.
+    /// We go through the valid configurations in their enumeration order and reduce each one
+    /// that is not yet covered by a prime implicant via [Ddnnf::prime_implicant].
+    /// Without a limit, the result covers all valid configurations, but it may miss prime implicants
+    /// that are not needed for the cover. Each prime implicant is sorted by the number of the features.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let primes = ddnnf.prime_implicants(None);
+    /// assert_eq!(2, primes.len());
+    /// assert!(primes.contains(&vec![1, 2, -3]) && primes.contains(&vec![1, -2, 3]));
+    /// assert_eq!(1, ddnnf.prime_implicants(Some(1)).len());
+    /// ```
+    pub fn prime_implicants(&self, limit: Option<usize>) -> Vec<Vec<i32>> {
+        let mut primes: Vec<Vec<i32>> = Vec::new();
+        if limit == Some(0) {
+            return primes;
+        }
+
+        // the enumeration needs a mutable d-DNNF while we count on the original one
+        let mut enumerating = self.clone();
+        let options = EnumOptions {
+            start: Some(0),
+            ..Default::default()
+        };
+        let configs = match enumerating.enumerate(&[], options) {
+            Some(configs) => configs,
+            None => return primes,
+        };
+
+        for mut config in configs {
+            if primes
+                .iter()
+                .any(|prime| prime.iter().all(|literal| config.contains(literal)))
+            {
+                continue;
+            }
+
+            config.sort_unstable_by_key(|literal| literal.abs());
+            primes.push(self.prime_implicant(&config).unwrap());
+            if Some(primes.len()) == limit {
+                break;
+            }
+        }
+        primes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn prime_implicants_match_brute_force() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+
+        // each variable is either positive, negative, or absent
+        let terms = (1..=4)
+            .map(|f| vec![Some(f), Some(-f), None])
+            .multi_cartesian_product()
+            .map(|term| term.into_iter().flatten().collect::<Vec<i32>>());
+        let models = ddnnf.brute_force_enumerate().unwrap();
+        let implicant = |term: &[i32]| {
+            models
+                .iter()
+                .filter(|model| term.iter().all(|l| model.contains(l)))
+                .count()
+                == 1 << (4 - term.len())
+        };
+        let all_primes: Vec<Vec<i32>> = terms
+            .filter(|term| {
+                implicant(term)
+                    && (0..term.len()).all(|index| {
+                        let mut reduced = term.clone();
+                        reduced.remove(index);
+                        !implicant(&reduced)
+                    })
+            })
+            .collect();
+        for term in all_primes.iter() {
+            assert!(ddnnf.is_prime_implicant(term));
+        }
+        assert!(!all_primes.is_empty());
+
+        let primes = ddnnf.prime_implicants(None);
+        for prime in primes.iter() {
+            assert!(all_primes.contains(prime));
+        }
+        for model in models.iter() {
+            assert!(primes
+                .iter()
+                .any(|prime| prime.iter().all(|l| model.contains(l))));
+        }
+    }
+
+    #[test]
+    fn first_prime_implicants_of_a_bigger_model() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+
+        let primes = vp9.prime_implicants(Some(10));
+        assert_eq!(10, primes.len());
+        assert!(primes.iter().all_unique());
+        for prime in primes.iter() {
+            assert!(vp9.is_prime_implicant(prime));
+            assert!(prime.len() < 42);
+        }
+
+        assert!(!vp9.is_implicant(&[]));
+        assert!(vp9.prime_implicant(&[1, -1]).is_none());
+        assert!(vp9.prime_implicants(Some(0)).is_empty());
+    }
+}
@@ -1,5 +1,10 @@
+use bitvec::prelude::*;
+
 use crate::{Ddnnf, NodeType::*};
 
+/// The marking of the nodes that is used by 'sat_propagate'. It uses one bit per node.
+pub type SatMark = BitVec;
+
 impl Ddnnf {
     /// Computes if a node is satisfiable with the marking algorithm:
     /// For each feature of the query, we search for its complementary literal.
@@ -13,23 +18,24 @@ impl Ddnnf {
     /// Vice versa the query is satisfiable.
     #[inline]
     pub fn sat(&mut self, features: &[i32]) -> bool {
-        self.sat_propagate(features, &mut vec![false; self.nodes.len()], None)
+        self.sat_propagate(features, &mut new_sat_mark_state(self.nodes.len()), None)
     }
 
     /// Does the exact same as 'sat' with the difference of choosing the marking Vec by ourself.
     /// That allows reusing that vector and therefore enabeling an efficient method to do decision propogation.
-    /// If wanted, one can supply an marking Vec<bool>, that can be reused in following method calls to propagate satisfiability.
+    /// If wanted, one can supply an marking SatMark, that can be reused in following method calls to propagate satisfiability.
     /// The root_index does not have to be the root of the DAG. Instead it can be any node. If 'None' is supplied we use the root of the DAG.
     #[inline]
     pub fn sat_propagate(
         &self,
         features: &[i32],
-        mark: &mut Vec<bool>,
+        mark: &mut SatMark,
         root_index: Option<usize>,
     ) -> bool {
         let root_index = root_index.unwrap_or(self.nodes.len() - 1);
 
-        if features.iter().any(|f| self.makes_query_unsat(f)) {
+        // without any model, there is nothing left to propagate
+        if self.nodes[root_index].count == 0 || features.iter().any(|f| self.makes_query_unsat(f)) {
             return false;
         }
 
@@ -48,38 +54,115 @@ impl Ddnnf {
         !mark[root_index]
     }
 
+    /// Checks whether there is a configuration that contains all literals of the interaction.
+    /// In contrast to 'sat', this does not need a mutable reference. To check many interactions
+    /// that share prefixes, an [InteractionChecker] avoids repeating the propagation of the prefixes.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert!(ddnnf.is_interaction_valid(&[2, -3]));
+    /// assert!(!ddnnf.is_interaction_valid(&[2, 3]));
+    /// ```
+    pub fn is_interaction_valid(&self, interaction: &[i32]) -> bool {
+        self.sat_propagate(interaction, &mut new_sat_mark_state(self.nodes.len()), None)
+    }
+
+    /// Creates an [InteractionChecker] for this d-DNNF
+    pub fn interaction_checker(&self) -> InteractionChecker<'_> {
+        InteractionChecker {
+            ddnnf: self,
+            prefix: Vec::new(),
+            marks: Vec::new(),
+            valid: Vec::new(),
+        }
+    }
+
     // marks a node and decides whether we have to continue the marking with its parent nodes
     #[inline]
-    fn propagate_mark(&self, index: usize, mark: &mut Vec<bool>) {
-        // if the node is already marked, we looked at its path and can stop
-        if mark[index] {
-            return;
-        }
+    fn propagate_mark(&self, index: usize, mark: &mut SatMark) {
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            // if the node is already marked, we looked at its path and can stop
+            if mark[index] {
+                continue;
+            }
 
-        if let Or { children } = &self.nodes[index].ntype {
-            // An Or node is only unsatisfiable if all of its children are either marked
-            // or have an count of zero (that handle False nodes).
-            if !children
-                .iter()
-                .all(|&c| mark[c] || self.nodes[c].count == 0)
-            {
-                return;
+            if let Or { children } = &self.nodes[index].ntype {
+                // An Or node is only unsatisfiable if all of its children are either marked
+                // or have an count of zero (that handle False nodes).
+                if !children
+                    .iter()
+                    .all(|&c| mark[c] || self.nodes[c].count == 0)
+                {
+                    continue;
+                }
             }
-        }
 
-        mark[index] = true;
-        // check the marking for all parents
-        self.nodes[index]
-            .parents
-            .iter()
-            .for_each(|&p| self.propagate_mark(p, mark))
+            mark.set(index, true);
+            // check the marking for all parents
+            stack.extend(self.nodes[index].parents.iter().rev());
+        }
     }
 }
 
 /// Creates a new marking vector that can wrapped be used as 'mark' parameter for 'sat_propagate'.
 #[inline]
-pub fn new_sat_mark_state(number_of_nodes: usize) -> Vec<bool> {
-    vec![false; number_of_nodes]
+pub fn new_sat_mark_state(number_of_nodes: usize) -> SatMark {
+    BitVec::repeat(false, number_of_nodes)
+}
+
+/// Checks the validity of interactions and memorizes the marking of the last checked
+/// interaction for each of its prefixes. If the next interaction starts with the same literals,
+/// only the remaining literals have to be propagated. A prefix that is unsatisfiable makes each
+/// interaction that starts with it unsatisfiable without any propagation.
+#[derive(Debug, Clone)]
+pub struct InteractionChecker<'a> {
+    ddnnf: &'a Ddnnf,
+    prefix: Vec<i32>,
+    // marks[i] and valid[i] describe the prefix that ends at index i
+    marks: Vec<SatMark>,
+    valid: Vec<bool>,
+}
+
+impl<'a> InteractionChecker<'a> {
+    /// Checks whether there is a configuration that contains all literals of the interaction.
+    /// The result is the same as of [Ddnnf::is_interaction_valid].
+    pub fn is_valid(&mut self, interaction: &[i32]) -> bool {
+        let common = self
+            .prefix
+            .iter()
+            .zip(interaction)
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.prefix.truncate(common);
+        self.marks.truncate(common);
+        self.valid.truncate(common);
+
+        for &literal in &interaction[common..] {
+            if self.valid.last() == Some(&false) {
+                return false;
+            }
+            let mut mark = match self.marks.last() {
+                Some(mark) => mark.clone(),
+                None => new_sat_mark_state(self.ddnnf.nodes.len()),
+            };
+            let valid = self.ddnnf.sat_propagate(&[literal], &mut mark, None);
+            self.prefix.push(literal);
+            self.marks.push(mark);
+            self.valid.push(valid);
+        }
+
+        match self.valid.last() {
+            Some(&valid) => valid,
+            None => self.ddnnf.is_interaction_valid(&[]),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -88,6 +171,7 @@ mod test {
 
     use super::*;
 
+    #[cfg(feature = "sampling")]
     #[test]
     fn sat_urs() {
         let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
@@ -110,6 +194,31 @@ mod test {
         }
     }
 
+    #[test]
+    fn interaction_validity() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let literals: Vec<i32> = (1..=42).flat_map(|f| [f, -f]).collect();
+
+        let vp9_ref = vp9.clone();
+        let mut checker = vp9_ref.interaction_checker();
+        for a in literals.iter().copied() {
+            for b in literals.iter().copied() {
+                for c in [3, -3, 20] {
+                    let interaction = [a, b, c];
+                    let expected = vp9.execute_query(&interaction) > 0;
+                    assert_eq!(expected, vp9_ref.is_interaction_valid(&interaction));
+                    assert_eq!(expected, checker.is_valid(&interaction));
+                    assert_eq!(expected, checker.is_valid(&interaction));
+                }
+            }
+        }
+        assert!(checker.is_valid(&[]));
+
+        let contradiction = vp9.condition(&[4, -4]);
+        assert!(!contradiction.is_interaction_valid(&[]));
+        assert!(!contradiction.interaction_checker().is_valid(&[]));
+    }
+
     #[test]
     fn sat_card_of_features() {
         let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
@@ -0,0 +1,348 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use super::{
+    config_creation::EnumOptions, packed_configs::PackedConfigs, variable_order::VariableOrder,
+};
+use crate::{parser::util::format_vec, Ddnnf};
+
+/// The name of the manifest within the directory of a sharded enumeration
+pub const MANIFEST_FILE: &str = "manifest.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The format of the shards of [Ddnnf::enumerate_to_dir]
+pub enum ShardFormat {
+    /// One configuration per line as list of literals
+    Text,
+    /// Bit-packed rows as written by [PackedConfigs::write_to], which needs one bit per variable
+    Packed,
+}
+
+impl ShardFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ShardFormat::Text => "csv",
+            ShardFormat::Packed => "bin",
+        }
+    }
+}
+
+impl fmt::Display for ShardFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShardFormat::Text => write!(f, "text"),
+            ShardFormat::Packed => write!(f, "packed"),
+        }
+    }
+}
+
+impl FromStr for ShardFormat {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ShardFormat::Text),
+            "packed" => Ok(ShardFormat::Packed),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown shard format {s}"),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A completely written shard of a sharded enumeration
+pub struct Shard {
+    /// The file name of the shard within the directory
+    pub file: String,
+    /// The index of the first configuration of the shard
+    pub start: usize,
+    /// The number of configurations in the shard
+    pub configs: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Describes a sharded enumeration. The manifest lists the shards in the order of the enumeration.
+/// A shard only gets listed after it is written completely.
+pub struct ShardManifest {
+    /// The number of configurations of the d-DNNF
+    pub total: usize,
+    /// The maximum number of configurations per shard
+    pub shard_size: usize,
+    pub format: ShardFormat,
+    pub shards: Vec<Shard>,
+}
+
+impl ShardManifest {
+    /// The number of configurations of all completely written shards
+    pub fn enumerated(&self) -> usize {
+        self.shards.iter().map(|shard| shard.configs).sum()
+    }
+
+    /// Checks whether each configuration is part of a shard
+    pub fn is_complete(&self) -> bool {
+        self.enumerated() == self.total
+    }
+
+    /// Reads the manifest of the directory. The first lines state the setup of the enumeration
+    /// ('total TOTAL', 'shard_size SIZE', and 'format FORMAT'). Each further line describes a shard
+    /// ('shard FILE START CONFIGS').
+    pub fn read(dir: &Path) -> io::Result<ShardManifest> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid line in manifest: {line}"),
+            )
+        };
+        let reader = BufReader::new(File::open(dir.join(MANIFEST_FILE))?);
+        let mut total = None;
+        let mut shard_size = None;
+        let mut format = None;
+        let mut shards = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                ["total", value] => total = Some(value.parse().map_err(|_| invalid(&line))?),
+                ["shard_size", value] => {
+                    shard_size = Some(value.parse().map_err(|_| invalid(&line))?)
+                }
+                ["format", value] => format = Some(value.parse()?),
+                ["shard", file, start, configs] => shards.push(Shard {
+                    file: file.to_string(),
+                    start: start.parse().map_err(|_| invalid(&line))?,
+                    configs: configs.parse().map_err(|_| invalid(&line))?,
+                }),
+                [] => (),
+                _ => return Err(invalid(&line)),
+            }
+        }
+
+        match (total, shard_size, format) {
+            (Some(total), Some(shard_size), Some(format)) => Ok(ShardManifest {
+                total,
+                shard_size,
+                format,
+                shards,
+            }),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "the manifest misses the setup of the enumeration",
+            )),
+        }
+    }
+}
+
+impl Ddnnf {
+    /// Enumerates all configurations into shards of at most shard_size configurations in the directory
+    /// and returns the manifest, which is saved as [MANIFEST_FILE] in the directory as well.
+    ///
+    /// If the directory already contains the manifest of an interrupted enumeration, the enumeration
+    /// resumes after the last completely written shard. The manifest has to match the number of configurations,
+    /// the shard size, and the format. Each shard gets written to a temporary file first and renamed afterwards. Hence,
+    /// an interruption leaves neither a partial shard nor a manifest entry of a missing shard behind.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::anomalies::sharded_enumeration::ShardFormat;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let manifest = ddnnf.enumerate_to_dir(dir.path(), 3, ShardFormat::Text).unwrap();
+    /// assert!(manifest.is_complete());
+    /// // the 4 configurations need two shards
+    /// assert_eq!(vec![3, 1], manifest.shards.iter().map(|s| s.configs).collect::<Vec<_>>());
+    /// ```
+    pub fn enumerate_to_dir(
+        &mut self,
+        dir: &Path,
+        shard_size: usize,
+        format: ShardFormat,
+    ) -> io::Result<ShardManifest> {
+        if shard_size == 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "a shard has to contain at least one configuration",
+            ));
+        }
+        let total = self.rc().to_usize().ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                "there are too many configurations to enumerate them",
+            )
+        })?;
+        fs::create_dir_all(dir)?;
+
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let mut manifest = if manifest_path.exists() {
+            let manifest = ShardManifest::read(dir)?;
+            if (manifest.total, manifest.shard_size, manifest.format) != (total, shard_size, format)
+            {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "the manifest belongs to a different enumeration",
+                ));
+            }
+            manifest
+        } else {
+            let mut file = File::create(&manifest_path)?;
+            writeln!(
+                file,
+                "total {total}\nshard_size {shard_size}\nformat {format}"
+            )?;
+            file.sync_all()?;
+            ShardManifest {
+                total,
+                shard_size,
+                format,
+                shards: Vec::new(),
+            }
+        };
+
+        let number_of_variables = self.number_of_variables;
+        let mut start = manifest.enumerated();
+        while start < total {
+            let options = EnumOptions {
+                start: Some(start),
+                limit: Some(shard_size),
+                ..Default::default()
+            };
+            let configs = match self.enumerate(&[], options) {
+                Some(enumeration) => enumeration,
+                None => break,
+            };
+
+            let file = format!("shard-{:06}.{}", manifest.shards.len(), format.extension());
+            let partial = dir.join(format!("{file}.partial"));
+            let mut writer = BufWriter::new(File::create(&partial)?);
+            let written = match format {
+                ShardFormat::Text => {
+                    let mut written = 0;
+                    for config in configs {
+                        writeln!(writer, "{}", format_vec(config.iter()))?;
+                        written += 1;
+                    }
+                    written
+                }
+                ShardFormat::Packed => {
+                    let mut packed =
+                        PackedConfigs::new(number_of_variables, &VariableOrder::default());
+                    for config in configs {
+                        packed.push(&config);
+                    }
+                    packed.write_to(&mut writer)?;
+                    packed.len()
+                }
+            };
+            writer.into_inner()?.sync_all()?;
+            fs::rename(&partial, dir.join(&file))?;
+
+            let mut entry = OpenOptions::new().append(true).open(&manifest_path)?;
+            writeln!(entry, "shard {file} {start} {written}")?;
+            entry.sync_all()?;
+            manifest.shards.push(Shard {
+                file,
+                start,
+                configs: written,
+            });
+            start += written;
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    // reads all configurations of the shards in the order of the manifest
+    fn read_shards(dir: &Path, manifest: &ShardManifest) -> Vec<Vec<i32>> {
+        let mut configs = Vec::new();
+        for shard in manifest.shards.iter() {
+            let mut reader = BufReader::new(File::open(dir.join(&shard.file)).unwrap());
+            match manifest.format {
+                ShardFormat::Text => configs.extend(reader.lines().map(|line| {
+                    line.unwrap()
+                        .split_whitespace()
+                        .map(|literal| literal.parse().unwrap())
+                        .collect()
+                })),
+                ShardFormat::Packed => {
+                    configs.extend(PackedConfigs::read_from(&mut reader).unwrap().iter())
+                }
+            }
+        }
+        configs
+    }
+
+    #[test]
+    fn shards_contain_all_configurations() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut ddnnf = vp9.condition(&[3, 7, 11, 16, 20]);
+        let expected: Vec<Vec<i32>> = ddnnf
+            .enumerate(&[], EnumOptions::default())
+            .unwrap()
+            .collect();
+        assert_eq!(400, expected.len());
+
+        for format in [ShardFormat::Text, ShardFormat::Packed] {
+            let dir = tempfile::tempdir().unwrap();
+            let manifest = ddnnf.enumerate_to_dir(dir.path(), 64, format).unwrap();
+            assert!(manifest.is_complete());
+            assert_eq!(expected.len().div_ceil(64), manifest.shards.len());
+            assert_eq!(manifest, ShardManifest::read(dir.path()).unwrap());
+            assert_eq!(expected, read_shards(dir.path(), &manifest));
+        }
+    }
+
+    #[test]
+    fn resumes_after_interruption() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut ddnnf = vp9.condition(&[3, 7, 11, 16, 20]);
+        let dir = tempfile::tempdir().unwrap();
+        let complete = ddnnf
+            .enumerate_to_dir(dir.path(), 100, ShardFormat::Text)
+            .unwrap();
+        let expected = read_shards(dir.path(), &complete);
+
+        // the interruption happened while writing the third shard
+        let manifest = fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap();
+        let lines: Vec<&str> = manifest.lines().collect();
+        fs::write(dir.path().join(MANIFEST_FILE), lines[..5].join("\n") + "\n").unwrap();
+        for shard in complete.shards[2..].iter() {
+            fs::remove_file(dir.path().join(&shard.file)).unwrap();
+        }
+        fs::write(dir.path().join("shard-000002.csv.partial"), "1 2").unwrap();
+        assert_eq!(2, ShardManifest::read(dir.path()).unwrap().shards.len());
+
+        let resumed = ddnnf
+            .enumerate_to_dir(dir.path(), 100, ShardFormat::Text)
+            .unwrap();
+        assert_eq!(complete, resumed);
+        assert_eq!(expected, read_shards(dir.path(), &resumed));
+
+        // a different setup can not continue the enumeration
+        assert!(ddnnf
+            .enumerate_to_dir(dir.path(), 50, ShardFormat::Text)
+            .is_err());
+        assert!(vp9
+            .enumerate_to_dir(dir.path(), 100, ShardFormat::Text)
+            .is_err());
+        assert!(ddnnf
+            .enumerate_to_dir(dir.path(), 0, ShardFormat::Text)
+            .is_err());
+    }
+}
@@ -1,5 +1,8 @@
 pub mod covering_strategies;
 pub mod data_structure;
+pub mod differential;
+pub mod interaction;
+mod invalid_prefixes;
 pub mod sample_merger;
 pub mod sat_wrapper;
 pub mod t_iterator;
@@ -7,15 +10,17 @@ pub mod t_iterator;
 use std::cmp::min;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::{fmt, fs, io, iter};
+use std::{fmt, fs, io, iter, thread};
 
-use crate::ddnnf::anomalies::t_wise_sampling::sample_merger::{AndMerger, OrMerger};
+use crate::ddnnf::anomalies::t_wise_sampling::sample_merger::{AndMerger, OrMerger, SampleMerger};
 use crate::ddnnf::anomalies::t_wise_sampling::SamplingResult::ResultWithSample;
+use crate::ddnnf::anomalies::variable_order::VariableOrder;
 use rand::prelude::{SliceRandom, StdRng};
 use rand::SeedableRng;
 use streaming_iterator::StreamingIterator;
 
 use crate::parser::util::format_vec;
+use crate::profiling::{self, Phase};
 use crate::{Ddnnf, NodeType::*};
 
 use self::covering_strategies::cover_with_caching;
@@ -26,44 +31,194 @@ use self::sat_wrapper::SatWrapper;
 use self::t_iterator::TInteractionIter;
 
 impl Ddnnf {
+    /// Computes a sample that covers all t-wise interactions of the configurations
+    /// that fulfill the assumptions. Instead of filtering the configurations afterwards,
+    /// we sample the conditioned d-DNNF. Hence, each configuration is valid and contains the assumptions.
+    pub fn sample_t_wise_with_assumptions(&self, t: usize, assumptions: &[i32]) -> SamplingResult {
+        if assumptions.is_empty() {
+            return self.sample_t_wise(t);
+        }
+        self.conditioned(assumptions).sample_t_wise(t)
+    }
+
     pub fn sample_t_wise(&self, t: usize) -> SamplingResult {
+        let _sampling = profiling::phase(Phase::Sample);
+        let sat_solver = SatWrapper::new(self);
+        let mut rng = StdRng::seed_from_u64(42);
+        let root_id = self.nodes.len() - 1;
+
+        match self.sample_subgraph(root_id, t, &sat_solver, &mut rng) {
+            ResultWithSample(mut sample) => {
+                self.complete_partial_configs(&mut sample, root_id, &sat_solver);
+                ResultWithSample(sample)
+            }
+            sampling_result => sampling_result,
+        }
+    }
+
+    /// Computes a t-wise sample like [Ddnnf::sample_t_wise], but divides the d-DNNF into independent
+    /// components first. Due to decomposability, the children of the root And node have disjoint
+    /// variables. Each of them gets sampled and trimmed on its own, in parallel with up to
+    /// 'max_worker' threads. Afterwards, the samples of the components get zipped together,
+    /// which preserves the coverage of all t-wise interactions.
+    /// The sample is the same for any number of threads (including a single one).
+    /// If the root is no And node, this is the same as [Ddnnf::sample_t_wise].
+    pub fn sample_t_wise_hierarchical(&self, t: usize) -> SamplingResult {
+        let root_id = self.nodes.len() - 1;
+        let components = match &self.nodes[root_id].ntype {
+            And { children } if children.len() > 1 => children,
+            _ => return self.sample_t_wise(t),
+        };
+
+        let workers = (self.max_worker as usize).clamp(1, components.len());
+        let mut component_results: Vec<(usize, SamplingResult)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|worker| {
+                    scope.spawn(move || {
+                        // the sat solver caches states and can not be shared between threads
+                        let sat_solver = SatWrapper::new(self);
+                        components
+                            .iter()
+                            .enumerate()
+                            .skip(worker)
+                            .step_by(workers)
+                            .map(|(index, &component)| {
+                                // each component gets its own seed to keep the sample
+                                // independent of the partitioning among the threads
+                                let mut rng = StdRng::seed_from_u64(42 + index as u64);
+                                let result =
+                                    self.sample_subgraph(component, t, &sat_solver, &mut rng);
+                                (index, result)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("sampling a component failed"))
+                .collect()
+        });
+        component_results.sort_unstable_by_key(|(index, _)| *index);
+
+        if component_results
+            .iter()
+            .any(|(_, result)| matches!(result, SamplingResult::Void))
+        {
+            return SamplingResult::Void;
+        }
+        let component_samples: Vec<&Sample> = component_results
+            .iter()
+            .filter_map(|(_, result)| result.get_sample())
+            .collect();
+
         let sat_solver = SatWrapper::new(self);
+        let mut rng = StdRng::seed_from_u64(42);
         let and_merger = ZippingMerger {
             t,
             sat_solver: &sat_solver,
             ddnnf: self,
         };
+        let sample = and_merger.merge_all(root_id, &component_samples, &mut rng);
+        if sample.is_empty() {
+            return SamplingResult::Empty;
+        }
+
+        let mut sample = trim_and_resample(
+            root_id,
+            sample,
+            t,
+            self.number_of_variables as usize,
+            &sat_solver,
+            &mut rng,
+        );
+        self.complete_partial_configs(&mut sample, root_id, &sat_solver);
+        ResultWithSample(sample)
+    }
+
+    /// Samples the sub-graph rooted at the given node bottom up and trims the resulting sample.
+    /// The configs only contain the variables of the sub-graph.
+    fn sample_subgraph(
+        &self,
+        root: usize,
+        t: usize,
+        sat_solver: &SatWrapper,
+        rng: &mut StdRng,
+    ) -> SamplingResult {
+        let and_merger = ZippingMerger {
+            t,
+            sat_solver,
+            ddnnf: self,
+        };
         let or_merger = SimilarityMerger { t };
-        let mut rng = StdRng::seed_from_u64(42);
         let mut sampler = TWiseSampler::new(self, and_merger, or_merger);
 
-        for node_id in 0..sampler.ddnnf.nodes.len() {
-            let partial_sample = sampler.make_partial_sample(node_id, &mut rng);
+        // the nodes are in postorder, hence, the children of a node get sampled before the node
+        let mut reachable = vec![false; root + 1];
+        reachable[root] = true;
+        for node_id in (0..=root).rev() {
+            if !reachable[node_id] {
+                continue;
+            }
+            if let And { children } | Or { children } = &self.nodes[node_id].ntype {
+                children.iter().for_each(|&child| reachable[child] = true);
+            }
+        }
+        for node_id in (0..=root).filter(|&node_id| reachable[node_id]) {
+            let partial_sample = sampler.make_partial_sample(node_id, rng);
             sampler.partial_samples.insert(node_id, partial_sample);
         }
 
-        let root_id = sampler.ddnnf.nodes.len() - 1;
-
         let sampling_result = sampler
             .partial_samples
-            .remove(&root_id)
+            .remove(&root)
             .expect("Root sample does not exist!");
 
-        if let ResultWithSample(mut sample) = sampling_result {
-            sample = trim_and_resample(
-                root_id,
+        if let ResultWithSample(sample) = sampling_result {
+            ResultWithSample(trim_and_resample(
+                root,
                 sample,
                 t,
                 self.number_of_variables as usize,
-                &sat_solver,
-                &mut rng,
-            );
-            sampler.complete_partial_configs(&mut sample, root_id, &sat_solver);
-            ResultWithSample(sample)
+                sat_solver,
+                rng,
+            ))
         } else {
             sampling_result
         }
     }
+
+    /// Completes the partial configs of the sample with the variables of the whole d-DNNF
+    fn complete_partial_configs(&self, sample: &mut Sample, root: usize, sat_solver: &SatWrapper) {
+        let vars: Vec<i32> = (1..=self.number_of_variables as i32).collect();
+        for config in sample.partial_configs.iter_mut() {
+            for &var in vars.iter() {
+                if config.contains(var) || config.contains(-var) {
+                    continue;
+                }
+
+                config.update_sat_state(sat_solver, root);
+
+                // copy sat state so that we don't change the state that is cached in the config
+                let mut sat_state = sat_solver.copy_state(
+                    config
+                        .sat_state()
+                        .expect("sat state should exist after calling update_sat_state()"),
+                );
+
+                if sat_solver.is_sat_cached(&[var], &mut sat_state) {
+                    config.add(var);
+                } else {
+                    config.add(-var);
+                }
+                sat_solver.recycle(sat_state);
+            }
+        }
+
+        debug_assert!(sample
+            .iter()
+            .all(|config| !config.get_literals().contains(&0)));
+    }
 }
 
 struct TWiseSampler<'a, A: AndMerger, O: OrMerger> {
@@ -237,35 +392,6 @@ impl<'a, A: AndMerger, O: OrMerger> TWiseSampler<'a, A, O> {
             ResultWithSample(sample)
         }
     }
-
-    fn complete_partial_configs(&self, sample: &mut Sample, root: usize, sat_solver: &SatWrapper) {
-        let vars: Vec<i32> = (1..=self.ddnnf.number_of_variables as i32).collect();
-        for config in sample.partial_configs.iter_mut() {
-            for &var in vars.iter() {
-                if config.contains(var) || config.contains(-var) {
-                    continue;
-                }
-
-                config.update_sat_state(sat_solver, root);
-
-                // clone sat state so that we don't change the state that is cached in the config
-                let mut sat_state = config
-                    .get_sat_state()
-                    .cloned()
-                    .expect("sat state should exist after calling update_sat_state()");
-
-                if sat_solver.is_sat_cached(&[var], &mut sat_state) {
-                    config.add(var);
-                } else {
-                    config.add(-var);
-                }
-            }
-        }
-
-        debug_assert!(sample
-            .iter()
-            .all(|config| !config.get_literals().contains(&0)));
-    }
 }
 
 #[inline]
@@ -290,15 +416,18 @@ fn trim_and_resample(
     literals_to_resample.sort_unstable();
     literals_to_resample.shuffle(rng);
 
-    let mut iter = TInteractionIter::new(&literals_to_resample, t);
-    while let Some(interaction) = iter.next() {
-        cover_with_caching(
-            &mut new_sample,
-            interaction,
-            sat_solver,
-            node_id,
-            number_of_variables,
-        );
+    // with less than t literals, the trimmed configs did not cover any t-wise interaction
+    if literals_to_resample.len() >= t {
+        let mut iter = TInteractionIter::new(&literals_to_resample, t);
+        while let Some(interaction) = iter.next() {
+            cover_with_caching(
+                &mut new_sample,
+                interaction,
+                sat_solver,
+                node_id,
+                number_of_variables,
+            );
+        }
     }
 
     if new_sample.len() < sample.len() {
@@ -312,7 +441,7 @@ fn trim_and_resample(
 fn trim_sample(sample: &Sample, ranks: &[f64], avg_rank: f64) -> (Sample, HashSet<i32>) {
     let mut literals_to_resample: HashSet<i32> = HashSet::new();
     let mut new_sample = Sample::new_from_samples(&[sample]);
-    let complete_len = sample.complete_configs.len();
+    let complete_len = sample.complete_configs().len();
 
     for (index, config) in sample.iter().enumerate() {
         if ranks[index] < avg_rank {
@@ -367,6 +496,16 @@ fn find_unique_covering_conf(sample: &Sample, interaction: &[i32]) -> Option<usi
 }
 
 pub fn save_sample_to_file(sampling_result: &SamplingResult, file_path: &str) -> io::Result<()> {
+    save_sample_to_file_ordered(sampling_result, file_path, &VariableOrder::model())
+}
+
+/// Saves the sample like [save_sample_to_file], but the literals of each configuration
+/// are sorted by the variable order.
+pub fn save_sample_to_file_ordered(
+    sampling_result: &SamplingResult,
+    file_path: &str,
+    variable_order: &VariableOrder,
+) -> io::Result<()> {
     let file_path = Path::new(file_path);
     if let Some(dir) = file_path.parent() {
         fs::create_dir_all(dir)?;
@@ -385,7 +524,9 @@ pub fn save_sample_to_file(sampling_result: &SamplingResult, file_path: &str) ->
         SamplingResult::Void => wtr.write_record(iter::once("false"))?,
         ResultWithSample(sample) => {
             for (index, config) in sample.iter().enumerate() {
-                wtr.write_record([index.to_string(), format_vec(config.get_literals().iter())])?;
+                let mut literals = config.get_literals().to_vec();
+                variable_order.sort(&mut literals);
+                wtr.write_record([index.to_string(), format_vec(literals.iter())])?;
             }
         }
     }
@@ -397,7 +538,8 @@ pub fn save_sample_to_file(sampling_result: &SamplingResult, file_path: &str) ->
 mod test {
     use itertools::Itertools;
 
-    use crate::{parser::build_ddnnf, Ddnnf};
+    use super::SamplingResult;
+    use crate::{parser::build_ddnnf, Ddnnf, NodeType::And};
 
     #[test]
     fn t_wise_sampling_validity() {
@@ -412,41 +554,108 @@ mod test {
 
         fn check_validity_samplingresult(ddnnf: &mut Ddnnf, t: usize) {
             let t_wise_samples = ddnnf.sample_t_wise(t);
-            let configs = t_wise_samples
-                .get_sample()
-                .unwrap()
-                .iter()
-                .map(|config| config.get_literals())
-                .collect_vec();
-
-            for config in configs.iter() {
-                // every config must be complete and satisfiable
-                assert_eq!(
-                    ddnnf.number_of_variables as usize,
-                    config.len(),
-                    "config is not complete"
-                );
-                assert!(ddnnf.sat(config));
-            }
+            check_validity(ddnnf, &t_wise_samples, t);
+        }
+    }
 
-            let mut possible_features = (-(ddnnf.number_of_variables as i32)
-                ..=ddnnf.number_of_variables as i32)
-                .collect_vec();
-            possible_features.remove(ddnnf.number_of_variables as usize); // remove the 0
-            for combi in possible_features.into_iter().combinations(t) {
-                // checks if the pair can be found in at least one of the samples
-                let combi_exists = |combi: &[i32]| -> bool {
-                    configs
-                        .iter()
-                        .any(|config| combi.iter().all(|&f| config[f.abs() as usize - 1] == f))
-                };
+    #[test]
+    fn hierarchical_t_wise_sampling_validity() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert!(matches!(vp9.nodes.last().unwrap().ntype, And { .. }));
 
-                assert!(
-                    combi_exists(&combi) || !ddnnf.sat(&combi),
-                    "combination: {:?} can neither be convered with samples nor is it unsat",
+        for t in 1..=3 {
+            let sample = vp9.sample_t_wise_hierarchical(t);
+            check_validity(&mut vp9, &sample, t);
+        }
+
+        // the number of threads does not change the result
+        vp9.max_worker = 1;
+        let sequential = vp9.sample_t_wise_hierarchical(2);
+        for workers in [2, 3, 8] {
+            vp9.max_worker = workers;
+            assert_eq!(sequential, vp9.sample_t_wise_hierarchical(2));
+        }
+
+        assert_eq!(
+            SamplingResult::Void,
+            vp9.condition(&[4, -4]).sample_t_wise_hierarchical(2)
+        );
+    }
+
+    /// Checks that each config of the sample is complete and satisfiable and that each
+    /// satisfiable t-wise interaction is covered
+    fn check_validity(ddnnf: &mut Ddnnf, t_wise_samples: &SamplingResult, t: usize) {
+        let configs = t_wise_samples
+            .get_sample()
+            .unwrap()
+            .iter()
+            .map(|config| config.get_literals())
+            .collect_vec();
+
+        for config in configs.iter() {
+            // every config must be complete and satisfiable
+            assert_eq!(
+                ddnnf.number_of_variables as usize,
+                config.len(),
+                "config is not complete"
+            );
+            assert!(ddnnf.sat(config));
+        }
+
+        let mut possible_features =
+            (-(ddnnf.number_of_variables as i32)..=ddnnf.number_of_variables as i32).collect_vec();
+        possible_features.remove(ddnnf.number_of_variables as usize); // remove the 0
+        for combi in possible_features.into_iter().combinations(t) {
+            // checks if the pair can be found in at least one of the samples
+            let combi_exists = |combi: &[i32]| -> bool {
+                configs.iter().any(|config| {
                     combi
-                )
-            }
+                        .iter()
+                        .all(|&f| config[f.unsigned_abs() as usize - 1] == f)
+                })
+            };
+
+            assert!(
+                combi_exists(&combi) || !ddnnf.sat(&combi),
+                "combination: {:?} can neither be convered with samples nor is it unsat",
+                combi
+            )
         }
     }
+
+    #[test]
+    fn t_wise_sampling_with_assumptions() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut conditioned = vp9.condition(&[4, -9]);
+
+        let sample = vp9.sample_t_wise_with_assumptions(2, &[4, -9]);
+        let configs = sample
+            .get_sample()
+            .unwrap()
+            .iter()
+            .map(|config| config.get_literals().to_vec())
+            .collect_vec();
+        assert!(!configs.is_empty());
+        for config in configs.iter() {
+            assert_eq!(42, config.len());
+            assert!(config.contains(&4) && config.contains(&-9));
+        }
+
+        // each pair that is possible under the assumptions is covered
+        for combi in (1..=42).flat_map(|f| [f, -f]).combinations(2) {
+            assert!(
+                configs
+                    .iter()
+                    .any(|config| combi.iter().all(|f| config.contains(f)))
+                    || !conditioned.sat(&combi),
+                "combination: {:?} is not covered",
+                combi
+            );
+        }
+
+        assert_eq!(
+            SamplingResult::Void,
+            vp9.sample_t_wise_with_assumptions(2, &[4, -4])
+        );
+    }
 }
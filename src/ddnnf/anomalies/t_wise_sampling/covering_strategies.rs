@@ -13,10 +13,10 @@ pub(super) fn cover_with_caching(
     if sample.covers(interaction) {
         return; // already covered
     }
-    let mut interaction_sat_state = sat_solver.new_state();
-    if !sat_solver.is_sat_in_subgraph_cached(interaction, node_id, &mut interaction_sat_state) {
-        return; // interaction invalid
-    }
+    let interaction_sat_state = match sat_solver.interaction_state(interaction, node_id) {
+        Some(state) => state,
+        None => return, // interaction invalid
+    };
 
     let mut found = None;
     for (index, config) in sample.partial_configs.iter_mut().enumerate() {
@@ -26,22 +26,27 @@ pub(super) fn cover_with_caching(
 
         config.update_sat_state(sat_solver, node_id);
 
-        // clone sat state so that we don't change the state that is cached in the config
-        let mut sat_state = config
-            .get_sat_state()
-            .cloned()
-            .expect("sat state should exist because update_sat_state() was called before");
+        // copy sat state so that we don't change the state that is cached in the config
+        let mut sat_state = sat_solver.copy_state(
+            config
+                .sat_state()
+                .expect("sat state should exist because update_sat_state() was called before"),
+        );
 
         if sat_solver.is_sat_in_subgraph_cached(interaction, node_id, &mut sat_state) {
             // we found a config - extend config with interaction and update sat state
             config.extend(interaction.iter().cloned());
-            config.set_sat_state(sat_state);
+            if let Some(old_state) = config.set_sat_state(sat_state) {
+                sat_solver.recycle_shared(old_state);
+            }
             found = Some(index);
             break;
         }
+        sat_solver.recycle(sat_state);
     }
 
     if let Some(index) = found {
+        sat_solver.recycle(interaction_sat_state);
         // move config to the complete configs if it is complete now
         let config = sample.partial_configs.get(index).expect("");
         if sample.is_config_complete(config) {
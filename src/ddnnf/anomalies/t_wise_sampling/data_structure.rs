@@ -1,16 +1,22 @@
+use crate::ddnnf::anomalies::sat::SatMark;
+use crate::ddnnf::anomalies::t_wise_sampling::interaction::Interaction;
 use crate::ddnnf::anomalies::t_wise_sampling::sat_wrapper::SatWrapper;
 use crate::parser::util::format_vec;
+use crate::Ddnnf;
+use bitvec::slice::BitSlice;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::iter;
+use std::sync::Arc;
 
 /// Represents a (partial) configuration
 #[derive(Debug, Clone, Eq)]
 pub struct Config {
     /// A vector of selected features (positive values) and deselected features (negative values)
     literals: Vec<i32>,
-    pub sat_state: Option<Vec<bool>>,
+    /// The cached sat state. Clones of a config share the state until one of them changes it (copy-on-write).
+    pub sat_state: Option<Arc<SatMark>>,
     sat_state_complete: bool,
 }
 
@@ -36,6 +42,31 @@ impl Display for Config {
     }
 }
 
+/// The reasons why literals do not form a valid config of a d-DNNF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidLiterals {
+    /// The literal is zero or refers to a feature that the d-DNNF does not contain
+    OutOfRange(i32),
+    /// The literal occurs together with its negation
+    Contradiction(i32),
+    /// The literals are consistent, but there is no model of the d-DNNF that contains all of them
+    Unsatisfiable,
+}
+
+impl Display for InvalidLiterals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfRange(literal) => write!(f, "the literal {literal} is out of range"),
+            Self::Contradiction(literal) => {
+                write!(f, "the literals {literal} and {} contradict", -literal)
+            }
+            Self::Unsatisfiable => write!(f, "the literals are unsatisfiable"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidLiterals {}
+
 impl Config {
     /// Creates a new config with the given literals
     pub fn from(literals: &[i32], number_of_variables: usize) -> Self {
@@ -48,6 +79,42 @@ impl Config {
         config
     }
 
+    /// Creates a new config with the given literals after checking that they are a valid
+    /// (partial) config of the d-DNNF, i.e. each literal refers to a feature of the d-DNNF,
+    /// no literal occurs together with its negation, and there is a model that contains all literals.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::anomalies::t_wise_sampling::data_structure::{Config, InvalidLiterals};
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert!(Config::validated(&[1, -2], &mut ddnnf).is_ok());
+    /// assert_eq!(Err(InvalidLiterals::OutOfRange(5)), Config::validated(&[5], &mut ddnnf));
+    /// assert_eq!(Err(InvalidLiterals::Contradiction(-2)), Config::validated(&[2, -2], &mut ddnnf));
+    /// ```
+    pub fn validated(literals: &[i32], ddnnf: &mut Ddnnf) -> Result<Self, InvalidLiterals> {
+        let number_of_variables = ddnnf.number_of_variables as usize;
+        let mut config = Self::from(&[], number_of_variables);
+        for &literal in literals {
+            if literal == 0 || literal.unsigned_abs() as usize > number_of_variables {
+                return Err(InvalidLiterals::OutOfRange(literal));
+            }
+            if config.contains(-literal) {
+                return Err(InvalidLiterals::Contradiction(literal));
+            }
+            config.add(literal);
+        }
+
+        if !ddnnf.sat(literals) {
+            return Err(InvalidLiterals::Unsatisfiable);
+        }
+        Ok(config)
+    }
+
     /// Creates a new config from two disjoint configs.
     pub fn from_disjoint(left: &Self, right: &Self, number_of_variables: usize) -> Self {
         let sat_state = match (left.sat_state.clone(), right.sat_state.clone()) {
@@ -95,15 +162,21 @@ impl Config {
             .filter(|&literal| literal != 0)
     }
 
-    /// Returns the cached sat state if there is one
-    pub fn get_sat_state(&mut self) -> Option<&mut Vec<bool>> {
-        self.sat_state.as_mut()
+    /// Returns the cached sat state if there is one.
+    /// If the state is shared with other configs, this config gets its own copy.
+    pub fn get_sat_state(&mut self) -> Option<&mut SatMark> {
+        self.sat_state.as_mut().map(Arc::make_mut)
+    }
+
+    /// Returns the cached sat state without copying it
+    pub fn sat_state(&self) -> Option<&BitSlice> {
+        self.sat_state.as_deref().map(SatMark::as_bitslice)
     }
 
-    /// Sets the cached sat state
-    pub fn set_sat_state(&mut self, sat_state: Vec<bool>) {
+    /// Sets the cached sat state and returns the previous one
+    pub fn set_sat_state(&mut self, sat_state: SatMark) -> Option<Arc<SatMark>> {
         self.sat_state_complete = true;
-        self.sat_state = Some(sat_state);
+        self.sat_state.replace(Arc::new(sat_state))
     }
 
     /// Returns whether the cached sat state is complete (true) or incomplete (false)
@@ -154,12 +227,15 @@ impl Config {
             .all(|&literal| self.contains(literal))
     }
 
+    /// Checks if this config contains the literal.
+    /// The literals are indexed by their variable, hence, this is a constant time lookup.
     pub fn contains(&self, literal: i32) -> bool {
         debug_assert!(literal != 0);
         let index = literal.unsigned_abs() as usize - 1;
         self.literals[index] == literal
     }
 
+    /// Adds the literal to this config. It replaces the literal of the same variable if there is one.
     pub fn add(&mut self, literal: i32) {
         if literal == 0 {
             return;
@@ -175,10 +251,10 @@ impl Config {
 /// The sample differentiates between complete and partial configs.
 /// A config is complete (in the context of this sample) if it contains all variables this sample
 /// defines. Otherwise the config is partial.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Sample {
-    /// Configs that contain all variables of this sample
-    pub complete_configs: Vec<Config>,
+    /// Configs that contain all variables of this sample. They are private to keep the index in sync.
+    complete_configs: Vec<Config>,
     /// Configs that do not contain all variables of this sample
     pub partial_configs: Vec<Config>,
     /// The variables that Configs of this sample may contain
@@ -186,6 +262,35 @@ pub struct Sample {
     /// The literals that actually occur in this sample, this is not a HashSet because we want
     /// a stable iteration order.
     pub(super) literals: Vec<i32>,
+    /// Maps each literal to the complete configs that contain it
+    complete_index: LiteralIndex,
+}
+
+impl PartialEq for Sample {
+    fn eq(&self, other: &Self) -> bool {
+        self.complete_configs == other.complete_configs
+            && self.partial_configs == other.partial_configs
+            && self.vars == other.vars
+            && self.literals == other.literals
+    }
+}
+
+impl Eq for Sample {}
+
+/// An index from literals to the positions of the configs that contain them.
+/// Complete configs do not change after they got added to a sample. Hence, the index only
+/// grows with each call of [Sample::add_complete].
+#[derive(Debug, Clone, Default)]
+struct LiteralIndex {
+    configs: HashMap<i32, Vec<usize>>,
+}
+
+impl LiteralIndex {
+    fn insert(&mut self, config: &Config, index: usize) {
+        for literal in config.get_decided_literals() {
+            self.configs.entry(literal).or_default().push(index);
+        }
+    }
 }
 
 impl PartialOrd<Self> for Sample {
@@ -216,6 +321,7 @@ impl Sample {
             partial_configs: vec![],
             vars,
             literals: vec![],
+            complete_index: LiteralIndex::default(),
         }
     }
 
@@ -230,9 +336,9 @@ impl Sample {
     /// let sample = Sample::new_from_configs(vec![conf_a, conf_b]);
     ///
     /// assert_eq!(2, sample.len());
-    /// assert_eq!(1, sample.complete_configs.len());
+    /// assert_eq!(1, sample.complete_configs().len());
     /// assert_eq!(1, sample.partial_configs.len());
-    /// assert_eq!(Some(&Config::from(&[1,2,3], 3)), sample.complete_configs.get(0));
+    /// assert_eq!(Some(&Config::from(&[1,2,3], 3)), sample.complete_configs().get(0));
     /// assert_eq!(Some(&Config::from(&[1,2], 3)), sample.partial_configs.get(0));
     /// ```
     pub fn new_from_configs(configs: Vec<Config>) -> Self {
@@ -246,10 +352,9 @@ impl Sample {
         let vars: HashSet<u32> = literals.iter().map(|x| x.unsigned_abs()).collect();
 
         let mut sample = Self {
-            complete_configs: vec![],
-            partial_configs: vec![],
             vars,
             literals,
+            ..Default::default()
         };
 
         sample.extend(configs);
@@ -294,6 +399,8 @@ impl Sample {
     /// complete. The added config is treated as a complete config without checking
     /// if it actually is complete.
     pub fn add_complete(&mut self, config: Config) {
+        self.complete_index
+            .insert(&config, self.complete_configs.len());
         self.complete_configs.push(config)
     }
 
@@ -335,6 +442,11 @@ impl Sample {
         decided_literals == self.vars.len()
     }
 
+    /// The configs that contain all variables of this sample
+    pub fn complete_configs(&self) -> &[Config] {
+        &self.complete_configs
+    }
+
     /// Creates an iterator that first iterates over complete_configs and then over partial_configs
     pub fn iter(&self) -> impl Iterator<Item = &Config> {
         self.complete_configs
@@ -372,30 +484,167 @@ impl Sample {
         self.complete_configs.is_empty() && self.partial_configs.is_empty()
     }
 
-    /// Checks if this sample covers the given interaction
+    /// Checks if this sample covers the given interaction.
+    /// For the complete configs, we only look at the configs that contain the rarest literal
+    /// of the interaction. The partial configs still change and get scanned.
     pub fn covers(&self, interaction: &[i32]) -> bool {
         debug_assert!(!interaction.contains(&0));
-        self.iter().any(|conf| conf.covers(interaction))
+        if interaction.is_empty() {
+            return self.iter().any(|conf| conf.covers(interaction));
+        }
+
+        let no_configs = Vec::new();
+        let candidates = interaction
+            .iter()
+            .map(|literal| {
+                self.complete_index
+                    .configs
+                    .get(literal)
+                    .unwrap_or(&no_configs)
+            })
+            .min_by_key(|configs| configs.len())
+            .expect("the interaction is not empty");
+        candidates
+            .iter()
+            .any(|&index| self.complete_configs[index].covers(interaction))
+            || self
+                .partial_configs
+                .iter()
+                .any(|conf| conf.covers(interaction))
+    }
+
+    /// Returns the t-wise interactions of the literals of this sample that no config covers.
+    /// The interactions are not checked for satisfiability.
+    pub fn uncovered_interactions(&self, t: usize) -> Vec<Interaction> {
+        Interaction::all(&self.literals, t)
+            .filter(|interaction| !self.covers(interaction))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod test {
+    use bitvec::prelude::*;
+
     use super::*;
+    use crate::ddnnf::anomalies::sat::new_sat_mark_state;
+    use crate::parser::build_ddnnf;
 
     #[test]
     fn test_sample_covering() {
-        let sample = Sample {
-            complete_configs: vec![Config::from(&[1, 2, 3, -4, -5], 5)],
-            partial_configs: vec![],
-            vars: HashSet::from([1, 2, 3, 4, 5]),
-            literals: vec![1, 2, 3, -4, -5],
-        };
+        let sample = Sample::new_from_configs(vec![Config::from(&[1, 2, 3, -4, -5], 5)]);
+        assert_eq!(1, sample.complete_configs().len());
 
         let covered_interaction = vec![1, 2, -4];
         assert!(sample.covers(&covered_interaction));
 
         let uncovered_interaction = vec![1, 2, 4];
         assert!(!sample.covers(&uncovered_interaction));
+        assert!(sample.uncovered_interactions(2).is_empty());
+
+        let sample =
+            Sample::new_from_configs(vec![Config::from(&[1, 2], 2), Config::from(&[-1, -2], 2)]);
+        let uncovered: Vec<Interaction> = sample
+            .uncovered_interactions(2)
+            .into_iter()
+            .filter(Interaction::is_consistent)
+            .collect();
+        assert_eq!(2, uncovered.len());
+        assert!(uncovered.contains(&Interaction::new(&[1, -2])));
+        assert!(uncovered.contains(&Interaction::new(&[-1, 2])));
+    }
+
+    #[test]
+    fn indexed_covering() {
+        let configs = vec![
+            Config::from(&[1, 2, 3], 3),
+            Config::from(&[-1, 2, -3], 3),
+            Config::from(&[1, -2, -3], 3),
+            Config::from(&[-1], 3),
+        ];
+        let mut sample = Sample::new_from_configs(configs.clone());
+        assert_eq!(Some(&vec![0, 2]), sample.complete_index.configs.get(&1));
+
+        for interaction in Interaction::all(&[1, -1, 2, -2, 3, -3], 2) {
+            assert_eq!(
+                configs.iter().any(|conf| conf.covers(&interaction)),
+                sample.covers(&interaction),
+                "{interaction}"
+            );
+        }
+        // covered by the partial config only
+        assert!(sample.covers(&[-1]));
+
+        // added complete configs extend the index
+        assert!(!sample.covers(&[-1, -2]));
+        sample.add_complete(Config::from(&[-1, -2, 3], 3));
+        assert_eq!(Some(&vec![1, 3]), sample.complete_index.configs.get(&-1));
+        assert!(sample.covers(&[-1, -2]));
+    }
+
+    #[test]
+    fn validated_configs() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+
+        let config = Config::validated(&[1, -3, 4], &mut vp9).unwrap();
+        assert_eq!(
+            vec![1, -3, 4],
+            config.get_decided_literals().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Err(InvalidLiterals::OutOfRange(0)),
+            Config::validated(&[1, 0], &mut vp9)
+        );
+        assert_eq!(
+            Err(InvalidLiterals::OutOfRange(-43)),
+            Config::validated(&[-43], &mut vp9)
+        );
+        assert_eq!(
+            Err(InvalidLiterals::Contradiction(-4)),
+            Config::validated(&[4, 9, -4], &mut vp9)
+        );
+
+        // 1 is a core feature of VP9
+        assert!(vp9.core.contains(&1));
+        assert_eq!(
+            Err(InvalidLiterals::Unsatisfiable),
+            Config::validated(&[-1], &mut vp9)
+        );
+    }
+
+    #[test]
+    fn sat_state_copy_on_write() {
+        let mut config = Config::from(&[1, -2], 3);
+        config.set_sat_state(new_sat_mark_state(4));
+        let mut clone = config.clone();
+        assert!(Arc::ptr_eq(
+            config.sat_state.as_ref().unwrap(),
+            clone.sat_state.as_ref().unwrap()
+        ));
+
+        clone.get_sat_state().unwrap().set(2, true);
+        assert!(!Arc::ptr_eq(
+            config.sat_state.as_ref().unwrap(),
+            clone.sat_state.as_ref().unwrap()
+        ));
+        assert_eq!(Some(bits![0; 4]), config.sat_state());
+        assert_eq!(Some(bits![0, 0, 1, 0]), clone.sat_state());
+    }
+
+    #[test]
+    fn sat_state_gets_cached() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let sat_solver = SatWrapper::new(&ddnnf);
+        let root = ddnnf.nodes.len() - 1;
+
+        let mut config = Config::from(&[1, -2], 4);
+        assert!(!config.is_sat_state_complete());
+        config.update_sat_state(&sat_solver, root);
+        assert!(config.is_sat_state_complete());
+
+        // an up to date config keeps its state
+        let state = config.sat_state.clone().unwrap();
+        config.update_sat_state(&sat_solver, root);
+        assert!(Arc::ptr_eq(&state, config.sat_state.as_ref().unwrap()));
     }
 }
@@ -0,0 +1,154 @@
+use streaming_iterator::StreamingIterator;
+
+use super::covering_strategies::cover_with_caching;
+use super::data_structure::Sample;
+use super::sat_wrapper::SatWrapper;
+use super::t_iterator::TInteractionIter;
+use super::SamplingResult::{self, ResultWithSample};
+use crate::profiling::{self, Phase};
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes a sample for regression tests of a new version of a model: It only covers the t-wise
+    /// interactions that are valid in this d-DNNF but not in the old one. Interactions with variables
+    /// that the old d-DNNF does not know are new as well. Each configuration is valid in this d-DNNF.
+    /// If there is no new interaction, the result is [SamplingResult::Empty].
+    ///
+    /// We check each interaction of this d-DNNF against the old one. Interactions that the sample
+    /// already covers are valid in this d-DNNF and get skipped before the check. Like for the sampling
+    /// itself, the checks propagate literal by literal and remember the invalid parts of interactions.
+    /// Hence, most interactions do not need a traversal of the old d-DNNF.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let new: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// // 4 used to be dead
+    /// let old = new.condition(&[-4]);
+    ///
+    /// let sample = new.sample_t_wise_differential(&old, 2);
+    /// let configs = sample.get_sample().unwrap();
+    /// // the new interactions are the pairs with 4, which two configurations cover
+    /// assert_eq!(2, configs.len());
+    /// assert!(configs.iter().all(|config| config.contains(4)));
+    /// ```
+    pub fn sample_t_wise_differential(&self, old: &Ddnnf, t: usize) -> SamplingResult {
+        let _sampling = profiling::phase(Phase::Sample);
+        if self.rc() == 0 {
+            return SamplingResult::Void;
+        }
+        let literals: Vec<i32> = (1..=self.number_of_variables as i32)
+            .flat_map(|variable| [variable, -variable])
+            .collect();
+        if t == 0 || literals.len() < t {
+            return SamplingResult::Empty;
+        }
+
+        let number_of_variables = self.number_of_variables as usize;
+        let sat_solver = SatWrapper::new(self);
+        let root = self.nodes.len() - 1;
+        let old_solver = SatWrapper::new(old);
+        let old_root = old.nodes.len() - 1;
+        let valid_in_old =
+            |interaction: &[i32]| match old_solver.interaction_state(interaction, old_root) {
+                Some(state) => {
+                    old_solver.recycle(state);
+                    true
+                }
+                None => false,
+            };
+        let mut sample = Sample::new((1..=self.number_of_variables).collect());
+        let mut iter = TInteractionIter::new(&literals, t);
+        while let Some(interaction) = iter.next() {
+            if interaction
+                .iter()
+                .any(|literal| interaction.contains(&-literal))
+                || sample.covers(interaction)
+            {
+                continue;
+            }
+            let known = interaction
+                .iter()
+                .all(|literal| literal.unsigned_abs() <= old.number_of_variables);
+            if known && valid_in_old(interaction) {
+                continue;
+            }
+            // interactions that are invalid in this d-DNNF get skipped by the covering
+            cover_with_caching(
+                &mut sample,
+                interaction,
+                &sat_solver,
+                root,
+                number_of_variables,
+            );
+        }
+
+        if sample.is_empty() {
+            return SamplingResult::Empty;
+        }
+        self.complete_partial_configs(&mut sample, root, &sat_solver);
+        ResultWithSample(sample)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use crate::ddnnf::anomalies::t_wise_sampling::interaction::Interaction;
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn differential_sample_covers_the_new_interactions() {
+        let mut new: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        // the old version did not allow 4 and required 9
+        let old = new.condition(&[-4, 9]);
+
+        for t in 1..=2 {
+            let sample = new.sample_t_wise_differential(&old, t);
+            let configs = sample.get_sample().unwrap();
+            for config in configs.iter() {
+                assert_eq!(42, config.get_literals().len());
+                assert!(new.sat(config.get_literals()));
+            }
+
+            let literals = (1..=42).flat_map(|v| [v, -v]).collect_vec();
+            let mut new_interactions = 0;
+            for interaction in Interaction::all(&literals, t) {
+                if !interaction.is_consistent() {
+                    continue;
+                }
+                let is_new = new.is_interaction_valid(&interaction)
+                    && !old.is_interaction_valid(&interaction);
+                if is_new {
+                    new_interactions += 1;
+                    // the interaction contradicts the old constraints
+                    let mut constrained = interaction.to_vec();
+                    constrained.extend([-4, 9]);
+                    assert!(!new.is_interaction_valid(&constrained));
+                    assert!(configs.covers(&interaction), "{interaction} is not covered");
+                }
+            }
+            assert!(new_interactions > 0);
+
+            // the focused sample is smaller than a complete one
+            assert!(configs.len() <= new.sample_t_wise(t).len());
+        }
+
+        // nothing is new in the same model
+        assert_eq!(
+            SamplingResult::Empty,
+            new.sample_t_wise_differential(&new.clone(), 2)
+        );
+        assert_eq!(
+            SamplingResult::Void,
+            new.condition(&[1, -1]).sample_t_wise_differential(&new, 2)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+use std::iter;
+use std::ops::Deref;
+
+use itertools::Either;
+use streaming_iterator::StreamingIterator;
+
+use super::t_iterator::TInteractionIter;
+use crate::parser::util::format_vec;
+
+/// An interaction is a set of literals that a config covers if it contains all of them.
+/// The literals are kept sorted and free of duplicates. Hence, interactions with the same
+/// literals are equal and hash the same, independent of the order they were created in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Interaction(Vec<i32>);
+
+impl Interaction {
+    /// Creates an interaction of the given literals. Zeros are ignored.
+    pub fn new(literals: &[i32]) -> Self {
+        literals.iter().copied().collect()
+    }
+
+    /// Returns the sorted literals of this interaction
+    pub fn literals(&self) -> &[i32] {
+        &self.0
+    }
+
+    /// Checks if the interaction contains the literal
+    pub fn contains(&self, literal: i32) -> bool {
+        self.0.binary_search(&literal).is_ok()
+    }
+
+    /// Checks if each literal of the other interaction is a literal of this one.
+    /// Each config that covers this interaction also covers a subsumed one.
+    pub fn subsumes(&self, other: &Interaction) -> bool {
+        other.len() <= self.len() && other.0.iter().all(|&literal| self.contains(literal))
+    }
+
+    /// Checks if no literal occurs together with its negation
+    pub fn is_consistent(&self) -> bool {
+        self.0
+            .iter()
+            .take_while(|&&literal| literal < 0)
+            .all(|&literal| !self.contains(-literal))
+    }
+
+    /// Generates all interactions that consist of t of the given literals.
+    /// If there are less than t literals, there is no interaction.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::ddnnf::anomalies::t_wise_sampling::interaction::Interaction;
+    ///
+    /// let pairs: Vec<Interaction> = Interaction::all(&[1, -2, 3], 2).collect();
+    /// assert_eq!(3, pairs.len());
+    /// assert!(pairs.contains(&Interaction::new(&[3, -2])));
+    /// ```
+    pub fn all(literals: &[i32], t: usize) -> impl Iterator<Item = Interaction> + '_ {
+        // there is exactly one empty interaction and none that is larger than the literals
+        if t == 0 || literals.len() < t {
+            return Either::Left((t == 0).then(Interaction::default).into_iter());
+        }
+
+        let mut iter = TInteractionIter::new(literals, t);
+        Either::Right(iter::from_fn(move || iter.next().map(Interaction::new)))
+    }
+}
+
+impl FromIterator<i32> for Interaction {
+    fn from_iter<T: IntoIterator<Item = i32>>(iter: T) -> Self {
+        let mut literals: Vec<i32> = iter.into_iter().filter(|&literal| literal != 0).collect();
+        literals.sort_unstable();
+        literals.dedup();
+        Self(literals)
+    }
+}
+
+impl Deref for Interaction {
+    type Target = [i32];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Interaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_vec(self.0.iter()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn canonical_interactions() {
+        let interaction = Interaction::new(&[3, -1, 0, 3]);
+        assert_eq!(&[-1, 3], interaction.literals());
+        assert_eq!(interaction, Interaction::new(&[-1, 3]));
+        assert_eq!("-1 3", interaction.to_string());
+
+        let set: HashSet<Interaction> = [vec![2, 1], vec![1, 2], vec![-2, 1]]
+            .iter()
+            .map(|literals| Interaction::new(literals))
+            .collect();
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn subsumption_and_consistency() {
+        let big = Interaction::new(&[1, -2, 5]);
+        assert!(big.subsumes(&Interaction::new(&[5, 1])));
+        assert!(big.subsumes(&Interaction::default()));
+        assert!(!big.subsumes(&Interaction::new(&[2])));
+        assert!(!Interaction::new(&[5]).subsumes(&big));
+
+        assert!(big.is_consistent());
+        assert!(!Interaction::new(&[-3, 1, 3]).is_consistent());
+    }
+
+    #[test]
+    fn generating_interactions() {
+        let literals = [1, -1, 2, 3];
+        assert_eq!(4, Interaction::all(&literals, 1).count());
+        assert_eq!(6, Interaction::all(&literals, 2).count());
+        assert_eq!(4, Interaction::all(&literals, 3).count());
+        assert_eq!(0, Interaction::all(&literals, 5).count());
+        assert_eq!(
+            vec![Interaction::default()],
+            Interaction::all(&literals, 0).collect::<Vec<_>>()
+        );
+
+        let unique: HashSet<Interaction> = Interaction::all(&literals, 2).collect();
+        assert_eq!(6, unique.len());
+        assert_eq!(5, unique.iter().filter(|pair| pair.is_consistent()).count());
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+/// A trie of literal sequences that can not be part of any valid configuration. Each sequence that
+/// starts with one of them is invalid as well, which lets us reject it without asking the SAT oracle.
+#[derive(Debug, Clone)]
+pub(super) struct InvalidPrefixes {
+    // the root is the empty prefix at index 0
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: HashMap<i32, usize>,
+    invalid: bool,
+}
+
+impl Default for InvalidPrefixes {
+    fn default() -> Self {
+        InvalidPrefixes {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+}
+
+impl InvalidPrefixes {
+    /// Remembers that the prefix is invalid
+    pub(super) fn insert(&mut self, prefix: impl IntoIterator<Item = i32>) {
+        let mut current = 0;
+        for literal in prefix {
+            if self.nodes[current].invalid {
+                return; // a shorter prefix already covers this one
+            }
+            current = match self.nodes[current].children.get(&literal) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[current].children.insert(literal, child);
+                    child
+                }
+            };
+        }
+        self.nodes[current].invalid = true;
+    }
+
+    /// Checks whether the literals start with an invalid prefix
+    pub(super) fn rejects(&self, literals: impl IntoIterator<Item = i32>) -> bool {
+        let mut current = 0;
+        for literal in literals {
+            if self.nodes[current].invalid {
+                return true;
+            }
+            current = match self.nodes[current].children.get(&literal) {
+                Some(&child) => child,
+                None => return false,
+            };
+        }
+        self.nodes[current].invalid
+    }
+
+    /// Forgets all prefixes
+    pub(super) fn clear(&mut self) {
+        self.nodes.truncate(1);
+        self.nodes[0] = TrieNode::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prefixes_reject_their_extensions() {
+        let mut trie = InvalidPrefixes::default();
+        assert!(!trie.rejects([1, 2]));
+        assert!(!trie.rejects([]));
+
+        trie.insert([3, -4]);
+        assert!(trie.rejects([3, -4]));
+        assert!(trie.rejects([3, -4, 7]));
+        assert!(!trie.rejects([3]));
+        assert!(!trie.rejects([-4, 3]));
+        assert!(!trie.rejects([3, 4, -4]));
+
+        // longer prefixes of an invalid prefix do not add anything
+        trie.insert([3, -4, 5]);
+        assert_eq!(3, trie.nodes.len());
+        trie.insert([3]);
+        assert!(trie.rejects([3, 4]));
+
+        trie.clear();
+        assert!(!trie.rejects([3, -4]));
+        assert_eq!(1, trie.nodes.len());
+    }
+}
@@ -147,12 +147,9 @@ mod test {
     fn new_with_literals(vars: HashSet<u32>, mut literals: Vec<i32>) -> Sample {
         literals.sort_unstable();
         literals.dedup();
-        Sample {
-            complete_configs: vec![],
-            partial_configs: vec![],
-            vars,
-            literals,
-        }
+        let mut sample = Sample::new(vars);
+        sample.literals = literals;
+        sample
     }
 
     #[test]
@@ -1,9 +1,27 @@
-use crate::Ddnnf;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use bitvec::slice::BitSlice;
+
+use super::invalid_prefixes::InvalidPrefixes;
+use crate::{
+    ddnnf::anomalies::sat::{new_sat_mark_state, SatMark},
+    Ddnnf,
+};
+
+/// The maximum number of unused sat states a [SatWrapper] keeps for later reuse
+const POOL_LIMIT: usize = 64;
 
 #[derive(Debug, Clone)]
 pub struct SatWrapper<'a> {
     ddnnf: &'a Ddnnf,
-    new_state: Vec<bool>,
+    new_state: SatMark,
+    /// Sat states that are no longer needed. Reusing them avoids allocating a vector
+    /// of the size of the d-DNNF for each SAT call. As the pool is not shared,
+    /// each thread that samples needs its own wrapper.
+    pool: RefCell<Vec<SatMark>>,
+    /// The invalid interactions of the subgraph that was checked last, see [SatWrapper::interaction_state]
+    invalid: RefCell<(usize, InvalidPrefixes)>,
 }
 
 impl<'a> SatWrapper<'a> {
@@ -11,20 +29,53 @@ impl<'a> SatWrapper<'a> {
     pub(super) fn new(ddnnf: &'a Ddnnf) -> Self {
         Self {
             ddnnf,
-            new_state: vec![false; ddnnf.nodes.len()],
+            new_state: new_sat_mark_state(ddnnf.nodes.len()),
+            pool: RefCell::new(Vec::new()),
+            invalid: RefCell::new((ddnnf.nodes.len() - 1, InvalidPrefixes::default())),
         }
     }
 
     /// Create a new state vec for this solver
-    pub(super) fn new_state(&self) -> Vec<bool> {
-        // clone the state where the false nodes are already marked
-        self.new_state.clone()
+    pub(super) fn new_state(&self) -> SatMark {
+        match self.pool.borrow_mut().pop() {
+            Some(mut state) => {
+                state.fill(false);
+                state
+            }
+            None => self.new_state.clone(),
+        }
+    }
+
+    /// Creates a copy of the state that can be changed without affecting the original one
+    pub(super) fn copy_state(&self, state: &BitSlice) -> SatMark {
+        match self.pool.borrow_mut().pop() {
+            Some(mut copy) => {
+                copy.copy_from_bitslice(state);
+                copy
+            }
+            None => state.to_bitvec(),
+        }
+    }
+
+    /// Hands a state that is no longer needed back to the pool
+    pub(super) fn recycle(&self, state: SatMark) {
+        let mut pool = self.pool.borrow_mut();
+        if pool.len() < POOL_LIMIT && state.len() == self.new_state.len() {
+            pool.push(state);
+        }
+    }
+
+    /// Hands a shared state back to the pool if no other config uses it
+    pub(super) fn recycle_shared(&self, state: Arc<SatMark>) {
+        if let Ok(state) = Arc::try_unwrap(state) {
+            self.recycle(state);
+        }
     }
 
     /// Calculates if the given config is SAT. This is the variant with cached state.
     ///
     /// See [SatWrapper] for more details
-    pub(super) fn is_sat_cached(&self, config: &[i32], cached_state: &mut Vec<bool>) -> bool {
+    pub(super) fn is_sat_cached(&self, config: &[i32], cached_state: &mut SatMark) -> bool {
         self.is_sat_in_subgraph_cached(config, self.ddnnf.nodes.len() - 1, cached_state)
     }
 
@@ -35,8 +86,108 @@ impl<'a> SatWrapper<'a> {
         &self,
         config: &[i32],
         root: usize,
-        cached_state: &mut Vec<bool>,
+        cached_state: &mut SatMark,
     ) -> bool {
         self.ddnnf.sat_propagate(config, cached_state, Some(root))
     }
+
+    /// Checks whether the interaction is satisfiable in the subgraph and returns its sat state if it is.
+    ///
+    /// The literals get propagated from the last to the first one, because the interactions of the
+    /// sampler vary their first literals the most. If the interaction is invalid, the shortest invalid
+    /// part from the end gets cached in a trie. Each later interaction that ends with the same literals
+    /// gets rejected without any propagation. The trie only holds the interactions of one subgraph
+    /// and starts over for the next one.
+    pub(super) fn interaction_state(&self, interaction: &[i32], root: usize) -> Option<SatMark> {
+        let mut invalid = self.invalid.borrow_mut();
+        if invalid.0 != root {
+            invalid.0 = root;
+            invalid.1.clear();
+        }
+        if invalid.1.rejects(interaction.iter().rev().copied()) {
+            return None;
+        }
+
+        let mut state = self.new_state();
+        let mut valid =
+            interaction.is_empty() && self.is_sat_in_subgraph_cached(&[], root, &mut state);
+        for (position, &literal) in interaction.iter().rev().enumerate() {
+            valid = self.is_sat_in_subgraph_cached(&[literal], root, &mut state);
+            if !valid {
+                invalid
+                    .1
+                    .insert(interaction.iter().rev().take(position + 1).copied());
+                break;
+            }
+        }
+
+        if valid {
+            Some(state)
+        } else {
+            self.recycle(state);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn sat_states_get_reused() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let sat_solver = SatWrapper::new(&vp9);
+
+        let mut state = sat_solver.new_state();
+        assert!(!sat_solver.is_sat_cached(&[4, -4], &mut state));
+        assert!(state.any());
+
+        let copy = sat_solver.copy_state(&state);
+        assert_eq!(state, copy);
+
+        let address = state.as_bitptr();
+        sat_solver.recycle(state);
+        let reused = sat_solver.new_state();
+        assert_eq!(address, reused.as_bitptr());
+        assert!(reused.not_any());
+
+        // a shared state stays with its other owner
+        let shared = Arc::new(copy);
+        sat_solver.recycle_shared(Arc::clone(&shared));
+        let fresh = sat_solver.new_state();
+        assert_ne!(shared.as_bitptr(), fresh.as_bitptr());
+    }
+
+    #[test]
+    fn invalid_interactions_get_cached() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let sat_solver = SatWrapper::new(&vp9);
+        let root = vp9.nodes.len() - 1;
+
+        let literals: Vec<i32> = (1..=42).flat_map(|f| [f, -f]).collect();
+        for &a in literals.iter() {
+            for &b in literals.iter() {
+                for c in [3, -3, 20, -40] {
+                    let interaction = [a, b, c];
+                    let state = sat_solver.interaction_state(&interaction, root);
+                    assert_eq!(vp9.is_interaction_valid(&interaction), state.is_some());
+
+                    // the state is the same as after propagating the whole interaction at once
+                    let mut expected = sat_solver.new_state();
+                    if sat_solver.is_sat_cached(&interaction, &mut expected) {
+                        assert_eq!(Some(expected), state);
+                    }
+                }
+            }
+        }
+
+        // 40 is a core feature, hence each interaction that ends with -40 gets rejected by the cache
+        let invalid = sat_solver.invalid.borrow();
+        assert_eq!(root, invalid.0);
+        assert!(invalid.1.rejects([-40, 2, 3]));
+        assert!(!invalid.1.rejects([40, 2, 3]));
+    }
 }
@@ -0,0 +1,215 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{Ddnnf, NodeType::*};
+
+/// The best configurations of a node, sorted by their score in descending order
+type Ranked = Vec<(f64, Vec<i32>)>;
+
+impl Ddnnf {
+    /// Computes the k valid configurations with the highest score. The score of a configuration is
+    /// the sum of the weights of its literals. Literals without a weight score 0. To maximize
+    /// the product of weights (e.g. probabilities), supply their logarithms.
+    ///
+    /// Each node keeps its k best partial configurations: Or nodes merge the lists of their children
+    /// and And nodes combine them best-first, hence we never enumerate more than k combinations
+    /// per node. The result is sorted by the score in descending order and each configuration by
+    /// the number of the features. If there are less than k configurations, all of them are returned.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::HashMap;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let weights = HashMap::from([(3, 2.0), (4, 1.0), (-2, 0.5)]);
+    /// let best = ddnnf.top_k(2, &weights);
+    /// assert_eq!(vec![(3.5, vec![1, -2, 3, 4]), (2.5, vec![1, -2, 3, -4])], best);
+    /// ```
+    pub fn top_k(&self, k: usize, weights: &HashMap<i32, f64>) -> Vec<(f64, Vec<i32>)> {
+        if k == 0 || self.nodes.is_empty() {
+            return Vec::new();
+        }
+
+        // the lists of a node are dropped after its last parent combined them
+        let last_use: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|node| node.parents.iter().copied().max().unwrap_or(0))
+            .collect();
+
+        let mut ranked: Vec<Ranked> = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.iter().enumerate() {
+            let best = if node.count == 0 {
+                Vec::new()
+            } else {
+                match &node.ntype {
+                    And { children } => {
+                        children.iter().fold(vec![(0.0, Vec::new())], |best, &c| {
+                            combine_best(&best, &ranked[c], k)
+                        })
+                    }
+                    Or { children } => {
+                        let mut merged: Ranked = children
+                            .iter()
+                            .flat_map(|&c| ranked[c].iter().cloned())
+                            .collect();
+                        merged.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+                        merged.truncate(k);
+                        merged
+                    }
+                    Literal { literal } => {
+                        vec![(weights.get(literal).copied().unwrap_or(0.0), vec![*literal])]
+                    }
+                    True => vec![(0.0, Vec::new())],
+                    False => Vec::new(),
+                }
+            };
+            if let And { children } | Or { children } = &node.ntype {
+                for &child in children.iter().filter(|&&c| last_use[c] == index) {
+                    ranked[child] = Vec::new();
+                }
+            }
+            ranked.push(best);
+        }
+
+        let mut best = ranked.pop().unwrap();
+        for (_, config) in best.iter_mut() {
+            config.sort_unstable_by_key(|literal| literal.abs());
+        }
+        best
+    }
+}
+
+// A candidate combination of the configurations at index i and j of two ranked lists
+struct Candidate {
+    score: f64,
+    i: usize,
+    j: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // the heap pops the highest score first and prefers smaller indices on ties
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| (other.i, other.j).cmp(&(self.i, self.j)))
+    }
+}
+
+// Computes the k best combinations of two ranked lists. Both lists are sorted in descending order,
+// hence the successors (i + 1, j) and (i, j + 1) of a combination can not score higher than the combination itself.
+fn combine_best(left: &Ranked, right: &Ranked, k: usize) -> Ranked {
+    let mut combined = Vec::new();
+    if left.is_empty() || right.is_empty() {
+        return combined;
+    }
+
+    let mut heap = BinaryHeap::from([Candidate {
+        score: left[0].0 + right[0].0,
+        i: 0,
+        j: 0,
+    }]);
+    let mut seen = HashSet::from([(0, 0)]);
+    while let Some(Candidate { score, i, j }) = heap.pop() {
+        let mut config = left[i].1.clone();
+        config.extend_from_slice(&right[j].1);
+        combined.push((score, config));
+        if combined.len() == k {
+            break;
+        }
+
+        for (i, j) in [(i + 1, j), (i, j + 1)] {
+            if i < left.len() && j < right.len() && seen.insert((i, j)) {
+                heap.push(Candidate {
+                    score: left[i].0 + right[j].0,
+                    i,
+                    j,
+                });
+            }
+        }
+    }
+    combined
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn top_k_matches_brute_force() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+        let weights: HashMap<i32, f64> = (1..=4)
+            .flat_map(|f| [(f, (f * 7 % 5) as f64), (-f, (f * 3 % 4) as f64 / 2.0)])
+            .collect();
+
+        let mut expected: Vec<(f64, Vec<i32>)> = ddnnf
+            .brute_force_enumerate()
+            .unwrap()
+            .into_iter()
+            .map(|config| (config.iter().map(|l| weights[l]).sum(), config))
+            .collect();
+        expected.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        for k in 1..=expected.len() + 1 {
+            let best = ddnnf.top_k(k, &weights);
+            assert_eq!(k.min(expected.len()), best.len());
+            for ((score, _), (actual, config)) in expected.iter().zip(best.iter()) {
+                assert_eq!(score, actual);
+                assert!(expected.contains(&(*actual, config.clone())));
+            }
+        }
+    }
+
+    #[test]
+    fn top_k_of_a_bigger_model() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let weights = HashMap::from([(3, 1.0), (4, 1.0), (5, 1.0)]);
+        let optimal = vp9.execute_query(&[3, 4, 5]).to_usize().unwrap();
+
+        let best = vp9.top_k(100, &weights);
+        assert_eq!(100, best.len());
+        assert!(best.windows(2).all(|w| w[0].0 >= w[1].0));
+        for (i, (score, config)) in best.iter().enumerate() {
+            assert_eq!(i < optimal, *score == 3.0);
+            assert_eq!(42, config.len());
+            assert!(vp9.is_interaction_valid(config));
+        }
+        let distinct: HashSet<&Vec<i32>> = best.iter().map(|(_, config)| config).collect();
+        assert_eq!(best.len(), distinct.len());
+    }
+
+    #[test]
+    fn top_k_edge_cases() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        assert!(ddnnf.top_k(0, &HashMap::new()).is_empty());
+        assert_eq!(4, ddnnf.top_k(10, &HashMap::new()).len());
+
+        // a negative weight ranks the configurations without the literal first
+        let best = ddnnf.top_k(4, &HashMap::from([(4, -1.0)]));
+        let scores: Vec<f64> = best.iter().map(|(score, _)| *score).collect();
+        assert_eq!(vec![0.0, 0.0, -1.0, -1.0], scores);
+        assert!(best[..2].iter().all(|(_, config)| config.contains(&-4)));
+    }
+}
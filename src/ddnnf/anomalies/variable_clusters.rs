@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Ddnnf, NodeType::Or};
+
+impl Ddnnf {
+    /// Clusters the variables by the decisions they depend on. Two variables belong to the same
+    /// cluster iff each Or node either has both of them in its sub-DAG or none of them.
+    /// Or nodes that only decide about a single variable (e.g. the ones that smoothing adds) are ignored.
+    /// Hence, the variables of a cluster always get decided together, which makes the clusters
+    /// de-facto modules of the configuration space, e.g. to group variables for sampling or visualization.
+    ///
+    /// Variables that are not below any such Or node (e.g. core, dead, and unconstrained features)
+    /// form a cluster of their own. The variables of each cluster as well as the clusters are sorted
+    /// by the smallest variable. The d-DNNF stays the same.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // (1 ∧ 2 ∨ ¬1 ∧ ¬2) ∧ (3 ∨ ¬3)
+    /// let lines = [
+    ///     "nnf 11 12 3", "L 1", "L 2", "L -1", "L -2", "L 3", "L -3",
+    ///     "A 2 0 1", "A 2 2 3", "O 1 2 6 7", "O 3 2 4 5", "A 2 8 9",
+    /// ];
+    /// let ddnnf = distribute_building(lines.map(String::from).to_vec(), None, None);
+    /// assert_eq!(vec![vec![1, 2], vec![3]], ddnnf.variable_clusters());
+    /// ```
+    pub fn variable_clusters(&self) -> Vec<Vec<u32>> {
+        let supports = self.supports();
+        let decisions: BTreeSet<&Vec<u32>> = self
+            .nodes
+            .iter()
+            .zip(supports.iter())
+            .filter(|(node, support)| matches!(node.ntype, Or { .. }) && support.len() > 1)
+            .map(|(_, support)| support.as_ref())
+            .collect();
+
+        let mut signatures: Vec<Vec<usize>> =
+            vec![Vec::new(); self.number_of_variables as usize + 1];
+        for (decision, support) in decisions.into_iter().enumerate() {
+            for &variable in support {
+                if let Some(signature) = signatures.get_mut(variable as usize) {
+                    signature.push(decision);
+                }
+            }
+        }
+
+        let mut clusters: BTreeMap<&Vec<usize>, Vec<u32>> = BTreeMap::new();
+        for variable in 1..=self.number_of_variables {
+            clusters
+                .entry(&signatures[variable as usize])
+                .or_default()
+                .push(variable);
+        }
+        let mut clusters: Vec<Vec<u32>> = clusters.into_values().collect();
+        clusters.sort_unstable();
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn clusters_partition_the_variables() {
+        for ddnnf in [
+            build_ddnnf("tests/data/VP9_d4.nnf", Some(42)),
+            build_ddnnf("tests/data/auto1_c2d.nnf", None),
+        ] {
+            let clusters = ddnnf.variable_clusters();
+            let mut variables: Vec<u32> = clusters.iter().flatten().copied().collect();
+            variables.sort_unstable();
+            assert_eq!(
+                (1..=ddnnf.number_of_variables).collect::<Vec<u32>>(),
+                variables
+            );
+            assert!(clusters.len() > 1);
+
+            // each Or node contains all variables of a cluster or none of them
+            let supports = ddnnf.supports();
+            let cluster_of: BTreeMap<u32, usize> = clusters
+                .iter()
+                .enumerate()
+                .flat_map(|(index, cluster)| cluster.iter().map(move |&v| (v, index)))
+                .collect();
+            for (node, support) in ddnnf.nodes.iter().zip(supports.iter()) {
+                if matches!(node.ntype, Or { .. }) && support.len() > 1 {
+                    for variable in support.iter() {
+                        let cluster = &clusters[cluster_of[variable]];
+                        assert!(cluster.iter().all(|v| support.binary_search(v).is_ok()));
+                    }
+                }
+            }
+        }
+    }
+}
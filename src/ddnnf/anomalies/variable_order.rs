@@ -0,0 +1,119 @@
+use std::{collections::HashMap, fs};
+
+/// The order of the variables in materialized configurations, i.e. in enumerations,
+/// samples, and their exports. By default, the literals are sorted by their variable number.
+/// A custom order, like the hierarchy of a feature model, lists the variables in the desired order.
+/// Variables that are not listed follow in ascending order of their number.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VariableOrder {
+    order: Vec<u32>,
+    ranks: HashMap<u32, usize>,
+}
+
+impl VariableOrder {
+    /// Sorts the literals by their variable number
+    pub fn model() -> Self {
+        Self::default()
+    }
+
+    /// Uses the given permutation of (a subset of) the variables.
+    /// Returns an error if a variable is 0 or appears twice.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::ddnnf::anomalies::variable_order::VariableOrder;
+    ///
+    /// let order = VariableOrder::custom(vec![3, 1]).unwrap();
+    /// let mut config = vec![1, -2, 3, -4];
+    /// order.sort(&mut config);
+    /// assert_eq!(vec![3, 1, -2, -4], config);
+    ///
+    /// assert!(VariableOrder::custom(vec![3, 1, 3]).is_err());
+    /// ```
+    pub fn custom(order: Vec<u32>) -> Result<Self, String> {
+        let mut ranks = HashMap::with_capacity(order.len());
+        for (rank, &variable) in order.iter().enumerate() {
+            if variable == 0 {
+                return Err(String::from("0 is no variable"));
+            }
+            if ranks.insert(variable, rank).is_some() {
+                return Err(format!("the variable {variable} appears more than once"));
+            }
+        }
+        Ok(Self { order, ranks })
+    }
+
+    /// Reads a custom order from a file that contains the variable numbers separated by whitespaces.
+    /// Lines starting with 'c' are comments.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("unable to read {path}: {err}"))?;
+        let order = content
+            .lines()
+            .filter(|line| !line.starts_with('c'))
+            .flat_map(str::split_whitespace)
+            .map(|word| {
+                word.parse::<u32>()
+                    .map_err(|_| format!("'{word}' is no variable number"))
+            })
+            .collect::<Result<_, _>>()?;
+        Self::custom(order)
+    }
+
+    /// The explicitly ordered variables. Empty for the model order.
+    pub fn variables(&self) -> &[u32] {
+        &self.order
+    }
+
+    /// Sorts the literals of a configuration according to the order
+    pub fn sort(&self, config: &mut [i32]) {
+        if self.ranks.is_empty() {
+            config.sort_unstable_by_key(|f| f.abs());
+        } else {
+            config.sort_by_cached_key(|f| {
+                let variable = f.unsigned_abs();
+                match self.ranks.get(&variable) {
+                    Some(&rank) => (false, rank),
+                    None => (true, variable as usize),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn orders_get_applied() {
+        let mut config = vec![-5, 2, 1, -3, 4];
+        VariableOrder::model().sort(&mut config);
+        assert_eq!(vec![1, 2, -3, 4, -5], config);
+
+        let reversed = VariableOrder::custom((1..=5).rev().collect()).unwrap();
+        reversed.sort(&mut config);
+        assert_eq!(vec![-5, 4, -3, 2, 1], config);
+
+        let partial = VariableOrder::custom(vec![4, 2]).unwrap();
+        partial.sort(&mut config);
+        assert_eq!(vec![4, 2, 1, -3, -5], config);
+        assert_eq!(&[4, 2], partial.variables());
+
+        assert!(VariableOrder::custom(vec![0]).is_err());
+    }
+
+    #[test]
+    fn orders_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("order.txt");
+        fs::write(&path, "c hierarchy order\n3 1\n2\n").unwrap();
+        let order = VariableOrder::from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(&[3, 1, 2], order.variables());
+
+        fs::write(&path, "3 x").unwrap();
+        assert!(VariableOrder::from_file(path.to_str().unwrap()).is_err());
+        assert!(VariableOrder::from_file("tests/data/does_not_exist.txt").is_err());
+    }
+}
@@ -1,11 +1,11 @@
 use std::collections::BTreeSet;
 
+#[cfg(feature = "editing")]
 use tempfile::Builder;
 
-use crate::{
-    parser::{build_ddnnf, persisting::write_cnf_to_file},
-    Ddnnf,
-};
+#[cfg(feature = "editing")]
+use crate::parser::{build_ddnnf_with_settings, persisting::write_cnf_to_file};
+use crate::{parser::CompileStats, settings::Settings, Ddnnf};
 
 #[derive(Debug, Clone, Default)]
 /// Represents all types of Nodes with its different parts
@@ -22,12 +22,82 @@ pub struct ClauseCache {
     old_total_features: Option<u32>,
     /// An old cached d-DNNF state that can be swaped with the currently used one.
     pub old_state: Option<Box<Ddnnf>>,
+    /// The measurements of the initial compilation and the recompilations of all edits
+    compilations: Vec<CompileStats>,
+    /// The clauses that got added by edits and are not part of the initial CNF
+    added: BTreeSet<BTreeSet<i32>>,
+    /// The clauses of the initial CNF that got removed by edits
+    removed_original: BTreeSet<BTreeSet<i32>>,
+    /// The settings the CNF got compiled with. Edits recompile with the same compiler and cache directory.
+    settings: Settings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Where a clause of the current CNF originates from
+pub enum ClauseOrigin {
+    /// The clause is part of the CNF the d-DNNF was compiled from
+    Original,
+    /// The clause got added by a clause update
+    Added,
+}
+
+/// Normalizes a clause that originates from an external source.
+/// Duplicate literals are removed. A tautology (a clause that contains x and ¬x) is satisfied by
+/// every configuration and results in None because it can be skipped.
+/// Returns an error for empty clauses, the literal 0, and variables beyond total_features.
+pub fn normalize_clause(
+    clause: &[i32],
+    total_features: u32,
+) -> Result<Option<BTreeSet<i32>>, String> {
+    if clause.is_empty() {
+        return Err(String::from("the clause is empty"));
+    }
+
+    let mut normalized = BTreeSet::new();
+    for &literal in clause {
+        if literal == 0 {
+            return Err(String::from("0 is not a valid literal"));
+        }
+        if literal.unsigned_abs() > total_features {
+            return Err(format!(
+                "the literal {} exceeds the number of features {}",
+                literal, total_features
+            ));
+        }
+        normalized.insert(literal);
+    }
+
+    if normalized
+        .iter()
+        .any(|literal| normalized.contains(&-literal))
+    {
+        return Ok(None);
+    }
+    Ok(Some(normalized))
+}
+
+/// Normalizes each clause (see [normalize_clause]) and skips tautologies.
+/// Fails if any clause is invalid.
+pub fn normalize_clauses<'a, I: IntoIterator<Item = &'a BTreeSet<i32>>>(
+    clauses: I,
+    total_features: u32,
+) -> Result<Vec<BTreeSet<i32>>, String> {
+    let mut normalized = Vec::new();
+    for clause in clauses {
+        let literals: Vec<i32> = clause.iter().copied().collect();
+        if let Some(clause) = normalize_clause(&literals, total_features)? {
+            normalized.push(clause);
+        }
+    }
+    Ok(normalized)
 }
 
 impl ClauseCache {
     /// Updates the ClauseCache with starting values.
     pub fn initialize(&mut self, clauses: BTreeSet<BTreeSet<i32>>, total_features: u32) {
         self.old_state = None;
+        self.added.clear();
+        self.removed_original.clear();
         self.clauses = clauses;
         self.total_features = Some(total_features);
         self.old_total_features = Some(total_features);
@@ -50,7 +120,15 @@ impl ClauseCache {
             }
         }
 
+        for clause in rmv.iter() {
+            if !self.added.remove(clause) {
+                self.removed_original.insert(clause.clone());
+            }
+        }
         for clause in add.clone().into_iter() {
+            if !self.removed_original.remove(&clause) && !self.clauses.contains(&clause) {
+                self.added.insert(clause.clone());
+            }
             self.clauses.insert(clause);
         }
 
@@ -62,6 +140,34 @@ impl ClauseCache {
         true
     }
 
+    /// Returns the clauses that got added and removed by the last edit (or undo)
+    #[cfg(feature = "sampling")]
+    pub(crate) fn last_edit(&self) -> (&[BTreeSet<i32>], &[BTreeSet<i32>]) {
+        (&self.edit_add, &self.edit_rmv)
+    }
+
+    /// The clauses that contain a literal of the feature together with their origin
+    pub fn clauses_with(&self, feature: u32) -> Vec<(&BTreeSet<i32>, ClauseOrigin)> {
+        let feature = feature as i32;
+        self.clauses
+            .iter()
+            .filter(|clause| clause.contains(&feature) || clause.contains(&-feature))
+            .map(|clause| {
+                let origin = if self.added.contains(clause) {
+                    ClauseOrigin::Added
+                } else {
+                    ClauseOrigin::Original
+                };
+                (clause, origin)
+            })
+            .collect()
+    }
+
+    /// The clauses of the current CNF that got added by clause updates
+    pub fn added_clauses(&self) -> impl Iterator<Item = &BTreeSet<i32>> {
+        self.added.iter()
+    }
+
     /// Sets up the edit operations for an undo operation by applying and flipping added and removed clauses.
     pub fn setup_for_undo(&mut self) -> bool {
         self.setup_for_edit(
@@ -71,6 +177,7 @@ impl ClauseCache {
         )
     }
 
+    #[cfg(feature = "editing")]
     pub fn apply_edits_and_replace(
         &mut self,
         add: Vec<BTreeSet<i32>>,
@@ -96,10 +203,42 @@ impl ClauseCache {
         write_cnf_to_file(&self.clauses, self.total_features.unwrap(), temp_path)
             .expect("Failed to save updated CNF to file");
 
-        self.old_state = Some(Box::new(build_ddnnf(temp_path, None)));
+        let recompiled = build_ddnnf_with_settings(temp_path, None, &self.settings);
+        self.compilations
+            .extend(recompiled.compile_stats().last().copied());
+        self.old_state = Some(Box::new(recompiled));
         true
     }
 
+    /// Without the editing feature, the edited CNF can not be recompiled, hence each edit fails
+    #[cfg(not(feature = "editing"))]
+    pub fn apply_edits_and_replace(
+        &mut self,
+        _add: Vec<BTreeSet<i32>>,
+        _rmv: Vec<BTreeSet<i32>>,
+        _total: u32,
+    ) -> bool {
+        false
+    }
+
+    /// The settings that get used to recompile the CNF after edits
+    pub(crate) fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub(crate) fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    /// The measurements of all compilations of this d-DNNF
+    pub(crate) fn compilations(&self) -> &[CompileStats] {
+        &self.compilations
+    }
+
+    pub(crate) fn record_compilation(&mut self, stats: CompileStats) {
+        self.compilations.push(stats);
+    }
+
     /// Sets up the edit operations for an undo operation by applying and flipping added and removed clauses.
     pub fn contains_conflicting_clauses(&mut self, total_features: u32) -> bool {
         self.clauses.iter().any(|clause| {
@@ -109,3 +248,78 @@ impl ClauseCache {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalizes_clauses() {
+        assert_eq!(
+            Ok(Some(BTreeSet::from([-3, 1, 2]))),
+            normalize_clause(&[2, 1, -3, 2, 1], 3)
+        );
+        assert_eq!(Ok(None), normalize_clause(&[1, -2, 3, 2], 3));
+
+        assert!(normalize_clause(&[], 3).is_err());
+        assert!(normalize_clause(&[1, 0, 2], 3).is_err());
+        assert!(normalize_clause(&[1, -4], 3).is_err());
+
+        let clauses = vec![
+            BTreeSet::from([1, -1]),
+            BTreeSet::from([1, 2]),
+            BTreeSet::from([-2, 3]),
+        ];
+        assert_eq!(
+            Ok(vec![BTreeSet::from([1, 2]), BTreeSet::from([-2, 3])]),
+            normalize_clauses(&clauses, 3)
+        );
+        assert!(normalize_clauses(&clauses, 2).is_err());
+    }
+
+    #[test]
+    fn origins_of_clauses() {
+        let mut cache = ClauseCache::default();
+        cache.initialize(
+            BTreeSet::from([BTreeSet::from([1, 2]), BTreeSet::from([-2, 3])]),
+            4,
+        );
+        let origins = |cache: &ClauseCache, feature| {
+            cache
+                .clauses_with(feature)
+                .into_iter()
+                .map(|(clause, origin)| (clause.clone(), origin))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![
+                (BTreeSet::from([-2, 3]), ClauseOrigin::Original),
+                (BTreeSet::from([1, 2]), ClauseOrigin::Original)
+            ],
+            origins(&cache, 2)
+        );
+
+        // adding an existing clause keeps its origin
+        assert!(cache.setup_for_edit(
+            vec![BTreeSet::from([-4, 2]), BTreeSet::from([1, 2])],
+            vec![BTreeSet::from([-2, 3])],
+            Some(4)
+        ));
+        assert_eq!(
+            vec![
+                (BTreeSet::from([-4, 2]), ClauseOrigin::Added),
+                (BTreeSet::from([1, 2]), ClauseOrigin::Original)
+            ],
+            origins(&cache, 2)
+        );
+        assert!(origins(&cache, 3).is_empty());
+
+        // undoing restores the original clause and drops the added one
+        assert!(cache.setup_for_undo());
+        assert_eq!(
+            vec![(BTreeSet::from([-2, 3]), ClauseOrigin::Original)],
+            origins(&cache, 3)
+        );
+        assert!(origins(&cache, 4).is_empty());
+    }
+}
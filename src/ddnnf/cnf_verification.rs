@@ -0,0 +1,271 @@
+use std::{collections::BTreeSet, fmt};
+
+use rug::Integer;
+
+use super::counting::brute_force::{satisfies, BRUTE_FORCE_LIMIT};
+use crate::{parser::util::format_vec, Ddnnf};
+
+/// A deviation between a d-DNNF and the CNF it was supposedly compiled from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+    /// The CNF and the d-DNNF are defined over different numbers of variables
+    VariableMismatch { cnf: u32, ddnnf: u32 },
+    /// The clause contains variables that the d-DNNF does not know and hence can not be checked
+    UnknownVariables { clause: BTreeSet<i32> },
+    /// That many models of the d-DNNF do not satisfy the clause
+    ViolatedClause {
+        clause: BTreeSet<i32>,
+        models: Integer,
+    },
+    /// The d-DNNF has a different number of models than the CNF
+    CountMismatch { ddnnf: Integer, cnf: Integer },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::VariableMismatch { cnf, ddnnf } => write!(
+                f,
+                "the CNF has {cnf} variables, but the d-DNNF has {ddnnf} variables"
+            ),
+            Discrepancy::UnknownVariables { clause } => write!(
+                f,
+                "the clause {} contains variables that are not part of the d-DNNF",
+                format_vec(clause.iter())
+            ),
+            Discrepancy::ViolatedClause { clause, models } => write!(
+                f,
+                "{models} models of the d-DNNF violate the clause {}",
+                format_vec(clause.iter())
+            ),
+            Discrepancy::CountMismatch { ddnnf, cnf } => write!(
+                f,
+                "the d-DNNF has {ddnnf} models, but the CNF has {cnf} models"
+            ),
+        }
+    }
+}
+
+/// The result of [Ddnnf::verify_against_cnf]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnfVerification {
+    /// The number of clauses that were checked for entailment
+    pub checked_clauses: usize,
+    /// The number of models of the CNF, if it was small enough to count them by brute force
+    pub reference_count: Option<Integer>,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl CnfVerification {
+    /// Checks whether the d-DNNF has exactly the models of the CNF. Returns None if there is no
+    /// discrepancy, but the CNF was too large to count its models. Then, we only know that each model
+    /// of the d-DNNF is a model of the CNF, but not whether the d-DNNF lacks models of the CNF.
+    pub fn is_valid(&self) -> Option<bool> {
+        if !self.discrepancies.is_empty() {
+            Some(false)
+        } else {
+            self.reference_count.as_ref().map(|_| true)
+        }
+    }
+}
+
+impl fmt::Display for CnfVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Each of the {} clauses was checked for entailment.",
+            self.checked_clauses
+        )?;
+        match &self.reference_count {
+            Some(count) => writeln!(f, "The CNF has {count} models.")?,
+            None => writeln!(
+                f,
+                "The CNF has more than {BRUTE_FORCE_LIMIT} variables, hence the counts were not compared."
+            )?,
+        }
+        for discrepancy in self.discrepancies.iter() {
+            writeln!(f, "Discrepancy: {discrepancy}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Ddnnf {
+    /// Checks whether this d-DNNF represents the CNF. Each model of the d-DNNF has to satisfy each clause,
+    /// which holds iff the d-DNNF has no model that contains the negations of all literals of the clause.
+    /// If the CNF has at most [BRUTE_FORCE_LIMIT] variables, its models are counted by brute force
+    /// and compared with the count of the d-DNNF. Together, both checks show that the models are the same.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::BTreeSet;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::parser::from_cnf::read_cnf;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let (variables, mut clauses) = read_cnf("./tests/data/small_ex.cnf").unwrap();
+    /// assert_eq!(Some(true), ddnnf.verify_against_cnf(variables, &clauses).is_valid());
+    ///
+    /// // the clause removes models of the CNF which the d-DNNF still contains
+    /// clauses.insert(BTreeSet::from([-4]));
+    /// assert_eq!(Some(false), ddnnf.verify_against_cnf(variables, &clauses).is_valid());
+    /// ```
+    pub fn verify_against_cnf(
+        &mut self,
+        number_of_variables: u32,
+        clauses: &BTreeSet<BTreeSet<i32>>,
+    ) -> CnfVerification {
+        let mut discrepancies = Vec::new();
+        if number_of_variables != self.number_of_variables {
+            discrepancies.push(Discrepancy::VariableMismatch {
+                cnf: number_of_variables,
+                ddnnf: self.number_of_variables,
+            });
+        }
+
+        let mut checked_clauses = 0;
+        for clause in clauses.iter() {
+            if clause
+                .iter()
+                .any(|l| l.unsigned_abs() > self.number_of_variables)
+            {
+                discrepancies.push(Discrepancy::UnknownVariables {
+                    clause: clause.clone(),
+                });
+                continue;
+            }
+            checked_clauses += 1;
+            // tautologies are satisfied by each model
+            if clause.iter().any(|l| clause.contains(&-l)) {
+                continue;
+            }
+
+            let violation: Vec<i32> = clause.iter().map(|l| -l).collect();
+            let models = self.execute_query(&violation);
+            if models > 0 {
+                discrepancies.push(Discrepancy::ViolatedClause {
+                    clause: clause.clone(),
+                    models,
+                });
+            }
+        }
+
+        let reference_count = brute_force_cnf_count(number_of_variables, clauses);
+        if let Some(count) = &reference_count {
+            if number_of_variables == self.number_of_variables && *count != self.rc() {
+                discrepancies.push(Discrepancy::CountMismatch {
+                    ddnnf: self.rc(),
+                    cnf: count.clone(),
+                });
+            }
+        }
+
+        CnfVerification {
+            checked_clauses,
+            reference_count,
+            discrepancies,
+        }
+    }
+}
+
+// Counts the models of the CNF by evaluating each complete assignment.
+// Returns None if the CNF has more than BRUTE_FORCE_LIMIT variables.
+fn brute_force_cnf_count(
+    number_of_variables: u32,
+    clauses: &BTreeSet<BTreeSet<i32>>,
+) -> Option<Integer> {
+    if number_of_variables > BRUTE_FORCE_LIMIT {
+        return None;
+    }
+    let models = (0..1_u64 << number_of_variables)
+        .filter(|&assignment| {
+            clauses
+                .iter()
+                .all(|clause| clause.iter().any(|&l| satisfies(assignment, &[l])))
+        })
+        .count();
+    Some(Integer::from(models))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{build_ddnnf, from_cnf::read_cnf};
+
+    use super::*;
+
+    #[test]
+    fn small_example_matches_its_cnf() {
+        let (variables, clauses) = read_cnf("tests/data/small_ex.cnf").unwrap();
+        let mut c2d: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let mut d4: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+
+        for ddnnf in [&mut c2d, &mut d4] {
+            let verification = ddnnf.verify_against_cnf(variables, &clauses);
+            assert_eq!(
+                CnfVerification {
+                    checked_clauses: 12,
+                    reference_count: Some(ddnnf.rc()),
+                    discrepancies: vec![]
+                },
+                verification
+            );
+        }
+    }
+
+    #[test]
+    fn discrepancies() {
+        let (_, mut clauses) = read_cnf("tests/data/small_ex.cnf").unwrap();
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+
+        // a model of the d-DNNF violates the unit clause, while the tautology holds trivially
+        clauses.insert(BTreeSet::from([-1]));
+        clauses.insert(BTreeSet::from([2, -2]));
+        clauses.insert(BTreeSet::from([5]));
+        let verification = ddnnf.verify_against_cnf(5, &clauses);
+        let violating = ddnnf.execute_query(&[1]);
+        assert_eq!(
+            vec![
+                Discrepancy::VariableMismatch { cnf: 5, ddnnf: 4 },
+                Discrepancy::ViolatedClause {
+                    clause: BTreeSet::from([-1]),
+                    models: violating
+                },
+                Discrepancy::UnknownVariables {
+                    clause: BTreeSet::from([5])
+                },
+            ],
+            verification.discrepancies
+        );
+        assert_eq!(14, verification.checked_clauses);
+        assert_eq!(Some(false), verification.is_valid());
+
+        // without clauses, the CNF has all configurations as models
+        let verification = ddnnf.verify_against_cnf(4, &BTreeSet::new());
+        assert_eq!(
+            vec![Discrepancy::CountMismatch {
+                ddnnf: ddnnf.rc(),
+                cnf: Integer::from(16)
+            }],
+            verification.discrepancies
+        );
+    }
+
+    #[test]
+    fn large_cnfs_only_get_checked_for_soundness() {
+        let (variables, clauses) = read_cnf("tests/data/VP9.cnf").unwrap();
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert!(variables > BRUTE_FORCE_LIMIT);
+        assert_eq!(None, vp9.verify_against_cnf(variables, &clauses).is_valid());
+
+        // a d-DNNF without any model satisfies each clause, but it lacks the models of the CNF
+        let mut unsatisfiable = Ddnnf {
+            number_of_variables: variables,
+            ..Default::default()
+        };
+        let verification = unsatisfiable.verify_against_cnf(variables, &clauses);
+        assert!(verification.discrepancies.is_empty());
+        assert_eq!(None, verification.is_valid());
+    }
+}
@@ -0,0 +1,125 @@
+use super::{
+    events::ModelEvent,
+    node::{NodeType, NodeType::*},
+    Ddnnf,
+};
+
+impl Ddnnf {
+    /// Creates a copy of the d-DNNF that only contains the configurations that fulfill the assumptions.
+    /// The literals that contradict an assumption become False and the resulting False nodes
+    /// get propagated upwards: And nodes with a False child become False and Or nodes lose their False children.
+    /// Hence, the conditioned d-DNNF is at most as large as the original one.
+    ///
+    /// The copy can not be edited via clause updates. If the assumptions are contradicting,
+    /// the conditioned d-DNNF consists of a single False node.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let mut conditioned = ddnnf.condition(&[3]);
+    ///
+    /// assert_eq!(ddnnf.execute_query(&[3]), conditioned.rc());
+    /// assert_eq!(0, conditioned.execute_query(&[-3]));
+    /// ```
+    pub fn condition(&self, assumptions: &[i32]) -> Ddnnf {
+        let conditioned = self.conditioned(assumptions);
+        self.notify(ModelEvent::Conditioned {
+            assumptions: assumptions.to_vec(),
+        });
+        conditioned
+    }
+
+    // Conditions the d-DNNF without notifying the listeners, e.g. for internal intermediate results
+    pub(crate) fn conditioned(&self, assumptions: &[i32]) -> Ddnnf {
+        let mut ntypes: Vec<NodeType> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let is_false = |ntypes: &[NodeType], child: usize| matches!(ntypes[child], False);
+            let ntype = match &node.ntype {
+                Literal { literal } if assumptions.contains(&-literal) => False,
+                And { children } if children.iter().any(|&c| is_false(&ntypes, c)) => False,
+                Or { children } => {
+                    let children: Vec<usize> = children
+                        .iter()
+                        .copied()
+                        .filter(|&c| !is_false(&ntypes, c))
+                        .collect();
+                    if children.is_empty() {
+                        False
+                    } else {
+                        Or { children }
+                    }
+                }
+                ntype => ntype.clone(),
+            };
+            ntypes.push(ntype);
+        }
+
+        let mut conditioned = Ddnnf {
+            max_worker: self.max_worker,
+            max_digits: self.max_digits,
+            number_of_variables: self.number_of_variables,
+            annotations: self.annotations.clone(),
+            feature_names: self.feature_names.clone(),
+            numeric_features: self.numeric_features.clone(),
+            variable_order: self.variable_order.clone(),
+            ..Default::default()
+        };
+        if matches!(ntypes.last(), Some(False)) {
+            // all other nodes are obsolete
+            ntypes = vec![False];
+        }
+        conditioned.replace_nodes(ntypes);
+        conditioned
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "sampling")]
+    use crate::ddnnf::generator::{generate_ddnnf, GeneratorConfig};
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn conditioning_matches_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        for assumptions in [vec![], vec![4], vec![-4, 9], vec![1, -38, 20]] {
+            let mut conditioned = vp9.condition(&assumptions);
+            assert_eq!(vp9.execute_query(&assumptions), conditioned.rc());
+            assert!(conditioned.nodes.len() <= vp9.nodes.len());
+
+            for feature in 1..=42 {
+                let mut query = assumptions.clone();
+                query.push(feature);
+                assert_eq!(
+                    vp9.execute_query(&query),
+                    conditioned.execute_query(&[feature])
+                );
+            }
+        }
+
+        let mut contradiction = vp9.condition(&[4, -4]);
+        assert_eq!(1, contradiction.nodes.len());
+        assert_eq!(0, contradiction.rc());
+        assert!(!contradiction.sat(&[]));
+
+        #[cfg(feature = "sampling")]
+        for seed in 0..10 {
+            let ddnnf = generate_ddnnf(&GeneratorConfig {
+                seed,
+                ..Default::default()
+            });
+            for literal in [1, -2, 3] {
+                assert_eq!(
+                    ddnnf.brute_force_count(&[literal]),
+                    ddnnf.condition(&[literal]).brute_force_count(&[])
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use rug::Integer;
+
+use super::counting::derivatives::Derivatives;
+use crate::Ddnnf;
+
+/// An interactive configuration of a d-DNNF, e.g. as backend of a configurator. The session keeps the
+/// decisions of the user and after each step the literals they imply, the number of remaining configurations,
+/// and a suggestion for the next decision. Decisions that would leave no valid configuration get rejected,
+/// hence the decisions are always consistent. Many sessions can share the same d-DNNF.
+/// Not to be confused with the sessions of the [Daemon](super::daemon::Daemon), which serve clients.
+///
+/// # Example
+/// ```
+/// extern crate ddnnf_lib;
+/// use ddnnf_lib::Ddnnf;
+/// use ddnnf_lib::ddnnf::configurator::Session;
+/// use ddnnf_lib::parser::*;
+///
+/// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+/// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+/// let mut session = Session::new(&ddnnf);
+/// assert_eq!(&[1], session.implied());
+/// assert_eq!(4, *session.remaining_count());
+///
+/// // selecting 2 excludes 3
+/// assert_eq!(Ok(vec![-3]), session.select(2));
+/// assert_eq!(2, *session.remaining_count());
+/// assert!(session.select(3).is_err());
+/// assert_eq!(Some(4), session.suggest_next());
+///
+/// assert_eq!(Some(2), session.undo());
+/// assert_eq!(&[1], session.implied());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session<'a> {
+    ddnnf: &'a Ddnnf,
+    decisions: Vec<i32>,
+    implied: Vec<i32>,
+    derivatives: Derivatives,
+}
+
+impl<'a> Session<'a> {
+    /// Starts a session without any decision
+    pub fn new(ddnnf: &'a Ddnnf) -> Self {
+        let mut session = Session {
+            ddnnf,
+            decisions: Vec::new(),
+            implied: Vec::new(),
+            derivatives: ddnnf.derivatives(&[]),
+        };
+        session.update();
+        session
+    }
+
+    /// Selects the feature. Returns the literals that the decision implies additionally or an error
+    /// if the feature is unknown or no valid configuration contains it under the current decisions.
+    pub fn select(&mut self, feature: u32) -> Result<Vec<i32>, String> {
+        self.decide(feature as i32)
+    }
+
+    /// Deselects the feature like [Session::select]
+    pub fn deselect(&mut self, feature: u32) -> Result<Vec<i32>, String> {
+        self.decide(-(feature as i32))
+    }
+
+    /// Reverts the last decision and returns it. Returns None if there is no decision.
+    pub fn undo(&mut self) -> Option<i32> {
+        let decision = self.decisions.pop()?;
+        self.update();
+        Some(decision)
+    }
+
+    /// The decisions of the user in their order
+    pub fn decisions(&self) -> &[i32] {
+        &self.decisions
+    }
+
+    /// The literals that the decisions imply, i.e. that each remaining configuration contains,
+    /// sorted by the number of the features. The decisions themselves are left out.
+    pub fn implied(&self) -> &[i32] {
+        &self.implied
+    }
+
+    /// The number of valid configurations that contain the decisions
+    pub fn remaining_count(&self) -> &Integer {
+        &self.derivatives.count
+    }
+
+    /// Suggests the open feature whose decision splits the remaining configurations most evenly. Asking the
+    /// user about it rules out the most configurations in the worst case. Ties go to the smaller feature.
+    /// Returns None if the decisions and the implied literals already determine a single configuration.
+    pub fn suggest_next(&self) -> Option<u32> {
+        (1..=self.ddnnf.number_of_variables)
+            .filter_map(|feature| {
+                let selected = self.derivatives.literal(feature as i32);
+                let deselected = self.derivatives.literal(-(feature as i32));
+                if selected == 0 || deselected == 0 {
+                    return None;
+                }
+                Some(((selected - deselected).abs(), feature))
+            })
+            .min()
+            .map(|(_, feature)| feature)
+    }
+
+    // Adds the decision if it is consistent and returns the newly implied literals
+    fn decide(&mut self, literal: i32) -> Result<Vec<i32>, String> {
+        if literal == 0 || literal.unsigned_abs() > self.ddnnf.number_of_variables {
+            return Err(format!("the feature {} does not exist", literal.abs()));
+        }
+        if self.derivatives.literal(literal) == 0 {
+            return Err(if self.derivatives.count == 0 {
+                String::from("the model has no valid configuration")
+            } else if self.decisions.contains(&-literal) {
+                format!("{literal} contradicts the decision {}", -literal)
+            } else {
+                format!("{literal} contradicts the implied {}", -literal)
+            });
+        }
+
+        let before = std::mem::take(&mut self.implied);
+        self.decisions.push(literal);
+        self.update();
+        Ok(self
+            .implied
+            .iter()
+            .copied()
+            .filter(|implied| !before.contains(implied))
+            .collect())
+    }
+
+    // Computes the counts of all literals under the decisions, which determine the implied literals
+    fn update(&mut self) {
+        self.derivatives = self.ddnnf.derivatives(&self.decisions);
+        self.implied = if self.derivatives.count == 0 {
+            Vec::new()
+        } else {
+            (1..=self.ddnnf.number_of_variables as i32)
+                .flat_map(|feature| [feature, -feature])
+                .filter(|&literal| {
+                    self.derivatives.literal(-literal) == 0 && !self.decisions.contains(&literal)
+                })
+                .collect()
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn sessions_stay_consistent() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut session = Session::new(&vp9);
+        assert_eq!(vp9.rc(), *session.remaining_count());
+        assert_eq!(vp9.propagate(&[]).unwrap(), session.implied());
+
+        // 3 and 5 exclude each other
+        let implied = session.select(3).unwrap();
+        assert!(implied.contains(&-5));
+        assert!(session.select(5).is_err());
+        assert!(session.select(6).is_ok());
+        assert!(session.deselect(6).is_err());
+        assert!(session.select(43).is_err() && session.deselect(0).is_err());
+        assert_eq!(&[3, 6], session.decisions());
+
+        assert_eq!(Some(6), session.undo());
+        assert_eq!(vp9.count_adaptive(&[3]), *session.remaining_count());
+        assert_eq!(vp9.propagate(&[3]).unwrap(), session.implied());
+
+        // following the suggestions leads to a single configuration
+        while let Some(feature) = session.suggest_next() {
+            let before = session.remaining_count().clone();
+            session.deselect(feature).unwrap();
+            assert!(*session.remaining_count() < before);
+            let decisions = session.decisions().to_vec();
+            assert_eq!(vp9.count_adaptive(&decisions), *session.remaining_count());
+            assert_eq!(vp9.propagate(&decisions).unwrap(), session.implied());
+        }
+        assert_eq!(1, *session.remaining_count());
+        assert_eq!(42, session.decisions().len() + session.implied().len());
+
+        while session.undo().is_some() {}
+        assert_eq!(None, session.undo());
+        assert_eq!(vp9.rc(), *session.remaining_count());
+
+        let void = vp9.condition(&[1, -1]);
+        let mut session = Session::new(&void);
+        assert!(session.implied().is_empty());
+        assert_eq!(None, session.suggest_next());
+        assert_eq!(
+            Err(String::from("the model has no valid configuration")),
+            session.select(1)
+        );
+    }
+}
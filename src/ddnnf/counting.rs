@@ -13,5 +13,20 @@
 pub mod features;
 
 // Modules that provide the basic counting logic.
+pub mod adaptive;
+pub mod assumption_search;
+pub mod bounded;
+pub mod brute_force;
+pub mod certificate;
+pub mod correlation;
 pub mod default_count;
+pub mod derivatives;
 pub mod marking;
+pub mod modular;
+pub mod provenance;
+pub mod ranking;
+pub mod ratios;
+pub mod selection_size;
+pub mod subgraph;
+pub mod weight_learning;
+pub mod weighted;
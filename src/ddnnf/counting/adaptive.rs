@@ -0,0 +1,157 @@
+use rug::Integer;
+
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+use AdaptiveCount::*;
+
+#[derive(Debug, Clone, PartialEq)]
+// A count that stays in machine arithmetic until it overflows
+enum AdaptiveCount {
+    Small(u128),
+    Big(Integer),
+}
+
+impl AdaptiveCount {
+    fn add(self, other: &AdaptiveCount) -> AdaptiveCount {
+        match (self, other) {
+            (Small(a), Small(b)) => match a.checked_add(*b) {
+                Some(sum) => Small(sum),
+                None => Big(Integer::from(a) + b),
+            },
+            (Small(a), Big(b)) => Big(Integer::from(a) + b),
+            (Big(a), Small(b)) => Big(a + *b),
+            (Big(a), Big(b)) => Big(a + b),
+        }
+    }
+
+    fn mul(self, other: &AdaptiveCount) -> AdaptiveCount {
+        match (self, other) {
+            (Small(a), Small(b)) => match a.checked_mul(*b) {
+                Some(product) => Small(product),
+                None => Big(Integer::from(a) * b),
+            },
+            (Small(a), Big(b)) => Big(Integer::from(a) * b),
+            (Big(a), Small(b)) => Big(a * *b),
+            (Big(a), Big(b)) => Big(a * b),
+        }
+    }
+}
+
+impl From<AdaptiveCount> for Integer {
+    fn from(count: AdaptiveCount) -> Self {
+        match count {
+            Small(count) => Integer::from(count),
+            Big(count) => count,
+        }
+    }
+}
+
+impl Ddnnf {
+    /// Computes the cardinality of a partial configuration like [Ddnnf::execute_query], but each node
+    /// starts with a u128 count and only switches to a big integer if its count overflows.
+    /// Typical models have small counts near the leaves, hence most of the DAG stays in fast machine
+    /// arithmetic. Because the nodes are not touched, this only needs a shared reference.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/auto1_d4.nnf", Some(2513));
+    ///
+    /// // auto1 has far more than 2^128 models
+    /// assert_eq!(ddnnf.rc(), ddnnf.count_adaptive(&[]));
+    /// assert_eq!(ddnnf.execute_query(&[1, -2]), ddnnf.count_adaptive(&[1, -2]));
+    /// ```
+    pub fn count_adaptive(&self, assumptions: &[i32]) -> Integer {
+        self.adaptive_counts(assumptions)
+            .pop()
+            .map_or(Integer::ZERO, Integer::from)
+    }
+
+    // Computes the count of each node under the assumptions
+    fn adaptive_counts(&self, assumptions: &[i32]) -> Vec<AdaptiveCount> {
+        let mut counts: Vec<AdaptiveCount> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let count = match &node.ntype {
+                And { children } => children
+                    .iter()
+                    .fold(Small(1), |product, &child| product.mul(&counts[child])),
+                Or { children } => children
+                    .iter()
+                    .fold(Small(0), |sum, &child| sum.add(&counts[child])),
+                Literal { literal } if assumptions.contains(&-literal) => Small(0),
+                Literal { .. } | True => Small(1),
+                False => Small(0),
+            };
+            counts.push(count);
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn adaptive_counts_match_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+
+        for assumptions in [vec![], vec![4], vec![-4, 9], vec![1, -38, 20], vec![3, -3]] {
+            assert_eq!(
+                vp9.execute_query(&assumptions),
+                vp9.count_adaptive(&assumptions)
+            );
+            assert_eq!(
+                auto1.execute_query(&assumptions),
+                auto1.count_adaptive(&assumptions)
+            );
+        }
+
+        // the counts of VP9 fit into u128, the ones of auto1 only near the leaves
+        assert!(vp9
+            .adaptive_counts(&[])
+            .iter()
+            .all(|count| matches!(count, Small(_))));
+        let counts = auto1.adaptive_counts(&[]);
+        let big = counts
+            .iter()
+            .filter(|count| matches!(count, Big(_)))
+            .count();
+        assert!(0 < big && big < counts.len());
+        for (count, node) in counts.into_iter().zip(auto1.nodes.iter()) {
+            assert_eq!(node.count, Integer::from(count));
+        }
+    }
+
+    #[test]
+    fn long_queries_count_adaptively() {
+        let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+        let query: Vec<i32> = (1..=30).map(|f| if f % 3 == 0 { -f } else { f }).collect();
+        for query in [query.clone(), [query, vec![-1]].concat()] {
+            let expected = auto1.operate_on_partial_config_default(&query, Ddnnf::calc_count);
+            assert_eq!(expected, auto1.execute_query(&query));
+        }
+    }
+
+    #[test]
+    fn overflows_switch_to_big_integers() {
+        let max = Small(u128::MAX);
+        assert_eq!(Small(u128::MAX), Small(u128::MAX - 1).add(&Small(1)));
+        assert_eq!(
+            Big(Integer::from(u128::MAX) + 1),
+            max.clone().add(&Small(1))
+        );
+        assert_eq!(
+            Big(Integer::from(u128::MAX) * 2),
+            max.clone().mul(&Small(2))
+        );
+        assert_eq!(Big(Integer::from(6)), Big(Integer::from(2)).mul(&Small(3)));
+        assert_eq!(Small(0), max.mul(&Small(0)));
+    }
+}
@@ -0,0 +1,144 @@
+use std::ops::RangeInclusive;
+
+use rug::Integer;
+
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Searches the assumptions whose conditioned count lies within the range, e.g. the decisions
+    /// that cut the configuration space by at least 90%. If pairwise is false, the assumptions are
+    /// single literals. Otherwise, they are pairs of literals of two different variables.
+    /// The result is sorted by the count in ascending order, hence the decisions with the largest
+    /// impact come first.
+    ///
+    /// The counts of all literals result from a single pass over the partial derivatives.
+    /// For pairs, we need one such pass per literal whose count reaches the lower bound,
+    /// because the count of a pair is at most the count of each of its literals.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use rug::Integer;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+    /// // the decisions that leave at most 10% of the configurations while keeping at least one
+    /// let impactful = ddnnf.search_assumptions(&(Integer::ONE.clone()..=ddnnf.rc() / 10), false);
+    ///
+    /// for (assumptions, count) in impactful {
+    ///     assert_eq!(1, assumptions.len());
+    ///     assert!(count > 0 && count * 10 <= ddnnf.rc());
+    /// }
+    /// ```
+    pub fn search_assumptions(
+        &mut self,
+        counts: &RangeInclusive<Integer>,
+        pairwise: bool,
+    ) -> Vec<(Vec<i32>, Integer)> {
+        let literal_counts: Vec<(i32, Integer)> = self
+            .literal_distribution()
+            .into_iter()
+            .zip(1..)
+            .flat_map(|((selected, deselected), feature)| {
+                [(feature, selected), (-feature, deselected)]
+            })
+            .collect();
+
+        let mut found: Vec<(Vec<i32>, Integer)> = Vec::new();
+        if !pairwise {
+            found.extend(
+                literal_counts
+                    .into_iter()
+                    .filter(|(_, count)| counts.contains(count))
+                    .map(|(literal, count)| (vec![literal], count)),
+            );
+        } else {
+            for (first, count) in literal_counts.iter() {
+                if count < counts.start() {
+                    continue;
+                }
+                let distribution = self.conditioned(&[*first]).literal_distribution();
+                for (index, (selected, deselected)) in distribution
+                    .into_iter()
+                    .enumerate()
+                    .skip(first.unsigned_abs() as usize)
+                {
+                    let second = index as i32 + 1;
+                    for (literal, count) in [(second, selected), (-second, deselected)] {
+                        if counts.contains(&count) {
+                            found.push((vec![*first, literal], count));
+                        }
+                    }
+                }
+            }
+        }
+
+        found.sort_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(a.cmp(b)));
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn search_matches_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let counts = Integer::from(1000)..=Integer::from(50_000);
+
+        let singles = vp9.search_assumptions(&counts, false);
+        let pairs = vp9.search_assumptions(&counts, true);
+        assert!(!singles.is_empty() && !pairs.is_empty());
+        assert!(singles.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(pairs.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        let mut expected_singles = 0;
+        let mut expected_pairs = 0;
+        for a in (-42..=42_i32).filter(|&a| a != 0) {
+            let count = vp9.execute_query(&[a]);
+            if counts.contains(&count) {
+                expected_singles += 1;
+                assert!(singles.contains(&(vec![a], count)));
+            }
+            for b in (a.abs() + 1..=42).flat_map(|b| [b, -b]) {
+                let count = vp9.execute_query(&[a, b]);
+                if counts.contains(&count) {
+                    expected_pairs += 1;
+                    assert!(pairs.contains(&(vec![a, b], count)), "{a} {b}");
+                }
+            }
+        }
+        assert_eq!(expected_singles, singles.len());
+        assert_eq!(expected_pairs, pairs.len());
+    }
+
+    #[test]
+    fn dead_decisions() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        // the feature 1 is core and its deselection leaves no configuration
+        assert_eq!(
+            vec![(vec![-1], Integer::ZERO)],
+            ddnnf.search_assumptions(&(Integer::ZERO..=Integer::ZERO), false)
+        );
+
+        let dead_pairs: Vec<Vec<i32>> = ddnnf
+            .search_assumptions(&(Integer::ZERO..=Integer::ZERO), true)
+            .into_iter()
+            .map(|(assumptions, _)| assumptions)
+            .collect();
+        let mut expected = Vec::new();
+        for a in [-4, -3, -2, -1, 1, 2, 3, 4_i32] {
+            for b in (a.abs() + 1..=4).flat_map(|b| [b, -b]) {
+                if ddnnf.execute_query(&[a, b]) == 0 {
+                    expected.push(vec![a, b]);
+                }
+            }
+        }
+        expected.sort();
+        assert_eq!(expected, dead_pairs);
+    }
+}
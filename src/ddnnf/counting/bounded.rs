@@ -0,0 +1,258 @@
+use std::{fmt, str::FromStr};
+
+use rug::Integer;
+
+use super::super::node::NodeType::*;
+use crate::{parser::util::log10, Ddnnf};
+
+/// A count that is either exact or, if it exceeds the digit limit, its decadic logarithm
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundedCount {
+    Exact(Integer),
+    Log10(f64),
+}
+
+impl BoundedCount {
+    /// Checks whether the count is exact
+    pub fn is_exact(&self) -> bool {
+        matches!(self, BoundedCount::Exact(_))
+    }
+
+    /// The decadic logarithm of the count, which is negative infinity for zero
+    pub fn log10(&self) -> f64 {
+        match self {
+            BoundedCount::Exact(count) => log10(count),
+            BoundedCount::Log10(log) => *log,
+        }
+    }
+}
+
+impl fmt::Display for BoundedCount {
+    /// Exact counts are shown as they are and approximated ones as '10^LOG10'
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundedCount::Exact(count) => write!(f, "{count}"),
+            BoundedCount::Log10(log) => write!(f, "10^{log:.6}"),
+        }
+    }
+}
+
+/// How a single count gets evaluated, see [Ddnnf::count_with_mode]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CountMode {
+    /// Big integers, which are exact but get slow for astronomically large counts
+    #[default]
+    Exact,
+    /// Exact, unless the count of the query has more digits than [Ddnnf::max_digits] allows
+    Bounded,
+    /// Floats in log10 space, which are fast but approximate
+    Log10,
+}
+
+impl fmt::Display for CountMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountMode::Exact => write!(f, "exact"),
+            CountMode::Bounded => write!(f, "bounded"),
+            CountMode::Log10 => write!(f, "log10"),
+        }
+    }
+}
+
+impl FromStr for CountMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(CountMode::Exact),
+            "bounded" => Ok(CountMode::Bounded),
+            "log10" => Ok(CountMode::Log10),
+            _ => Err(format!("unknown count mode {s}")),
+        }
+    }
+}
+
+impl Ddnnf {
+    /// Computes the cardinality of a partial configuration with the given mode. In contrast to
+    /// [Ddnnf::max_digits], the mode only applies to this query. Hence, interactive tools can switch
+    /// to floats for single queries on huge models and still get exact counts otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::counting::bounded::{BoundedCount, CountMode};
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(BoundedCount::Exact(2.into()), ddnnf.count_with_mode(&[2], CountMode::Exact));
+    /// let count = ddnnf.count_with_mode(&[2], CountMode::Log10);
+    /// assert!(!count.is_exact());
+    /// assert!((count.log10() - 2_f64.log10()).abs() < 1e-9);
+    /// ```
+    pub fn count_with_mode(&mut self, query: &[i32], mode: CountMode) -> BoundedCount {
+        match mode {
+            CountMode::Exact => BoundedCount::Exact(self.execute_query(query)),
+            CountMode::Bounded => self.bounded_count(query),
+            CountMode::Log10 => BoundedCount::Log10(self.log10_count(query)),
+        }
+    }
+
+    /// Computes the cardinality of a partial configuration while respecting [Ddnnf::max_digits].
+    /// If the count of the query has more digits than allowed, it gets reported in log10 form instead.
+    /// The float approximation decides whether the exact count is needed at all. Hence, huge counts
+    /// are neither computed nor printed with all of their digits. Counts without any digit beyond the
+    /// limit, including zero, stay exact.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::counting::bounded::BoundedCount;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+    /// assert_eq!(BoundedCount::Exact(ddnnf.execute_query(&[4])), ddnnf.bounded_count(&[4]));
+    ///
+    /// // VP9 has 216000 models, which are 6 digits
+    /// ddnnf.max_digits = Some(5);
+    /// let count = ddnnf.bounded_count(&[]);
+    /// assert!(!count.is_exact());
+    /// assert!((count.log10() - 216_000_f64.log10()).abs() < 1e-9);
+    /// // but the configurations with feature 4 are few enough
+    /// assert!(ddnnf.bounded_count(&[4]).is_exact());
+    /// ```
+    pub fn bounded_count(&mut self, query: &[i32]) -> BoundedCount {
+        let max_digits = match self.max_digits {
+            Some(max_digits) => max_digits,
+            None => return BoundedCount::Exact(self.execute_query(query)),
+        };
+        let log = self.log10_count(query);
+        // the approximation can be slightly off, hence we only trust it with a margin
+        if log.is_finite() && log >= max_digits as f64 + 1.0 {
+            return BoundedCount::Log10(log);
+        }
+        let count = self.execute_query(query);
+        if count != 0 && log10(&count).floor() as usize >= max_digits {
+            BoundedCount::Log10(log)
+        } else {
+            BoundedCount::Exact(count)
+        }
+    }
+
+    /// Computes the decadic logarithm of the cardinality of a partial configuration with floats.
+    /// Hence, the result is an approximation, which is negative infinity if there is no such configuration.
+    pub fn log10_count(&self, query: &[i32]) -> f64 {
+        let mut logs: Vec<f64> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let log = match &node.ntype {
+                And { children } => children.iter().map(|&c| logs[c]).sum(),
+                Or { children } => {
+                    let max = children
+                        .iter()
+                        .map(|&c| logs[c])
+                        .fold(f64::NEG_INFINITY, f64::max);
+                    if max == f64::NEG_INFINITY {
+                        max
+                    } else {
+                        // factoring out the largest summand avoids overflows
+                        max + children
+                            .iter()
+                            .map(|&c| 10_f64.powf(logs[c] - max))
+                            .sum::<f64>()
+                            .log10()
+                    }
+                }
+                Literal { literal } if query.contains(&-literal) => f64::NEG_INFINITY,
+                Literal { .. } | True => 0.0,
+                False => f64::NEG_INFINITY,
+            };
+            logs.push(log);
+        }
+        logs.last().copied().unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn log10_counts_match_exact_counts() {
+        let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+        for query in [vec![], vec![1], vec![-1, 20], vec![1, -1]] {
+            let exact = log10(&auto1.execute_query(&query));
+            let approximated = auto1.log10_count(&query);
+            if exact.is_finite() {
+                assert!(
+                    (exact - approximated).abs() < 1e-6,
+                    "{exact} {approximated}"
+                );
+            } else {
+                assert_eq!(exact, approximated);
+            }
+        }
+    }
+
+    #[test]
+    fn digit_limit() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        vp9.max_digits = Some(6);
+        assert_eq!(BoundedCount::Exact(vp9.rc()), vp9.bounded_count(&[]));
+        assert_eq!("216000", vp9.bounded_count(&[]).to_string());
+
+        // the limit applies to the count of each query and zero has no digits beyond any limit
+        vp9.max_digits = Some(0);
+        assert_eq!(BoundedCount::Exact(0.into()), vp9.bounded_count(&[1, -1]));
+        assert_eq!(f64::NEG_INFINITY, vp9.bounded_count(&[1, -1]).log10());
+        assert!(!vp9.bounded_count(&[]).is_exact());
+        vp9.max_digits = Some(5);
+        for query in [vec![], vec![1], vec![4], vec![-4, 20]] {
+            let exact = vp9.execute_query(&query);
+            match vp9.bounded_count(&query) {
+                BoundedCount::Exact(count) => {
+                    assert_eq!(exact, count);
+                    assert!(count.to_string().len() <= 5);
+                }
+                BoundedCount::Log10(log) => {
+                    assert!(exact.to_string().len() > 5);
+                    assert!((log - log10(&exact)).abs() < 1e-9);
+                }
+            }
+        }
+        assert_eq!("10^0.000000", BoundedCount::Log10(0.0).to_string());
+    }
+
+    #[test]
+    fn count_modes() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        vp9.max_digits = Some(3);
+        for query in [vec![], vec![4], vec![-4, 9], vec![1, -1]] {
+            let exact = vp9.execute_query(&query);
+            assert_eq!(
+                BoundedCount::Exact(exact.clone()),
+                vp9.count_with_mode(&query, CountMode::Exact)
+            );
+            assert_eq!(
+                vp9.bounded_count(&query),
+                vp9.count_with_mode(&query, CountMode::Bounded)
+            );
+            let approximated = vp9.count_with_mode(&query, CountMode::Log10);
+            assert!(!approximated.is_exact());
+            if exact == 0 {
+                assert_eq!(f64::NEG_INFINITY, approximated.log10());
+            } else {
+                assert!((log10(&exact) - approximated.log10()).abs() < 1e-9);
+            }
+        }
+
+        for mode in [CountMode::Exact, CountMode::Bounded, CountMode::Log10] {
+            assert_eq!(Ok(mode), mode.to_string().parse());
+        }
+        assert!("float".parse::<CountMode>().is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use rug::Integer;
+
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+
+/// The maximum number of variables for which we evaluate a d-DNNF by its truth table
+pub const BRUTE_FORCE_LIMIT: u32 = 25;
+
+impl Ddnnf {
+    /// Computes the cardinality of a partial configuration by evaluating each complete assignment.
+    /// The result is independent of the counting algorithms and can serve as an oracle for them.
+    /// Returns None if the d-DNNF has more than [BRUTE_FORCE_LIMIT] variables.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(Some(ddnnf.execute_query(&[3])), ddnnf.brute_force_count(&[3]));
+    /// assert_eq!(Some(0.into()), ddnnf.brute_force_count(&[3, -3]));
+    /// ```
+    pub fn brute_force_count(&self, assumptions: &[i32]) -> Option<Integer> {
+        let models = self
+            .assignments()?
+            .filter(|&assignment| satisfies(assignment, assumptions) && self.is_model(assignment))
+            .count();
+        Some(Integer::from(models))
+    }
+
+    /// Enumerates all satisfying complete configurations by evaluating each complete assignment.
+    /// Each configuration contains all variables as literals, ordered by the variable number.
+    /// The configurations are ordered by their binary representation with variable 1 as least significant bit.
+    /// Returns None if the d-DNNF has more than [BRUTE_FORCE_LIMIT] variables.
+    pub fn brute_force_enumerate(&self) -> Option<Vec<Vec<i32>>> {
+        let configs = self
+            .assignments()?
+            .filter(|&assignment| self.is_model(assignment))
+            .map(|assignment| {
+                (1..=self.number_of_variables as i32)
+                    .map(|var| {
+                        if satisfies(assignment, &[var]) {
+                            var
+                        } else {
+                            -var
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(configs)
+    }
+
+    // Iterates over all complete assignments.
+    // The i-th bit of an assignment is the value of the variable i + 1.
+    pub(crate) fn assignments(&self) -> Option<std::ops::Range<u64>> {
+        if self.number_of_variables > BRUTE_FORCE_LIMIT {
+            return None;
+        }
+        Some(0..1_u64 << self.number_of_variables)
+    }
+
+    // Evaluates the d-DNNF for a complete assignment
+    pub(crate) fn is_model(&self, assignment: u64) -> bool {
+        let mut values: Vec<bool> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let value = match &node.ntype {
+                And { children } => children.iter().all(|&c| values[c]),
+                Or { children } => children.iter().any(|&c| values[c]),
+                Literal { literal } => satisfies(assignment, &[*literal]),
+                True => true,
+                False => false,
+            };
+            values.push(value);
+        }
+        values.last().copied().unwrap_or(false)
+    }
+}
+
+// Checks whether the assignment fulfills each of the literals
+pub(crate) fn satisfies(assignment: u64, literals: &[i32]) -> bool {
+    literals
+        .iter()
+        .all(|&l| (assignment >> (l.unsigned_abs() - 1) & 1 == 1) == l.is_positive())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn brute_force_matches_counting() {
+        let mut c2d: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let mut d4: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+
+        for ddnnf in [&mut c2d, &mut d4] {
+            assert_eq!(Some(ddnnf.rc()), ddnnf.brute_force_count(&[]));
+            for a in -4..=4_i32 {
+                for b in -4..=4_i32 {
+                    let query: Vec<i32> = [a, b].into_iter().filter(|&l| l != 0).collect();
+                    assert_eq!(
+                        Some(ddnnf.execute_query(&query)),
+                        ddnnf.brute_force_count(&query),
+                        "query: {query:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn brute_force_enumeration() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        assert_eq!(
+            Some(vec![
+                vec![1, 2, -3, -4],
+                vec![1, -2, 3, -4],
+                vec![1, 2, -3, 4],
+                vec![1, -2, 3, 4]
+            ]),
+            ddnnf.brute_force_enumerate()
+        );
+
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert_eq!(None, vp9.brute_force_count(&[]));
+        assert_eq!(None, vp9.brute_force_enumerate());
+    }
+}
@@ -0,0 +1,244 @@
+use std::{fmt, str::FromStr};
+
+use rug::{Complete, Integer};
+
+use super::super::node::{NodeId, NodeType::*};
+use crate::Ddnnf;
+
+/// A checkable trace of a counting query. It contains the count of each node under the query
+/// and the structural id of the root, which identifies the d-DNNF the counts belong to.
+/// A certificate can be verified with [Certificate::verify] without trusting the counting
+/// algorithms of the reasoner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    /// The structural id of the root of the d-DNNF
+    pub root_id: NodeId,
+    /// The literals that have to hold in each counted configuration
+    pub query: Vec<i32>,
+    /// The count of each node under the query in the order of the nodes
+    pub counts: Vec<Integer>,
+}
+
+impl Ddnnf {
+    /// Computes the cardinality of the partial configuration and returns a certificate for it.
+    /// In contrast to [Ddnnf::execute_query], this always evaluates the whole d-DNNF bottom up
+    /// and does not apply any optimizations.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let certificate = ddnnf.certified_count(&[3]);
+    ///
+    /// assert_eq!(&ddnnf.execute_query(&[3]), certificate.count());
+    /// assert_eq!(Ok(certificate.count().clone()), certificate.verify(&ddnnf));
+    /// ```
+    pub fn certified_count(&self, query: &[i32]) -> Certificate {
+        let mut counts: Vec<Integer> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let count = match &node.ntype {
+                And { children } => {
+                    Integer::product(children.iter().map(|&c| &counts[c])).complete()
+                }
+                Or { children } => Integer::sum(children.iter().map(|&c| &counts[c])).complete(),
+                Literal { literal } if query.contains(&-literal) => Integer::ZERO,
+                Literal { .. } | True => Integer::from(1),
+                False => Integer::ZERO,
+            };
+            counts.push(count);
+        }
+
+        Certificate {
+            root_id: *self
+                .node_ids()
+                .last()
+                .expect("a d-DNNF has at least one node"),
+            query: query.to_vec(),
+            counts,
+        }
+    }
+}
+
+impl Certificate {
+    /// The certified count, i.e. the count of the root
+    pub fn count(&self) -> &Integer {
+        self.counts.last().expect("a certificate contains the root")
+    }
+
+    /// Checks the certificate against the d-DNNF. The d-DNNF must have the same structure as the
+    /// one the certificate was created for. Then, the count of each node is recomputed from the
+    /// claimed counts of its children.
+    /// Returns the certified count if the certificate is valid and a description of the first
+    /// deviation otherwise.
+    pub fn verify(&self, ddnnf: &Ddnnf) -> Result<Integer, String> {
+        if self.counts.len() != ddnnf.nodes.len() {
+            return Err(format!(
+                "the certificate contains {} counts, but the d-DNNF has {} nodes",
+                self.counts.len(),
+                ddnnf.nodes.len()
+            ));
+        }
+        let root_id = ddnnf.node_ids()[ddnnf.nodes.len() - 1];
+        if root_id != self.root_id {
+            return Err(format!(
+                "the certificate belongs to the d-DNNF {}, but the root of this d-DNNF is {}",
+                self.root_id, root_id
+            ));
+        }
+
+        for (index, node) in ddnnf.nodes.iter().enumerate() {
+            let claims = |children: &[usize]| -> Result<Vec<&Integer>, String> {
+                children
+                    .iter()
+                    .map(|&child| {
+                        if child < index {
+                            Ok(&self.counts[child])
+                        } else {
+                            Err(format!(
+                                "node {index} has the child {child} that is no predecessor"
+                            ))
+                        }
+                    })
+                    .collect()
+            };
+            let expected = match &node.ntype {
+                And { children } => Integer::product(claims(children)?.into_iter()).complete(),
+                Or { children } => Integer::sum(claims(children)?.into_iter()).complete(),
+                Literal { literal } if self.query.contains(&-literal) => Integer::ZERO,
+                Literal { .. } | True => Integer::from(1),
+                False => Integer::ZERO,
+            };
+            if expected != self.counts[index] {
+                return Err(format!(
+                    "node {index} claims the count {}, but its children result in {expected}",
+                    self.counts[index]
+                ));
+            }
+        }
+        Ok(self.count().clone())
+    }
+}
+
+impl fmt::Display for Certificate {
+    /// The certificate is a text file with the structural id of the root, the query,
+    /// and one count per node:
+    ///
+    /// h ROOT_ID
+    /// q LITERALS 0
+    /// v COUNT
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "c counting certificate of ddnnife")?;
+        writeln!(f, "h {}", self.root_id)?;
+        write!(f, "q")?;
+        for literal in self.query.iter() {
+            write!(f, " {literal}")?;
+        }
+        writeln!(f, " 0")?;
+        for count in self.counts.iter() {
+            writeln!(f, "v {count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Certificate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut root_id = None;
+        let mut query = None;
+        let mut counts = Vec::new();
+
+        for (number, line) in s.lines().enumerate() {
+            let invalid = || format!("line {}: unable to parse '{line}'", number + 1);
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("h") => {
+                    root_id = Some(
+                        words
+                            .next()
+                            .and_then(|id| id.parse().ok())
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                Some("q") => {
+                    let literals: Vec<i32> = words
+                        .map(|word| word.parse::<i32>())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid())?;
+                    match literals.split_last() {
+                        Some((0, literals)) => query = Some(literals.to_vec()),
+                        _ => return Err(invalid()),
+                    }
+                }
+                Some("v") => counts.push(
+                    words
+                        .next()
+                        .and_then(|count| count.parse().ok())
+                        .ok_or_else(invalid)?,
+                ),
+                Some("c") | None => (),
+                Some(_) => return Err(invalid()),
+            }
+        }
+
+        match (root_id, query) {
+            (Some(root_id), Some(query)) if !counts.is_empty() => Ok(Certificate {
+                root_id,
+                query,
+                counts,
+            }),
+            _ => Err(String::from(
+                "a certificate needs a root id, a query, and at least one count",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn certificates_are_checkable() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        for query in [vec![], vec![4], vec![-4, 9], vec![1, -38, 20], vec![4, -4]] {
+            let certificate = vp9.certified_count(&query);
+            assert_eq!(&vp9.execute_query(&query), certificate.count());
+            assert_eq!(Ok(certificate.count().clone()), certificate.verify(&vp9));
+
+            let parsed: Certificate = certificate.to_string().parse().unwrap();
+            assert_eq!(certificate, parsed);
+        }
+    }
+
+    #[test]
+    fn forged_certificates_get_rejected() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let certificate = vp9.certified_count(&[4]);
+
+        let mut forged = certificate.clone();
+        *forged.counts.last_mut().unwrap() += 1;
+        assert!(forged.verify(&vp9).is_err());
+
+        // a consistent change of the query is no valid certificate for the new query
+        let mut forged = certificate.clone();
+        forged.query = vec![-4];
+        assert!(forged.verify(&vp9).is_err());
+
+        let small_ex: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        assert!(certificate.verify(&small_ex).is_err());
+        let mut forged = small_ex.certified_count(&[]);
+        forged.counts.truncate(3);
+        assert!(forged.verify(&small_ex).is_err());
+
+        assert!("h 12\nv 3".parse::<Certificate>().is_err());
+        assert!("h zz\nq 0\nv 3".parse::<Certificate>().is_err());
+        assert!("h 12\nq 1\nv 3".parse::<Certificate>().is_err());
+    }
+}
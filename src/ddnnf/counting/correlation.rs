@@ -0,0 +1,146 @@
+use std::{io, path::Path};
+
+use rug::{Float, Integer};
+
+use crate::Ddnnf;
+
+/// The number of configurations that contain each pair of features, see [Ddnnf::correlation_matrix]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationMatrix {
+    /// The number of all configurations
+    pub total: Integer,
+    /// The entry at [a - 1][b - 1] holds the number of configurations that select both features a and b.
+    /// The diagonal holds the number of configurations that select the feature.
+    pub both: Vec<Vec<Integer>>,
+}
+
+impl CorrelationMatrix {
+    /// The number of configurations that select both features
+    pub fn count(&self, a: u32, b: u32) -> &Integer {
+        &self.both[a as usize - 1][b as usize - 1]
+    }
+
+    /// The share of the configurations with a that also select b, i.e. the conditional probability
+    /// of b given a under uniform sampling. 1 means that a requires b and 0 that a excludes b.
+    /// Returns None if a is dead.
+    pub fn implication_strength(&self, a: u32, b: u32) -> Option<f64> {
+        let with_a = self.count(a, a);
+        if *with_a == 0 {
+            return None;
+        }
+        Some((Float::with_val(200, self.count(a, b)) / with_a).to_f64())
+    }
+
+    /// The correlation (phi coefficient) of the selections of both features over all configurations.
+    /// 1 means that the features are always selected together, -1 that exactly one of them is selected,
+    /// and 0 that they are independent. Returns None if a feature is core or dead.
+    pub fn correlation(&self, a: u32, b: u32) -> Option<f64> {
+        let (with_a, with_b) = (self.count(a, a), self.count(b, b));
+        let variance = |count: &Integer| count * Integer::from(&self.total - count);
+        let denominator = variance(with_a) * variance(with_b);
+        if denominator == 0 {
+            return None;
+        }
+        let numerator =
+            Integer::from(&self.total * self.count(a, b)) - Integer::from(with_a * with_b);
+        Some((Float::with_val(200, numerator) / Float::with_val(200, denominator).sqrt()).to_f64())
+    }
+
+    /// Writes the matrix of the pairwise counts as CSV. The first record holds the numbers of the
+    /// features and each further record starts with the number of its feature.
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        let features = (1..=self.both.len()).map(|feature| feature.to_string());
+        wtr.write_record(std::iter::once(String::new()).chain(features))?;
+        for (index, row) in self.both.iter().enumerate() {
+            let counts = row.iter().map(Integer::to_string);
+            wtr.write_record(std::iter::once((index + 1).to_string()).chain(counts))?;
+        }
+        wtr.flush()
+    }
+}
+
+impl Ddnnf {
+    /// Computes for all pairs of features the number of configurations that select both of them.
+    ///
+    /// Instead of one query per pair, we condition the d-DNNF on each selected feature and get the
+    /// whole row of the matrix from a single pass over the partial derivatives of the conditioned
+    /// d-DNNF (see [Ddnnf::literal_distribution]). Hence, the effort is linear in the number of
+    /// features times the size of the d-DNNF.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let matrix = ddnnf.correlation_matrix();
+    ///
+    /// assert_eq!(4, *matrix.count(1, 1));
+    /// assert_eq!(0, *matrix.count(2, 3));
+    /// assert_eq!(1, *matrix.count(2, 4));
+    /// assert_eq!(Some(1.0), matrix.implication_strength(2, 1));
+    /// assert_eq!(Some(-1.0), matrix.correlation(2, 3));
+    /// assert_eq!(Some(0.0), matrix.correlation(2, 4));
+    /// assert_eq!(None, matrix.correlation(1, 2));
+    /// ```
+    pub fn correlation_matrix(&self) -> CorrelationMatrix {
+        let both = (1..=self.number_of_variables as i32)
+            .map(|feature| {
+                self.conditioned(&[feature])
+                    .literal_distribution()
+                    .into_iter()
+                    .map(|(selected, _)| selected)
+                    .collect()
+            })
+            .collect();
+        CorrelationMatrix {
+            total: self.rc(),
+            both,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn correlation_matrix_matches_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let matrix = vp9.correlation_matrix();
+        assert_eq!(42, matrix.both.len());
+
+        for a in 1..=42 {
+            for b in 1..=42 {
+                let expected = vp9.execute_query(&[a as i32, b as i32]);
+                assert_eq!(expected, *matrix.count(a, b));
+                assert_eq!(matrix.count(a, b), matrix.count(b, a));
+                if let Some(strength) = matrix.implication_strength(a, b) {
+                    assert!((0.0..=1.0).contains(&strength));
+                    assert_eq!(
+                        strength == 1.0,
+                        vp9.execute_query(&[a as i32, -(b as i32)]) == 0
+                    );
+                }
+                if let Some(correlation) = matrix.correlation(a, b) {
+                    assert!((-1.0 - 1e-9..=1.0 + 1e-9).contains(&correlation));
+                }
+            }
+        }
+        // core features do not correlate
+        assert_eq!(None, matrix.correlation(1, 3));
+        assert_eq!(Some(1.0), matrix.correlation(3, 3));
+
+        let path = "tests/data/correlation_out.csv";
+        matrix.write_csv(path).unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(43, written.lines().count());
+        assert!(written.lines().nth(1).unwrap().starts_with("1,216000,"));
+    }
+}
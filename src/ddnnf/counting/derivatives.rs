@@ -0,0 +1,214 @@
+use rug::{Complete, Integer, Rational};
+
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+
+/// The partial derivatives of the count with respect to the nodes, see [Ddnnf::derivatives]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Derivatives {
+    /// The number of configurations under the assumptions
+    pub count: Integer,
+    /// The partial derivative of the count of the root with respect to the count of each node.
+    /// Multiplied with the count of the node, it is the number of configurations that pass through the node.
+    pub nodes: Vec<Integer>,
+    /// The derivatives of the negative and the positive literal of each variable
+    literals: Vec<[Integer; 2]>,
+}
+
+impl Derivatives {
+    /// The partial derivative with respect to the node at the index
+    pub fn node(&self, index: usize) -> &Integer {
+        &self.nodes[index]
+    }
+
+    /// The derivative of the literal, i.e. the number of configurations under the assumptions that contain it.
+    /// It sums up the derivatives of the nodes of the literal, except for the nodes that the assumptions
+    /// exclude. Because the d-DNNF is smooth,
+    /// each configuration contains either the literal or its negation. Hence, a literal without a node
+    /// occurs in each configuration that does not contain its negation.
+    pub fn literal(&self, literal: i32) -> Integer {
+        self.literals
+            .get(literal.unsigned_abs() as usize)
+            .map_or(Integer::ZERO, |derivatives| {
+                derivatives[literal.is_positive() as usize].clone()
+            })
+    }
+
+    /// The share of the configurations under the assumptions that contain the literal.
+    /// Returns None if there is no configuration at all.
+    pub fn marginal(&self, literal: i32) -> Option<Rational> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(Rational::from((self.literal(literal), self.count.clone())))
+    }
+
+    /// The influence of the feature, i.e. by how many configurations the selections of the feature
+    /// outnumber its deselections. Positive values indicate that the model favors the feature.
+    pub fn influence(&self, feature: u32) -> Integer {
+        self.literal(feature as i32) - self.literal(-(feature as i32))
+    }
+}
+
+impl Ddnnf {
+    /// Computes the partial derivatives of the count under the assumptions with respect to all nodes
+    /// with one pass upwards for the counts and a single backward pass for the derivatives. The derivative
+    /// of a literal is the number of configurations that contain it, hence one call yields the marginal
+    /// counts of all features. In contrast to [Ddnnf::literal_distribution], this does not use the
+    /// scratch space of the nodes and works with a shared reference.
+    ///
+    /// The derivative of the root is 1. Or nodes pass their derivative on to each child and And nodes
+    /// multiply it with the counts of the other children.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let derivatives = ddnnf.derivatives(&[]);
+    /// assert_eq!(4, derivatives.literal(1));
+    /// assert_eq!(2, derivatives.literal(-3));
+    /// assert_eq!(4, derivatives.influence(1));
+    /// assert_eq!(0, derivatives.influence(4));
+    ///
+    /// let derivatives = ddnnf.derivatives(&[2]);
+    /// assert_eq!(2, derivatives.count);
+    /// assert_eq!(0, derivatives.literal(3));
+    /// assert_eq!(Some((1, 2).into()), derivatives.marginal(4));
+    /// ```
+    pub fn derivatives(&self, assumptions: &[i32]) -> Derivatives {
+        let mut counts: Vec<Integer> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let count = match &node.ntype {
+                And { children } => {
+                    Integer::product(children.iter().map(|&c| &counts[c])).complete()
+                }
+                Or { children } => Integer::sum(children.iter().map(|&c| &counts[c])).complete(),
+                Literal { literal } if assumptions.contains(&-literal) => Integer::ZERO,
+                Literal { .. } | True => Integer::from(1),
+                False => Integer::ZERO,
+            };
+            counts.push(count);
+        }
+
+        let mut derivatives = vec![Integer::ZERO; self.nodes.len()];
+        if let Some(root) = self.nodes.len().checked_sub(1) {
+            derivatives[root] = Integer::from(1);
+        }
+        for index in (0..self.nodes.len()).rev() {
+            if derivatives[index] == 0 {
+                continue;
+            }
+            match &self.nodes[index].ntype {
+                And { children } => {
+                    for (position, &child) in children.iter().enumerate() {
+                        let others = children
+                            .iter()
+                            .enumerate()
+                            .filter(|&(other, _)| other != position)
+                            .map(|(_, &other)| &counts[other]);
+                        let derivative = Integer::product(others).complete() * &derivatives[index];
+                        derivatives[child] += derivative;
+                    }
+                }
+                Or { children } => {
+                    let derivative = derivatives[index].clone();
+                    for &child in children {
+                        derivatives[child] += &derivative;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let count = counts.last().cloned().unwrap_or_default();
+        let mut occurring = vec![[None, None]; self.number_of_variables as usize + 1];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Literal { literal } = node.ntype {
+                let derivative = occurring[literal.unsigned_abs() as usize]
+                    [literal.is_positive() as usize]
+                    .get_or_insert(Integer::ZERO);
+                // literals that contradict the assumptions do not occur in any configuration
+                if counts[index] != 0 {
+                    *derivative += &derivatives[index];
+                }
+            }
+        }
+        let literals = occurring
+            .into_iter()
+            .enumerate()
+            .map(|(variable, [negative, positive])| {
+                let excluded = |literal: i32| assumptions.contains(&-literal);
+                let variable = variable as i32;
+                match (negative, positive) {
+                    (Some(negative), Some(positive)) => [negative, positive],
+                    (Some(negative), None) => [negative.clone(), (&count - &negative).complete()],
+                    (None, Some(positive)) => [(&count - &positive).complete(), positive],
+                    // the variable is free, unless the assumptions decide it
+                    (None, None) => [-variable, variable].map(|literal| {
+                        if excluded(literal) {
+                            Integer::ZERO
+                        } else {
+                            count.clone()
+                        }
+                    }),
+                }
+            })
+            .collect();
+
+        Derivatives {
+            count,
+            nodes: derivatives,
+            literals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn derivatives_match_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let distribution = vp9.literal_distribution();
+        let derivatives = vp9.derivatives(&[]);
+        assert_eq!(vp9.rc(), derivatives.count);
+        for feature in 1..=42 {
+            let (selected, deselected) = &distribution[feature as usize - 1];
+            assert_eq!(*selected, derivatives.literal(feature));
+            assert_eq!(*deselected, derivatives.literal(-feature));
+        }
+
+        for assumptions in [vec![3], vec![-4, 9], vec![3, -7, 20], vec![4, -4]] {
+            let derivatives = vp9.derivatives(&assumptions);
+            assert_eq!(vp9.execute_query(&assumptions), derivatives.count);
+            for feature in 1..=42 {
+                for literal in [feature, -feature] {
+                    if assumptions.contains(&-literal) {
+                        assert_eq!(0, derivatives.literal(literal));
+                        continue;
+                    }
+                    let mut query = assumptions.clone();
+                    query.push(literal);
+                    assert_eq!(vp9.execute_query(&query), derivatives.literal(literal));
+                }
+                assert_eq!(
+                    derivatives.literal(feature) - derivatives.literal(-feature),
+                    derivatives.influence(feature as u32)
+                );
+            }
+        }
+
+        // the derivative of a node times its count is the number of configurations through it
+        let root = vp9.nodes.len() - 1;
+        assert_eq!(1, *derivatives.node(root));
+        assert!(vp9.derivatives(&[1, -1]).marginal(1).is_none());
+    }
+}
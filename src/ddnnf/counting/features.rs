@@ -1,4 +1,4 @@
-use rug::Float;
+use rug::{Float, Integer};
 use std::error::Error;
 
 use super::super::Ddnnf;
@@ -26,15 +26,14 @@ impl Ddnnf {
     ///
     /// ```
     pub fn card_of_each_feature(&mut self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        self.annotate_partial_derivatives();
+        let distribution = self.literal_distribution();
 
         // start the csv writer with the file_path
         let mut wtr = csv::Writer::from_path(file_path)?;
 
-        for work in 1_i32..self.number_of_variables as i32 + 1 {
-            let cardinality = self.card_of_feature_with_partial_derivatives(work);
+        for (index, (cardinality, _)) in distribution.into_iter().enumerate() {
             wtr.write_record(vec![
-                work.to_string(),
+                (index + 1).to_string(),
                 cardinality.to_string(),
                 format!("{:.20}", Float::with_val(200, cardinality) / self.rc()),
             ])?;
@@ -42,6 +41,35 @@ impl Ddnnf {
 
         Ok(())
     }
+
+    /// Computes for each literal in how many models it occurs.
+    /// The i-th entry holds the counts for the feature i + 1: first for the selected feature
+    /// and second for the deselected feature. All counts result from a single pass over the partial derivatives.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let distribution = ddnnf.literal_distribution();
+    ///
+    /// assert_eq!((4.into(), 0.into()), distribution[0]);
+    /// assert_eq!((2.into(), 2.into()), distribution[2]);
+    /// ```
+    pub fn literal_distribution(&mut self) -> Vec<(Integer, Integer)> {
+        self.annotate_partial_derivatives();
+
+        (1..=self.number_of_variables as i32)
+            .map(|feature| {
+                (
+                    self.card_of_feature_with_partial_derivatives(feature),
+                    self.card_of_feature_with_partial_derivatives(-feature),
+                )
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -102,4 +130,18 @@ mod test {
 
         fs::remove_file(PD_FILE).unwrap();
     }
+
+    #[test]
+    fn literal_distribution_matches_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+        let distribution = vp9.literal_distribution();
+        assert_eq!(42, distribution.len());
+
+        for (index, (selected, deselected)) in distribution.into_iter().enumerate() {
+            let feature = index as i32 + 1;
+            assert_eq!(vp9.execute_query(&[feature]), selected);
+            assert_eq!(vp9.execute_query(&[-feature]), deselected);
+            assert_eq!(vp9.rc(), selected + deselected);
+        }
+    }
 }
@@ -214,28 +214,16 @@ impl Ddnnf {
     fn mark_nodes_start(&mut self, i: usize) {
         self.nodes[i].marker = true;
 
-        for parent in self.nodes[i].parents.clone() {
-            // check for parent nodes and adjust their count resulting of the changes to their children
-            if !self.nodes[parent].marker {
-                // only mark those nodes which aren't already marked to specificly avoid marking nodes near the root multple times
-                self.mark_nodes(parent);
-            }
-        }
-    }
-
-    #[inline]
-    // marks the nodes starting from an initial Literal. All parents and parents of parents til
-    // the root nodes get marked
-    fn mark_nodes(&mut self, i: usize) {
-        self.nodes[i].marker = true;
-        self.md.push(i);
-
-        for parent in self.nodes[i].parents.clone() {
-            // check for parent nodes and adjust their count resulting of the changes to their children
-            if !self.nodes[parent].marker {
-                // only mark those nodes which aren't already marked to specificly avoid marking nodes near the root multple times
-                self.mark_nodes(parent);
+        // the ancestors get visited with an explicit stack to support arbitrarily deep d-DNNFs
+        let mut stack: Vec<usize> = self.nodes[i].parents.iter().rev().copied().collect();
+        while let Some(node) = stack.pop() {
+            // only mark those nodes which aren't already marked to specificly avoid marking nodes near the root multple times
+            if self.nodes[node].marker {
+                continue;
             }
+            self.nodes[node].marker = true;
+            self.md.push(node);
+            stack.extend(self.nodes[node].parents.iter().rev());
         }
     }
 }
@@ -0,0 +1,67 @@
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes the number of models modulo the prime with machine integers instead of big integers.
+    /// Equal d-DNNFs have equal residues, hence the residue serves as cheap fingerprint to compare
+    /// models, e.g. before and after an edit. Different residues for a few primes show that the
+    /// models differ, while equal residues only make it likely that the counts are the same.
+    ///
+    /// # Panics
+    ///
+    /// If the prime is 0.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+    /// // VP9 has 216000 models
+    /// assert_eq!(216_000 % 1_000_003, ddnnf.count_mod(1_000_003));
+    /// assert_eq!(216_000 % 7, ddnnf.count_mod(7));
+    /// ```
+    pub fn count_mod(&self, prime: u64) -> u64 {
+        assert!(prime > 0, "counting modulo 0 is not possible");
+        let modulus = prime as u128;
+
+        let mut residues: Vec<u64> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let residue = match &node.ntype {
+                And { children } => children.iter().fold(1 % modulus, |product, &child| {
+                    product * residues[child] as u128 % modulus
+                }) as u64,
+                Or { children } => children
+                    .iter()
+                    .fold(0, |sum, &child| (sum + residues[child] as u128) % modulus)
+                    as u64,
+                Literal { .. } | True => (1 % modulus) as u64,
+                False => 0,
+            };
+            residues.push(residue);
+        }
+        residues.last().copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn residues_match_counts() {
+        let auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+        for prime in [2_u64, 3, 65_537, 1_000_000_007, 18_446_744_073_709_551_557] {
+            let expected = (auto1.rc() % prime).to_u64().unwrap();
+            assert_eq!(expected, auto1.count_mod(prime));
+        }
+        assert_eq!(0, auto1.count_mod(1));
+
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert_ne!(vp9.count_mod(1_000_000_007), auto1.count_mod(1_000_000_007));
+        assert_eq!(0, vp9.condition(&[1, -1]).count_mod(13));
+    }
+}
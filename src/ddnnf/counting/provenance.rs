@@ -0,0 +1,150 @@
+use super::super::node::{NodeType, NodeType::*};
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes the nodes that contribute to the count under the assumptions in ascending order.
+    /// A node contributes if it is part of at least one configuration that fulfills the assumptions,
+    /// i.e. its count under the assumptions is nonzero and it is reachable from the root via
+    /// such nodes. The remaining nodes explain why a conditioned count is smaller than expected:
+    /// their configurations got excluded.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// assert_eq!(ddnnf.nodes.len(), ddnnf.contributing_nodes(&[]).len());
+    ///
+    /// // 3 excludes the branch with 2 and -3
+    /// let contributing = ddnnf.contributing_nodes(&[3]);
+    /// assert!(!contributing.contains(&ddnnf.literals[&2]));
+    /// assert!(contributing.contains(&ddnnf.literals[&-2]));
+    /// assert!(ddnnf.contributing_nodes(&[2, -2]).is_empty());
+    /// ```
+    pub fn contributing_nodes(&self, assumptions: &[i32]) -> Vec<usize> {
+        let mut satisfiable: Vec<bool> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let sat = node.count != 0
+                && match &node.ntype {
+                    And { children } => children.iter().all(|&c| satisfiable[c]),
+                    Or { children } => children.iter().any(|&c| satisfiable[c]),
+                    Literal { literal } => !assumptions.contains(&-literal),
+                    True => true,
+                    False => false,
+                };
+            satisfiable.push(sat);
+        }
+
+        // the nodes are in postorder, hence parents get visited before their children
+        let mut contributing = vec![false; self.nodes.len()];
+        if let Some(root) = self.nodes.len().checked_sub(1) {
+            contributing[root] = satisfiable[root];
+        }
+        for index in (0..self.nodes.len()).rev() {
+            if let (true, And { children } | Or { children }) =
+                (contributing[index], &self.nodes[index].ntype)
+            {
+                for &child in children {
+                    contributing[child] |= satisfiable[child];
+                }
+            }
+        }
+
+        (0..self.nodes.len())
+            .filter(|&index| contributing[index])
+            .collect()
+    }
+
+    /// Creates a copy of the d-DNNF that only consists of the contributing nodes under the assumptions
+    /// (see [Ddnnf::contributing_nodes]). Or nodes lose their children that do not contribute, hence
+    /// the count of each node in the copy is its count under the assumptions. The copy can be exported
+    /// for visualization, e.g. with [crate::parser::persisting::write_as_dot].
+    ///
+    /// Additionally, returns the index of each node of the copy in this d-DNNF. If no node contributes,
+    /// the copy consists of a single False node that corresponds to the root.
+    pub fn contributing_subgraph(&self, assumptions: &[i32]) -> (Ddnnf, Vec<usize>) {
+        let mut origins = self.contributing_nodes(assumptions);
+        let mut new_index = vec![None; self.nodes.len()];
+        for (new, &old) in origins.iter().enumerate() {
+            new_index[old] = Some(new);
+        }
+
+        let keep = |children: &[usize]| -> Vec<usize> {
+            children.iter().filter_map(|&c| new_index[c]).collect()
+        };
+        let mut ntypes: Vec<NodeType> = origins
+            .iter()
+            .map(|&old| match &self.nodes[old].ntype {
+                And { children } => And {
+                    children: keep(children),
+                },
+                Or { children } => Or {
+                    children: keep(children),
+                },
+                ntype => ntype.clone(),
+            })
+            .collect();
+        if ntypes.is_empty() {
+            ntypes.push(False);
+            origins.push(self.nodes.len().saturating_sub(1));
+        }
+
+        let mut subgraph = Ddnnf {
+            max_worker: self.max_worker,
+            max_digits: self.max_digits,
+            number_of_variables: self.number_of_variables,
+            annotations: self.annotations.clone(),
+            ..Default::default()
+        };
+        subgraph.replace_nodes(ntypes);
+        (subgraph, origins)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn contributing_subgraphs_count_under_assumptions() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert_eq!(vp9.nodes.len(), vp9.contributing_nodes(&[]).len());
+
+        for assumptions in [vec![4], vec![-4, 9], vec![1, -38, 20]] {
+            let contributing = vp9.contributing_nodes(&assumptions);
+            assert!(contributing.len() < vp9.nodes.len());
+            for &node in contributing.iter() {
+                assert!(vp9.count_at(node, &assumptions) > 0);
+            }
+
+            let (mut subgraph, origins) = vp9.contributing_subgraph(&assumptions);
+            assert_eq!(contributing, origins);
+            assert_eq!(vp9.execute_query(&assumptions), subgraph.rc());
+            assert!(subgraph.nodes.iter().all(|node| node.count > 0));
+            for (node, &origin) in subgraph.nodes.iter().zip(origins.iter()) {
+                if let Literal { literal } = node.ntype {
+                    assert_eq!(Literal { literal }, vp9.nodes[origin].ntype);
+                    assert!(!assumptions.contains(&-literal));
+                }
+            }
+            for feature in 1..=42 {
+                let mut query = assumptions.clone();
+                query.push(feature);
+                assert_eq!(
+                    vp9.execute_query(&query),
+                    subgraph.execute_query(&[feature])
+                );
+            }
+        }
+
+        assert!(vp9.contributing_nodes(&[4, -4]).is_empty());
+        let (mut contradiction, origins) = vp9.contributing_subgraph(&[4, -4]);
+        assert_eq!(vec![vp9.nodes.len() - 1], origins);
+        assert_eq!(0, contradiction.rc());
+        assert!(!contradiction.sat(&[]));
+    }
+}
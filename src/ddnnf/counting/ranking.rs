@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use rug::Integer;
+
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Scores each candidate decision by the number of configurations that remain if the candidate
+    /// gets added to the partial configuration. The result is sorted by the score in descending order,
+    /// hence the decisions that keep the most options open come first. Candidates with the same score keep
+    /// their order. Candidates that contradict the partial configuration or refer to unknown variables score 0.
+    ///
+    /// All scores result from a single pass over the partial derivatives of the conditioned d-DNNF
+    /// instead of one query per candidate.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+    /// let ranking = ddnnf.rank_extensions(&[3], &[4, -4, 7, -3]);
+    ///
+    /// assert_eq!(4, ranking.len());
+    /// assert_eq!((-3, 0.into()), ranking[3]);
+    /// for (candidate, score) in ranking {
+    ///     assert_eq!(ddnnf.execute_query(&[3, candidate]), score);
+    /// }
+    /// ```
+    pub fn rank_extensions(&self, partial: &[i32], candidates: &[i32]) -> Vec<(i32, Integer)> {
+        let mut conditioned = self.conditioned(partial);
+        let distribution = conditioned.literal_distribution();
+        let mut ranking: Vec<(i32, Integer)> = candidates
+            .iter()
+            .map(|&candidate| {
+                let score =
+                    match distribution.get((candidate.unsigned_abs() as usize).wrapping_sub(1)) {
+                        Some((selected, _)) if candidate.is_positive() => selected.clone(),
+                        Some((_, deselected)) => deselected.clone(),
+                        None => Integer::ZERO,
+                    };
+                (candidate, score)
+            })
+            .collect();
+        ranking.sort_by(|(_, a), (_, b)| b.cmp(a));
+        ranking
+    }
+
+    /// Like [Ddnnf::rank_extensions], but scores each candidate by the weighted count of the remaining
+    /// configurations (see [Ddnnf::count_weighted]), e.g. to rank by the probability mass of the decisions.
+    /// Needs one weighted count per candidate.
+    pub fn rank_extensions_weighted(
+        &self,
+        partial: &[i32],
+        candidates: &[i32],
+        weights: &HashMap<i32, f64>,
+    ) -> Vec<(i32, f64)> {
+        let mut restricted = weights.clone();
+        for &literal in partial {
+            restricted.insert(-literal, 0.0);
+        }
+
+        let mut ranking: Vec<(i32, f64)> = candidates
+            .iter()
+            .map(|&candidate| {
+                if candidate == 0 || candidate.unsigned_abs() > self.number_of_variables {
+                    return (candidate, 0.0);
+                }
+                let mut extended = restricted.clone();
+                extended.insert(-candidate, 0.0);
+                (candidate, self.count_weighted(&extended))
+            })
+            .collect();
+        ranking.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn rankings_match_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let candidates: Vec<i32> = (-42..=42).filter(|&c| c != 0).chain([43, 0]).collect();
+
+        for partial in [vec![], vec![3, -7], vec![3, -3]] {
+            let ranking = vp9.rank_extensions(&partial, &candidates);
+            assert_eq!(candidates.len(), ranking.len());
+            assert!(ranking.windows(2).all(|w| w[0].1 >= w[1].1));
+            for (candidate, score) in ranking.iter() {
+                let mut query = partial.clone();
+                query.push(*candidate);
+                let expected = if (1..=42).contains(&candidate.unsigned_abs()) {
+                    vp9.execute_query(&query)
+                } else {
+                    Integer::ZERO
+                };
+                assert_eq!(expected, *score, "{candidate}");
+            }
+        }
+
+        // without weights, the weighted ranking is the same
+        let weighted = vp9.rank_extensions_weighted(&[3, -7], &candidates, &HashMap::new());
+        let ranking = vp9.rank_extensions(&[3, -7], &candidates);
+        for ((a, weighted_score), (b, score)) in weighted.iter().zip(ranking.iter()) {
+            assert_eq!(a, b);
+            assert_eq!(score.to_f64(), *weighted_score);
+        }
+    }
+
+    #[test]
+    fn weights_change_the_ranking() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        // 3 and -3 have the same count, but the configurations with -3 are more likely
+        let weights = HashMap::from([(3, 0.2), (-3, 0.8)]);
+        let ranking = ddnnf.rank_extensions_weighted(&[], &[3, -3], &weights);
+        assert_eq!(
+            vec![-3, 3],
+            ranking.iter().map(|(c, _)| *c).collect::<Vec<i32>>()
+        );
+        assert_eq!(4.0 * ranking[1].1, ranking[0].1);
+    }
+}
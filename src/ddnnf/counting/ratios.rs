@@ -0,0 +1,81 @@
+use rug::{Integer, Rational};
+
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes the fold change between the counts of two partial configurations as exact, reduced
+    /// fraction, i.e. count(assumptions_a) / count(assumptions_b).
+    /// Returns None if there is no configuration that fulfills assumptions_b.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use rug::Rational;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// // 2 of the 4 configurations contain feature 3
+    /// assert_eq!(Some(Rational::from((1, 2))), ddnnf.ratio(&[3], &[]));
+    /// assert_eq!(Some(Rational::from(2)), ddnnf.ratio(&[3], &[3, 4]));
+    /// assert_eq!(None, ddnnf.ratio(&[3], &[3, -3]));
+    /// ```
+    pub fn ratio(&mut self, assumptions_a: &[i32], assumptions_b: &[i32]) -> Option<Rational> {
+        let denominator = self.execute_query(assumptions_b);
+        if denominator == 0 {
+            return None;
+        }
+        Some(Rational::from((
+            self.execute_query(assumptions_a),
+            denominator,
+        )))
+    }
+
+    /// Computes the share of all configurations that fulfill the assumptions as exact fraction.
+    /// Returns None if the d-DNNF has no configuration at all.
+    pub fn share(&mut self, assumptions: &[i32]) -> Option<Rational> {
+        self.ratio(assumptions, &[])
+    }
+
+    /// Computes the percentage of all configurations that fulfill the assumptions as exact fraction.
+    /// Returns None if the d-DNNF has no configuration at all.
+    pub fn percentage(&mut self, assumptions: &[i32]) -> Option<Rational> {
+        self.share(assumptions)
+            .map(|share| share * Integer::from(100))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn exact_ratios() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let total = vp9.rc();
+
+        for assumptions in [vec![4], vec![-4, 9], vec![1, -38, 20]] {
+            let count = vp9.execute_query(&assumptions);
+            let share = vp9.share(&assumptions).unwrap();
+            assert_eq!(Rational::from((count.clone(), total.clone())), share);
+            assert_eq!(
+                share.clone() * Integer::from(100),
+                vp9.percentage(&assumptions).unwrap()
+            );
+
+            // a reduced fraction can not be reduced any further
+            assert_eq!(1, share.numer().clone().gcd(share.denom()));
+        }
+
+        assert_eq!(Some(Rational::from(1)), vp9.ratio(&[4], &[4]));
+        assert_eq!(Some(Rational::from(0)), vp9.ratio(&[4, -4], &[4]));
+        assert_eq!(None, vp9.ratio(&[4], &[4, -4]));
+        assert_eq!(Some(Rational::from(100)), vp9.percentage(&[]));
+
+        let mut void = vp9.condition(&[4, -4]);
+        assert_eq!(None, void.share(&[]));
+    }
+}
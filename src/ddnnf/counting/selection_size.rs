@@ -0,0 +1,216 @@
+use std::{
+    collections::HashSet,
+    ops::{Bound, RangeBounds},
+};
+
+use rug::{Complete, Integer};
+
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes the cardinality of a partial configuration, but only counts the configurations
+    /// whose number of selected features lies in the range. That allows bounding the complexity
+    /// of the products in analyses without adding cardinality constraints to the model.
+    ///
+    /// Each node computes the polynomial whose i-th coefficient is the number of its configurations
+    /// that select i features: And nodes multiply and Or nodes add the polynomials of their children.
+    /// Coefficients beyond the upper bound are dropped on the way.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// // two of the four configurations select two features and the other two select three
+    /// assert_eq!(4, ddnnf.count_with_selection_size(&[], 2..=3));
+    /// assert_eq!(2, ddnnf.count_with_selection_size(&[], 3..));
+    /// assert_eq!(0, ddnnf.count_with_selection_size(&[4], ..3));
+    /// ```
+    pub fn count_with_selection_size(
+        &self,
+        assumptions: &[i32],
+        sizes: impl RangeBounds<usize>,
+    ) -> Integer {
+        let min = match sizes.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min + 1,
+            Bound::Unbounded => 0,
+        };
+        let max = match sizes.end_bound() {
+            Bound::Included(&max) => max,
+            Bound::Excluded(&0) => return Integer::ZERO,
+            Bound::Excluded(&max) => max - 1,
+            Bound::Unbounded => self.number_of_variables as usize,
+        };
+        if min > max {
+            return Integer::ZERO;
+        }
+
+        Integer::sum(
+            self.selection_size_polynomial(assumptions, max)
+                .iter()
+                .skip(min),
+        )
+        .complete()
+    }
+
+    /// Computes the distribution of the configurations over their number of selected features in a single
+    /// pass over the d-DNNF. The count at index i belongs to the configurations with i selected features,
+    /// which allows plotting the sizes of the configurations without enumerating them.
+    /// The distribution has an entry for each size from 0 to the number of variables.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(vec![0, 0, 2, 2, 0], ddnnf.selection_size_distribution(&[]));
+    /// assert_eq!(vec![0, 0, 0, 2, 0], ddnnf.selection_size_distribution(&[4]));
+    /// ```
+    pub fn selection_size_distribution(&self, assumptions: &[i32]) -> Vec<Integer> {
+        let size = self.number_of_variables as usize;
+        let mut distribution = self.selection_size_polynomial(assumptions, size);
+        distribution.resize(size + 1, Integer::ZERO);
+        distribution
+    }
+
+    // Computes the number of configurations under the assumptions for each number of selected features
+    // up to max_size. The coefficient at index i belongs to the configurations with i selected features.
+    pub(crate) fn selection_size_polynomial(
+        &self,
+        assumptions: &[i32],
+        max_size: usize,
+    ) -> Vec<Integer> {
+        let excluded: HashSet<i32> = assumptions.iter().map(|literal| -literal).collect();
+
+        let mut polynomials: Vec<Vec<Integer>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let polynomial = match &node.ntype {
+                And { children } => children
+                    .iter()
+                    .fold(vec![Integer::from(1)], |product, &child| {
+                        multiply(&product, &polynomials[child], max_size)
+                    }),
+                Or { children } => {
+                    let mut sum: Vec<Integer> = Vec::new();
+                    for &child in children {
+                        let summand = &polynomials[child];
+                        if sum.len() < summand.len() {
+                            sum.resize(summand.len(), Integer::ZERO);
+                        }
+                        for (coefficient, value) in sum.iter_mut().zip(summand) {
+                            *coefficient += value;
+                        }
+                    }
+                    sum
+                }
+                Literal { literal } if excluded.contains(literal) => Vec::new(),
+                Literal { literal } if literal.is_positive() => {
+                    let mut polynomial = vec![Integer::ZERO, Integer::from(1)];
+                    polynomial.truncate(max_size.saturating_add(1));
+                    polynomial
+                }
+                Literal { .. } | True => vec![Integer::from(1)],
+                False => Vec::new(),
+            };
+            polynomials.push(polynomial);
+        }
+        polynomials.pop().unwrap_or_default()
+    }
+}
+
+// Multiplies two polynomials and drops the coefficients beyond max_size
+fn multiply(a: &[Integer], b: &[Integer], max_size: usize) -> Vec<Integer> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![Integer::ZERO; (a.len() + b.len() - 1).min(max_size.saturating_add(1))];
+    for (i, x) in a.iter().enumerate().filter(|(_, x)| **x != 0) {
+        for (j, y) in b.iter().enumerate().take(product.len().saturating_sub(i)) {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{ddnnf::anomalies::config_creation::EnumOptions, parser::build_ddnnf};
+
+    use super::*;
+
+    #[test]
+    fn selection_sizes_match_brute_force() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+        let models = ddnnf.brute_force_enumerate().unwrap();
+        let selected = |model: &Vec<i32>| model.iter().filter(|l| l.is_positive()).count();
+
+        for assumptions in [vec![], vec![1], vec![-2, 3], vec![2, -2]] {
+            for min in 0..=5 {
+                for max in min..=5 {
+                    let expected = models
+                        .iter()
+                        .filter(|model| assumptions.iter().all(|l| model.contains(l)))
+                        .filter(|model| (min..=max).contains(&selected(model)))
+                        .count();
+                    assert_eq!(
+                        expected,
+                        ddnnf.count_with_selection_size(&assumptions, min..=max),
+                        "{assumptions:?} {min}..={max}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn selection_sizes_of_a_bigger_model() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        for assumptions in [vec![], vec![3, -7], vec![3, -3]] {
+            let polynomial = vp9.selection_size_polynomial(&assumptions, 42);
+            assert_eq!(
+                vp9.execute_query(&assumptions),
+                vp9.count_with_selection_size(&assumptions, ..)
+            );
+
+            // evaluating the polynomial at 2 doubles the weight of each selected feature
+            let mut weights: HashMap<i32, Integer> =
+                (1..=42).map(|f| (f, Integer::from(2))).collect();
+            for literal in assumptions.iter() {
+                weights.insert(-literal, Integer::ZERO);
+            }
+            let evaluated: Integer = polynomial
+                .iter()
+                .enumerate()
+                .map(|(i, coefficient)| Integer::from(coefficient << i as u32))
+                .sum();
+            assert_eq!(vp9.count_weighted(&weights), evaluated);
+
+            // the upper bound cuts the polynomial
+            let bounded = vp9.selection_size_polynomial(&assumptions, 10);
+            assert!(bounded.len() <= 11);
+            assert_eq!(polynomial[..bounded.len()], bounded[..]);
+        }
+
+        let distribution = vp9.selection_size_distribution(&[]);
+        assert_eq!(43, distribution.len());
+        assert_eq!(vp9.rc(), Integer::sum(distribution.iter()).complete());
+        let mut sizes = vec![Integer::ZERO; 43];
+        for config in vp9.enumerate(&[], EnumOptions::default()).unwrap() {
+            sizes[config.iter().filter(|l| l.is_positive()).count()] += 1;
+        }
+        assert_eq!(sizes, distribution);
+
+        assert_eq!(0, vp9.count_with_selection_size(&[], ..0));
+        assert_eq!(0, vp9.count_with_selection_size(&[], 43..));
+    }
+}
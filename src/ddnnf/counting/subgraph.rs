@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+
+use rug::Integer;
+
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes the number of models of the sub-DAG rooted at the node under the assumptions.
+    /// The models range over the variables that occur in the sub-DAG. Assumptions about other
+    /// variables are ignored. Contradicting assumptions result in 0.
+    ///
+    /// # Panics
+    ///
+    /// If there is no node with that index.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let root = ddnnf.nodes.len() - 1;
+    /// assert_eq!(ddnnf.rc(), ddnnf.count_at(root, &[]));
+    ///
+    /// // the literal 2 has one model over its single variable
+    /// let literal = ddnnf.literals[&2];
+    /// assert_eq!(1, ddnnf.count_at(literal, &[]));
+    /// assert_eq!(0, ddnnf.count_at(literal, &[-2]));
+    /// // scoped to the variables 2 and 3, the variable 3 is free
+    /// assert_eq!(2, ddnnf.count_at_in_scope(literal, &[], &[2, 3]));
+    /// assert_eq!(1, ddnnf.count_at_in_scope(literal, &[3], &[2, 3]));
+    /// ```
+    pub fn count_at(&self, node: usize, assumptions: &[i32]) -> Integer {
+        self.count_at_in_scope(node, assumptions, &[])
+    }
+
+    /// Like [Ddnnf::count_at], but the models range over the scope in addition to the variables
+    /// of the sub-DAG. Each variable of the scope that does not occur in the sub-DAG is free
+    /// and doubles the count, unless an assumption fixes it.
+    pub fn count_at_in_scope(&self, node: usize, assumptions: &[i32], scope: &[u32]) -> Integer {
+        assert!(node < self.nodes.len(), "there is no node {node}");
+        let assumed: BTreeSet<i32> = assumptions.iter().copied().collect();
+
+        // the nodes are in postorder, hence parents get visited before their children
+        let mut reachable = vec![false; node + 1];
+        reachable[node] = true;
+        let mut variables = BTreeSet::new();
+        for index in (0..=node).rev() {
+            if !reachable[index] {
+                continue;
+            }
+            match &self.nodes[index].ntype {
+                And { children } | Or { children } => {
+                    for &child in children {
+                        reachable[child] = true;
+                    }
+                }
+                Literal { literal } => {
+                    variables.insert(literal.unsigned_abs());
+                }
+                _ => (),
+            }
+        }
+
+        let mut counts: Vec<Integer> = vec![Integer::ZERO; node + 1];
+        for index in (0..=node).filter(|&index| reachable[index]) {
+            counts[index] = match &self.nodes[index].ntype {
+                And { children } => children.iter().map(|&c| &counts[c]).product(),
+                Or { children } => children.iter().map(|&c| &counts[c]).sum(),
+                Literal { literal } if assumed.contains(&-literal) => Integer::ZERO,
+                Literal { .. } | True => Integer::from(1),
+                False => Integer::ZERO,
+            };
+        }
+
+        let mut count = counts.swap_remove(node);
+        let free: BTreeSet<u32> = scope
+            .iter()
+            .copied()
+            .filter(|variable| !variables.contains(variable))
+            .collect();
+        for variable in free {
+            let variable = variable as i32;
+            match (assumed.contains(&variable), assumed.contains(&-variable)) {
+                (true, true) => return Integer::ZERO,
+                (false, false) => count <<= 1,
+                _ => (),
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn counts_of_subgraphs() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let root = vp9.nodes.len() - 1;
+        for query in [vec![], vec![3], vec![-3, 7], vec![1, -1]] {
+            assert_eq!(vp9.execute_query(&query), vp9.count_at(root, &query));
+        }
+
+        // the children of an And node are decomposable, hence their counts multiply
+        let scope: Vec<u32> = (1..=42).collect();
+        for and in (0..=root).filter(|&i| matches!(vp9.nodes[i].ntype, And { .. })) {
+            if let And { children } = &vp9.nodes[and].ntype {
+                let product: Integer = children.iter().map(|&c| vp9.count_at(c, &[])).product();
+                assert_eq!(vp9.count_at(and, &[]), product);
+                // the variables outside of the sub-DAG are free
+                let scoped = vp9.count_at_in_scope(and, &[], &scope);
+                assert!((scoped / product).is_power_of_two());
+                assert_eq!(0, vp9.count_at_in_scope(and, &[5, -5], &scope));
+            }
+        }
+
+        // the count of a literal depends on the scope and the assumptions only
+        let literal = vp9.literals[&4];
+        assert_eq!(1, vp9.count_at(literal, &[4, 9]));
+        assert_eq!(4, vp9.count_at_in_scope(literal, &[], &[4, 9, 10]));
+        assert_eq!(2, vp9.count_at_in_scope(literal, &[9], &[4, 9, 10]));
+        assert_eq!(0, vp9.count_at_in_scope(literal, &[-4], &[4, 9, 10]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_node() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        ddnnf.count_at(ddnnf.nodes.len(), &[]);
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Derives weights for [Ddnnf::count_weighted] from observed configurations. The weight of a literal
+    /// is its relative frequency among the configurations. Each literal counts as observed once more
+    /// (Laplace smoothing), hence unobserved literals keep a small positive weight.
+    /// Literals that are not part of a configuration count as unobserved for that configuration.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let observed = vec![vec![1, 2, -3, 4], vec![1, -2, 3, 4]];
+    /// let weights = ddnnf.frequency_weights(&observed);
+    ///
+    /// // 4 was selected twice: (2 + 1) / (2 + 2)
+    /// assert_eq!(0.75, weights[&4]);
+    /// assert_eq!(0.25, weights[&-4]);
+    /// assert_eq!(0.5, weights[&3]);
+    /// ```
+    pub fn frequency_weights(&self, observed: &[Vec<i32>]) -> HashMap<i32, f64> {
+        let mut occurrences: HashMap<i32, usize> = HashMap::new();
+        for config in observed {
+            for &literal in config {
+                *occurrences.entry(literal).or_default() += 1;
+            }
+        }
+
+        let total = observed.len() as f64 + 2.0;
+        (1..=self.number_of_variables as i32)
+            .flat_map(|feature| [feature, -feature])
+            .map(|literal| {
+                let seen = occurrences.get(&literal).copied().unwrap_or(0);
+                (literal, (seen as f64 + 1.0) / total)
+            })
+            .collect()
+    }
+
+    /// Fits weights such that the weighted model assigns each literal its observed frequency as probability.
+    /// Under the weighted model, the probability of a configuration is the product of the weights of its literals
+    /// divided by the weighted count. In contrast to [Ddnnf::frequency_weights], the fitting takes the constraints
+    /// of the d-DNNF into account, e.g. a literal that the d-DNNF implies has probability 1 regardless of its weight.
+    ///
+    /// The frequencies get smoothed by one additional observation that follows the distribution of the literal
+    /// among all configurations. In contrast to Laplace smoothing, this keeps the frequencies consistent with
+    /// the d-DNNF, i.e. there are weights that reproduce them. Starting with the frequency weights, each iteration
+    /// rescales the weights of each variable by the ratio between the smoothed frequency and the probability
+    /// under the current weights (iterative proportional fitting). An iteration needs four weighted counts per variable.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::anomalies::config_creation::EnumOptions;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// // the 2 configurations that contain 4 and one that does not
+    /// let mut observed: Vec<Vec<i32>> = ddnnf.enumerate(&[4], EnumOptions::default()).unwrap().collect();
+    /// observed.extend(ddnnf.enumerate(&[-4], EnumOptions::with_limit(1)).unwrap());
+    /// let weights = ddnnf.fit_weights(&observed, 100);
+    ///
+    /// // the observation of the smoothing selects 4 with its share among all configurations
+    /// let share = ddnnf.share(&[4]).unwrap().to_f64();
+    /// let mut selected = weights.clone();
+    /// selected.insert(-4, 0.0);
+    /// let probability = ddnnf.count_weighted(&selected) / ddnnf.count_weighted(&weights);
+    /// assert!((probability - (2.0 + share) / 4.0).abs() < 1e-6);
+    /// ```
+    pub fn fit_weights(&self, observed: &[Vec<i32>], iterations: usize) -> HashMap<i32, f64> {
+        let mut occurrences: HashMap<i32, usize> = HashMap::new();
+        for config in observed {
+            for &literal in config {
+                *occurrences.entry(literal).or_default() += 1;
+            }
+        }
+        let uniform: HashMap<i32, f64> = HashMap::new();
+        let targets: HashMap<i32, f64> = (1..=self.number_of_variables as i32)
+            .flat_map(|feature| [feature, -feature])
+            .map(|literal| {
+                let seen = occurrences.get(&literal).copied().unwrap_or(0) as f64;
+                let share = self.probability(&uniform, literal);
+                (literal, (seen + share) / (observed.len() as f64 + 1.0))
+            })
+            .collect();
+
+        let mut weights = self.frequency_weights(observed);
+        for _ in 0..iterations {
+            for feature in 1..=self.number_of_variables as i32 {
+                for literal in [feature, -feature] {
+                    let probability = self.probability(&weights, literal);
+                    // the d-DNNF determines the value of the variable, hence the weight does not matter
+                    if probability > 0.0 && probability < 1.0 {
+                        *weights.get_mut(&literal).unwrap() *= targets[&literal] / probability;
+                    }
+                }
+            }
+        }
+        weights
+    }
+
+    // The probability of the literal under the weighted model. NaN if there is no configuration.
+    fn probability(&self, weights: &HashMap<i32, f64>, literal: i32) -> f64 {
+        let mut restricted = weights.clone();
+        restricted.insert(-literal, 0.0);
+        self.count_weighted(&restricted) / self.count_weighted(weights)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ddnnf::anomalies::config_creation::EnumOptions, parser::build_ddnnf};
+
+    use super::*;
+
+    #[test]
+    fn fitted_weights_reproduce_frequencies() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        // the observed configurations prefer 3 and 7
+        let mut observed: Vec<Vec<i32>> = Vec::new();
+        for (assumptions, amount) in [(vec![3, 7], 100), (vec![3, -7], 50), (vec![-3, 7], 50)] {
+            observed.extend(
+                vp9.enumerate(&assumptions, EnumOptions::with_limit(amount))
+                    .unwrap(),
+            );
+        }
+        assert_eq!(200, observed.len());
+
+        let weights = vp9.fit_weights(&observed, 100);
+        let uniform = HashMap::new();
+        for feature in 1..=42 {
+            for literal in [feature, -feature] {
+                let seen = observed.iter().filter(|c| c.contains(&literal)).count() as f64;
+                let target = (seen + vp9.probability(&uniform, literal)) / 201.0;
+                let fitted = vp9.probability(&weights, literal);
+                assert!(
+                    (target - fitted).abs() < 1e-4,
+                    "{literal}: {target} {fitted}"
+                );
+            }
+        }
+
+        // the core feature 1 always holds, regardless of its weight
+        assert_eq!(1.0, vp9.probability(&weights, 1));
+        assert!(weights
+            .values()
+            .all(|weight| weight.is_finite() && *weight > 0.0));
+    }
+
+    #[test]
+    fn unobserved_literals() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let weights = ddnnf.frequency_weights(&[]);
+        assert_eq!(8, weights.len());
+        assert!(weights.values().all(|&weight| weight == 0.5));
+        assert_eq!(weights, ddnnf.fit_weights(&[], 0));
+    }
+}
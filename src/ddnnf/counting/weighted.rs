@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    ops::{Add, Mul},
+};
+
+use super::super::node::NodeType::*;
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Computes the weighted model count, i.e. the sum over all models of the product of the weights
+    /// of their literals. Literals without a weight have the weight 1, hence the weighted count
+    /// equals the count if there are no weights at all.
+    ///
+    /// The weights can be exact (e.g. rug::Rational) or approximate (e.g. f64).
+    /// If the weights of each variable are the probabilities of selecting and deselecting it
+    /// (i.e. they add up to 1), the weighted count is the probability that randomly selecting the
+    /// features results in a valid configuration.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::HashMap;
+    /// use rug::Rational;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// assert_eq!(Rational::from(4), ddnnf.count_weighted(&HashMap::<i32, Rational>::new()));
+    ///
+    /// // the weight 0 for -3 leaves the 2 configurations that contain 3,
+    /// // and the one of them that also contains 4 counts half
+    /// let weights = HashMap::from([(-3, 0.0), (4, 0.5)]);
+    /// assert_eq!(1.5, ddnnf.count_weighted(&weights));
+    /// ```
+    pub fn count_weighted<W>(&self, weights: &HashMap<i32, W>) -> W
+    where
+        W: Clone + From<u8> + for<'a> Add<&'a W, Output = W> + for<'a> Mul<&'a W, Output = W>,
+    {
+        let mut values: Vec<W> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let value = match &node.ntype {
+                And { children } => children
+                    .iter()
+                    .fold(W::from(1), |product, &child| product * &values[child]),
+                Or { children } => children
+                    .iter()
+                    .fold(W::from(0), |sum, &child| sum + &values[child]),
+                Literal { literal } => weights.get(literal).cloned().unwrap_or(W::from(1)),
+                True => W::from(1),
+                False => W::from(0),
+            };
+            values.push(value);
+        }
+        values.pop().unwrap_or(W::from(0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{Integer, Rational};
+
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn weighted_counts() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let ones: HashMap<i32, Integer> = (1..=42)
+            .flat_map(|f| [(f, 1.into()), (-f, 1.into())])
+            .collect();
+        assert_eq!(vp9.rc(), vp9.count_weighted(&ones));
+
+        // a weight of 0 excludes the literal
+        let weights = HashMap::from([(-4, Integer::ZERO), (9, Integer::ZERO)]);
+        assert_eq!(vp9.execute_query(&[4, -9]), vp9.count_weighted(&weights));
+
+        // each model contributes the product of the weights of its selected features among 3 to 5
+        let counter =
+            HashMap::from([(3, 2_u32), (4, 3), (5, 5)].map(|(f, w)| (f, Integer::from(w))));
+        let product = vp9.count_weighted(&counter);
+        let mut expected = Integer::ZERO;
+        for selection in 0..8 {
+            let query: Vec<i32> = (0..3)
+                .map(|bit| {
+                    if selection & (1 << bit) != 0 {
+                        3 + bit
+                    } else {
+                        -(3 + bit)
+                    }
+                })
+                .collect();
+            let weight: u32 = query
+                .iter()
+                .filter(|l| l.is_positive())
+                .map(|&l| [2, 3, 5][l as usize - 3])
+                .product();
+            expected += vp9.execute_query(&query) * weight;
+        }
+        assert_eq!(expected, product);
+    }
+
+    #[test]
+    fn probabilities() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+        let probability = |feature: i32| Rational::from((feature, 6));
+        let weights: HashMap<i32, Rational> = (1..=4)
+            .flat_map(|f| {
+                [
+                    (f, probability(f)),
+                    (-f, Rational::from(1) - probability(f)),
+                ]
+            })
+            .collect();
+
+        let mut expected = Rational::new();
+        for model in ddnnf.brute_force_enumerate().unwrap() {
+            expected += model
+                .iter()
+                .map(|l| weights[l].clone())
+                .product::<Rational>();
+        }
+        assert_eq!(expected, ddnnf.count_weighted(&weights));
+
+        let approximate: HashMap<i32, f64> =
+            weights.iter().map(|(&l, w)| (l, w.to_f64())).collect();
+        assert!((expected.to_f64() - ddnnf.count_weighted(&approximate)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,663 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use super::integrity::{Corruption, IntegritySnapshot};
+use super::stream::EDITING_OPERATIONS;
+use crate::{parser::build_ddnnf, Ddnnf};
+
+// The number of queries that each self-check compares against the snapshot of a model
+const SELF_CHECK_QUERIES: usize = 8;
+const SELF_CHECK_SEED: u64 = 42;
+
+/// Serves the stream protocol (see [Ddnnf::handle_stream_msg]) to local clients via a UNIX domain socket.
+/// The daemon keeps multiple models loaded under their names. Each client gets its own session
+/// and thread, hence clients that query different models run concurrently. Messages for the same model
+/// get handled one after another.
+///
+/// Sessions only have read access to the models. To edit a model (via "clause-update" or "undo-update"),
+/// a session needs the exclusive lock of the model. As long as a session holds the lock, the other sessions
+/// can still query the model, but they can neither edit nor replace it. Read-only models can not be locked at all.
+///
+/// In addition to the stream protocol, each session understands the following messages:
+/// - `models` lists the names of the loaded models
+/// - `use <name>` selects the model that answers the following messages of the session
+/// - `load <name> <path> [total-features]` loads a d-DNNF or CNF file as model (and replaces a model of the same name)
+/// - `lock` acquires the exclusive lock of the selected model
+/// - `unlock` releases the exclusive lock of the selected model
+/// - `exit` ends the session and releases all of its locks
+///
+/// Each message is one line and gets answered with one line.
+///
+/// If an edit fails with a panic, the model gets restored to its state before the edit. If any other
+/// message of the stream protocol panics, the model becomes unusable until it gets replaced.
+#[derive(Debug, Default)]
+pub struct Daemon {
+    models: RwLock<BTreeMap<String, Arc<Model>>>,
+    sessions: AtomicU64,
+}
+
+#[derive(Debug)]
+// A model that is shared by all sessions
+struct Model {
+    ddnnf: Mutex<Ddnnf>,
+    read_only: bool,
+    // the id of the session that holds the exclusive lock
+    owner: Mutex<Option<u64>>,
+    // the state of the model at the last self-check, which edits reset
+    snapshot: Mutex<Option<IntegritySnapshot>>,
+    // whether a query panicked and might have left the d-DNNF in an intermediate state
+    unusable: AtomicBool,
+}
+
+/// The state of one client of a [Daemon], which gets created by [Daemon::session]
+#[derive(Debug)]
+pub struct Session {
+    /// The name of the model that answers the stream messages of this session
+    pub model: Option<String>,
+    id: u64,
+}
+
+impl Daemon {
+    /// Adds the model under the name. A model with the same name gets replaced, even if it is locked.
+    /// Sessions that use the old model switch to the new one with their next message.
+    pub fn insert(&self, name: impl Into<String>, ddnnf: Ddnnf) {
+        self.models
+            .write()
+            .unwrap()
+            .insert(name.into(), Arc::new(Model::new(ddnnf, false)));
+    }
+
+    /// Adds the model under the name like [Daemon::insert], but no session can lock, edit, or replace it.
+    pub fn insert_read_only(&self, name: impl Into<String>, ddnnf: Ddnnf) {
+        self.models
+            .write()
+            .unwrap()
+            .insert(name.into(), Arc::new(Model::new(ddnnf, true)));
+    }
+
+    /// The names of the loaded models in ascending order
+    pub fn model_names(&self) -> Vec<String> {
+        self.models.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Starts a new session without a selected model and without locks
+    pub fn session(&self) -> Session {
+        Session {
+            model: None,
+            id: self.sessions.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Ends the session and releases all of its locks
+    pub fn close(&self, session: Session) {
+        for model in self.models.read().unwrap().values() {
+            let mut owner = lock(&model.owner);
+            if *owner == Some(session.id) {
+                *owner = None;
+            }
+        }
+    }
+
+    /// Listens on the socket and serves each client in its own thread. The socket file must not exist yet.
+    /// Only returns if the socket can not be created.
+    pub fn serve(self: Arc<Self>, path: impl AsRef<Path>) -> io::Result<()> {
+        let listener = UnixListener::bind(path)?;
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let daemon = self.clone();
+                    thread::spawn(move || {
+                        let mut session = daemon.session();
+                        let result = daemon.serve_client(stream, &mut session);
+                        // a client that disconnects unexpectedly must not keep its locks
+                        daemon.close(session);
+                        if let Err(err) = result {
+                            eprintln!("Error while serving a client of the daemon: {err}");
+                        }
+                    });
+                }
+                Err(err) => eprintln!("Error while accepting a client of the daemon: {err}"),
+            }
+        }
+        Ok(())
+    }
+
+    // Answers the messages of one client til it exits or closes the connection
+    fn serve_client(&self, stream: UnixStream, session: &mut Session) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let response = self.handle_msg(session, &line?);
+            if response == "exit" {
+                break;
+            }
+            writeln!(writer, "{response}")?;
+        }
+        Ok(())
+    }
+
+    /// Computes the response to a message of the session. Uses the error codes of the stream protocol.
+    /// A message that lets the stream protocol panic gets answered with an E5 error.
+    pub fn handle_msg(&self, session: &mut Session, msg: &str) -> String {
+        let args: Vec<&str> = msg.split_whitespace().collect();
+        match args.as_slice() {
+            ["models"] => self.model_names().join(" "),
+            ["use", name] => {
+                if self.models.read().unwrap().contains_key(*name) {
+                    session.model = Some(name.to_string());
+                    String::new()
+                } else {
+                    format!("E5 error: there is no model named \"{name}\"")
+                }
+            }
+            ["load", name, path, total_features @ ..] => {
+                let total_features = match total_features {
+                    [] => None,
+                    [total_features] => match total_features.parse::<u32>() {
+                        Ok(total_features) => Some(total_features),
+                        Err(e) => return format!("E3 error: {e}"),
+                    },
+                    _ => return String::from("E4 error: \"load\" expects a name, a path, and optionally the number of features"),
+                };
+                if let Err(err) = replaceable(&self.models.read().unwrap(), session, name) {
+                    return err;
+                }
+                if !Path::new(path).is_file() {
+                    return format!("E6 error: there is no file {path}");
+                }
+                // the parser panics on malformed files, which must not take down the daemon
+                let ddnnf = match panic::catch_unwind(AssertUnwindSafe(|| {
+                    build_ddnnf(path, total_features)
+                })) {
+                    Ok(ddnnf) => ddnnf,
+                    Err(_) => return format!("E6 error: unable to load {path}"),
+                };
+
+                // another session might have locked the model in the meantime
+                let mut models = self.models.write().unwrap();
+                if let Err(err) = replaceable(&models, session, name) {
+                    return err;
+                }
+                models.insert(name.to_string(), Arc::new(Model::new(ddnnf, false)));
+                String::new()
+            }
+            ["lock"] => match self.selected(session) {
+                Ok(model) if model.read_only => {
+                    String::from("E5 error: the selected model is read-only")
+                }
+                Ok(model) => {
+                    let mut owner = lock(&model.owner);
+                    match *owner {
+                        Some(id) if id != session.id => String::from(
+                            "E5 error: the selected model is locked by another session",
+                        ),
+                        _ => {
+                            *owner = Some(session.id);
+                            String::new()
+                        }
+                    }
+                }
+                Err(err) => err,
+            },
+            ["unlock"] => {
+                match self.selected(session) {
+                    Ok(model) => {
+                        let mut owner = lock(&model.owner);
+                        if *owner == Some(session.id) {
+                            *owner = None;
+                            String::new()
+                        } else {
+                            String::from("E5 error: the session does not hold the lock of the selected model")
+                        }
+                    }
+                    Err(err) => err,
+                }
+            }
+            ["models" | "use" | "load" | "lock" | "unlock", ..] => {
+                format!("E4 error: wrong number of arguments for \"{}\"", args[0])
+            }
+            ["exit"] => String::from("exit"),
+            _ => {
+                let model = match self.selected(session) {
+                    Ok(model) => model,
+                    Err(err) => return err,
+                };
+                let editing = args
+                    .first()
+                    .is_some_and(|operation| EDITING_OPERATIONS.contains(operation));
+                // the lock of the model can not change hands until the edit is done
+                let _owner = if editing {
+                    let owner = lock(&model.owner);
+                    if *owner != Some(session.id) {
+                        return String::from(
+                            "E5 error: editing the model requires its lock; acquire it with \"lock\"",
+                        );
+                    }
+                    Some(owner)
+                } else {
+                    None
+                };
+                let mut ddnnf = lock(&model.ddnnf);
+                if !model.usable() {
+                    return String::from(
+                        "E5 error: the selected model is unusable after a failed query; replace it with \"load\"",
+                    );
+                }
+                // a panicking query must neither take down the daemon nor leave a half-updated model behind
+                let backup = editing.then(|| ddnnf.clone());
+                let response = match panic::catch_unwind(AssertUnwindSafe(|| {
+                    ddnnf.handle_stream_msg(msg)
+                })) {
+                    Ok(response) => response,
+                    Err(_) => match backup {
+                        Some(backup) => {
+                            *ddnnf = backup;
+                            format!("E5 error: unable to answer \"{msg}\"; the model got restored")
+                        }
+                        None => {
+                            model.unusable.store(true, Ordering::Release);
+                            format!(
+                                "E5 error: unable to answer \"{msg}\"; the model is unusable now"
+                            )
+                        }
+                    },
+                };
+                if editing {
+                    // the edit changes the structure on purpose, hence the next self-check starts over
+                    *lock(&model.snapshot) = None;
+                }
+                response
+            }
+        }
+    }
+
+    /// Verifies each model against its snapshot of the previous self-check (see [Ddnnf::verify_snapshot])
+    /// and returns the corruptions together with the names of the affected models. Models that are new
+    /// or got edited since the previous self-check only get a snapshot. Corrupted models keep their
+    /// snapshot, hence each self-check reports them again until they get replaced.
+    pub fn self_check(&self) -> Vec<(String, Corruption)> {
+        let models: Vec<(String, Arc<Model>)> = self
+            .models
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, model)| (name.clone(), model.clone()))
+            .collect();
+
+        let mut corruptions = Vec::new();
+        for (name, model) in models {
+            // holding the model prevents edits between the check and the snapshot
+            let ddnnf = lock(&model.ddnnf);
+            if !model.usable() {
+                continue;
+            }
+            let mut snapshot = lock(&model.snapshot);
+            match snapshot.as_ref() {
+                Some(snapshot) => corruptions.extend(
+                    ddnnf
+                        .verify_snapshot(snapshot)
+                        .into_iter()
+                        .map(|corruption| (name.clone(), corruption)),
+                ),
+                None => *snapshot = Some(ddnnf.snapshot(SELF_CHECK_QUERIES, SELF_CHECK_SEED)),
+            }
+        }
+        corruptions
+    }
+
+    /// Runs [Daemon::self_check] in a background thread after each interval and reports
+    /// the corruptions on stderr. Long-running daemons with many edits should enable it as a safety net.
+    pub fn spawn_self_check(self: Arc<Self>, interval: Duration) -> thread::JoinHandle<()> {
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            for (name, corruption) in self.self_check() {
+                eprintln!("Integrity alert for the model \"{name}\": {corruption}");
+            }
+        })
+    }
+
+    // The model that the session currently uses
+    fn selected(&self, session: &Session) -> Result<Arc<Model>, String> {
+        match &session.model {
+            Some(name) => self
+                .models
+                .read()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .ok_or_else(|| String::from("E5 error: the selected model does not exist anymore")),
+            None => Err(String::from(
+                "E5 error: no model selected; choose one with \"use <name>\"",
+            )),
+        }
+    }
+}
+
+// Checks whether the session may replace the model with the name (if there is one)
+fn replaceable(
+    models: &BTreeMap<String, Arc<Model>>,
+    session: &Session,
+    name: &str,
+) -> Result<(), String> {
+    match models.get(name) {
+        Some(model) if model.read_only => {
+            Err(format!("E5 error: the model \"{name}\" is read-only"))
+        }
+        Some(model) => match *lock(&model.owner) {
+            Some(id) if id != session.id => Err(format!(
+                "E5 error: the model \"{name}\" is locked by another session"
+            )),
+            _ => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
+
+// Locks the mutex even if another thread panicked while holding it. Only the d-DNNFs can be left
+// half-updated by a panic, hence models with a poisoned d-DNNF are unusable (see Model::usable).
+// The other mutexes guard plain values that a panic can not leave half-updated.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Model {
+    // Whether the d-DNNF is in a consistent state. The daemon catches the panics of the stream protocol
+    // and marks the model as unusable itself, but a panic of another thread that held the d-DNNF
+    // poisons its mutex instead.
+    fn usable(&self) -> bool {
+        !self.unusable.load(Ordering::Acquire) && !self.ddnnf.is_poisoned()
+    }
+
+    fn new(ddnnf: Ddnnf, read_only: bool) -> Self {
+        Model {
+            ddnnf: Mutex::new(ddnnf),
+            read_only,
+            owner: Mutex::new(None),
+            snapshot: Mutex::new(None),
+            unusable: AtomicBool::new(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn sessions_select_models() {
+        let daemon = Daemon::default();
+        daemon.insert("vp9", build_ddnnf("tests/data/VP9_d4.nnf", Some(42)));
+        daemon.insert("small", build_ddnnf("tests/data/small_ex_c2d.nnf", None));
+        assert_eq!(
+            "small vp9",
+            daemon.handle_msg(&mut daemon.session(), "models")
+        );
+
+        let mut first = daemon.session();
+        let mut second = daemon.session();
+        assert!(daemon.handle_msg(&mut first, "count").starts_with("E5"));
+        assert!(daemon.handle_msg(&mut first, "use auto1").starts_with("E5"));
+        assert!(daemon.handle_msg(&mut first, "use").starts_with("E4"));
+
+        assert_eq!("", daemon.handle_msg(&mut first, "use vp9"));
+        assert_eq!("", daemon.handle_msg(&mut second, "use small"));
+        assert_eq!("216000", daemon.handle_msg(&mut first, "count"));
+        assert_eq!("4", daemon.handle_msg(&mut second, "count"));
+        assert_eq!("2", daemon.handle_msg(&mut second, "count a 3"));
+        assert_eq!("exit", daemon.handle_msg(&mut second, "exit"));
+
+        assert!(daemon
+            .handle_msg(&mut first, "load auto1 tests/data/missing.nnf")
+            .starts_with("E6"));
+        assert!(daemon
+            .handle_msg(&mut first, "load auto1 tests/data/auto1_d4.nnf x")
+            .starts_with("E3"));
+        assert_eq!(
+            "",
+            daemon.handle_msg(&mut first, "load auto1 tests/data/auto1_d4.nnf 2513")
+        );
+        assert_eq!("auto1 small vp9", daemon.handle_msg(&mut first, "models"));
+        assert_eq!("", daemon.handle_msg(&mut first, "use auto1"));
+        assert_eq!(
+            build_ddnnf("tests/data/auto1_d4.nnf", Some(2513))
+                .rc()
+                .to_string(),
+            daemon.handle_msg(&mut first, "count")
+        );
+    }
+
+    #[test]
+    fn editing_requires_the_lock() {
+        let daemon = Daemon::default();
+        daemon.insert("auto1", build_ddnnf("tests/data/auto1_c2d.nnf", Some(2513)));
+        daemon.insert_read_only("vp9", build_ddnnf("tests/data/VP9_d4.nnf", Some(42)));
+
+        let mut editor = daemon.session();
+        let mut reader = daemon.session();
+        for session in [&mut editor, &mut reader] {
+            assert!(daemon.handle_msg(session, "lock").starts_with("E5"));
+            assert_eq!("", daemon.handle_msg(session, "use auto1"));
+        }
+        let count = daemon.handle_msg(&mut reader, "count");
+
+        // without the lock, nobody can edit the model
+        assert!(daemon
+            .handle_msg(&mut editor, "undo-update")
+            .starts_with("E5 error: editing"));
+        assert!(daemon.handle_msg(&mut editor, "unlock").starts_with("E5"));
+        assert_eq!("", daemon.handle_msg(&mut editor, "lock"));
+        assert_eq!("", daemon.handle_msg(&mut editor, "lock"));
+
+        // the other session can still query, but neither lock, edit, nor replace the model
+        assert_eq!(count, daemon.handle_msg(&mut reader, "count"));
+        assert!(daemon
+            .handle_msg(&mut reader, "lock")
+            .starts_with("E5 error: the selected model is locked"));
+        assert!(daemon
+            .handle_msg(&mut reader, "clause-update add 1 2")
+            .starts_with("E5 error: editing"));
+        assert!(daemon
+            .handle_msg(&mut reader, "load auto1 tests/data/auto1_d4.nnf 2513")
+            .contains("locked by another session"));
+
+        // the lock gets released by unlock or when the session ends
+        assert_eq!("", daemon.handle_msg(&mut editor, "unlock"));
+        assert_eq!("", daemon.handle_msg(&mut reader, "lock"));
+        daemon.close(reader);
+        assert_eq!("", daemon.handle_msg(&mut editor, "lock"));
+
+        // read-only models can be queried, but not locked or replaced
+        assert_eq!("", daemon.handle_msg(&mut editor, "use vp9"));
+        assert_eq!("216000", daemon.handle_msg(&mut editor, "count"));
+        assert!(daemon.handle_msg(&mut editor, "lock").contains("read-only"));
+        assert!(daemon
+            .handle_msg(&mut editor, "undo-update")
+            .starts_with("E5 error: editing"));
+        assert!(daemon
+            .handle_msg(&mut editor, "load vp9 tests/data/VP9_d4.nnf 42")
+            .contains("read-only"));
+    }
+
+    #[test]
+    fn edits_keep_the_lock_of_the_model() {
+        let daemon = Arc::new(Daemon::default());
+        daemon.insert("auto1", build_ddnnf("tests/data/auto1_c2d.nnf", Some(2513)));
+        let mut editor = daemon.session();
+        assert_eq!("", daemon.handle_msg(&mut editor, "use auto1"));
+        assert_eq!("", daemon.handle_msg(&mut editor, "lock"));
+        let auto1 = daemon.selected(&editor).unwrap();
+
+        // while the edit waits for the d-DNNF, its session keeps holding the lock
+        let ddnnf = auto1.ddnnf.lock().unwrap();
+        let editing = daemon.clone();
+        let edit = thread::spawn(move || editing.handle_msg(&mut editor, "undo-update"));
+        let mut waited = 0;
+        while auto1.owner.try_lock().is_ok() {
+            assert!(
+                waited < 1000,
+                "the edit does not hold the lock of the model"
+            );
+            thread::sleep(Duration::from_millis(1));
+            waited += 1;
+        }
+        drop(ddnnf);
+        assert!(edit
+            .join()
+            .unwrap()
+            .starts_with("E5 error: could not perform undo"));
+        assert!(auto1.owner.try_lock().is_ok());
+    }
+
+    #[test]
+    fn self_checks_detect_corruptions() {
+        let daemon = Daemon::default();
+        daemon.insert("vp9", build_ddnnf("tests/data/VP9_d4.nnf", Some(42)));
+        daemon.insert("small", build_ddnnf("tests/data/small_ex_c2d.nnf", None));
+        let mut session = daemon.session();
+        assert_eq!("", daemon.handle_msg(&mut session, "use vp9"));
+
+        // the first check takes the snapshots, queries keep the state intact
+        assert!(daemon.self_check().is_empty());
+        daemon.handle_msg(&mut session, "count a 3 -4");
+        daemon.handle_msg(&mut session, "core");
+        assert!(daemon.self_check().is_empty());
+
+        // a corrupted count gets reported until the model gets replaced
+        let vp9 = daemon.selected(&session).unwrap();
+        vp9.ddnnf.lock().unwrap().nodes[10].count += 1;
+        for _ in 0..2 {
+            let corruptions = daemon.self_check();
+            assert!(!corruptions.is_empty());
+            assert!(corruptions.iter().all(|(name, _)| name == "vp9"));
+            assert!(corruptions.iter().any(|(_, corruption)| matches!(
+                corruption,
+                Corruption::StaleCount { node: 10, .. }
+            )));
+        }
+
+        // changing the structure without an edit shows up in the hash
+        let small = daemon.models.read().unwrap()["small"].clone();
+        small.ddnnf.lock().unwrap().nodes.pop();
+        assert!(daemon
+            .self_check()
+            .iter()
+            .any(|(name, corruption)| name == "small"
+                && matches!(corruption, Corruption::StructureChanged { .. })));
+
+        // edits reset the snapshot
+        daemon.insert("small", build_ddnnf("tests/data/small_ex_c2d.nnf", None));
+        daemon.insert("vp9", build_ddnnf("tests/data/VP9_d4.nnf", Some(42)));
+        assert_eq!("", daemon.handle_msg(&mut session, "lock"));
+        assert!(daemon.self_check().is_empty());
+        daemon.handle_msg(&mut session, "undo-update");
+        assert!(daemon
+            .selected(&session)
+            .unwrap()
+            .snapshot
+            .lock()
+            .unwrap()
+            .is_none());
+        assert!(daemon.self_check().is_empty());
+    }
+
+    #[test]
+    fn panics_do_not_lock_out_other_sessions() {
+        let daemon = Daemon::default();
+        daemon.insert("vp9", build_ddnnf("tests/data/VP9_d4.nnf", Some(42)));
+        let mut session = daemon.session();
+        assert_eq!("", daemon.handle_msg(&mut session, "use vp9"));
+        assert_eq!("", daemon.handle_msg(&mut session, "lock"));
+
+        // the d-DNNF file comes without clauses, which the stream handler does not expect here
+        let vp9 = daemon.selected(&session).unwrap();
+        let ids = vp9.ddnnf.lock().unwrap().node_ids();
+        assert!(daemon
+            .handle_msg(&mut session, "clause-update t 3")
+            .ends_with("the model got restored"));
+        assert_eq!("216000", daemon.handle_msg(&mut session, "count"));
+        assert_eq!(ids, vp9.ddnnf.lock().unwrap().node_ids());
+
+        // a panic outside of the daemon poisons the mutex, hence the model might be half-updated
+        let poisoned = vp9.clone();
+        thread::spawn(move || {
+            let _ddnnf = poisoned.ddnnf.lock().unwrap();
+            panic!("poisons the mutex");
+        })
+        .join()
+        .unwrap_err();
+        assert!(vp9.ddnnf.is_poisoned());
+        assert!(daemon
+            .handle_msg(&mut session, "count")
+            .starts_with("E5 error: the selected model is unusable"));
+        assert!(daemon.self_check().is_empty());
+
+        // replacing the model makes it usable again, until a query fails
+        assert_eq!(
+            "",
+            daemon.handle_msg(&mut session, "load vp9 tests/data/VP9_d4.nnf 42")
+        );
+        assert_eq!("216000", daemon.handle_msg(&mut session, "count"));
+        daemon
+            .selected(&session)
+            .unwrap()
+            .unusable
+            .store(true, Ordering::Release);
+        assert!(daemon
+            .handle_msg(&mut session, "core")
+            .starts_with("E5 error: the selected model is unusable"));
+        assert!(daemon.self_check().is_empty());
+    }
+
+    #[test]
+    fn serves_clients_via_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("ddnnife.sock");
+
+        let daemon = Arc::new(Daemon::default());
+        daemon.insert("vp9", build_ddnnf("tests/data/VP9_d4.nnf", Some(42)));
+        let served = socket.clone();
+        thread::spawn(move || daemon.serve(served));
+
+        let connect = || {
+            for _ in 0..100 {
+                if let Ok(stream) = UnixStream::connect(&socket) {
+                    return stream;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            panic!("the daemon does not listen on {socket:?}");
+        };
+
+        let mut clients: Vec<(UnixStream, BufReader<UnixStream>)> = (0..3)
+            .map(|_| {
+                let stream = connect();
+                let reader = BufReader::new(stream.try_clone().unwrap());
+                (stream, reader)
+            })
+            .collect();
+        for (index, (stream, reader)) in clients.iter_mut().enumerate() {
+            writeln!(stream, "use vp9").unwrap();
+            writeln!(stream, "count a {}", index + 1).unwrap();
+            let mut responses = [String::new(), String::new()];
+            for response in responses.iter_mut() {
+                reader.read_line(response).unwrap();
+            }
+            assert_eq!("\n", responses[0]);
+            assert!(responses[1].trim().parse::<u32>().unwrap() <= 216000);
+        }
+
+        // after exit, the daemon closes the connection
+        let (stream, reader) = &mut clients[0];
+        writeln!(stream, "exit").unwrap();
+        let mut rest = String::new();
+        assert_eq!(0, reader.read_line(&mut rest).unwrap());
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeSet;
+
+use itertools::Either;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use tempfile::Builder;
+
+use crate::{
+    parser::{build_ddnnf, persisting::write_cnf_to_file},
+    Ddnnf,
+};
+
+/// A random CNF together with a random clause update. Editing the compiled CNF has to yield the
+/// same models as compiling the edited CNF from scratch, which makes such cases property tests
+/// for the clause updates of [Ddnnf::update_cached_state].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditCase {
+    pub number_of_variables: u32,
+    pub clauses: BTreeSet<BTreeSet<i32>>,
+    /// The clauses that get added by the update
+    pub added: Vec<BTreeSet<i32>>,
+    /// The clauses of the CNF that get removed by the update
+    pub removed: Vec<BTreeSet<i32>>,
+}
+
+impl EditCase {
+    /// Generates a CNF with up to three literals per clause and an update that adds and removes
+    /// up to max_edits clauses each. Reusing the same seed yields the same case.
+    pub fn random(
+        number_of_variables: u32,
+        number_of_clauses: usize,
+        max_edits: usize,
+        seed: u64,
+    ) -> Self {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let clauses: BTreeSet<BTreeSet<i32>> = (0..number_of_clauses)
+            .map(|_| random_clause(number_of_variables, &mut rng))
+            .collect();
+        let added = (0..rng.gen_range(0..=max_edits))
+            .map(|_| random_clause(number_of_variables, &mut rng))
+            .collect();
+        let amount = rng.gen_range(0..=max_edits);
+        let removed = clauses
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .choose_multiple(&mut rng, amount)
+            .cloned()
+            .collect();
+
+        EditCase {
+            number_of_variables,
+            clauses,
+            added,
+            removed,
+        }
+    }
+
+    /// The CNF after the update
+    pub fn edited_clauses(&self) -> BTreeSet<BTreeSet<i32>> {
+        self.clauses
+            .iter()
+            .filter(|clause| !self.removed.contains(clause))
+            .chain(self.added.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Compiles the CNF and updates it with the clauses. The resulting d-DNNF has to have the same
+    /// count and the same cardinality of each feature as the compilation of the edited CNF.
+    /// Returns the first deviation.
+    pub fn check(&self) -> Result<(), String> {
+        let mut edited = compile(&self.clauses, self.number_of_variables)?;
+        if !edited.update_cached_state(
+            Either::Left((self.added.clone(), self.removed.clone())),
+            Some(self.number_of_variables),
+        ) {
+            return Err(String::from("the clause update failed"));
+        }
+        let mut recompiled = compile(&self.edited_clauses(), self.number_of_variables)?;
+
+        if edited.rc() != recompiled.rc() {
+            return Err(format!(
+                "the updated d-DNNF has {} models, but the recompiled one has {}",
+                edited.rc(),
+                recompiled.rc()
+            ));
+        }
+        for feature in 1..=self.number_of_variables as i32 {
+            let (updated, expected) = (
+                edited.execute_query(&[feature]),
+                recompiled.execute_query(&[feature]),
+            );
+            if updated != expected {
+                return Err(format!(
+                    "the feature {feature} has the cardinality {updated} in the updated d-DNNF, but {expected} in the recompiled one"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Shrinks a failing case by removing single clauses and literals as long as the case still
+    /// fails the check. Returns the minimal case with its failure.
+    pub fn shrink(
+        self,
+        failure: String,
+        check: impl Fn(&EditCase) -> Result<(), String>,
+    ) -> (EditCase, String) {
+        let (mut case, mut failure) = (self, failure);
+        'shrinking: loop {
+            for smaller in case.smaller_cases() {
+                if let Err(smaller_failure) = check(&smaller) {
+                    (case, failure) = (smaller, smaller_failure);
+                    continue 'shrinking;
+                }
+            }
+            return (case, failure);
+        }
+    }
+
+    // All cases that miss exactly one clause or one literal of a clause with more than one literal
+    fn smaller_cases(&self) -> Vec<EditCase> {
+        let mut cases = Vec::new();
+
+        for index in 0..self.added.len() {
+            let mut case = self.clone();
+            case.added.remove(index);
+            cases.push(case);
+            for literal in self.added[index]
+                .iter()
+                .filter(|_| self.added[index].len() > 1)
+            {
+                let mut case = self.clone();
+                case.added[index].remove(literal);
+                cases.push(case);
+            }
+        }
+
+        for clause in self.clauses.iter() {
+            // removed clauses have to stay part of the CNF
+            let mut case = self.clone();
+            case.clauses.remove(clause);
+            case.removed.retain(|removed| removed != clause);
+            cases.push(case);
+
+            for literal in clause.iter().filter(|_| clause.len() > 1) {
+                let mut smaller_clause = clause.clone();
+                smaller_clause.remove(literal);
+                if self.clauses.contains(&smaller_clause) {
+                    continue;
+                }
+                let mut case = self.clone();
+                case.clauses.remove(clause);
+                case.clauses.insert(smaller_clause.clone());
+                for removed in case.removed.iter_mut().filter(|removed| *removed == clause) {
+                    *removed = smaller_clause.clone();
+                }
+                cases.push(case);
+            }
+        }
+
+        for index in 0..self.removed.len() {
+            let mut case = self.clone();
+            case.removed.remove(index);
+            cases.push(case);
+        }
+        cases
+    }
+}
+
+/// Checks the given number of random edit cases of CNFs over number_of_variables variables.
+/// The first failing case gets shrunk (see [EditCase::shrink]) and is returned with its failure.
+/// Compiling the CNFs requires the d4 compiler.
+pub fn check_random_edits(
+    number_of_variables: u32,
+    cases: usize,
+    seed: u64,
+) -> Result<(), (EditCase, String)> {
+    for case_seed in seed..seed + cases as u64 {
+        let case = EditCase::random(
+            number_of_variables,
+            number_of_variables as usize * 2,
+            3,
+            case_seed,
+        );
+        if let Err(failure) = case.check() {
+            return Err(case.shrink(failure, EditCase::check));
+        }
+    }
+    Ok(())
+}
+
+// Writes the CNF into a temporary file and compiles it
+fn compile(clauses: &BTreeSet<BTreeSet<i32>>, number_of_variables: u32) -> Result<Ddnnf, String> {
+    let file = Builder::new()
+        .prefix("edit_case")
+        .suffix(".cnf")
+        .tempfile()
+        .map_err(|err| err.to_string())?;
+    let path = file
+        .path()
+        .to_str()
+        .ok_or("the path of the temporary CNF is no valid string")?;
+    write_cnf_to_file(clauses, number_of_variables, path).map_err(|err| err.to_string())?;
+    Ok(build_ddnnf(path, None))
+}
+
+// A clause with one to three literals over distinct variables
+fn random_clause(number_of_variables: u32, rng: &mut Pcg32) -> BTreeSet<i32> {
+    let variables: Vec<i32> = (1..=number_of_variables as i32).collect();
+    let length = rng.gen_range(1..=3.min(variables.len()));
+    variables
+        .choose_multiple(rng, length)
+        .map(|&variable| {
+            if rng.gen_bool(0.5) {
+                variable
+            } else {
+                -variable
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn random_cases() {
+        let case = EditCase::random(6, 12, 3, 42);
+        assert_eq!(case, EditCase::random(6, 12, 3, 42));
+        assert!(case
+            .clauses
+            .iter()
+            .all(|clause| (1..=3).contains(&clause.len())));
+        assert!(case.added.len() <= 3 && case.removed.len() <= 3);
+        assert!(case
+            .removed
+            .iter()
+            .all(|clause| case.clauses.contains(clause)));
+
+        let edited = case.edited_clauses();
+        assert!(case.added.iter().all(|clause| edited.contains(clause)));
+        assert!(case
+            .removed
+            .iter()
+            .all(|clause| case.added.contains(clause) || !edited.contains(clause)));
+    }
+
+    #[test]
+    fn shrinking_finds_minimal_cases() {
+        // the check fails as soon as the edited CNF contains the literal -3
+        let check = |case: &EditCase| match case.edited_clauses().iter().any(|c| c.contains(&-3)) {
+            true => Err(String::from("contains -3")),
+            false => Ok(()),
+        };
+        let case = (0..)
+            .map(|seed| EditCase::random(5, 10, 3, seed))
+            .find(|case| case.removed.len() > 1 && check(case).is_err())
+            .unwrap();
+
+        let (minimal, failure) = case.shrink(String::from("contains -3"), check);
+        assert_eq!("contains -3", failure);
+        assert_eq!(
+            vec![BTreeSet::from([-3])],
+            minimal.edited_clauses().into_iter().collect::<Vec<_>>()
+        );
+        assert!(minimal.removed.is_empty());
+        assert_eq!(1, minimal.clauses.len() + minimal.added.len());
+    }
+
+    #[cfg(feature = "d4")]
+    #[test]
+    fn edits_match_recompilation() {
+        if let Err((case, failure)) = check_random_edits(6, 20, 0) {
+            panic!("{failure} for the minimal case {case:?}");
+        }
+    }
+}
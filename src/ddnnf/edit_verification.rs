@@ -0,0 +1,222 @@
+use std::collections::BTreeSet;
+
+use rug::Integer;
+
+use crate::Ddnnf;
+
+/// The maximum number of added clauses for which we compare the counts exactly.
+/// The inclusion-exclusion needs one query per subset of the added clauses.
+pub const EXACT_CHECK_LIMIT: usize = 12;
+
+impl Ddnnf {
+    /// Checks that this d-DNNF results from adding the clauses to the old d-DNNF. That means, the
+    /// models of this d-DNNF restricted to the variables of the old one have to be the models of the
+    /// old d-DNNF that satisfy the clauses.
+    ///
+    /// We probe both directions with 'probes' uniform random samples: Each sampled model of the old
+    /// d-DNNF that satisfies the clauses has to be extendable to a model of this d-DNNF and each
+    /// sampled model of this d-DNNF has to satisfy the clauses and be a model of the old d-DNNF.
+    /// If no variables got introduced and there are at most [EXACT_CHECK_LIMIT] clauses, the count
+    /// of this d-DNNF is additionally compared with the exact count of the old d-DNNF and the clauses.
+    ///
+    /// Returns a description of the first deviation.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::BTreeSet;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut old: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// // adding the unit clause -4 is the same as conditioning on -4
+    /// let mut new = old.condition(&[-4]);
+    /// let clauses = vec![BTreeSet::from([-4])];
+    ///
+    /// assert!(new.verify_added_clauses(&mut old, &clauses, 10, 42).is_ok());
+    /// assert!(old.clone().verify_added_clauses(&mut old, &clauses, 10, 42).is_err());
+    /// ```
+    pub fn verify_added_clauses(
+        &mut self,
+        old: &mut Ddnnf,
+        clauses: &[BTreeSet<i32>],
+        probes: usize,
+        seed: u64,
+    ) -> Result<(), String> {
+        let satisfies_clauses = |config: &[i32]| {
+            clauses
+                .iter()
+                .all(|clause| clause.iter().any(|l| config.contains(l)))
+        };
+
+        if self.number_of_variables == old.number_of_variables && clauses.len() <= EXACT_CHECK_LIMIT
+        {
+            let expected = count_with_clauses(old, clauses);
+            if expected != self.rc() {
+                return Err(format!(
+                    "the d-DNNF has {} models, but the old d-DNNF has {expected} models that satisfy the clauses",
+                    self.rc()
+                ));
+            }
+        }
+
+        for config in old
+            .uniform_random_sampling(&[], probes, seed)
+            .unwrap_or_default()
+        {
+            if satisfies_clauses(&config) && !self.sat(&config) {
+                return Err(format!(
+                    "the model {config:?} of the old d-DNNF satisfies the clauses but has no extension in the d-DNNF"
+                ));
+            }
+        }
+
+        let old_variables = old.number_of_variables as i32;
+        for config in self
+            .uniform_random_sampling(&[], probes, seed)
+            .unwrap_or_default()
+        {
+            if !satisfies_clauses(&config) {
+                return Err(format!(
+                    "the model {config:?} of the d-DNNF does not satisfy the clauses"
+                ));
+            }
+            let projection: Vec<i32> = config
+                .into_iter()
+                .filter(|literal| literal.abs() <= old_variables)
+                .collect();
+            if !old.sat(&projection) {
+                return Err(format!(
+                    "the model {projection:?} of the d-DNNF is no model of the old d-DNNF"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the last clause update with [Ddnnf::verify_added_clauses] against the d-DNNF
+    /// that was used before the update. Only updates that solely added clauses can be verified.
+    pub fn verify_last_update(&mut self, probes: usize, seed: u64) -> Result<(), String> {
+        let (mut old, added) = match self.cached_state.as_ref() {
+            Some(state) => match (state.old_state.as_deref(), state.last_edit()) {
+                (Some(old), (added, [])) => (old.clone(), added.to_vec()),
+                (Some(_), _) => {
+                    return Err(String::from(
+                        "the last update removed clauses, which can not be verified",
+                    ))
+                }
+                (None, _) => return Err(String::from("there was no update")),
+            },
+            None => return Err(String::from("the d-DNNF does not support updates")),
+        };
+        self.verify_added_clauses(&mut old, &added, probes, seed)
+    }
+}
+
+// Counts the models of the d-DNNF that satisfy all clauses with inclusion-exclusion over the
+// configurations that violate at least one of the clauses
+fn count_with_clauses(ddnnf: &mut Ddnnf, clauses: &[BTreeSet<i32>]) -> Integer {
+    let mut count = Integer::ZERO;
+    for subset in 0..1_usize << clauses.len() {
+        // the configurations that violate each clause of the subset
+        let violation: BTreeSet<i32> = clauses
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| subset & (1 << index) != 0)
+            .flat_map(|(_, clause)| clause.iter().map(|literal| -literal))
+            .collect();
+        if violation
+            .iter()
+            .any(|literal| violation.contains(&-literal))
+        {
+            continue;
+        }
+
+        let violating = ddnnf.execute_query(&violation.into_iter().collect::<Vec<i32>>());
+        if subset.count_ones() % 2 == 0 {
+            count += violating;
+        } else {
+            count -= violating;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn added_clauses_get_verified() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let clauses = vec![
+            BTreeSet::from([4]),
+            BTreeSet::from([-9]),
+            BTreeSet::from([-4, 20]),
+        ];
+        let mut updated = vp9.condition(&[4, -9, 20]);
+        assert!(updated
+            .verify_added_clauses(&mut vp9, &clauses, 50, 42)
+            .is_ok());
+
+        // the clause -4 and 20 is missing
+        let mut incomplete = vp9.condition(&[4, -9]);
+        let err = incomplete
+            .verify_added_clauses(&mut vp9, &clauses, 50, 42)
+            .unwrap_err();
+        assert!(err.contains("models"), "{err}");
+
+        // too many clauses to compare the counts exactly, but the probes detect the deviation
+        let many: Vec<BTreeSet<i32>> = (0..=EXACT_CHECK_LIMIT)
+            .map(|_| BTreeSet::from([4]))
+            .chain(clauses.iter().cloned())
+            .collect();
+        assert!(updated
+            .verify_added_clauses(&mut vp9, &many, 50, 42)
+            .is_ok());
+        let err = incomplete
+            .verify_added_clauses(&mut vp9, &many, 50, 42)
+            .unwrap_err();
+        assert!(err.contains("does not satisfy"), "{err}");
+
+        assert!(vp9.verify_last_update(10, 42).is_err());
+    }
+
+    #[cfg(feature = "d4")]
+    #[test]
+    fn clause_updates_get_verified() {
+        use itertools::Either;
+
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex.cnf", None);
+        assert!(ddnnf.verify_last_update(10, 42).is_err());
+
+        let added = vec![BTreeSet::from([-4, 5])];
+        assert!(ddnnf.update_cached_state(Either::Left((added, vec![])), Some(5)));
+        assert_eq!(Ok(()), ddnnf.verify_last_update(20, 42));
+    }
+
+    #[test]
+    fn inclusion_exclusion_count() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert_eq!(vp9.rc(), count_with_clauses(&mut vp9, &[]));
+
+        let count = vp9.execute_query(&[4]) + vp9.execute_query(&[-4, 9]);
+        assert_eq!(
+            count,
+            count_with_clauses(&mut vp9, &[BTreeSet::from([4, 9])])
+        );
+        assert_eq!(
+            vp9.execute_query(&[4, -9]),
+            count_with_clauses(
+                &mut vp9,
+                &[
+                    BTreeSet::from([4]),
+                    BTreeSet::from([-9]),
+                    BTreeSet::from([9, 4])
+                ]
+            )
+        );
+    }
+}
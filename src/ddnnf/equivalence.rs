@@ -0,0 +1,159 @@
+use super::{node::NodeType::*, Ddnnf};
+
+// The largest Mersenne prime that fits into 64 bits. All evaluations are modulo this prime.
+const PRIME: u64 = (1 << 61) - 1;
+// Each round divides the probability of a wrong answer by roughly 2^61 / #variables
+const ROUNDS: usize = 3;
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+impl Ddnnf {
+    /// Decides whether both d-DNNFs represent the same Boolean function, e.g. to validate that
+    /// a refactored model still has the same configurations. The d-DNNFs may stem from different
+    /// compilers and differ arbitrarily in their structure. Variables that only one of them knows
+    /// are unconstrained in the other one.
+    ///
+    /// The check is randomized: We evaluate the multilinear extension of both functions at a few
+    /// pseudo-random points, which only requires a single pass over each d-DNNF per point.
+    /// Each literal x gets a random weight r and ¬x gets 1 - r. Because of determinism and
+    /// decomposability, the d-DNNF then computes the sum over its models of the product of their weights,
+    /// which is the same polynomial for the same function. If the values differ, the functions
+    /// differ for sure. If they match for all points, the functions are the same except for
+    /// a probability of at most (#variables / 2^61)^3.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let c2d: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let d4: Ddnnf = build_ddnnf("./tests/data/small_ex_d4.nnf", Some(4));
+    ///
+    /// assert!(c2d.equivalent(&d4));
+    /// assert!(!c2d.equivalent(&d4.condition(&[4])));
+    /// ```
+    pub fn equivalent(&self, other: &Ddnnf) -> bool {
+        // literals beyond the declared number of variables (see Ddnnf::check_integrity) need a weight as well
+        let variables = self
+            .largest_variable()
+            .max(other.largest_variable())
+            .max(self.number_of_variables)
+            .max(other.number_of_variables);
+
+        // the counts only match for the same function if both range over the same variables
+        if self.number_of_variables == variables
+            && other.number_of_variables == variables
+            && self.rc() != other.rc()
+        {
+            return false;
+        }
+
+        let mut state = SEED;
+        (0..ROUNDS).all(|_| {
+            let point: Vec<u64> = (0..variables as usize)
+                .map(|_| split_mix(&mut state) % PRIME)
+                .collect();
+            self.multilinear_extension(&point) == other.multilinear_extension(&point)
+        })
+    }
+
+    // The largest variable that occurs in a literal of the d-DNNF
+    fn largest_variable(&self) -> u32 {
+        self.literals
+            .keys()
+            .map(|literal| literal.unsigned_abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Evaluates the multilinear extension of the function at the point modulo PRIME.
+    // The point contains the weight of each positive literal, starting with the variable 1,
+    // and has to cover all variables that occur in a literal.
+    fn multilinear_extension(&self, point: &[u64]) -> u64 {
+        let multiply = |a: u64, b: u64| ((a as u128 * b as u128) % PRIME as u128) as u64;
+
+        let mut values: Vec<u64> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let value = match &node.ntype {
+                And { children } => children
+                    .iter()
+                    .fold(1, |product, &child| multiply(product, values[child])),
+                Or { children } => children
+                    .iter()
+                    .fold(0, |sum, &child| (sum + values[child]) % PRIME),
+                Literal { literal } => {
+                    let weight = point[literal.unsigned_abs() as usize - 1];
+                    if literal.is_positive() {
+                        weight
+                    } else {
+                        (1 + PRIME - weight) % PRIME
+                    }
+                }
+                True => 1,
+                False => 0,
+            };
+            values.push(value);
+        }
+        values.pop().unwrap_or(0)
+    }
+}
+
+// The SplitMix64 generator, which keeps the check deterministic and independent of the sampling feature
+pub(crate) fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn equivalence_of_different_compilations() {
+        let c2d: Ddnnf = build_ddnnf("tests/data/auto1_c2d.nnf", Some(2513));
+        let d4: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
+        assert!(c2d.equivalent(&d4));
+        assert!(d4.equivalent(&c2d));
+
+        // conditioned d-DNNFs can have the same count without having the same configurations
+        let (selected, deselected) = (c2d.condition(&[1, -2]), d4.condition(&[-1, 2]));
+        assert!(selected.equivalent(&d4.condition(&[1, -2])));
+        if selected.rc() == deselected.rc() {
+            assert!(!selected.equivalent(&deselected));
+        }
+    }
+
+    #[test]
+    fn equivalence_detects_differences() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert!(vp9.equivalent(&vp9));
+        assert!(vp9.equivalent(&vp9.condition(&[])));
+        assert!(!vp9.equivalent(&vp9.condition(&[4])));
+
+        // a feature and its negation lead to different functions unless both are void
+        for feature in 1..=42 {
+            let (selected, deselected) = (vp9.condition(&[feature]), vp9.condition(&[-feature]));
+            assert_eq!(
+                selected.rc() == 0 && deselected.rc() == 0,
+                selected.equivalent(&deselected)
+            );
+        }
+
+        // an additional unconstrained variable does not change the function
+        let mut wider = vp9.clone();
+        wider.number_of_variables = 43;
+        assert!(vp9.equivalent(&wider));
+
+        // literals of undeclared variables get a weight as well
+        let mut narrower = vp9.clone();
+        narrower.number_of_variables = 40;
+        assert!(narrower.equivalent(&vp9));
+        assert!(vp9.equivalent(&narrower));
+        assert!(!narrower.equivalent(&vp9.condition(&[-42])));
+    }
+}
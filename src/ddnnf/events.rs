@@ -0,0 +1,190 @@
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+use super::Ddnnf;
+
+/// A change of a d-DNNF that is reported to the listeners registered with [Ddnnf::subscribe]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelEvent {
+    /// The clauses got edited and the d-DNNF was replaced by the recompiled one
+    ClausesEdited {
+        added: Vec<BTreeSet<i32>>,
+        removed: Vec<BTreeSet<i32>>,
+    },
+    /// The last clause update got undone (or redone)
+    Undone,
+    /// A conditioned copy of the d-DNNF was created. The d-DNNF itself stays the same.
+    Conditioned { assumptions: Vec<i32> },
+    /// The nodes got replaced by equivalent ones, e.g. by [Ddnnf::minimize]
+    Rebuilt,
+}
+
+/// The identifier of a registered listener that is needed to unsubscribe it
+pub type ListenerId = usize;
+
+type Listener = Arc<dyn Fn(&ModelEvent) + Send + Sync>;
+
+/// The listeners of a d-DNNF. Clones of a d-DNNF start without listeners, because the listeners
+/// were registered for the original one.
+#[derive(Default)]
+pub(crate) struct Listeners {
+    next_id: ListenerId,
+    listeners: Vec<(ListenerId, Listener)>,
+}
+
+impl Clone for Listeners {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} listeners", self.listeners.len())
+    }
+}
+
+impl Ddnnf {
+    /// Registers a listener that gets called after each change of this d-DNNF.
+    /// Caches and services that depend on the d-DNNF can invalidate themselves that way.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::sync::{Arc, Mutex};
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::events::ModelEvent;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let received = events.clone();
+    /// let id = ddnnf.subscribe(move |event| received.lock().unwrap().push(event.clone()));
+    ///
+    /// let _conditioned = ddnnf.condition(&[4]);
+    /// assert_eq!(vec![ModelEvent::Conditioned { assumptions: vec![4] }], *events.lock().unwrap());
+    /// assert!(ddnnf.unsubscribe(id));
+    /// ```
+    pub fn subscribe(
+        &mut self,
+        listener: impl Fn(&ModelEvent) + Send + Sync + 'static,
+    ) -> ListenerId {
+        let id = self.listeners.next_id;
+        self.listeners.next_id += 1;
+        self.listeners.listeners.push((id, Arc::new(listener)));
+        id
+    }
+
+    /// Removes the listener. Returns false if there is no listener with that id.
+    pub fn unsubscribe(&mut self, id: ListenerId) -> bool {
+        let before = self.listeners.listeners.len();
+        self.listeners
+            .listeners
+            .retain(|(listener_id, _)| *listener_id != id);
+        before != self.listeners.listeners.len()
+    }
+
+    // Calls each listener with the event in the order of their registration
+    pub(crate) fn notify(&self, event: ModelEvent) {
+        for (_, listener) in self.listeners.listeners.iter() {
+            listener(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use crate::parser::distribute_building;
+
+    use super::*;
+
+    #[test]
+    fn listeners_receive_events() {
+        // the Or nodes 5 and 6 are isomorphic, hence the d-DNNF is not minimal
+        let lines = [
+            "nnf 10 12 3",
+            "L 1",
+            "L -1",
+            "L 2",
+            "L -2",
+            "L 3",
+            "O 2 2 2 3",
+            "O 2 2 2 3",
+            "A 3 0 5 4",
+            "A 3 1 6 4",
+            "O 1 2 7 8",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let mut ddnnf = distribute_building(lines, None, None);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = events.clone();
+        let first = ddnnf.subscribe(move |event| received.lock().unwrap().push(event.clone()));
+        let counter = Arc::new(Mutex::new(0));
+        let count = counter.clone();
+        ddnnf.subscribe(move |_| *count.lock().unwrap() += 1);
+
+        let mut conditioned = ddnnf.condition(&[1]);
+        ddnnf.minimize();
+        assert_eq!(
+            vec![
+                ModelEvent::Conditioned {
+                    assumptions: vec![1]
+                },
+                ModelEvent::Rebuilt
+            ],
+            *events.lock().unwrap()
+        );
+        assert_eq!(2, *counter.lock().unwrap());
+
+        // the conditioned copy and clones have their own listeners
+        conditioned.subscribe(|_| ());
+        conditioned.condition(&[2]);
+        ddnnf.clone().condition(&[2]);
+        assert_eq!(2, events.lock().unwrap().len());
+
+        // nothing changes, if the d-DNNF is already minimal
+        ddnnf.minimize();
+        assert_eq!(2, *counter.lock().unwrap());
+
+        // queries that condition internally do not report it
+        #[cfg(feature = "sampling")]
+        ddnnf.sample_t_wise_with_assumptions(1, &[1]);
+        ddnnf.correlation_matrix();
+        assert_eq!(2, *counter.lock().unwrap());
+
+        assert!(ddnnf.unsubscribe(first));
+        assert!(!ddnnf.unsubscribe(first));
+        ddnnf.condition(&[2]);
+        assert_eq!(2, events.lock().unwrap().len());
+        assert_eq!(3, *counter.lock().unwrap());
+    }
+
+    #[cfg(feature = "d4")]
+    #[test]
+    fn listeners_receive_clause_edits() {
+        use crate::parser::build_ddnnf;
+        use itertools::Either;
+
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex.cnf", None);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = events.clone();
+        ddnnf.subscribe(move |event| received.lock().unwrap().push(event.clone()));
+
+        let added = vec![BTreeSet::from([-4, 5])];
+        assert!(ddnnf.update_cached_state(Either::Left((added.clone(), vec![])), Some(5)));
+        assert!(ddnnf.undo_on_cached_state());
+        assert_eq!(
+            vec![
+                ModelEvent::ClausesEdited {
+                    added,
+                    removed: vec![]
+                },
+                ModelEvent::Undone
+            ],
+            *events.lock().unwrap()
+        );
+    }
+}
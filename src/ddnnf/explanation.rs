@@ -0,0 +1,367 @@
+use std::collections::BTreeSet;
+
+use crate::Ddnnf;
+
+/// Explains why a partial configuration has no valid configuration, see [Ddnnf::explain_void]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VoidExplanation {
+    /// A minimal subset of the assumptions that is void on its own:
+    /// without any of its literals, the remaining ones are satisfiable.
+    pub assumptions: Vec<i32>,
+    /// A minimal subset of the clauses of the CNF that is unsatisfiable together with the assumptions above.
+    /// Empty if the clauses were not requested or the d-DNNF was not compiled from a CNF.
+    /// Clauses whose removal could not be decided within the conflict limit of the SAT checks are kept,
+    /// hence the subset is only minimal if all checks succeeded.
+    pub clauses: Vec<BTreeSet<i32>>,
+}
+
+impl Ddnnf {
+    /// Computes why the assumptions are void, i.e. why [Ddnnf::execute_query] returns 0 for them.
+    /// Returns None if the assumptions are satisfiable.
+    ///
+    /// The assumptions get shrunk by deletion: we drop one literal after another and keep it dropped
+    /// if the remaining literals are still unsatisfiable, which we check via [Ddnnf::is_interaction_valid].
+    /// The result is minimal but not necessarily the smallest one. If the d-DNNF itself is
+    /// unsatisfiable, no assumption is needed.
+    ///
+    /// If with_clauses is set and the d-DNNF stems from a CNF, we additionally shrink the clauses of the CNF
+    /// to a minimal subset that contradicts the minimal assumptions. These are the constraints of the model
+    /// that the user has to revise to make the partial configuration possible.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// assert_eq!(None, ddnnf.explain_void(&[2, 4], false));
+    /// let explanation = ddnnf.explain_void(&[2, 4, 3], false).unwrap();
+    /// assert_eq!(vec![2, 3], explanation.assumptions);
+    /// assert_eq!(vec![-1], ddnnf.explain_void(&[-1, 4, -3], false).unwrap().assumptions);
+    /// ```
+    pub fn explain_void(&self, assumptions: &[i32], with_clauses: bool) -> Option<VoidExplanation> {
+        if self.is_interaction_valid(assumptions) {
+            return None;
+        }
+
+        let mut core = assumptions.to_vec();
+        for index in (0..core.len()).rev() {
+            let literal = core.remove(index);
+            if self.is_interaction_valid(&core) {
+                core.insert(index, literal);
+            }
+        }
+
+        let clauses = match (with_clauses, self.cached_state.as_ref()) {
+            (true, Some(state)) => {
+                let clauses: Vec<&BTreeSet<i32>> = state.clauses.iter().collect();
+                minimal_unsat_clauses(&clauses, &core)
+                    .into_iter()
+                    .cloned()
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        Some(VoidExplanation {
+            assumptions: core,
+            clauses,
+        })
+    }
+}
+
+// Shrinks the clauses to a minimal subset that is unsatisfiable together with the assumptions.
+// We first try to drop large chunks of clauses and halve the chunk size til we drop single clauses.
+// Returns no clause if the clauses are satisfiable together with the assumptions.
+fn minimal_unsat_clauses<'a>(
+    clauses: &[&'a BTreeSet<i32>],
+    assumptions: &[i32],
+) -> Vec<&'a BTreeSet<i32>> {
+    if cnf_satisfiable(clauses, assumptions, CONFLICT_LIMIT) == Some(true) {
+        return Vec::new();
+    }
+
+    let mut core = clauses.to_vec();
+    let mut chunk = (core.len() / 2).max(1);
+    loop {
+        let mut start = 0;
+        while start < core.len() {
+            let end = (start + chunk).min(core.len());
+            let remaining: Vec<&BTreeSet<i32>> = core[..start]
+                .iter()
+                .chain(core[end..].iter())
+                .copied()
+                .collect();
+            // only clauses that are proven to be unnecessary get dropped
+            if cnf_satisfiable(&remaining, assumptions, CONFLICT_LIMIT) == Some(false) {
+                core = remaining;
+            } else {
+                start = end;
+            }
+        }
+        if chunk == 1 {
+            return core;
+        }
+        chunk /= 2;
+    }
+}
+
+// The number of conflicts after which a single SAT check of the clauses gives up
+const CONFLICT_LIMIT: usize = 10_000;
+
+// Decides whether the clauses are satisfiable together with the assumptions via DPLL with unit propagation.
+// Feature models are usually easy for this, because most of their clauses are short. Still, DPLL is
+// exponential in the worst case, hence we return None after conflict_limit conflicts.
+fn cnf_satisfiable(
+    clauses: &[&BTreeSet<i32>],
+    assumptions: &[i32],
+    conflict_limit: usize,
+) -> Option<bool> {
+    let number_of_variables = clauses
+        .iter()
+        .flat_map(|clause| clause.iter())
+        .chain(assumptions.iter())
+        .map(|literal| literal.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+    let mut assignment = Assignment {
+        values: vec![None; number_of_variables + 1],
+        trail: Vec::new(),
+    };
+    for &literal in assumptions {
+        match assignment.value(literal) {
+            Some(false) => return Some(false),
+            Some(true) => (),
+            None => assignment.assign(literal),
+        }
+    }
+
+    // each decision consists of the length of the trail before it, the literal, and whether we already tried its negation
+    let mut decisions: Vec<(usize, i32, bool)> = Vec::new();
+    let mut conflicts = 0;
+    loop {
+        if assignment.propagate(clauses) {
+            let open_clause = clauses
+                .iter()
+                .find(|clause| !clause.iter().any(|&l| assignment.value(l) == Some(true)));
+            match open_clause {
+                Some(clause) => {
+                    let literal = *clause
+                        .iter()
+                        .find(|&&l| assignment.value(l).is_none())
+                        .unwrap();
+                    decisions.push((assignment.trail.len(), literal, false));
+                    assignment.assign(literal);
+                }
+                None => return Some(true),
+            }
+        } else {
+            conflicts += 1;
+            if conflicts > conflict_limit {
+                return None;
+            }
+            loop {
+                match decisions.pop() {
+                    Some((length, literal, false)) => {
+                        assignment.undo(length);
+                        decisions.push((length, -literal, true));
+                        assignment.assign(-literal);
+                        break;
+                    }
+                    Some(_) => (),
+                    None => return Some(false),
+                }
+            }
+        }
+    }
+}
+
+// A partial assignment of the variables that remembers the order of the assigned literals
+struct Assignment {
+    values: Vec<Option<bool>>,
+    trail: Vec<i32>,
+}
+
+impl Assignment {
+    fn value(&self, literal: i32) -> Option<bool> {
+        self.values[literal.unsigned_abs() as usize].map(|value| value == literal.is_positive())
+    }
+
+    fn assign(&mut self, literal: i32) {
+        self.values[literal.unsigned_abs() as usize] = Some(literal.is_positive());
+        self.trail.push(literal);
+    }
+
+    // Unassigns the literals that got assigned after the trail had the length
+    fn undo(&mut self, length: usize) {
+        for literal in self.trail.drain(length..) {
+            self.values[literal.unsigned_abs() as usize] = None;
+        }
+    }
+
+    // Assigns the last open literal of each clause til nothing changes. Returns false on a conflict.
+    fn propagate(&mut self, clauses: &[&BTreeSet<i32>]) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in clauses {
+                let mut open = None;
+                let mut open_literals = 0;
+                let mut satisfied = false;
+                for &literal in clause.iter() {
+                    match self.value(literal) {
+                        Some(true) => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(false) => (),
+                        None => {
+                            open = Some(literal);
+                            open_literals += 1;
+                        }
+                    }
+                }
+                if satisfied {
+                    continue;
+                }
+                match (open_literals, open) {
+                    (0, _) => return false,
+                    (1, Some(literal)) => {
+                        self.assign(literal);
+                        changed = true;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Either;
+
+    use crate::ddnnf::counting::brute_force::satisfies;
+    use crate::parser::{build_ddnnf, from_cnf::read_cnf};
+
+    use super::*;
+
+    #[test]
+    fn minimal_assumptions_of_void_queries() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert!(vp9.explain_void(&[], false).is_none());
+
+        let mut void_pairs = 0;
+        for a in -42..=42 {
+            for b in (a + 1)..=42 {
+                if a == 0 || b == 0 || a == -b || vp9.is_interaction_valid(&[a, b]) {
+                    continue;
+                }
+                void_pairs += 1;
+
+                // additional literals that are fine on their own do not end up in the explanation
+                let mut assumptions = vec![a, b];
+                assumptions.extend((1..=42).filter(|&f| f != a.abs() && f != b.abs()).take(3));
+                let explanation = vp9.explain_void(&assumptions, false).unwrap();
+                assert!(!vp9.is_interaction_valid(&explanation.assumptions));
+                assert!(explanation.assumptions.iter().all(|l| [a, b].contains(l)));
+                for index in 0..explanation.assumptions.len() {
+                    let mut reduced = explanation.assumptions.clone();
+                    reduced.remove(index);
+                    assert!(vp9.is_interaction_valid(&reduced));
+                }
+                assert!(explanation.clauses.is_empty());
+            }
+        }
+        assert!(void_pairs > 0);
+
+        // contradicting assumptions explain themselves
+        let explanation = vp9.explain_void(&[3, -3], false).unwrap();
+        assert_eq!(vec![3, -3], explanation.assumptions);
+    }
+
+    #[test]
+    fn minimal_clauses_of_void_queries() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let (variables, clauses) = read_cnf("tests/data/small_ex.cnf").unwrap();
+        assert!(ddnnf.update_cached_state(Either::Right(clauses.clone()), Some(variables)));
+
+        for assumptions in [vec![-1], vec![2, 3, 4], vec![-2, -3], vec![2, -2, 1]] {
+            let explanation = ddnnf.explain_void(&assumptions, true).unwrap();
+            assert!(!explanation.clauses.is_empty() || explanation.assumptions.len() == 2);
+            assert!(explanation.clauses.iter().all(|c| clauses.contains(c)));
+
+            // the clauses contradict the assumptions, but each proper subset does not
+            let satisfiable = |clauses: &[BTreeSet<i32>]| {
+                (0..16_u64).any(|assignment| {
+                    satisfies(assignment, &explanation.assumptions)
+                        && clauses
+                            .iter()
+                            .all(|clause| clause.iter().any(|&l| satisfies(assignment, &[l])))
+                })
+            };
+            assert!(!satisfiable(&explanation.clauses));
+            for index in 0..explanation.clauses.len() {
+                let mut reduced = explanation.clauses.clone();
+                reduced.remove(index);
+                assert!(satisfiable(&reduced));
+            }
+        }
+
+        // with 2 and 3, two clauses require 1 and two others forbid it
+        let explanation = ddnnf.explain_void(&[4, 2, 3], true).unwrap();
+        assert_eq!(vec![2, 3], explanation.assumptions);
+        assert_eq!(4, explanation.clauses.len());
+
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let (variables, clauses) = read_cnf("tests/data/VP9.cnf").unwrap();
+        assert!(vp9.update_cached_state(Either::Right(clauses.clone()), Some(variables)));
+        let void: Vec<i32> = (1..=42)
+            .flat_map(|f| [f, -f])
+            .find_map(|a: i32| {
+                (1..=42)
+                    .find(|&b| b != a.abs() && !vp9.is_interaction_valid(&[a, b]))
+                    .map(|b| vec![a, b])
+            })
+            .unwrap();
+        let explanation = vp9.explain_void(&void, true).unwrap();
+        assert!(!explanation.clauses.is_empty() && explanation.clauses.len() < clauses.len());
+        assert_eq!(
+            Some(false),
+            cnf_satisfiable(
+                &explanation.clauses.iter().collect::<Vec<_>>(),
+                &explanation.assumptions,
+                CONFLICT_LIMIT
+            )
+        );
+    }
+
+    #[test]
+    fn sat_checks_give_up_after_the_conflict_limit() {
+        // 6 pigeons do not fit into 5 holes, which takes DPLL many conflicts to notice
+        let pigeon = |p: i32, h: i32| p * 5 + h + 1;
+        let mut clauses: Vec<BTreeSet<i32>> = (0..6)
+            .map(|p| (0..5).map(|h| pigeon(p, h)).collect())
+            .collect();
+        for h in 0..5 {
+            for p in 0..6 {
+                for q in (p + 1)..6 {
+                    clauses.push(BTreeSet::from([-pigeon(p, h), -pigeon(q, h)]));
+                }
+            }
+        }
+        let clauses: Vec<&BTreeSet<i32>> = clauses.iter().collect();
+
+        assert_eq!(None, cnf_satisfiable(&clauses, &[], 10));
+        assert_eq!(Some(false), cnf_satisfiable(&clauses, &[], CONFLICT_LIMIT));
+        assert_eq!(
+            Some(false),
+            cnf_satisfiable(&clauses, &[-1, -2, -3, -4, -5], 10)
+        );
+        assert_eq!(Some(true), cnf_satisfiable(&clauses[1..], &[], 10));
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use crate::parser::distribute_building;
+
+use super::{counting::brute_force::satisfies, Ddnnf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Defines the size and shape of the randomly generated d-DNNFs
+pub struct GeneratorConfig {
+    /// The number of variables over which the d-DNNF is defined. Each of them occurs in the d-DNNF.
+    pub number_of_variables: u32,
+    /// The maximum depth of inner nodes. Deeper subgraphs are flattened into a single AND node.
+    pub max_depth: usize,
+    /// The maximum number of children of an AND node
+    pub max_children: usize,
+    /// The probability that an inner node becomes a decision (OR) node instead of an AND node
+    pub decision_probability: f64,
+    /// The probability that a decision omits one of its branches, resulting in core and dead features
+    pub unsat_branch_probability: f64,
+    /// The probability that both branches of a decision share the same subgraph
+    pub sharing_probability: f64,
+    /// Reusing the same seed yields the same d-DNNF
+    pub seed: u64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            number_of_variables: 8,
+            max_depth: 6,
+            max_children: 3,
+            decision_probability: 0.5,
+            unsat_branch_probability: 0.1,
+            sharing_probability: 0.5,
+            seed: 42,
+        }
+    }
+}
+
+/// Generates a random smooth d-DNNF that follows the shape of the config.
+/// The d-DNNF is built via its c2d representation, hence it passes the same parser as any input file.
+///
+/// # Example
+/// ```
+/// use ddnnf_lib::ddnnf::generator::{generate_ddnnf, GeneratorConfig};
+///
+/// let ddnnf = generate_ddnnf(&GeneratorConfig { number_of_variables: 5, ..Default::default() });
+/// assert_eq!(5, ddnnf.number_of_variables);
+/// assert!(ddnnf.rc() <= 32);
+/// ```
+pub fn generate_ddnnf(config: &GeneratorConfig) -> Ddnnf {
+    distribute_building(generate_c2d_lines(config), None, None)
+}
+
+/// Generates a random d-DNNF like [generate_ddnnf] and attaches an equivalent CNF.
+/// As a result, the d-DNNF can be edited via clause updates.
+///
+/// # Panics
+///
+/// The CNF is computed by brute force, hence the number of variables must not exceed
+/// [BRUTE_FORCE_LIMIT](super::counting::brute_force::BRUTE_FORCE_LIMIT).
+pub fn generate_ddnnf_with_cnf(config: &GeneratorConfig) -> (Ddnnf, BTreeSet<BTreeSet<i32>>) {
+    let lines = generate_c2d_lines(config);
+    let clauses = matching_cnf(&distribute_building(lines.clone(), None, None));
+    (
+        distribute_building(lines, None, Some(clauses.clone())),
+        clauses,
+    )
+}
+
+/// Generates the c2d representation of a random smooth d-DNNF
+pub fn generate_c2d_lines(config: &GeneratorConfig) -> Vec<String> {
+    assert!(
+        config.number_of_variables > 0,
+        "A d-DNNF needs at least one variable"
+    );
+
+    let mut generator = Generator {
+        config,
+        rng: Pcg32::seed_from_u64(config.seed),
+        lines: Vec::new(),
+        literal_nodes: HashMap::new(),
+    };
+    let variables: Vec<i32> = (1..=config.number_of_variables as i32).collect();
+    generator.node(&variables, 0);
+
+    let mut lines = Vec::with_capacity(generator.lines.len() + 1);
+    lines.push(format!(
+        "nnf {} 0 {}",
+        generator.lines.len(),
+        config.number_of_variables
+    ));
+    lines.append(&mut generator.lines);
+    lines
+}
+
+struct Generator<'a> {
+    config: &'a GeneratorConfig,
+    rng: Pcg32,
+    lines: Vec<String>,
+    literal_nodes: HashMap<i32, usize>,
+}
+
+impl Generator<'_> {
+    // Adds a node (in postorder) and returns its index
+    fn push(&mut self, line: String) -> usize {
+        self.lines.push(line);
+        self.lines.len() - 1
+    }
+
+    // Each literal exists only once in a d-DNNF
+    fn literal(&mut self, literal: i32) -> usize {
+        match self.literal_nodes.get(&literal) {
+            Some(&index) => index,
+            None => {
+                let index = self.push(format!("L {literal}"));
+                self.literal_nodes.insert(literal, index);
+                index
+            }
+        }
+    }
+
+    fn and(&mut self, children: &[usize]) -> usize {
+        self.push(format!("A {} {}", children.len(), join(children)))
+    }
+
+    fn or(&mut self, decision: i32, children: &[usize]) -> usize {
+        self.push(format!(
+            "O {} {} {}",
+            decision,
+            children.len(),
+            join(children)
+        ))
+    }
+
+    // Generates a smooth subgraph that contains exactly the variables
+    fn node(&mut self, variables: &[i32], depth: usize) -> usize {
+        if variables.len() == 1 {
+            let var = variables[0];
+            return match self.rng.gen_range(0..4) {
+                0 => self.literal(var),
+                1 => self.literal(-var),
+                _ => {
+                    let children = [self.literal(var), self.literal(-var)];
+                    self.or(var, &children)
+                }
+            };
+        }
+
+        if depth >= self.config.max_depth {
+            let children: Vec<usize> = variables.iter().map(|&v| self.node(&[v], depth)).collect();
+            return self.and(&children);
+        }
+
+        if self.rng.gen_bool(self.config.decision_probability) {
+            let decision = *variables.choose(&mut self.rng).unwrap();
+            let rest: Vec<i32> = variables
+                .iter()
+                .copied()
+                .filter(|&v| v != decision)
+                .collect();
+
+            // nodes must not be generated for omitted branches because each node has to be reachable
+            let children = if self.rng.gen_bool(self.config.unsat_branch_probability) {
+                let literal = if self.rng.gen_bool(0.5) {
+                    decision
+                } else {
+                    -decision
+                };
+                let branch = [self.literal(literal), self.node(&rest, depth + 1)];
+                vec![self.and(&branch)]
+            } else {
+                let positive_rest = self.node(&rest, depth + 1);
+                let negative_rest = if self.rng.gen_bool(self.config.sharing_probability) {
+                    positive_rest
+                } else {
+                    self.node(&rest, depth + 1)
+                };
+
+                let positive = [self.literal(decision), positive_rest];
+                let negative = [self.literal(-decision), negative_rest];
+                vec![self.and(&positive), self.and(&negative)]
+            };
+            self.or(decision, &children)
+        } else {
+            let mut variables = variables.to_vec();
+            variables.shuffle(&mut self.rng);
+
+            let amount = self
+                .rng
+                .gen_range(2..=self.config.max_children.clamp(2, variables.len()));
+            let mut splits: Vec<usize> = (1..variables.len()).collect();
+            splits.shuffle(&mut self.rng);
+            splits.truncate(amount - 1);
+            splits.sort_unstable();
+
+            let mut children = Vec::with_capacity(amount);
+            let mut start = 0;
+            for end in splits.into_iter().chain([variables.len()]) {
+                children.push(self.node(&variables[start..end], depth + 1));
+                start = end;
+            }
+            self.and(&children)
+        }
+    }
+}
+
+fn join(children: &[usize]) -> String {
+    children
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Computes a CNF that is equivalent to the d-DNNF by blocking each non-model
+pub fn matching_cnf(ddnnf: &Ddnnf) -> BTreeSet<BTreeSet<i32>> {
+    ddnnf
+        .assignments()
+        .expect("The d-DNNF has too many variables to compute a matching CNF")
+        .filter(|&a| !ddnnf.is_model(a))
+        .map(|a| {
+            (1..=ddnnf.number_of_variables as i32)
+                .map(|v| if satisfies(a, &[v]) { -v } else { v })
+                .collect()
+        })
+        .collect()
+}
+
+/// Compares the results of the counting and SAT algorithms with a brute force evaluation.
+/// We check the overall count and all queries that consist of up to two literals.
+/// The first deviation is returned as error.
+pub fn check_against_brute_force(ddnnf: &mut Ddnnf) -> Result<(), String> {
+    let literals: Vec<i32> = (1..=ddnnf.number_of_variables as i32)
+        .flat_map(|v| [v, -v])
+        .collect();
+
+    let mut queries: Vec<Vec<i32>> = vec![vec![]];
+    for (index, &first) in literals.iter().enumerate() {
+        queries.push(vec![first]);
+        for &second in literals[index + 1..].iter() {
+            queries.push(vec![first, second]);
+        }
+    }
+
+    for query in queries {
+        let expected = ddnnf
+            .brute_force_count(&query)
+            .ok_or("brute force evaluation is limited to small d-DNNFs")?;
+        let count = ddnnf.execute_query(&query);
+        if count != expected {
+            return Err(format!(
+                "count for {query:?} is {count} but should be {expected}"
+            ));
+        }
+        let satisfiable = expected > 0;
+        if ddnnf.sat(&query) != satisfiable {
+            return Err(format!(
+                "sat for {query:?} is {} but should be {satisfiable}",
+                !satisfiable
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generated_ddnnfs_match_brute_force() {
+        for seed in 0..40 {
+            let config = GeneratorConfig {
+                number_of_variables: 1 + seed as u32 % 8,
+                seed,
+                ..Default::default()
+            };
+            let mut ddnnf = generate_ddnnf(&config);
+            assert_eq!(config.number_of_variables, ddnnf.number_of_variables);
+            if let Err(msg) = check_against_brute_force(&mut ddnnf) {
+                panic!("seed {seed}: {msg}");
+            }
+        }
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        let config = GeneratorConfig {
+            number_of_variables: 12,
+            ..Default::default()
+        };
+        assert_eq!(generate_c2d_lines(&config), generate_c2d_lines(&config));
+        assert_ne!(
+            generate_c2d_lines(&config),
+            generate_c2d_lines(&GeneratorConfig { seed: 7, ..config })
+        );
+    }
+
+    #[test]
+    fn matching_cnf_has_same_models() {
+        let config = GeneratorConfig {
+            number_of_variables: 6,
+            seed: 3,
+            ..Default::default()
+        };
+        let (ddnnf, clauses) = generate_ddnnf_with_cnf(&config);
+        assert!(ddnnf.can_save_state());
+
+        let cnf_models = (0..1_u64 << 6)
+            .filter(|&a| {
+                clauses
+                    .iter()
+                    .all(|clause| clause.iter().any(|&l| satisfies(a, &[l])))
+            })
+            .count();
+        assert_eq!(ddnnf.rc(), cnf_models);
+    }
+}
@@ -1,15 +1,168 @@
+use std::collections::{BTreeMap, VecDeque};
+
 use super::{node::NodeType::*, Ddnnf};
 use crate::Node;
 
+#[derive(Debug, Clone, PartialEq)]
+/// Structural quality metrics of a d-DNNF that allow comparing the results of different compiler settings
+pub struct StructureReport {
+    /// Maps the number of children of an Or node to the number of Or nodes with that many children
+    pub or_fanout: BTreeMap<usize, usize>,
+    /// The fraction of nodes (except the root) that have more than one parent
+    pub sharing_ratio: f64,
+    /// The i-th entry is the length of the shortest path from the root to a literal of the feature i + 1.
+    /// Features without any literal node have no depth.
+    pub variable_depths: Vec<Option<usize>>,
+    /// The fraction of literal nodes that are reachable from the root by only passing And nodes
+    pub root_and_literal_fraction: f64,
+}
+
 impl Ddnnf {
     /// Computes and prints some heuristics including:
     /// 1) The distribution of the different types of nodes
     /// 2) The number of child nodes (averages, ...)
     /// 3) The length of paths starting from the root to the leafs (averages, ...)
+    /// 4) Structural attributes like the fanout of Or nodes and the sharing of nodes
     pub fn print_all_heuristics(&mut self) {
         self.get_nodetype_numbers();
         self.get_child_number();
-        self.get_depths()
+        self.get_depths();
+        self.print_structure_report();
+    }
+
+    /// Computes metrics about the structure of the d-DNNF
+    /// (see [StructureReport] for the individual metrics).
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let report = ddnnf.structure_report();
+    ///
+    /// assert_eq!(Some(&2), report.or_fanout.get(&2));
+    /// assert_eq!(vec![Some(1), Some(3), Some(3), Some(2)], report.variable_depths);
+    /// ```
+    pub fn structure_report(&self) -> StructureReport {
+        let root = self.nodes.len() - 1;
+
+        let mut or_fanout = BTreeMap::new();
+        let mut shared_nodes = 0;
+        let mut literal_nodes = 0;
+        for node in self.nodes.iter() {
+            match &node.ntype {
+                Or { children } => *or_fanout.entry(children.len()).or_insert(0) += 1,
+                Literal { .. } => literal_nodes += 1,
+                _ => (),
+            }
+            if node.parents.len() > 1 {
+                shared_nodes += 1;
+            }
+        }
+
+        // breadth first search starting from the root to get the shortest paths
+        let mut depths: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut queue = VecDeque::from([root]);
+        depths[root] = Some(0);
+        while let Some(current) = queue.pop_front() {
+            if let And { children } | Or { children } = &self.nodes[current].ntype {
+                for &child in children {
+                    if depths[child].is_none() {
+                        depths[child] = depths[current].map(|depth| depth + 1);
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+
+        // the nodes are in postorder, hence parents get visited before their children
+        let mut and_reachable = vec![false; self.nodes.len()];
+        and_reachable[root] = true;
+        for index in (0..self.nodes.len()).rev() {
+            if let And { children } = &self.nodes[index].ntype {
+                if and_reachable[index] {
+                    for &child in children {
+                        and_reachable[child] = true;
+                    }
+                }
+            }
+        }
+
+        let mut variable_depths: Vec<Option<usize>> = vec![None; self.number_of_variables as usize];
+        let mut root_and_literals = 0;
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Literal { literal } = node.ntype {
+                let entry = &mut variable_depths[literal.unsigned_abs() as usize - 1];
+                *entry = match (*entry, depths[index]) {
+                    (Some(old), Some(new)) => Some(old.min(new)),
+                    (old, new) => old.or(new),
+                };
+                if and_reachable[index] {
+                    root_and_literals += 1;
+                }
+            }
+        }
+
+        StructureReport {
+            or_fanout,
+            sharing_ratio: ratio(shared_nodes, self.nodes.len() - 1),
+            variable_depths,
+            root_and_literal_fraction: ratio(root_and_literals, literal_nodes),
+        }
+    }
+
+    /// Computes the length of the longest path from the root to a leaf.
+    /// All traversals use explicit stacks, hence a deep d-DNNF does not overflow the call stack,
+    /// but the depth still indicates how costly path-based operations get.
+    pub fn depth(&self) -> usize {
+        // the children of a node precede it, hence one pass in postorder suffices
+        let mut depths: Vec<usize> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let depth = match &node.ntype {
+                And { children } | Or { children } => children
+                    .iter()
+                    .map(|&child| depths[child] + 1)
+                    .max()
+                    .unwrap_or(0),
+                _ => 0,
+            };
+            depths.push(depth);
+        }
+        depths.last().copied().unwrap_or(0)
+    }
+
+    // prints the structure report in the format of the other heuristics
+    fn print_structure_report(&self) {
+        let report = self.structure_report();
+        let fanouts = report
+            .or_fanout
+            .iter()
+            .map(|(fanout, amount)| format!("{} Or nodes with {} children", amount, fanout))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let depths: Vec<usize> = report.variable_depths.iter().flatten().copied().collect();
+        let average_depth = if depths.is_empty() {
+            String::from("There is no literal that could be away from the root")
+        } else {
+            format!(
+                "The literals of a feature are on average at least ≈{:.2} units away from the root",
+                depths.iter().sum::<usize>() as f64 / depths.len() as f64
+            )
+        };
+
+        println!(
+            "\nThe d-DNNF has the following structural attributes:\n\
+                \t |-> The Or nodes have the following fanout: {}\n\
+                \t |-> ≈{:.2}% of the nodes have more than one parent\n\
+                \t |-> {}\n\
+                \t |-> ≈{:.2}% of the literals are reachable from the root via And nodes only\n",
+            fanouts,
+            report.sharing_ratio * 100_f64,
+            average_depth,
+            report.root_and_literal_fraction * 100_f64
+        );
     }
 
     // computes the occurences of different node types (number of and nodes, or, positive literal, negative literal, true, false)
@@ -140,30 +293,30 @@ impl Ddnnf {
     }
 }
 
+// divides and returns 0 for an empty total
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
 #[inline]
-// computes the depth/length of a path starting from indize to the leaf
+// computes the depth/length of each path starting from indize to a leaf
 fn get_depth(nodes: &[Node], indize: usize, count: u64) -> Vec<u64> {
-    let current: &Node = &nodes[indize];
-
-    match &current.ntype {
-        And { children } => {
-            let mut child_depths: Vec<Vec<u64>> = Vec::new();
-            for &i in children {
-                child_depths.push(get_depth(nodes, i, count + 1));
-            }
-            child_depths.into_iter().flatten().collect()
-        }
-        Or { children } => {
-            let mut child_depths: Vec<Vec<u64>> = Vec::new();
-            for &i in children {
-                child_depths.push(get_depth(nodes, i, count + 1));
+    let mut depths = Vec::new();
+    // the paths are followed with an explicit stack to support arbitrarily deep d-DNNFs
+    let mut stack = vec![(indize, count)];
+    while let Some((index, count)) = stack.pop() {
+        match &nodes[index].ntype {
+            And { children } | Or { children } => {
+                stack.extend(children.iter().rev().map(|&child| (child, count + 1)))
             }
-            child_depths.into_iter().flatten().collect()
+            Literal { .. } | True | False => depths.push(count),
         }
-        Literal { literal: _ } => vec![count],
-        True => vec![count],
-        False => vec![count],
     }
+    depths
 }
 
 // Functions that are currently not used but necessary to collect data regarding marking percentages,...
@@ -222,8 +375,9 @@ fn std_deviation(data: &[u64]) -> f64 {
 #[cfg(test)]
 mod test {
     use crate::ddnnf::heuristics::{median, std_deviation};
+    use crate::parser::{build_ddnnf, distribute_building};
 
-    use super::average;
+    use super::*;
 
     #[test]
     fn math_functions() {
@@ -242,4 +396,85 @@ mod test {
         assert_eq!(-1.0, median(&mut data_3));
         assert_eq!(-1.0, std_deviation(&data_3));
     }
+
+    #[test]
+    fn structure_report() {
+        let small: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        assert_eq!(
+            StructureReport {
+                or_fanout: BTreeMap::from([(2, 2)]),
+                sharing_ratio: 0.0,
+                variable_depths: vec![Some(1), Some(3), Some(3), Some(2)],
+                root_and_literal_fraction: 1.0 / 7.0,
+            },
+            small.structure_report()
+        );
+
+        // the And node 3 is a child of the Or node 4, which is closer to the root than the And path to 3
+        let lines = [
+            "nnf 8 9 2",
+            "L 1",
+            "L 2",
+            "L -2",
+            "A 1 0",
+            "O 0 2 3 2",
+            "A 1 3",
+            "A 1 5",
+            "A 3 4 6 1",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let detour = distribute_building(lines, None, None);
+        assert_eq!(
+            2.0 / 3.0,
+            detour.structure_report().root_and_literal_fraction
+        );
+
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let report = vp9.structure_report();
+        assert!(report.variable_depths.iter().all(|depth| depth.is_some()));
+        assert!(report.sharing_ratio > 0.0 && report.sharing_ratio < 1.0);
+        assert_eq!(
+            vp9.nodes
+                .iter()
+                .filter(|node| matches!(node.ntype, Or { .. }))
+                .count(),
+            report.or_fanout.values().sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn deep_ddnnfs() {
+        // a chain of And nodes, each one conjoining a new literal with the previous And node
+        let variables = 100_000;
+        let mut lines = vec![format!(
+            "nnf {} {} {}",
+            2 * variables,
+            2 * variables,
+            variables
+        )];
+        lines.push(String::from("L 1"));
+        for variable in 2..=variables {
+            let previous = 2 * variable - 4;
+            lines.push(format!("L {variable}"));
+            lines.push(format!("A 2 {} {}", previous, previous + 1));
+        }
+        let mut chain = distribute_building(lines, Some(variables as u32), None);
+
+        assert_eq!(variables - 1, chain.depth());
+        assert_eq!(1, chain.execute_query(&[1, 2, 3]));
+        assert_eq!(0, chain.execute_query(&[-1]));
+        assert_eq!(0, chain.execute_query(&[-1, 2]));
+        assert!(chain.sat(&[variables as i32]));
+        #[cfg(feature = "sampling")]
+        assert_eq!(
+            (1..=variables as i32).collect::<Vec<i32>>(),
+            chain.uniform_random_sampling(&[], 1, 42).unwrap()[0]
+        );
+        assert_eq!(
+            chain.node_id(chain.nodes.len() - 1),
+            chain.clone().node_id(chain.nodes.len() - 1)
+        );
+    }
 }
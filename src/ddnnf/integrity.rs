@@ -0,0 +1,528 @@
+use std::{collections::BTreeSet, fmt, rc::Rc};
+
+use rug::{Complete, Integer};
+
+use super::{
+    equivalence::split_mix,
+    node::{NodeId, NodeType::*},
+};
+use crate::{parser::util::format_vec, Ddnnf};
+
+/// A suspicious structure of a parsed d-DNNF that hints at a malformed input file.
+/// Each of them can lead to wrong results without any further notice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityWarning {
+    /// Literals refer to variables beyond the declared number of variables
+    UndeclaredVariables { variables: Vec<u32>, declared: u32 },
+    /// The children of the And node share variables, hence the node is not decomposable
+    OverlappingAnd { node: usize, variables: Vec<u32> },
+    /// The two children of the Or node share the model, hence the node is not deterministic
+    OverlappingOr {
+        node: usize,
+        children: (usize, usize),
+        model: Vec<i32>,
+    },
+}
+
+impl IntegrityWarning {
+    /// Checks whether [Ddnnf::repair_variable_count] resolves the warning
+    pub fn is_repairable(&self) -> bool {
+        matches!(self, IntegrityWarning::UndeclaredVariables { .. })
+    }
+
+    /// Describes how to get rid of the warning
+    pub fn suggestion(&self) -> String {
+        match self {
+            IntegrityWarning::UndeclaredVariables { variables, .. } => format!(
+                "Declare {} variables, e.g. via the header of the file or the total number of features.",
+                variables.last().unwrap()
+            ),
+            IntegrityWarning::OverlappingAnd { .. } => String::from(
+                "The file is no d-DNNF and its counts are not reliable. Recompile the CNF, e.g. with d4.",
+            ),
+            IntegrityWarning::OverlappingOr { .. } => String::from(
+                "The file is no d-DNNF and models get counted more than once. Recompile the CNF, e.g. with d4.",
+            ),
+        }
+    }
+}
+
+impl fmt::Display for IntegrityWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityWarning::UndeclaredVariables {
+                variables,
+                declared,
+            } => write!(
+                f,
+                "the variables {} exceed the {declared} declared variables",
+                format_vec(variables.iter())
+            ),
+            IntegrityWarning::OverlappingAnd { node, variables } => write!(
+                f,
+                "the children of the And node {node} share the variables {}",
+                format_vec(variables.iter())
+            ),
+            IntegrityWarning::OverlappingOr {
+                node,
+                children: (first, second),
+                model,
+            } => write!(
+                f,
+                "the children {first} and {second} of the Or node {node} share the model {}",
+                format_vec(model.iter())
+            ),
+        }
+    }
+}
+
+/// The state of a d-DNNF at some point in time, see [Ddnnf::snapshot]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegritySnapshot {
+    /// The structural hash of the d-DNNF, i.e. the id of its root
+    pub hash: NodeId,
+    /// Partial configurations together with their counts at the time of the snapshot
+    pub queries: Vec<(Vec<i32>, Integer)>,
+}
+
+/// A sign that the in-memory state of a d-DNNF got corrupted, see [Ddnnf::verify_snapshot]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    /// The structure differs from the one of the snapshot
+    StructureChanged { expected: NodeId, actual: NodeId },
+    /// The cached count of the node differs from the count of its children
+    StaleCount {
+        node: usize,
+        cached: Integer,
+        fresh: Integer,
+    },
+    /// The count of the query differs from the one of the snapshot
+    QueryChanged {
+        query: Vec<i32>,
+        expected: Integer,
+        actual: Integer,
+    },
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Corruption::StructureChanged { expected, actual } => {
+                write!(f, "the structural hash changed from {expected} to {actual}")
+            }
+            Corruption::StaleCount {
+                node,
+                cached,
+                fresh,
+            } => write!(
+                f,
+                "the node {node} caches the count {cached}, but its children result in {fresh}"
+            ),
+            Corruption::QueryChanged {
+                query,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "the query {} counted {expected} before, but counts {actual} now",
+                format_vec(query.iter())
+            ),
+        }
+    }
+}
+
+impl Ddnnf {
+    /// Searches for structures that a d-DNNF must not contain. The check is meant to run right
+    /// after parsing a file of unknown origin.
+    ///
+    /// Undeclared variables and And nodes whose children share variables are detected exactly.
+    /// The determinism of Or nodes is checked by sampling: each probe takes a model of one child
+    /// and checks whether one of its siblings has that model, too. The Or nodes closest to the root
+    /// get probed first, until the number of probes is used up. Hence, overlapping Or nodes might go unnoticed.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::integrity::IntegrityWarning;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// assert!(ddnnf.check_integrity(100).is_empty());
+    ///
+    /// // the header declares a single variable, but the literal 2 occurs as well
+    /// let lines = ["nnf 3 2 1", "L 1", "L 2", "A 2 0 1"];
+    /// let mut malformed = distribute_building(lines.map(String::from).to_vec(), None, None);
+    /// let warnings = malformed.check_integrity(100);
+    /// assert_eq!(
+    ///     vec![IntegrityWarning::UndeclaredVariables { variables: vec![2], declared: 1 }],
+    ///     warnings
+    /// );
+    /// assert!(malformed.repair_variable_count());
+    /// assert_eq!(2, malformed.number_of_variables);
+    /// ```
+    pub fn check_integrity(&self, probes: usize) -> Vec<IntegrityWarning> {
+        let mut warnings = Vec::new();
+
+        let undeclared: BTreeSet<u32> = self
+            .literals
+            .keys()
+            .map(|literal| literal.unsigned_abs())
+            .filter(|&variable| variable > self.number_of_variables)
+            .collect();
+        if !undeclared.is_empty() {
+            warnings.push(IntegrityWarning::UndeclaredVariables {
+                variables: undeclared.into_iter().collect(),
+                declared: self.number_of_variables,
+            });
+        }
+
+        let supports = self.supports();
+        for (node, ntype) in self.nodes.iter().map(|node| &node.ntype).enumerate() {
+            if let And { children } = ntype {
+                let mut seen = BTreeSet::new();
+                let mut shared = BTreeSet::new();
+                for &child in children {
+                    for &variable in supports[child].iter() {
+                        if !seen.insert(variable) {
+                            shared.insert(variable);
+                        }
+                    }
+                }
+                if !shared.is_empty() {
+                    warnings.push(IntegrityWarning::OverlappingAnd {
+                        node,
+                        variables: shared.into_iter().collect(),
+                    });
+                }
+            }
+        }
+
+        let mut remaining = probes;
+        for node in (0..self.nodes.len()).rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Or { children } = &self.nodes[node].ntype {
+                if let Some(warning) = self.probe_or(node, children, &mut remaining) {
+                    warnings.push(warning);
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Raises the number of variables to the largest variable that occurs in a literal, which
+    /// repairs [IntegrityWarning::UndeclaredVariables]. The core features get recomputed.
+    /// Returns false if there is nothing to repair.
+    pub fn repair_variable_count(&mut self) -> bool {
+        let largest = self
+            .literals
+            .keys()
+            .map(|literal| literal.unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        if largest <= self.number_of_variables {
+            return false;
+        }
+        self.number_of_variables = largest;
+        self.get_core();
+        true
+    }
+
+    /// Records the structural hash and the counts of a few random partial configurations, which
+    /// [Ddnnf::verify_snapshot] compares against a fresh evaluation later on. The seed decides the queries.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::integrity::Corruption;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let snapshot = ddnnf.snapshot(5, 42);
+    /// assert_eq!(5, snapshot.queries.len());
+    /// assert!(ddnnf.verify_snapshot(&snapshot).is_empty());
+    ///
+    /// // a bit flip in a cached count
+    /// ddnnf.nodes[0].count += 1;
+    /// let corruptions = ddnnf.verify_snapshot(&snapshot);
+    /// assert!(matches!(corruptions[0], Corruption::StaleCount { node: 0, .. }));
+    /// ```
+    pub fn snapshot(&self, queries: usize, seed: u64) -> IntegritySnapshot {
+        let mut state = seed;
+        let variables = self.number_of_variables as u64;
+        let queries = (0..queries)
+            .map(|_| {
+                let size = split_mix(&mut state) % (variables.min(3) + 1);
+                let query: Vec<i32> = (0..size)
+                    .map(|_| {
+                        let random = split_mix(&mut state);
+                        let variable = (random >> 1) % variables + 1;
+                        if random & 1 == 0 {
+                            variable as i32
+                        } else {
+                            -(variable as i32)
+                        }
+                    })
+                    .collect();
+                let count = self.count_adaptive(&query);
+                (query, count)
+            })
+            .collect();
+
+        IntegritySnapshot {
+            hash: self.structural_hash(),
+            queries,
+        }
+    }
+
+    /// Compares the d-DNNF against the snapshot: The structural hash has to stay the same, the cached
+    /// count of each node has to match the counts of its children, and the queries of the snapshot
+    /// have to result in the same counts. Returns all deviations, hence an empty result means
+    /// that no corruption got detected. Edits change the structure on purpose and require a new snapshot.
+    pub fn verify_snapshot(&self, snapshot: &IntegritySnapshot) -> Vec<Corruption> {
+        let mut corruptions = Vec::new();
+
+        for (node, current) in self.nodes.iter().enumerate() {
+            let fresh = match &current.ntype {
+                And { children } => {
+                    Integer::product(children.iter().map(|&c| &self.nodes[c].count)).complete()
+                }
+                Or { children } => {
+                    Integer::sum(children.iter().map(|&c| &self.nodes[c].count)).complete()
+                }
+                Literal { .. } | True => Integer::from(1),
+                False => Integer::ZERO,
+            };
+            if fresh != current.count {
+                corruptions.push(Corruption::StaleCount {
+                    node,
+                    cached: current.count.clone(),
+                    fresh,
+                });
+            }
+        }
+
+        let actual = self.structural_hash();
+        if actual != snapshot.hash {
+            corruptions.push(Corruption::StructureChanged {
+                expected: snapshot.hash,
+                actual,
+            });
+        }
+
+        for (query, expected) in snapshot.queries.iter() {
+            let actual = self.count_adaptive(query);
+            if actual != *expected {
+                corruptions.push(Corruption::QueryChanged {
+                    query: query.clone(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        corruptions
+    }
+
+    // The id of the root, which changes with each change of the structure
+    fn structural_hash(&self) -> NodeId {
+        self.node_id(self.nodes.len() - 1)
+    }
+
+    // The sorted variables that occur in the sub-DAG of each node.
+    // Or nodes of a smooth d-DNNF share the support of their children.
+    pub(crate) fn supports(&self) -> Vec<Rc<Vec<u32>>> {
+        let mut supports: Vec<Rc<Vec<u32>>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let support = match &node.ntype {
+                And { children } | Or { children } => {
+                    let first = children.first().map(|&child| &supports[child]);
+                    match first {
+                        Some(first) if children.iter().all(|&child| supports[child] == *first) => {
+                            first.clone()
+                        }
+                        _ => {
+                            let union: BTreeSet<u32> = children
+                                .iter()
+                                .flat_map(|&child| supports[child].iter().copied())
+                                .collect();
+                            Rc::new(union.into_iter().collect())
+                        }
+                    }
+                }
+                Literal { literal } => Rc::new(vec![literal.unsigned_abs()]),
+                True | False => Rc::new(Vec::new()),
+            };
+            supports.push(support);
+        }
+        supports
+    }
+
+    // Probes each child of the Or node with one of its models until a sibling shares it
+    // or the probes are used up.
+    fn probe_or(
+        &self,
+        node: usize,
+        children: &[usize],
+        remaining: &mut usize,
+    ) -> Option<IntegrityWarning> {
+        for (index, &child) in children.iter().enumerate() {
+            if *remaining == 0 {
+                return None;
+            }
+            *remaining -= 1;
+            let model = match self.witness(child, node) {
+                Some(model) => model,
+                None => continue,
+            };
+            let assumed: BTreeSet<i32> = model.iter().copied().collect();
+            for (other_index, &other) in children.iter().enumerate() {
+                if other_index != index && self.is_consistent(other, &assumed) {
+                    return Some(IntegrityWarning::OverlappingOr {
+                        node,
+                        children: (child.min(other), child.max(other)),
+                        model,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    // Takes a model of the sub-DAG below the node. The seed determines the choices at Or nodes,
+    // hence different seeds lead to different models.
+    // Returns None if there is no model or the literals contradict each other.
+    fn witness(&self, node: usize, seed: usize) -> Option<Vec<i32>> {
+        let mut visited = vec![false; node + 1];
+        let mut stack = vec![node];
+        let mut model = BTreeSet::new();
+        while let Some(current) = stack.pop() {
+            if visited[current] {
+                continue;
+            }
+            visited[current] = true;
+            match &self.nodes[current].ntype {
+                And { children } => stack.extend(children),
+                Or { children } => {
+                    let satisfiable: Vec<usize> = children
+                        .iter()
+                        .copied()
+                        .filter(|&child| self.nodes[child].count > 0)
+                        .collect();
+                    if satisfiable.is_empty() {
+                        return None;
+                    }
+                    stack.push(satisfiable[(seed + current) % satisfiable.len()]);
+                }
+                Literal { literal } => {
+                    model.insert(*literal);
+                }
+                True => (),
+                False => return None,
+            }
+        }
+        if model.iter().any(|literal| model.contains(&-literal)) {
+            return None;
+        }
+        Some(model.into_iter().collect())
+    }
+
+    // Checks whether the sub-DAG below the node has a model that is consistent with the assumptions
+    fn is_consistent(&self, node: usize, assumed: &BTreeSet<i32>) -> bool {
+        let mut reachable = vec![false; node + 1];
+        reachable[node] = true;
+        for index in (0..=node).rev() {
+            if let (true, And { children } | Or { children }) =
+                (reachable[index], &self.nodes[index].ntype)
+            {
+                for &child in children {
+                    reachable[child] = true;
+                }
+            }
+        }
+
+        let mut consistent = vec![false; node + 1];
+        for index in (0..=node).filter(|&index| reachable[index]) {
+            consistent[index] = match &self.nodes[index].ntype {
+                And { children } => children.iter().all(|&child| consistent[child]),
+                Or { children } => children.iter().any(|&child| consistent[child]),
+                Literal { literal } => !assumed.contains(&-literal),
+                True => true,
+                False => false,
+            };
+        }
+        consistent[node]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{build_ddnnf, distribute_building};
+
+    use super::*;
+
+    fn parse(lines: &[&str]) -> Ddnnf {
+        distribute_building(lines.iter().map(|l| l.to_string()).collect(), None, None)
+    }
+
+    #[test]
+    fn well_formed_files() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let small: Ddnnf = build_ddnnf("tests/data/small_ex_d4.nnf", Some(4));
+        let auto1: Ddnnf = build_ddnnf("tests/data/auto1_c2d.nnf", None);
+        for ddnnf in [vp9, small, auto1] {
+            assert!(ddnnf.check_integrity(1_000).is_empty());
+        }
+    }
+
+    #[test]
+    fn malformed_files() {
+        let mut undeclared = parse(&["nnf 4 3 1", "L 1", "L 2", "L 3", "A 3 0 1 2"]);
+        let warnings = undeclared.check_integrity(10);
+        assert_eq!(
+            vec![IntegrityWarning::UndeclaredVariables {
+                variables: vec![2, 3],
+                declared: 1
+            }],
+            warnings
+        );
+        assert!(warnings[0].is_repairable());
+        assert!(warnings[0].suggestion().starts_with("Declare 3 variables"));
+        assert!(undeclared.repair_variable_count());
+        assert!(!undeclared.repair_variable_count());
+        assert!(undeclared.check_integrity(10).is_empty());
+        assert_eq!(3, undeclared.core.len());
+
+        // 1 and -1 can not hold at the same time
+        let contradiction = parse(&["nnf 4 3 2", "L 1", "L -1", "L 2", "A 3 0 1 2"]);
+        let warnings = contradiction.check_integrity(10);
+        assert!(matches!(
+            &warnings[..],
+            [IntegrityWarning::OverlappingAnd { variables, .. }] if variables == &vec![1]
+        ));
+        assert!(!warnings[0].is_repairable());
+
+        // both children of the root have the model {1, 2}
+        let ambiguous = parse(&[
+            "nnf 8 10 2",
+            "L 1",
+            "L -2",
+            "L 2",
+            "O 2 2 1 2",
+            "A 2 0 3",
+            "A 2 0 2",
+            "O 0 2 4 5",
+        ]);
+        let warnings = ambiguous.check_integrity(10);
+        assert!(matches!(
+            &warnings[..],
+            [IntegrityWarning::OverlappingOr { model, .. }] if model == &vec![1, 2]
+        ));
+        assert!(warnings[0].to_string().ends_with("share the model 1 2"));
+        // without probes, the Or node stays unchecked
+        assert!(ambiguous.check_integrity(0).is_empty());
+    }
+}
@@ -0,0 +1,432 @@
+use std::{fmt::Write, io, path::Path};
+
+use rug::{Integer, Rational};
+
+use crate::{
+    parser::{build_ddnnf_with_settings, util::json_string},
+    settings::Settings,
+    Ddnnf,
+};
+
+/// Standard metrics of the feature model that is represented by a d-DNNF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics {
+    pub number_of_variables: u32,
+    /// The number of valid configurations
+    pub configurations: Integer,
+    /// The ratio of valid configurations to all 2^n assignments
+    pub variability: Rational,
+    /// The features that are part of each valid configuration
+    pub core_features: Vec<i32>,
+    /// The features that are part of no valid configuration
+    pub dead_features: Vec<i32>,
+    /// The share of valid configurations that contain the feature, for each feature.
+    /// A commonality of 1 / 2 means that the feature is as variable as it can be.
+    pub commonalities: Vec<Rational>,
+    /// The average commonality of all features
+    pub homogeneity: Rational,
+}
+
+impl Ddnnf {
+    /// Computes all [Metrics] of the feature model in a single pass over the d-DNNF.
+    /// Core and dead features are derived from the counts. Thereby, they do not depend on the
+    /// literals that occur in the d-DNNF.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use rug::Rational;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let metrics = ddnnf.metrics();
+    ///
+    /// assert_eq!(4, metrics.configurations);
+    /// assert_eq!(Rational::from((1, 4)), metrics.variability);
+    /// assert_eq!(vec![1], metrics.core_features);
+    /// ```
+    pub fn metrics(&mut self) -> Metrics {
+        let configurations = self.rc();
+        let distribution = self.literal_distribution();
+
+        let commonality = |selected: &Integer| {
+            if configurations == 0 {
+                Rational::new()
+            } else {
+                Rational::from((selected, &configurations))
+            }
+        };
+        let commonalities: Vec<Rational> = distribution
+            .iter()
+            .map(|(selected, _)| commonality(selected))
+            .collect();
+        let feature = |index: usize| index as i32 + 1;
+
+        let homogeneity = if commonalities.is_empty() {
+            Rational::new()
+        } else {
+            Rational::from(Rational::sum(commonalities.iter())) / commonalities.len() as u32
+        };
+
+        Metrics {
+            number_of_variables: self.number_of_variables,
+            variability: Rational::from((
+                &configurations,
+                Integer::from(Integer::u_pow_u(2, self.number_of_variables)),
+            )),
+            core_features: distribution
+                .iter()
+                .enumerate()
+                .filter(|(_, (selected, deselected))| *selected > 0 && *deselected == 0)
+                .map(|(index, _)| feature(index))
+                .collect(),
+            dead_features: distribution
+                .iter()
+                .enumerate()
+                .filter(|(_, (selected, _))| *selected == 0)
+                .map(|(index, _)| feature(index))
+                .collect(),
+            configurations,
+            commonalities,
+            homogeneity,
+        }
+    }
+}
+
+impl Metrics {
+    /// The column names of [Metrics::csv_record]
+    pub const CSV_HEADER: [&'static str; 6] = [
+        "variables",
+        "configurations",
+        "variability",
+        "core",
+        "dead",
+        "homogeneity",
+    ];
+
+    /// The metrics that describe the whole model as one CSV record.
+    /// Ratios are rounded to floating point numbers and the counts stay exact.
+    pub fn csv_record(&self) -> [String; 6] {
+        [
+            self.number_of_variables.to_string(),
+            self.configurations.to_string(),
+            self.variability.to_f64().to_string(),
+            self.core_features.len().to_string(),
+            self.dead_features.len().to_string(),
+            self.homogeneity.to_f64().to_string(),
+        ]
+    }
+
+    /// Serializes all metrics as JSON object. The number of configurations is a string,
+    /// because it usually exceeds the range of JSON numbers.
+    pub fn to_json(&self) -> String {
+        let list = |values: &mut dyn Iterator<Item = String>| values.collect::<Vec<_>>().join(",");
+        let mut json = String::from("{");
+        write!(
+            json,
+            "\"variables\":{},\"configurations\":\"{}\",\"variability\":{},",
+            self.number_of_variables,
+            self.configurations,
+            self.variability.to_f64()
+        )
+        .unwrap();
+        write!(
+            json,
+            "\"core\":[{}],\"dead\":[{}],\"homogeneity\":{},\"commonalities\":[{}]",
+            list(&mut self.core_features.iter().map(i32::to_string)),
+            list(&mut self.dead_features.iter().map(i32::to_string)),
+            self.homogeneity.to_f64(),
+            list(&mut self.commonalities.iter().map(|c| c.to_f64().to_string()))
+        )
+        .unwrap();
+        json.push('}');
+        json
+    }
+}
+
+/// The changes of the metrics from one version of a model to the next one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsDelta {
+    pub variables: i64,
+    pub configurations: Integer,
+    pub variability: Rational,
+    pub homogeneity: Rational,
+    /// The features that became core
+    pub added_core: Vec<i32>,
+    /// The features that are no longer core
+    pub removed_core: Vec<i32>,
+    /// The features that became dead
+    pub added_dead: Vec<i32>,
+    /// The features that are no longer dead
+    pub removed_dead: Vec<i32>,
+}
+
+impl Metrics {
+    /// Computes how the metrics changed compared to a previous version
+    pub fn delta(&self, previous: &Metrics) -> MetricsDelta {
+        let difference = |current: &[i32], other: &[i32]| -> Vec<i32> {
+            current
+                .iter()
+                .copied()
+                .filter(|feature| !other.contains(feature))
+                .collect()
+        };
+        MetricsDelta {
+            variables: self.number_of_variables as i64 - previous.number_of_variables as i64,
+            configurations: Integer::from(&self.configurations - &previous.configurations),
+            variability: Rational::from(&self.variability - &previous.variability),
+            homogeneity: Rational::from(&self.homogeneity - &previous.homogeneity),
+            added_core: difference(&self.core_features, &previous.core_features),
+            removed_core: difference(&previous.core_features, &self.core_features),
+            added_dead: difference(&self.dead_features, &previous.dead_features),
+            removed_dead: difference(&previous.dead_features, &self.dead_features),
+        }
+    }
+}
+
+impl MetricsDelta {
+    /// Checks whether any metric changed
+    pub fn is_unchanged(&self) -> bool {
+        self.variables == 0
+            && self.configurations == 0
+            && self.variability == 0
+            && self.homogeneity == 0
+            && self.added_core.is_empty()
+            && self.removed_core.is_empty()
+            && self.added_dead.is_empty()
+            && self.removed_dead.is_empty()
+    }
+}
+
+/// The metrics of a sequence of model versions, e.g. the releases of a product line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsHistory {
+    /// The label of each version (by default its path) and its metrics, in chronological order
+    pub versions: Vec<(String, Metrics)>,
+}
+
+impl MetricsHistory {
+    /// Computes the metrics of the d-DNNFs, which are labeled with their names
+    pub fn from_ddnnfs<'a>(ddnnfs: impl IntoIterator<Item = (String, &'a mut Ddnnf)>) -> Self {
+        Self {
+            versions: ddnnfs
+                .into_iter()
+                .map(|(label, ddnnf)| (label, ddnnf.metrics()))
+                .collect(),
+        }
+    }
+
+    /// Loads the models one after another and computes their metrics.
+    /// Only one model is kept in memory at a time.
+    ///
+    /// # Panics
+    ///
+    /// The same as for [build_ddnnf_with_settings]. Hence, d-DNNFs in the d4 format have to
+    /// be loaded with [MetricsHistory::from_ddnnfs].
+    pub fn from_paths(paths: &[&str], settings: &Settings) -> Self {
+        Self {
+            versions: paths
+                .iter()
+                .map(|&path| {
+                    let mut ddnnf = build_ddnnf_with_settings(path, None, settings);
+                    (path.to_string(), ddnnf.metrics())
+                })
+                .collect(),
+        }
+    }
+
+    /// The changes of each version compared to its predecessor. The first version has no delta.
+    pub fn deltas(&self) -> Vec<Option<MetricsDelta>> {
+        self.versions
+            .iter()
+            .enumerate()
+            .map(|(index, (_, metrics))| {
+                index
+                    .checked_sub(1)
+                    .map(|previous| metrics.delta(&self.versions[previous].1))
+            })
+            .collect()
+    }
+
+    /// Writes one CSV record per version. Next to the metrics, each record contains the changes
+    /// compared to its predecessor, where the changed core and dead features are listed as
+    /// '+FEATURE' (became core or dead) and '-FEATURE' (no longer core or dead).
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        let mut header = vec!["version"];
+        header.extend(Metrics::CSV_HEADER);
+        header.extend([
+            "delta_configurations",
+            "delta_variability",
+            "delta_homogeneity",
+            "changed_core",
+            "changed_dead",
+        ]);
+        wtr.write_record(header)?;
+
+        for ((label, metrics), delta) in self.versions.iter().zip(self.deltas()) {
+            let mut record = vec![label.clone()];
+            record.extend(metrics.csv_record());
+            match delta {
+                Some(delta) => record.extend([
+                    delta.configurations.to_string(),
+                    delta.variability.to_f64().to_string(),
+                    delta.homogeneity.to_f64().to_string(),
+                    changes(&delta.added_core, &delta.removed_core).join(" "),
+                    changes(&delta.added_dead, &delta.removed_dead).join(" "),
+                ]),
+                None => record.extend(vec![String::new(); 5]),
+            }
+            wtr.write_record(record)?;
+        }
+        wtr.flush()
+    }
+
+    /// Serializes the history as JSON array. Each element contains the label, the metrics,
+    /// whether any metric changed, and the delta to its predecessor (null for the first version).
+    pub fn to_json(&self) -> String {
+        let list = |values: &[i32]| {
+            values
+                .iter()
+                .map(i32::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let elements: Vec<String> = self
+            .versions
+            .iter()
+            .zip(self.deltas())
+            .map(|((label, metrics), delta)| {
+                let (changed, delta) = match delta {
+                    Some(delta) => (
+                        !delta.is_unchanged(),
+                        format!(
+                            "{{\"variables\":{},\"configurations\":\"{}\",\"variability\":{},\"homogeneity\":{},\
+                            \"added_core\":[{}],\"removed_core\":[{}],\"added_dead\":[{}],\"removed_dead\":[{}]}}",
+                            delta.variables,
+                            delta.configurations,
+                            delta.variability.to_f64(),
+                            delta.homogeneity.to_f64(),
+                            list(&delta.added_core),
+                            list(&delta.removed_core),
+                            list(&delta.added_dead),
+                            list(&delta.removed_dead)
+                        ),
+                    ),
+                    None => (false, String::from("null")),
+                };
+                format!(
+                    "{{\"version\":{},\"metrics\":{},\"changed\":{changed},\"delta\":{delta}}}",
+                    json_string(label),
+                    metrics.to_json()
+                )
+            })
+            .collect();
+        format!("[{}]", elements.join(","))
+    }
+}
+
+// Lists the added features with a leading '+' and the removed ones with a leading '-'
+fn changes(added: &[i32], removed: &[i32]) -> Vec<String> {
+    added
+        .iter()
+        .map(|feature| format!("+{feature}"))
+        .chain(removed.iter().map(|feature| format!("-{feature}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn metrics_of_vp9() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let metrics = vp9.metrics();
+
+        assert_eq!(216000, metrics.configurations);
+        assert_eq!(
+            Rational::from((216000, Integer::from(Integer::u_pow_u(2, 42)))),
+            metrics.variability
+        );
+        for (index, commonality) in metrics.commonalities.iter().enumerate() {
+            let feature = index as i32 + 1;
+            assert_eq!(
+                &Rational::from((vp9.execute_query(&[feature]), 216000)),
+                commonality
+            );
+            assert_eq!(*commonality == 1, metrics.core_features.contains(&feature));
+            assert_eq!(*commonality == 0, metrics.dead_features.contains(&feature));
+        }
+        let sum = Rational::from(Rational::sum(metrics.commonalities.iter()));
+        assert_eq!(sum / 42, metrics.homogeneity);
+
+        let json = metrics.to_json();
+        assert!(json.starts_with("{\"variables\":42,\"configurations\":\"216000\""));
+        assert_eq!(
+            42,
+            json.split("commonalities\":[")
+                .nth(1)
+                .unwrap()
+                .split(',')
+                .count()
+        );
+        assert_eq!("42", metrics.csv_record()[0]);
+    }
+
+    #[test]
+    fn metrics_history() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let mut restricted = vp9.condition(&[4]);
+        let mut same = restricted.clone();
+        let history = MetricsHistory::from_ddnnfs([
+            (String::from("v1"), &mut vp9),
+            (String::from("v2"), &mut restricted),
+            (String::from("v\"3\t"), &mut same),
+        ]);
+
+        let deltas = history.deltas();
+        assert_eq!(None, deltas[0]);
+        let delta = deltas[1].as_ref().unwrap();
+        assert_eq!(vp9.execute_query(&[4]) - vp9.rc(), delta.configurations);
+        assert!(delta.added_core.contains(&4));
+        assert!(delta.removed_core.is_empty());
+        assert!(!delta.is_unchanged());
+        assert!(deltas[2].as_ref().unwrap().is_unchanged());
+
+        let json = history.to_json();
+        assert!(json.starts_with("[{\"version\":\"v1\""));
+        assert!(json.contains(r#""version":"v\"3\t""#));
+        assert_eq!(2, json.matches("\"changed\":false").count());
+        assert_eq!(1, json.matches("\"delta\":null").count());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.csv");
+        history.write_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(4, lines.len());
+        assert!(lines[0].starts_with("version,variables,configurations"));
+        assert!(lines[2].contains("+4"));
+
+        let from_paths = MetricsHistory::from_paths(
+            &["tests/data/small_ex_c2d.nnf", "tests/data/small_ex_c2d.nnf"],
+            &Settings::default(),
+        );
+        assert_eq!(2, from_paths.versions.len());
+        assert!(from_paths.deltas()[1].as_ref().unwrap().is_unchanged());
+    }
+
+    #[test]
+    fn metrics_of_a_void_model() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let metrics = vp9.condition(&[4, -4]).metrics();
+        assert_eq!(0, metrics.configurations);
+        assert_eq!(42, metrics.dead_features.len());
+        assert!(metrics.core_features.is_empty());
+        assert_eq!(Rational::new(), metrics.homogeneity);
+    }
+}
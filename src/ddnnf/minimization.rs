@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use once_cell::sync::OnceCell;
+use rug::{Complete, Integer};
+
+use super::{
+    events::ModelEvent,
+    node::{Node, NodeType, NodeType::*},
+    Ddnnf,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Summarizes the effect of [Ddnnf::minimize]
+pub struct MinimizationStats {
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub edges_before: usize,
+    pub edges_after: usize,
+    /// The number of nodes that got replaced by an isomorphic node
+    pub merged_nodes: usize,
+    /// The number of Or nodes whose children had a common set of And children that got factored out
+    pub factored_or_nodes: usize,
+}
+
+impl std::fmt::Display for MinimizationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "nodes: {} -> {}, edges: {} -> {} ({} merged nodes, {} factored Or nodes)",
+            self.nodes_before,
+            self.nodes_after,
+            self.edges_before,
+            self.edges_after,
+            self.merged_nodes,
+            self.factored_or_nodes
+        )
+    }
+}
+
+// The structure of a node that identifies it among all nodes
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    And(Vec<usize>),
+    Or(Vec<usize>),
+    Literal(i32),
+    True,
+    False,
+}
+
+// Creates the minimized node vector and avoids duplicates by hash consing
+#[derive(Default)]
+pub(super) struct Builder {
+    pub(super) ntypes: Vec<NodeType>,
+    lookup: HashMap<Key, usize>,
+    merged_nodes: usize,
+    factored_or_nodes: usize,
+}
+
+impl Builder {
+    // Returns the index of an isomorphic node if there is any. Otherwise, the node gets added.
+    pub(super) fn push(&mut self, ntype: NodeType) -> usize {
+        let key = match &ntype {
+            And { children } => Key::And(sorted(children)),
+            Or { children } => Key::Or(sorted(children)),
+            Literal { literal } => Key::Literal(*literal),
+            True => Key::True,
+            False => Key::False,
+        };
+        if let Some(&index) = self.lookup.get(&key) {
+            self.merged_nodes += 1;
+            return index;
+        }
+        self.ntypes.push(ntype);
+        self.lookup.insert(key, self.ntypes.len() - 1);
+        self.ntypes.len() - 1
+    }
+
+    // Emits an And node for the children, but avoids And nodes with a single child
+    fn push_and(&mut self, children: Vec<usize>) -> usize {
+        if children.len() == 1 {
+            children[0]
+        } else {
+            self.push(And { children })
+        }
+    }
+}
+
+fn sorted(children: &[usize]) -> Vec<usize> {
+    let mut children = children.to_vec();
+    children.sort_unstable();
+    children
+}
+
+impl Ddnnf {
+    /// Reduces the size of the d-DNNF with rewrites that keep the represented models:
+    /// 1) isomorphic subgraphs get merged and
+    /// 2) children that are shared between all And children of an Or node get factored out, i.e.
+    ///    OR(AND(X, A), AND(X, B)) becomes AND(X, OR(A, B)).
+    ///
+    /// The rewrites are repeated until the d-DNNF does not change anymore.
+    /// The counts of all remaining nodes stay the same and determinism, decomposability,
+    /// and smoothness are preserved.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+    /// let count = ddnnf.rc();
+    /// let stats = ddnnf.minimize();
+    ///
+    /// assert!(stats.nodes_after <= stats.nodes_before);
+    /// assert_eq!(count, ddnnf.rc());
+    /// ```
+    pub fn minimize(&mut self) -> MinimizationStats {
+        let mut stats = MinimizationStats {
+            nodes_before: self.nodes.len(),
+            edges_before: self.number_of_edges(),
+            ..Default::default()
+        };
+
+        loop {
+            let builder = self.rewrite();
+            if builder.merged_nodes == 0 && builder.factored_or_nodes == 0 {
+                break;
+            }
+            stats.merged_nodes += builder.merged_nodes;
+            stats.factored_or_nodes += builder.factored_or_nodes;
+            self.replace_nodes(builder.ntypes);
+        }
+
+        stats.nodes_after = self.nodes.len();
+        stats.edges_after = self.number_of_edges();
+        if stats.merged_nodes > 0 || stats.factored_or_nodes > 0 {
+            self.notify(ModelEvent::Rebuilt);
+        }
+        stats
+    }
+
+    fn number_of_edges(&self) -> usize {
+        self.nodes
+            .iter()
+            .map(|node| match &node.ntype {
+                And { children } | Or { children } => children.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    // Performs one pass of both rewrites
+    fn rewrite(&self) -> Builder {
+        let mut builder = Builder::default();
+        let mut new_index: Vec<usize> = Vec::with_capacity(self.nodes.len());
+
+        for node in self.nodes.iter() {
+            let index = match &node.ntype {
+                And { children } => builder.push(And {
+                    children: children.iter().map(|&c| new_index[c]).collect(),
+                }),
+                Or { children } => match self.factor(children) {
+                    Some((common, remainders)) => {
+                        builder.factored_or_nodes += 1;
+                        let map = |nodes: &[usize]| nodes.iter().map(|&c| new_index[c]).collect();
+                        let or_children = remainders
+                            .iter()
+                            .map(|remainder| builder.push_and(map(remainder)))
+                            .collect();
+                        let or = builder.push(Or {
+                            children: or_children,
+                        });
+                        let mut and_children: Vec<usize> = map(&common);
+                        and_children.push(or);
+                        builder.push(And {
+                            children: and_children,
+                        })
+                    }
+                    None => builder.push(Or {
+                        children: children.iter().map(|&c| new_index[c]).collect(),
+                    }),
+                },
+                ntype => builder.push(ntype.clone()),
+            };
+            new_index.push(index);
+        }
+
+        // only the nodes that are reachable from the root remain
+        builder.ntypes.truncate(new_index[self.nodes.len() - 1] + 1);
+        builder
+    }
+
+    // Determines the children that all And children of the Or node have in common.
+    // We only factor if the And nodes are not used elsewhere. Otherwise, the d-DNNF would grow.
+    // Returns the common children and for each And child its remaining children.
+    fn factor(&self, or_children: &[usize]) -> Option<(Vec<usize>, Vec<Vec<usize>>)> {
+        if or_children.len() < 2 {
+            return None;
+        }
+
+        let mut and_children = Vec::with_capacity(or_children.len());
+        for &child in or_children {
+            match &self.nodes[child].ntype {
+                And { children } if self.nodes[child].parents.len() == 1 => {
+                    and_children.push(children)
+                }
+                _ => return None,
+            }
+        }
+
+        let common: Vec<usize> = sorted(and_children[0])
+            .into_iter()
+            .filter(|c| {
+                and_children[1..]
+                    .iter()
+                    .all(|children| children.contains(c))
+            })
+            .collect();
+        if common.is_empty() {
+            return None;
+        }
+
+        let remainders: Vec<Vec<usize>> = and_children
+            .iter()
+            .map(|children| {
+                children
+                    .iter()
+                    .copied()
+                    .filter(|c| !common.contains(c))
+                    .collect()
+            })
+            .collect();
+        // if a remainder is empty, the Or node would not be deterministic
+        if remainders.iter().any(|remainder| remainder.is_empty()) {
+            return None;
+        }
+        Some((common, remainders))
+    }
+
+    // Swaps the nodes with the nodes in the order of the node types and recomputes the meta data
+    pub(crate) fn replace_nodes(&mut self, mut ntypes: Vec<NodeType>) {
+        if ntypes.is_empty() {
+            ntypes.push(False);
+        }
+        let mut nodes: Vec<Node> = Vec::with_capacity(ntypes.len());
+        self.literals.clear();
+        self.true_nodes.clear();
+
+        for ntype in ntypes {
+            let index = nodes.len();
+            let node = match ntype {
+                And { children } => {
+                    let count =
+                        Integer::product(children.iter().map(|&c| &nodes[c].count)).complete();
+                    Node::new_and(count, children)
+                }
+                Or { children } => {
+                    let count = Integer::sum(children.iter().map(|&c| &nodes[c].count)).complete();
+                    Node::new_or(0, count, children)
+                }
+                Literal { literal } => {
+                    self.literals.insert(literal, index);
+                    Node::new_literal(literal)
+                }
+                True => {
+                    self.true_nodes.push(index);
+                    Node::new_bool(true)
+                }
+                False => Node::new_bool(false),
+            };
+            if let And { children } | Or { children } = &node.ntype {
+                for &child in children {
+                    nodes[child].parents.push(index);
+                }
+            }
+            nodes.push(node);
+        }
+
+        self.nodes = nodes;
+        self.md.clear();
+        self.node_indices = OnceCell::new();
+        // a rewrite can leave nodes behind that are only used by replaced nodes
+        self.collect_garbage();
+        self.get_core();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "sampling")]
+    use crate::ddnnf::generator::{check_against_brute_force, generate_ddnnf, GeneratorConfig};
+    use crate::parser::{build_ddnnf, distribute_building};
+
+    use super::*;
+
+    #[test]
+    fn minimization_merges_and_factors() {
+        // both branches of the Or node contain the same subgraph over 2 and 3 twice
+        let lines = [
+            "nnf 10 12 3",
+            "L 1",
+            "L -1",
+            "L 2",
+            "L -2",
+            "L 3",
+            "O 2 2 2 3",
+            "O 2 2 2 3",
+            "A 3 0 5 4",
+            "A 3 1 6 4",
+            "O 1 2 7 8",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let mut ddnnf = distribute_building(lines, None, None);
+        assert_eq!(4, ddnnf.rc());
+        let root = ddnnf.node_id(9);
+        assert_eq!(Some(9), ddnnf.node_index(root));
+
+        let stats = ddnnf.minimize();
+        assert_eq!(1, stats.merged_nodes);
+        assert_eq!(2, stats.factored_or_nodes);
+        assert_eq!(10, stats.nodes_before);
+        assert_eq!(stats.nodes_after, ddnnf.nodes.len());
+        assert!(stats.edges_after < stats.edges_before);
+        assert_eq!(4, ddnnf.rc());
+        assert_eq!(2, ddnnf.execute_query(&[1]));
+        assert_eq!(0, ddnnf.execute_query(&[-3]));
+        assert!(matches!(ddnnf.nodes.last().unwrap().ntype, And { .. }));
+        // the lookup of the indices follows the replaced nodes
+        assert_eq!(None, ddnnf.node_index(root));
+        let root = ddnnf.node_id(ddnnf.nodes.len() - 1);
+        assert_eq!(Some(ddnnf.nodes.len() - 1), ddnnf.node_index(root));
+
+        // a minimized d-DNNF can not be minimized any further
+        assert_eq!(0, ddnnf.minimize().merged_nodes);
+    }
+
+    #[test]
+    fn minimization_keeps_models() {
+        #[cfg(feature = "sampling")]
+        for seed in 0..20 {
+            let config = GeneratorConfig {
+                number_of_variables: 2 + seed as u32 % 7,
+                seed,
+                ..Default::default()
+            };
+            let mut ddnnf = generate_ddnnf(&config);
+            let stats = ddnnf.minimize();
+            assert!(stats.nodes_after <= stats.nodes_before);
+            if let Err(msg) = check_against_brute_force(&mut ddnnf) {
+                panic!("seed {seed}: {msg}");
+            }
+        }
+
+        let mut vp9 = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let distribution = vp9.literal_distribution();
+        vp9.minimize();
+        assert_eq!(distribution, vp9.literal_distribution());
+    }
+}
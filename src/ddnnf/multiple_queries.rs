@@ -1,4 +1,6 @@
 use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
     error::Error,
     fs::File,
     io::{BufWriter, Write},
@@ -6,11 +8,151 @@ use std::{
     thread,
 };
 
+use itertools::Itertools;
+use rug::{Complete, Integer};
 use workctl::WorkQueue;
 
-use crate::{parser, Ddnnf};
+use crate::{parser, Ddnnf, NodeType::*};
 
 impl Ddnnf {
+    /// Computes the cardinalities of many partial configurations at once. The result contains the
+    /// count of each query in the order of the queries. Like [Ddnnf::count_adaptive], contradicting
+    /// queries have no configuration.
+    ///
+    /// Consecutive queries share the work for their common literals: We order the literals of each query by
+    /// their frequency among all queries and sort the queries. Then, we walk through them while we keep the counts
+    /// of the common prefix. Adding a literal only recomputes the ancestors of the node of its negation and leaving
+    /// the prefix restores their previous counts. The sorted queries get split into one contiguous part
+    /// per worker (see [Ddnnf::max_worker]), which are processed in parallel.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let counts = ddnnf.execute_queries(&[vec![], vec![2], vec![2, 4], vec![2, 3], vec![-1]]);
+    /// assert_eq!(vec![4, 2, 1, 0, 0], counts);
+    /// ```
+    pub fn execute_queries(&self, queries: &[Vec<i32>]) -> Vec<Integer> {
+        let mut frequencies: HashMap<i32, usize> = HashMap::new();
+        for literal in queries.iter().flatten() {
+            *frequencies.entry(*literal).or_default() += 1;
+        }
+        let mut ordered: Vec<(Vec<i32>, usize)> = queries
+            .iter()
+            .enumerate()
+            .map(|(position, query)| {
+                let literals = query
+                    .iter()
+                    .copied()
+                    .collect::<BTreeSet<i32>>()
+                    .into_iter()
+                    .sorted_by_key(|literal| {
+                        (
+                            Reverse(frequencies[literal]),
+                            literal.unsigned_abs(),
+                            *literal,
+                        )
+                    })
+                    .collect();
+                (literals, position)
+            })
+            .collect();
+        ordered.sort_unstable();
+
+        let mut counts = vec![Integer::ZERO; queries.len()];
+        if ordered.is_empty() {
+            return counts;
+        }
+        let workers = (self.max_worker.max(1) as usize).min(ordered.len());
+        let chunk_size = ordered.len().div_ceil(workers);
+        let results: Vec<Vec<(usize, Integer)>> = thread::scope(|scope| {
+            let handles: Vec<_> = ordered
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.execute_sorted_queries(chunk)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        for (position, count) in results.into_iter().flatten() {
+            counts[position] = count;
+        }
+        counts
+    }
+
+    // Counts the sorted queries one after another while keeping the counts of the common prefix.
+    // The changes of each literal get logged, so that we can undo them when we leave the prefix.
+    fn execute_sorted_queries(&self, queries: &[(Vec<i32>, usize)]) -> Vec<(usize, Integer)> {
+        let mut counts: Vec<Integer> = self.nodes.iter().map(|node| node.count.clone()).collect();
+        let mut prefix: Vec<i32> = Vec::new();
+        let mut changes: Vec<Vec<(usize, Integer)>> = Vec::new();
+        let mut results = Vec::with_capacity(queries.len());
+
+        for (query, position) in queries {
+            let common = prefix
+                .iter()
+                .zip(query.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            while prefix.len() > common {
+                prefix.pop();
+                for (index, count) in changes.pop().unwrap().into_iter().rev() {
+                    counts[index] = count;
+                }
+            }
+            for &literal in &query[common..] {
+                prefix.push(literal);
+                changes.push(self.recount_without_literal(&mut counts, -literal));
+            }
+            results.push((*position, counts.last().cloned().unwrap_or_default()));
+        }
+        results
+    }
+
+    // Sets the count of the literal node to zero and recomputes its ancestors in postorder.
+    // Returns the previous counts of all nodes that changed.
+    fn recount_without_literal(
+        &self,
+        counts: &mut [Integer],
+        literal: i32,
+    ) -> Vec<(usize, Integer)> {
+        let start = match self.literals.get(&literal) {
+            Some(&index) if counts[index] != 0 => index,
+            _ => return Vec::new(),
+        };
+
+        let mut affected = vec![start];
+        let mut visited = BTreeSet::from([start]);
+        while let Some(index) = affected.pop() {
+            for &parent in self.nodes[index].parents.iter() {
+                if visited.insert(parent) {
+                    affected.push(parent);
+                }
+            }
+        }
+
+        let mut changes = Vec::with_capacity(visited.len());
+        for index in visited {
+            let count = match &self.nodes[index].ntype {
+                And { children } => {
+                    Integer::product(children.iter().map(|&c| &counts[c])).complete()
+                }
+                Or { children } => Integer::sum(children.iter().map(|&c| &counts[c])).complete(),
+                _ => Integer::ZERO,
+            };
+            if count != counts[index] {
+                changes.push((index, std::mem::replace(&mut counts[index], count)));
+            }
+        }
+        changes
+    }
+
     #[inline]
     /// Computes the given operation for all queries in path_in.
     /// The results are saved in the path_out. The .csv ending always gets added to the user input.
@@ -171,6 +313,25 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn batched_queries() {
+        let mut vp9: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+        let mut queries: Vec<Vec<i32>> = parser::parse_queries_file("./tests/data/VP9.config")
+            .into_iter()
+            .map(|(_, query)| query)
+            .collect();
+        queries.extend([vec![], vec![4, -4], vec![4, 4, 9], vec![9, 4], vec![-1]]);
+
+        let expected: Vec<Integer> = queries.iter().map(|q| vp9.count_adaptive(q)).collect();
+        for workers in [1, 3, 16] {
+            vp9.max_worker = workers;
+            assert_eq!(expected, vp9.execute_queries(&queries));
+        }
+        assert_eq!(0, expected[queries.len() - 4]);
+        assert_eq!(vp9.execute_query(&[4, 9]), expected[queries.len() - 3]);
+        assert!(vp9.execute_queries(&[]).is_empty());
+    }
+
     #[test]
     fn card_multi_queries() {
         let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
@@ -29,6 +29,63 @@ pub enum NodeType {
     False,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Identifies a node by its structure instead of its position in the node vector.
+/// The id only depends on the node type, its literal, and the ids of its children (regardless of their order).
+/// Hence, a node keeps its id if the d-DNNF gets rebuilt or reordered as long as its subgraph stays the same.
+/// Structurally identical nodes share the same id.
+pub struct NodeId(pub u64);
+
+impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl std::str::FromStr for NodeId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(NodeId)
+    }
+}
+
+impl NodeId {
+    // We use FNV-1a instead of the std hasher because the ids have to stay the same across
+    // different versions of the compiler to keep persisted ids valid.
+    fn hash(tag: u8, payload: impl Iterator<Item = u8>) -> NodeId {
+        const OFFSET: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+        NodeId(
+            std::iter::once(tag)
+                .chain(payload)
+                .fold(OFFSET, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(PRIME)
+                }),
+        )
+    }
+
+    /// Computes the id of a node based on the ids of its children, which child_id yields for each child index.
+    pub fn of(ntype: &NodeType, mut child_id: impl FnMut(usize) -> NodeId) -> NodeId {
+        let mut children_payload = |children: &[usize]| {
+            let mut child_ids: Vec<u64> = children.iter().map(|&c| child_id(c).0).collect();
+            child_ids.sort_unstable();
+            child_ids
+                .into_iter()
+                .flat_map(u64::to_le_bytes)
+                .collect::<Vec<u8>>()
+        };
+
+        match ntype {
+            And { children } => NodeId::hash(0, children_payload(children).into_iter()),
+            Or { children } => NodeId::hash(1, children_payload(children).into_iter()),
+            Literal { literal } => NodeId::hash(2, literal.to_le_bytes().into_iter()),
+            True => NodeId::hash(3, std::iter::empty()),
+            False => NodeId::hash(4, std::iter::empty()),
+        }
+    }
+}
+
 use rug::Integer;
 use NodeType::{And, False, Literal, Or, True};
 
@@ -160,4 +217,32 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn node_ids() {
+        let leaf = |ntype: NodeType| NodeId::of(&ntype, |_| unreachable!());
+        let literal = leaf(Literal { literal: 3 });
+        assert_ne!(literal, leaf(Literal { literal: -3 }));
+        assert_ne!(leaf(True), leaf(False));
+
+        let ids = [literal, leaf(Literal { literal: 4 })];
+        let inner = |ntype: NodeType| NodeId::of(&ntype, |c| ids[c]);
+        let and = inner(And {
+            children: vec![0, 1],
+        });
+        assert_eq!(
+            and,
+            inner(And {
+                children: vec![1, 0]
+            })
+        );
+        assert_ne!(
+            and,
+            inner(Or {
+                children: vec![0, 1]
+            })
+        );
+
+        assert_eq!(Ok(and), and.to_string().parse());
+    }
 }
@@ -0,0 +1,337 @@
+use std::collections::BTreeSet;
+
+use itertools::Either;
+use rug::{Integer, Rational};
+
+use crate::Ddnnf;
+
+/// A bounded integer attribute of a feature model, e.g. a cache size of 1, 2, 4, or 8, that is
+/// encoded as one-hot group of variables. Each value has its own variable and each configuration
+/// selects exactly one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumericFeature {
+    pub name: String,
+    /// The values and the variables that select them
+    pub values: Vec<(i64, u32)>,
+}
+
+impl NumericFeature {
+    /// Encodes the values with consecutive variables starting at first_variable.
+    /// Duplicate values get only one variable.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::BTreeSet;
+    /// use ddnnf_lib::ddnnf::numeric::NumericFeature;
+    ///
+    /// // a CNF with 10 variables gets a cache size of 1, 2, 4, or 8
+    /// let cache_size = NumericFeature::one_hot("cache_size", &[1, 2, 4, 8], 11);
+    /// assert_eq!(vec![(1, 11), (2, 12), (4, 13), (8, 14)], cache_size.values);
+    ///
+    /// let mut clauses: BTreeSet<BTreeSet<i32>> = BTreeSet::new();
+    /// clauses.extend(cache_size.clauses());
+    /// // at least one value and none of the 6 pairs of values
+    /// assert_eq!(7, clauses.len());
+    /// assert!(clauses.contains(&BTreeSet::from([11, 12, 13, 14])));
+    /// ```
+    pub fn one_hot(name: &str, values: &[i64], first_variable: u32) -> NumericFeature {
+        let mut distinct = Vec::with_capacity(values.len());
+        for &value in values {
+            if !distinct.contains(&value) {
+                distinct.push(value);
+            }
+        }
+        NumericFeature {
+            name: name.to_string(),
+            values: distinct
+                .into_iter()
+                .zip(first_variable..)
+                .collect::<Vec<(i64, u32)>>(),
+        }
+    }
+
+    /// The clauses that ensure that exactly one value gets selected
+    pub fn clauses(&self) -> Vec<BTreeSet<i32>> {
+        let literals: Vec<i32> = self.variables().map(|var| var as i32).collect();
+        let mut clauses = vec![literals.iter().copied().collect::<BTreeSet<i32>>()];
+        for (index, &a) in literals.iter().enumerate() {
+            for &b in literals[index + 1..].iter() {
+                clauses.push(BTreeSet::from([-a, -b]));
+            }
+        }
+        clauses
+    }
+
+    /// The variables of the values
+    pub fn variables(&self) -> impl Iterator<Item = u32> + '_ {
+        self.values.iter().map(|&(_, var)| var)
+    }
+
+    /// Renames the variables of the values. Values whose variable gets no new name are dropped.
+    /// None if no value remains.
+    pub(crate) fn renumbered(&self, rename: impl Fn(u32) -> Option<u32>) -> Option<NumericFeature> {
+        let values: Vec<(i64, u32)> = self
+            .values
+            .iter()
+            .filter_map(|&(value, var)| rename(var).map(|var| (value, var)))
+            .collect();
+        (!values.is_empty()).then(|| NumericFeature {
+            name: self.name.clone(),
+            values,
+        })
+    }
+
+    /// The value that the configuration selects. None if it selects no value at all.
+    pub fn value_of(&self, config: &[i32]) -> Option<i64> {
+        self.values
+            .iter()
+            .find(|&&(_, var)| config.contains(&(var as i32)))
+            .map(|&(value, _)| value)
+    }
+}
+
+impl Ddnnf {
+    /// Tracks the numeric feature such that it can be queried by its name. The clauses of the feature
+    /// (see [NumericFeature::clauses]) have to be encoded in the d-DNNF already, e.g. by adding them
+    /// to the CNF before compiling it.
+    ///
+    /// Returns false if the feature has no values, refers to unknown variables, shares a variable with another
+    /// numeric feature, or has the name of another one.
+    pub fn add_numeric_feature(&mut self, feature: NumericFeature) -> bool {
+        let taken: BTreeSet<u32> = self
+            .numeric_features
+            .iter()
+            .flat_map(|other| other.variables())
+            .collect();
+        let mut variables = BTreeSet::new();
+        let valid = !feature.values.is_empty()
+            && feature.variables().all(|var| {
+                (1..=self.number_of_variables).contains(&var)
+                    && !taken.contains(&var)
+                    && variables.insert(var)
+            })
+            && self.numeric_feature(&feature.name).is_none();
+        if valid {
+            self.numeric_features.push(feature);
+        }
+        valid
+    }
+
+    /// Encodes a numeric feature with the values as one-hot group of new variables that follow the
+    /// existing ones (see [NumericFeature::one_hot]). Its clauses get added to the CNF of the d-DNNF,
+    /// which recompiles it like any other clause update, and the feature gets tracked.
+    /// Undoing the update removes the variables again, but the feature stays tracked.
+    ///
+    /// Returns an error if the d-DNNF does not stem from a CNF, the name is taken, there are no values,
+    /// or the update fails.
+    pub fn encode_numeric_feature(
+        &mut self,
+        name: &str,
+        values: &[i64],
+    ) -> Result<NumericFeature, String> {
+        if !self.can_save_state() {
+            return Err(String::from(
+                "numeric features can only be encoded into d-DNNFs that stem from a CNF",
+            ));
+        }
+        if self.numeric_feature(name).is_some() {
+            return Err(format!("there is already a numeric feature named {name}"));
+        }
+        if values.is_empty() {
+            return Err(format!("the numeric feature {name} has no values"));
+        }
+
+        let feature = NumericFeature::one_hot(name, values, self.number_of_variables + 1);
+        let total_features = self.number_of_variables + feature.values.len() as u32;
+        if !self.update_cached_state(
+            Either::Left((feature.clauses(), Vec::new())),
+            Some(total_features),
+        ) {
+            return Err(format!("unable to add the clauses of {name}"));
+        }
+        self.numeric_features.push(feature.clone());
+        Ok(feature)
+    }
+
+    /// The numeric feature with that name
+    pub fn numeric_feature(&self, name: &str) -> Option<&NumericFeature> {
+        self.numeric_features
+            .iter()
+            .find(|feature| feature.name == name)
+    }
+
+    /// Counts the configurations per value of the numeric feature under the assumptions.
+    /// None if there is no numeric feature with that name.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::ddnnf::numeric::NumericFeature;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // each configuration of the small example contains either 2 or 3
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// assert!(ddnnf.add_numeric_feature(NumericFeature {
+    ///     name: String::from("size"),
+    ///     values: vec![(16, 2), (32, 3)],
+    /// }));
+    ///
+    /// let counts = ddnnf.value_counts("size", &[]).unwrap();
+    /// assert_eq!(vec![(16, ddnnf.execute_query(&[2])), (32, ddnnf.execute_query(&[3]))], counts);
+    /// assert_eq!(None, ddnnf.value_counts("speed", &[]));
+    /// ```
+    pub fn value_counts(&mut self, name: &str, assumptions: &[i32]) -> Option<Vec<(i64, Integer)>> {
+        let feature = self.numeric_feature(name)?.clone();
+        let mut query = assumptions.to_vec();
+        query.push(0);
+        Some(
+            feature
+                .values
+                .iter()
+                .map(|&(value, var)| {
+                    *query.last_mut().unwrap() = var as i32;
+                    (value, self.execute_query(&query))
+                })
+                .collect(),
+        )
+    }
+
+    /// The average value of the numeric feature among the configurations under the assumptions.
+    /// None if there is no numeric feature with that name or the configurations select no value at all.
+    pub fn expected_value(&mut self, name: &str, assumptions: &[i32]) -> Option<Rational> {
+        let counts = self.value_counts(name, assumptions)?;
+        let total: Integer = counts.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return None;
+        }
+        let sum: Integer = counts.into_iter().map(|(value, count)| count * value).sum();
+        Some(Rational::from((sum, total)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::distribute_building;
+
+    use super::*;
+
+    // the cache size 1, 2, 4, or 8 as one-hot group of the variables 1 to 4 and the unconstrained feature 5
+    fn cache() -> Ddnnf {
+        let lines = [
+            "nnf 17 24 5",
+            "L 1",
+            "L -1",
+            "L 2",
+            "L -2",
+            "L 3",
+            "L -3",
+            "L 4",
+            "L -4",
+            "A 4 0 3 5 7",
+            "A 4 1 2 5 7",
+            "A 4 1 3 4 7",
+            "A 4 1 3 5 6",
+            "O 0 4 8 9 10 11",
+            "L 5",
+            "L -5",
+            "O 5 2 13 14",
+            "A 2 12 15",
+        ];
+        let mut ddnnf = distribute_building(lines.map(String::from).to_vec(), None, None);
+        assert!(ddnnf.add_numeric_feature(NumericFeature::one_hot("cache_size", &[1, 2, 4, 8], 1)));
+        ddnnf
+    }
+
+    #[test]
+    fn encoding_matches_the_d_dnnf() {
+        let mut ddnnf = cache();
+        let feature = ddnnf.numeric_feature("cache_size").unwrap().clone();
+        let models = ddnnf.brute_force_enumerate().unwrap();
+        assert_eq!(8, models.len());
+        for model in models {
+            assert!(feature
+                .clauses()
+                .iter()
+                .all(|clause| clause.iter().any(|literal| model.contains(literal))));
+            assert!(feature.value_of(&model).is_some());
+        }
+        assert_eq!(Some(4), feature.value_of(&[-1, -2, 3, -4, 5]));
+        assert_eq!(None, feature.value_of(&[-1, -2, -3, -4, 5]));
+
+        // each value has the same number of configurations
+        assert_eq!(
+            Some(vec![
+                (1, 2.into()),
+                (2, 2.into()),
+                (4, 2.into()),
+                (8, 2.into())
+            ]),
+            ddnnf.value_counts("cache_size", &[])
+        );
+        assert_eq!(
+            Some(Rational::from((15, 4))),
+            ddnnf.expected_value("cache_size", &[])
+        );
+        assert_eq!(
+            Some(Rational::from((14, 3))),
+            ddnnf.expected_value("cache_size", &[-1, 5])
+        );
+        assert_eq!(None, ddnnf.expected_value("cache_size", &[-1, -2, -3, -4]));
+        assert_eq!(None, ddnnf.expected_value("threads", &[]));
+
+        // the grouping survives conditioning
+        let mut conditioned = ddnnf.condition(&[4]);
+        assert_eq!(
+            Some(Rational::from(8)),
+            conditioned.expected_value("cache_size", &[])
+        );
+    }
+
+    #[test]
+    fn invalid_numeric_features() {
+        let mut ddnnf = cache();
+        let feature = |name: &str, variables: &[u32]| NumericFeature {
+            name: name.to_string(),
+            values: variables.iter().map(|&var| (var as i64, var)).collect(),
+        };
+        assert!(!ddnnf.add_numeric_feature(feature("cache_size", &[5])));
+        assert!(!ddnnf.add_numeric_feature(feature("threads", &[4, 5])));
+        assert!(!ddnnf.add_numeric_feature(feature("threads", &[5, 6])));
+        assert!(!ddnnf.add_numeric_feature(feature("threads", &[5, 5])));
+        assert!(!ddnnf.add_numeric_feature(feature("threads", &[])));
+        assert!(ddnnf.add_numeric_feature(feature("threads", &[5])));
+        assert_eq!(2, ddnnf.numeric_features.len());
+
+        // without a CNF, there is nothing to encode the feature into
+        assert!(ddnnf.encode_numeric_feature("level", &[1, 2]).is_err());
+        assert_eq!(5, ddnnf.number_of_variables);
+    }
+
+    #[cfg(feature = "d4")]
+    #[test]
+    fn encoding_into_the_cnf() {
+        use crate::parser::build_ddnnf;
+
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex.cnf", None);
+        let count = ddnnf.rc();
+        let feature = ddnnf
+            .encode_numeric_feature("cache_size", &[1, 2, 4, 8, 4])
+            .unwrap();
+        assert_eq!(vec![(1, 5), (2, 6), (4, 7), (8, 8)], feature.values);
+        assert_eq!(8, ddnnf.number_of_variables);
+        assert_eq!(count.clone() * 4, ddnnf.rc());
+        assert_eq!(
+            Some(vec![
+                (1, count.clone()),
+                (2, count.clone()),
+                (4, count.clone()),
+                (8, count)
+            ]),
+            ddnnf.value_counts("cache_size", &[])
+        );
+        assert!(ddnnf.encode_numeric_feature("cache_size", &[3]).is_err());
+        assert!(ddnnf.encode_numeric_feature("threads", &[]).is_err());
+    }
+}
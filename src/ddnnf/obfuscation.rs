@@ -0,0 +1,193 @@
+use super::{equivalence::split_mix, numeric::NumericFeature};
+use crate::{Ddnnf, NodeType, NodeType::*};
+
+impl Ddnnf {
+    /// Creates an anonymized copy of the d-DNNF that can be shared as benchmark without leaking
+    /// details of the product line. The variables get renamed by a random permutation, the nodes
+    /// get a random postorder, and the children of each node get shuffled. Annotations and feature
+    /// names are dropped. Numeric features keep their values under the renamed variables, but get the
+    /// names numeric_1, numeric_2, and so on. The logical structure and hence all counts stay the same.
+    ///
+    /// Returns the copy together with the renaming, i.e. the new number of each old variable
+    /// at its index (index 0 is unused). Reusing the same seed yields the same copy.
+    /// Fails if a literal exceeds the declared number of variables, because the renaming would not cover it.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// let (anonymized, renaming) = ddnnf.anonymize(42).unwrap();
+    ///
+    /// assert_eq!(ddnnf.rc(), anonymized.rc());
+    /// // the core feature 1 is core under its new number as well
+    /// assert!(anonymized.core.contains(&(renaming[1] as i32)));
+    /// ```
+    pub fn anonymize(&self, seed: u64) -> Result<(Ddnnf, Vec<u32>), String> {
+        if let Some(literal) = self
+            .literals
+            .keys()
+            .find(|literal| literal.unsigned_abs() > self.number_of_variables)
+        {
+            return Err(format!(
+                "The literal {literal} exceeds the {} declared variables",
+                self.number_of_variables
+            ));
+        }
+
+        let mut state = seed;
+        let mut renaming: Vec<u32> = (1..=self.number_of_variables).collect();
+        shuffle(&mut renaming, &mut state);
+        renaming.insert(0, 0);
+
+        // a random topological order: each step places one of the nodes whose children are placed
+        let mut parents: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        let mut remaining: Vec<usize> = vec![0; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let And { children } | Or { children } = &node.ntype {
+                remaining[index] = children.len();
+                for &child in children {
+                    parents[child].push(index);
+                }
+            }
+        }
+        let mut ready: Vec<usize> = (0..self.nodes.len())
+            .filter(|&index| remaining[index] == 0)
+            .collect();
+        let mut new_index = vec![0; self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while !ready.is_empty() {
+            let node = ready.swap_remove(split_mix(&mut state) as usize % ready.len());
+            new_index[node] = order.len();
+            order.push(node);
+            for &parent in parents[node].iter() {
+                remaining[parent] -= 1;
+                if remaining[parent] == 0 {
+                    ready.push(parent);
+                }
+            }
+        }
+
+        let ntypes: Vec<NodeType> = order
+            .into_iter()
+            .map(|node| match &self.nodes[node].ntype {
+                And { children } | Or { children } => {
+                    let mut children: Vec<usize> = children.iter().map(|&c| new_index[c]).collect();
+                    shuffle(&mut children, &mut state);
+                    if matches!(self.nodes[node].ntype, And { .. }) {
+                        And { children }
+                    } else {
+                        Or { children }
+                    }
+                }
+                Literal { literal } => Literal {
+                    literal: renaming[literal.unsigned_abs() as usize] as i32 * literal.signum(),
+                },
+                ntype => ntype.clone(),
+            })
+            .collect();
+
+        let mut anonymized = Ddnnf {
+            max_worker: self.max_worker,
+            max_digits: self.max_digits,
+            number_of_variables: self.number_of_variables,
+            ..Default::default()
+        };
+        anonymized.numeric_features = self
+            .numeric_features
+            .iter()
+            .filter_map(|feature| feature.renumbered(|var| renaming.get(var as usize).copied()))
+            .zip(1..)
+            .map(|(feature, index)| NumericFeature {
+                name: format!("numeric_{index}"),
+                ..feature
+            })
+            .collect();
+        anonymized.replace_nodes(ntypes);
+        Ok((anonymized, renaming))
+    }
+}
+
+// Shuffles the elements via Fisher-Yates with the SplitMix64 generator
+fn shuffle<T>(elements: &mut [T], state: &mut u64) {
+    for index in (1..elements.len()).rev() {
+        elements.swap(index, split_mix(state) as usize % (index + 1));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn anonymization_keeps_the_structure() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        vp9.feature_names.insert(1, String::from("root"));
+        assert!(vp9.add_numeric_feature(NumericFeature::one_hot("level", &[1, 2, 3], 10)));
+        let (mut anonymized, renaming) = vp9.anonymize(7).unwrap();
+
+        assert!(anonymized.feature_names.is_empty());
+        assert_eq!(vp9.nodes.len(), anonymized.nodes.len());
+        assert_eq!(vp9.rc(), anonymized.rc());
+        assert_ne!((1..=42).collect::<Vec<u32>>(), renaming[1..]);
+        let mut sorted = renaming.clone();
+        sorted.sort_unstable();
+        assert_eq!((0..=42).collect::<Vec<u32>>(), sorted);
+
+        // the counts stay the same under the renamed assumptions
+        let rename = |literals: &[i32]| -> Vec<i32> {
+            literals
+                .iter()
+                .map(|l| renaming[l.unsigned_abs() as usize] as i32 * l.signum())
+                .collect()
+        };
+        for assumptions in [vec![3], vec![-4, 9], vec![3, -7, 20], vec![-1]] {
+            assert_eq!(
+                vp9.execute_query(&assumptions),
+                anonymized.execute_query(&rename(&assumptions))
+            );
+        }
+        let core: Vec<i32> = vp9.core.iter().copied().collect();
+        let mut expected = rename(&core);
+        let mut actual: Vec<i32> = anonymized.core.iter().copied().collect();
+        expected.sort_unstable();
+        actual.sort_unstable();
+        assert_eq!(expected, actual);
+
+        // numeric features keep their values under the new variables, but not their names
+        assert!(anonymized.numeric_feature("level").is_none());
+        let level = anonymized.numeric_feature("numeric_1").unwrap().clone();
+        assert_eq!(
+            vec![(1, renaming[10]), (2, renaming[11]), (3, renaming[12])],
+            level.values
+        );
+        assert_eq!(
+            vp9.value_counts("level", &[]),
+            anonymized.value_counts("numeric_1", &[])
+        );
+
+        // the same seed yields the same copy, another seed a different one
+        let (again, _) = vp9.anonymize(7).unwrap();
+        let (other, _) = vp9.anonymize(8).unwrap();
+        let types = |ddnnf: &Ddnnf| -> Vec<NodeType> {
+            ddnnf.nodes.iter().map(|node| node.ntype.clone()).collect()
+        };
+        assert_eq!(types(&anonymized), types(&again));
+        assert_ne!(types(&anonymized), types(&other));
+        assert_ne!(types(&vp9), types(&anonymized));
+    }
+
+    #[test]
+    fn undeclared_variables_get_rejected() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        vp9.number_of_variables = 40;
+        assert!(vp9
+            .anonymize(7)
+            .unwrap_err()
+            .contains("40 declared variables"));
+    }
+}
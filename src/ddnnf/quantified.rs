@@ -0,0 +1,180 @@
+use rug::Integer;
+
+use crate::Ddnnf;
+
+/// The answer to a quantified query, see [Ddnnf::forall_exists]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Quantified {
+    /// Each valid choice of the group has a valid completion that satisfies the property
+    Holds,
+    /// A valid choice of the group (one literal per feature of the group) without any valid completion
+    /// that satisfies the property
+    Counterexample(Vec<i32>),
+}
+
+impl Ddnnf {
+    /// Answers whether for every valid choice of the group features there exists a valid completion
+    /// that satisfies the property, i.e. ∀ group ∃ rest: model ∧ property. The property is a partial
+    /// configuration. Choices of the group that are invalid on their own can not be completed anyway
+    /// and do not count as counterexamples.
+    ///
+    /// The choices get assigned feature by feature. Each partial choice gets pruned as soon as the
+    /// counts decide it: if no configuration contains it, no choice extends it. If all configurations
+    /// that contain it satisfy the property, each valid choice that extends it has a completion.
+    /// If none of them satisfies the property, each valid choice that extends it is a counterexample.
+    ///
+    /// Returns an error if the group or the property contains a variable that does not exist.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    /// use ddnnf_lib::ddnnf::quantified::Quantified;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// // however 2 and 3 get chosen, 4 can still be selected
+    /// assert_eq!(Ok(Quantified::Holds), ddnnf.forall_exists(&[2, 3], &[4]));
+    /// // but selecting 2 rules out 3
+    /// assert_eq!(
+    ///     Ok(Quantified::Counterexample(vec![2, 4])),
+    ///     ddnnf.forall_exists(&[2, 4], &[3])
+    /// );
+    /// assert!(ddnnf.forall_exists(&[5], &[]).is_err());
+    /// ```
+    pub fn forall_exists(&self, group: &[u32], property: &[i32]) -> Result<Quantified, String> {
+        let variables = group
+            .iter()
+            .copied()
+            .chain(property.iter().map(|literal| literal.unsigned_abs()));
+        for variable in variables {
+            if variable == 0 || variable > self.number_of_variables {
+                return Err(format!(
+                    "the variable {variable} does not exist in a model with {} variables",
+                    self.number_of_variables
+                ));
+            }
+        }
+
+        let mut choice = Vec::with_capacity(group.len());
+        Ok(
+            match self.search_counterexample(group, &mut choice, property) {
+                Some(counterexample) => Quantified::Counterexample(counterexample),
+                None => Quantified::Holds,
+            },
+        )
+    }
+
+    // Searches a valid choice of the remaining group features that extends the partial choice
+    // and has no valid completion that satisfies the property
+    fn search_counterexample(
+        &self,
+        remaining: &[u32],
+        choice: &mut Vec<i32>,
+        property: &[i32],
+    ) -> Option<Vec<i32>> {
+        let valid = self.count_adaptive(choice);
+        if valid == 0 {
+            return None;
+        }
+        let satisfying = self.count_with_property(choice, property);
+        if satisfying == 0 {
+            return Some(self.complete_choice(remaining, choice.clone()));
+        }
+        let (&feature, remaining) = match remaining.split_first() {
+            Some(split) if satisfying != valid => split,
+            // either the choice is complete or all of its extensions have a completion
+            _ => return None,
+        };
+
+        for literal in [feature as i32, -(feature as i32)] {
+            choice.push(literal);
+            let counterexample = self.search_counterexample(remaining, choice, property);
+            choice.pop();
+            if counterexample.is_some() {
+                return counterexample;
+            }
+        }
+        None
+    }
+
+    // The number of configurations that contain the choice and satisfy the property
+    fn count_with_property(&self, choice: &[i32], property: &[i32]) -> Integer {
+        let mut query = choice.to_vec();
+        query.extend_from_slice(property);
+        self.count_adaptive(&query)
+    }
+
+    // Extends the valid partial choice by a valid literal for each remaining group feature
+    fn complete_choice(&self, remaining: &[u32], mut choice: Vec<i32>) -> Vec<i32> {
+        for &feature in remaining {
+            choice.push(feature as i32);
+            if self.count_adaptive(&choice) == 0 {
+                choice.pop();
+                choice.push(-(feature as i32));
+            }
+        }
+        choice
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    // Checks each assignment of the group without any pruning
+    fn brute_force(ddnnf: &Ddnnf, group: &[u32], property: &[i32]) -> Vec<Vec<i32>> {
+        group
+            .iter()
+            .map(|&feature| [feature as i32, -(feature as i32)])
+            .multi_cartesian_product()
+            .filter(|choice| {
+                ddnnf.count_adaptive(choice) > 0 && ddnnf.count_with_property(choice, property) == 0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quantified_queries_match_brute_force() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let queries: [(&[u32], &[i32]); 6] = [
+            (&[1, 2, 3], &[4]),
+            (&[3, 4, 9], &[-20]),
+            (&[5, 6, 7, 8], &[10, -11]),
+            (&[20, 21, 22], &[3]),
+            (&[2], &[4, -4]),
+            (&[9, 4, 38], &[1]),
+        ];
+        for (group, property) in queries {
+            let counterexamples = brute_force(&vp9, group, property);
+            match vp9.forall_exists(group, property).unwrap() {
+                Quantified::Holds => assert!(counterexamples.is_empty()),
+                Quantified::Counterexample(choice) => {
+                    assert!(
+                        counterexamples.contains(&choice),
+                        "{choice:?} is no counterexample for {group:?} and {property:?}"
+                    );
+                }
+            }
+        }
+
+        // a property that contradicts a group feature fails for the choices with that feature
+        let core = *vp9
+            .core
+            .iter()
+            .find(|literal| literal.is_positive())
+            .unwrap();
+        assert_eq!(
+            Ok(Quantified::Counterexample(vec![core])),
+            vp9.forall_exists(&[core as u32], &[-core])
+        );
+        assert!(vp9.forall_exists(&[0], &[]).is_err());
+        assert!(vp9.forall_exists(&[1], &[-43]).is_err());
+    }
+}
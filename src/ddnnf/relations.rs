@@ -0,0 +1,258 @@
+use std::{collections::BTreeSet, iter};
+
+use itertools::Either;
+
+use crate::Ddnnf;
+
+/// A relation between two features of a feature model that can be established via clause updates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Relation {
+    /// The child is selected iff its parent is selected
+    Mandatory { child: u32, parent: u32 },
+    /// The child can only be selected together with its parent, but the parent does not need it
+    Optional { child: u32, parent: u32 },
+    /// Selecting the first feature requires the second one
+    Requires(u32, u32),
+    /// The two features can not be selected together
+    Excludes(u32, u32),
+    /// Each child requires the parent, which requires exactly one of the children
+    AlternativeGroup { parent: u32, children: Vec<u32> },
+    /// Each child requires the parent, which requires at least one of the children
+    OrGroup { parent: u32, children: Vec<u32> },
+}
+
+impl Relation {
+    /// The clauses that have to be part of the CNF and the clauses that must not be part of it
+    /// for the relation to hold
+    pub fn clauses(&self) -> (Vec<BTreeSet<i32>>, Vec<BTreeSet<i32>>) {
+        let implication = |from: u32, to: u32| BTreeSet::from([-(from as i32), to as i32]);
+        let group = |parent: u32, children: &[u32]| {
+            let mut clauses: Vec<BTreeSet<i32>> = children
+                .iter()
+                .map(|&child| implication(child, parent))
+                .collect();
+            let at_least_one = children.iter().map(|&child| child as i32);
+            clauses.push(iter::once(-(parent as i32)).chain(at_least_one).collect());
+            clauses
+        };
+        match *self {
+            Relation::Mandatory { child, parent } => (
+                vec![implication(child, parent), implication(parent, child)],
+                vec![],
+            ),
+            Relation::Optional { child, parent } => (
+                vec![implication(child, parent)],
+                vec![implication(parent, child)],
+            ),
+            Relation::Requires(a, b) => (vec![implication(a, b)], vec![]),
+            Relation::Excludes(a, b) => (vec![BTreeSet::from([-(a as i32), -(b as i32)])], vec![]),
+            Relation::AlternativeGroup {
+                parent,
+                ref children,
+            } => {
+                let mut clauses = group(parent, children);
+                for (index, &a) in children.iter().enumerate() {
+                    for &b in children[index + 1..].iter() {
+                        clauses.push(BTreeSet::from([-(a as i32), -(b as i32)]));
+                    }
+                }
+                (clauses, vec![])
+            }
+            Relation::OrGroup {
+                parent,
+                ref children,
+            } => (group(parent, children), vec![]),
+        }
+    }
+}
+
+impl Ddnnf {
+    /// Establishes the relation by adding the missing clauses and removing the contradicting ones.
+    /// The d-DNNF gets recompiled via [Ddnnf::update_cached_state], hence the d-DNNF has to stem
+    /// from a CNF. If the CNF already reflects the relation, nothing changes.
+    /// Returns false if the update is not possible, e.g. because a feature does not exist.
+    ///
+    /// Relations only describe the dependency between the two features. Other clauses, such as the
+    /// clauses of an alternative group, stay as they are.
+    pub fn establish(&mut self, relation: Relation) -> bool {
+        let clauses = match self.cached_state.as_ref() {
+            Some(state) => &state.clauses,
+            None => return false,
+        };
+        let (required, forbidden) = relation.clauses();
+        let add: Vec<BTreeSet<i32>> = required
+            .into_iter()
+            .filter(|clause| !clauses.contains(clause))
+            .collect();
+        let rmv: Vec<BTreeSet<i32>> = forbidden
+            .into_iter()
+            .filter(|clause| clauses.contains(clause))
+            .collect();
+
+        let total_features = self.number_of_variables;
+        let features_exist = add
+            .iter()
+            .flatten()
+            .all(|literal| (1..=total_features).contains(&literal.unsigned_abs()));
+        if !features_exist {
+            return false;
+        }
+        if add.is_empty() && rmv.is_empty() {
+            return true;
+        }
+        self.update_cached_state(Either::Left((add, rmv)), Some(total_features))
+    }
+
+    /// Makes the child a mandatory feature of the parent, see [Relation::Mandatory]
+    pub fn make_mandatory(&mut self, child: u32, parent: u32) -> bool {
+        self.establish(Relation::Mandatory { child, parent })
+    }
+
+    /// Makes the child an optional feature of the parent, see [Relation::Optional]
+    pub fn make_optional(&mut self, child: u32, parent: u32) -> bool {
+        self.establish(Relation::Optional { child, parent })
+    }
+
+    /// Adds the cross-tree constraint that a requires b
+    pub fn add_requires(&mut self, a: u32, b: u32) -> bool {
+        self.establish(Relation::Requires(a, b))
+    }
+
+    /// Adds the cross-tree constraint that a and b exclude each other
+    pub fn add_excludes(&mut self, a: u32, b: u32) -> bool {
+        self.establish(Relation::Excludes(a, b))
+    }
+
+    /// Makes the children an alternative group of the parent, see [Relation::AlternativeGroup].
+    /// All clauses get added with a single recompilation. Returns false for an empty group.
+    pub fn add_alternative_group(&mut self, parent: u32, children: &[u32]) -> bool {
+        !children.is_empty()
+            && self.establish(Relation::AlternativeGroup {
+                parent,
+                children: children.to_vec(),
+            })
+    }
+
+    /// Makes the children an or group of the parent, see [Relation::OrGroup].
+    /// All clauses get added with a single recompilation. Returns false for an empty group.
+    pub fn add_or_group(&mut self, parent: u32, children: &[u32]) -> bool {
+        !children.is_empty()
+            && self.establish(Relation::OrGroup {
+                parent,
+                children: children.to_vec(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::build_ddnnf;
+
+    use super::*;
+
+    #[test]
+    fn clauses_of_relations() {
+        assert_eq!(
+            (
+                vec![BTreeSet::from([-3, 1]), BTreeSet::from([-1, 3])],
+                vec![]
+            ),
+            Relation::Mandatory {
+                child: 3,
+                parent: 1
+            }
+            .clauses()
+        );
+        assert_eq!(
+            (vec![BTreeSet::from([-3, 1])], vec![BTreeSet::from([-1, 3])]),
+            Relation::Optional {
+                child: 3,
+                parent: 1
+            }
+            .clauses()
+        );
+        assert_eq!(
+            (vec![BTreeSet::from([-2, 4])], vec![]),
+            Relation::Requires(2, 4).clauses()
+        );
+        assert_eq!(
+            (vec![BTreeSet::from([-4, -2])], vec![]),
+            Relation::Excludes(2, 4).clauses()
+        );
+
+        let or_group = vec![
+            BTreeSet::from([-2, 1]),
+            BTreeSet::from([-3, 1]),
+            BTreeSet::from([-4, 1]),
+            BTreeSet::from([-1, 2, 3, 4]),
+        ];
+        assert_eq!(
+            (or_group.clone(), vec![]),
+            Relation::OrGroup {
+                parent: 1,
+                children: vec![2, 3, 4]
+            }
+            .clauses()
+        );
+        let mut alternative = or_group;
+        alternative.extend([
+            BTreeSet::from([-3, -2]),
+            BTreeSet::from([-4, -2]),
+            BTreeSet::from([-4, -3]),
+        ]);
+        assert_eq!(
+            (alternative, vec![]),
+            Relation::AlternativeGroup {
+                parent: 1,
+                children: vec![2, 3, 4]
+            }
+            .clauses()
+        );
+    }
+
+    #[test]
+    fn relations_need_clauses() {
+        // the d-DNNF does not stem from a CNF
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        assert!(!ddnnf.add_requires(1, 2));
+        assert!(!ddnnf.add_or_group(1, &[2, 3]));
+    }
+
+    #[cfg(feature = "d4")]
+    #[test]
+    fn relations_restrict_models() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9.cnf", None);
+        let expected = vp9.execute_query(&[-20]) + vp9.execute_query(&[20, 30]);
+
+        assert!(vp9.add_requires(20, 30));
+        assert_eq!(expected, vp9.rc());
+        assert_eq!(0, vp9.execute_query(&[20, -30]));
+        // the relation holds already, hence there is no recompilation
+        let compilations = vp9.compile_stats().len();
+        assert!(vp9.add_requires(20, 30));
+        assert_eq!(compilations, vp9.compile_stats().len());
+
+        assert!(vp9.add_excludes(21, 22));
+        assert_eq!(0, vp9.execute_query(&[21, 22]));
+        assert!(!vp9.add_excludes(21, 43));
+
+        assert!(vp9.make_mandatory(23, 24));
+        assert_eq!(0, vp9.execute_query(&[-23, 24]));
+        assert_eq!(0, vp9.execute_query(&[23, -24]));
+        let mandatory = vp9.rc();
+        assert!(vp9.make_optional(23, 24));
+        assert_eq!(0, vp9.execute_query(&[23, -24]));
+        assert!(vp9.rc() >= mandatory);
+
+        let compilations = vp9.compile_stats().len();
+        assert!(vp9.add_alternative_group(25, &[26, 27, 28]));
+        assert_eq!(compilations + 1, vp9.compile_stats().len());
+        assert_eq!(0, vp9.execute_query(&[25, -26, -27, -28]));
+        assert_eq!(0, vp9.execute_query(&[26, 27]));
+        assert_eq!(0, vp9.execute_query(&[-25, 28]));
+
+        assert!(vp9.add_or_group(29, &[31, 32]));
+        assert_eq!(0, vp9.execute_query(&[29, -31, -32]));
+        assert!(!vp9.add_or_group(29, &[]));
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeSet;
+
+use rug::Integer;
+
+use crate::Ddnnf;
+
+use super::clause_cache::{normalize_clause, ClauseCache};
+
+/// The effect of toggling a constraint on the number of configurations, see [Ddnnf::sensitivity]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Perturbation {
+    /// The toggled constraint as normalized clause
+    pub clause: BTreeSet<i32>,
+    /// Whether the clause got removed from the CNF. Otherwise, it got added.
+    pub removed: bool,
+    /// The number of configurations after the perturbation
+    pub count: Integer,
+    /// The change of the number of configurations caused by the perturbation
+    pub change: Integer,
+}
+
+impl Ddnnf {
+    /// Computes how the number of configurations changes if we toggle each of the constraints on its own:
+    /// Constraints that are part of the CNF get removed and all others get added. Constraints that change
+    /// the count a lot are brittle, because a small modeling error in them has a large effect.
+    ///
+    /// Adding a clause needs a single query, because the configurations that violate the clause are
+    /// the ones that contain the negation of each of its literals. Removing a clause needs the CNF and
+    /// a recompilation of the edited CNF like a clause update. Without the editing feature or without
+    /// a CNF, removals fail. The d-DNNF itself stays untouched.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::BTreeSet;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    ///
+    /// let perturbations = ddnnf
+    ///     .sensitivity(&[BTreeSet::from([-2, 4]), BTreeSet::from([1, 4])])
+    ///     .unwrap();
+    /// // requiring 4 for 2 removes one configuration and the second clause holds anyway
+    /// assert_eq!(-1, perturbations[0].change);
+    /// assert_eq!(0, perturbations[1].change);
+    /// assert!(!perturbations[0].removed);
+    /// ```
+    pub fn sensitivity(&self, constraints: &[BTreeSet<i32>]) -> Result<Vec<Perturbation>, String> {
+        let total = self.rc();
+        let mut perturbations = Vec::with_capacity(constraints.len());
+        for constraint in constraints {
+            let literals: Vec<i32> = constraint.iter().copied().collect();
+            let clause = match normalize_clause(&literals, self.number_of_variables)? {
+                Some(clause) => clause,
+                // a tautology does not change anything
+                None => constraint.clone(),
+            };
+
+            let state = self.cached_state.as_ref();
+            let removed = state.is_some_and(|state| state.clauses.contains(&clause));
+            let count = if removed {
+                let state = state.unwrap();
+                let mut edit = ClauseCache::default();
+                edit.initialize(state.clauses.clone(), self.number_of_variables);
+                edit.set_settings(state.settings().clone());
+                if !edit.apply_edits_and_replace(
+                    Vec::new(),
+                    vec![clause.clone()],
+                    self.number_of_variables,
+                ) {
+                    return Err(format!("could not recompile the CNF without {clause:?}"));
+                }
+                edit.old_state.map(|ddnnf| ddnnf.rc()).unwrap()
+            } else if clause.iter().any(|literal| clause.contains(&-literal)) {
+                total.clone()
+            } else {
+                let violation: Vec<i32> = clause.iter().map(|literal| -literal).collect();
+                &total - self.count_adaptive(&violation)
+            };
+
+            perturbations.push(Perturbation {
+                change: Integer::from(&count - &total),
+                clause,
+                removed,
+                count,
+            });
+        }
+        Ok(perturbations)
+    }
+
+    /// Computes the sensitivity (see [Ddnnf::sensitivity]) of each clause that got added by clause updates,
+    /// i.e. how many configurations we regain by dropping the clause again.
+    pub fn sensitivity_of_added_clauses(&self) -> Result<Vec<Perturbation>, String> {
+        let added: Vec<BTreeSet<i32>> = match self.cached_state.as_ref() {
+            Some(state) => state.added_clauses().cloned().collect(),
+            None => Vec::new(),
+        };
+        self.sensitivity(&added)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "d4")]
+    use itertools::Either;
+
+    #[cfg(feature = "d4")]
+    use crate::ddnnf::counting::brute_force::satisfies;
+    use crate::parser::build_ddnnf;
+    #[cfg(feature = "d4")]
+    use crate::parser::from_cnf::read_cnf;
+
+    use super::*;
+
+    #[test]
+    fn sensitivity_of_added_constraints() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let constraints: Vec<BTreeSet<i32>> = (2..=42)
+            .step_by(3)
+            .map(|f| BTreeSet::from([-f, (f % 42) + 1]))
+            .chain([
+                BTreeSet::from([1]),
+                BTreeSet::from([5, -5]),
+                BTreeSet::from([-1, -3]),
+            ])
+            .collect();
+        let perturbations = vp9.sensitivity(&constraints).unwrap();
+        assert_eq!(constraints.len(), perturbations.len());
+
+        for perturbation in perturbations.iter() {
+            assert!(!perturbation.removed);
+            let clause = &perturbation.clause;
+            // adding the clause is the same as conditioning on each of its satisfying partial assignments
+            let violation: Vec<i32> = clause.iter().map(|l| -l).collect();
+            let expected = if clause.len() == 2 && clause.iter().any(|l| clause.contains(&-l)) {
+                vp9.rc()
+            } else {
+                vp9.rc() - vp9.execute_query(&violation)
+            };
+            assert_eq!(expected, perturbation.count);
+            assert_eq!(&expected - vp9.rc(), perturbation.change);
+            assert!(perturbation.change <= 0);
+        }
+        // 1 is core, hence requiring it changes nothing
+        assert_eq!(0, perturbations[perturbations.len() - 3].change);
+        assert_eq!(0, perturbations[perturbations.len() - 2].change);
+
+        assert!(vp9.sensitivity(&[BTreeSet::from([43])]).is_err());
+        assert!(vp9.sensitivity_of_added_clauses().unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "d4")]
+    fn sensitivity_of_removed_constraints() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        let (variables, clauses) = read_cnf("tests/data/small_ex.cnf").unwrap();
+        assert!(ddnnf.update_cached_state(Either::Right(clauses.clone()), Some(variables)));
+
+        let constraints: Vec<BTreeSet<i32>> = clauses.iter().cloned().collect();
+        let perturbations = ddnnf.sensitivity(&constraints).unwrap();
+        for perturbation in perturbations {
+            assert!(perturbation.removed);
+            let expected = (0..16_u64)
+                .filter(|&assignment| {
+                    clauses
+                        .iter()
+                        .filter(|&clause| *clause != perturbation.clause)
+                        .all(|clause| clause.iter().any(|&l| satisfies(assignment, &[l])))
+                })
+                .count();
+            assert_eq!(expected, perturbation.count);
+            assert_eq!(&perturbation.count - ddnnf.rc(), perturbation.change);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use std::{collections::BTreeSet, io};
+
+use itertools::Itertools;
+
+use crate::{
+    ddnnf::{explanation::VoidExplanation, relations::Relation},
+    parser::{from_cnf::read_feature_names, util::json_string},
+    Ddnnf,
+};
+
+impl Ddnnf {
+    /// Loads the names of the features from the comments of a CNF file (see [read_feature_names]).
+    /// Afterwards, the sentences of this module refer to the features by their names.
+    pub fn load_feature_names(&mut self, path: &str) -> io::Result<()> {
+        self.feature_names = read_feature_names(path)?;
+        Ok(())
+    }
+
+    /// The name of the feature or its number if it has no name
+    pub fn feature_name(&self, feature: u32) -> String {
+        self.feature_names
+            .get(&feature)
+            .cloned()
+            .unwrap_or_else(|| feature.to_string())
+    }
+
+    /// Describes why the partial configuration of the explanation is void (see [Ddnnf::explain_void]),
+    /// e.g. "Selecting A and B is impossible because A excludes B."
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// ddnnf.feature_names.insert(2, String::from("Wifi"));
+    /// ddnnf.feature_names.insert(3, String::from("Cable"));
+    ///
+    /// let explanation = ddnnf.explain_void(&[4, 2, 3], false).unwrap();
+    /// assert_eq!(
+    ///     "Selecting Wifi and Cable is impossible.",
+    ///     ddnnf.describe_void(&explanation)
+    /// );
+    /// ```
+    pub fn describe_void(&self, explanation: &VoidExplanation) -> String {
+        let mut sentence = if explanation.assumptions.is_empty() {
+            String::from("The model has no valid configuration")
+        } else {
+            format!("{} is impossible", self.choices(&explanation.assumptions))
+        };
+        if !explanation.clauses.is_empty() {
+            let reasons: Vec<String> = explanation
+                .clauses
+                .iter()
+                .map(|clause| self.describe_clause(clause))
+                .collect();
+            sentence.push_str(" because ");
+            sentence.push_str(&enumeration(&reasons, "and"));
+        }
+        sentence.push('.');
+        sentence
+    }
+
+    /// Describes the decisions that the partial configuration forces (see [Ddnnf::propagate]),
+    /// e.g. "Selecting A forces B and excludes C." Core and dead features are only mentioned
+    /// for the empty partial configuration, because each configuration decides them anyway.
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let mut ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// ddnnf.feature_names.insert(1, String::from("Router"));
+    /// ddnnf.feature_names.insert(2, String::from("Wifi"));
+    /// ddnnf.feature_names.insert(3, String::from("Cable"));
+    ///
+    /// assert_eq!("Selecting Wifi excludes Cable.", ddnnf.describe_propagation(&[2]));
+    /// assert_eq!("The model forces Router.", ddnnf.describe_propagation(&[]));
+    /// ```
+    pub fn describe_propagation(&self, partial_config: &[i32]) -> String {
+        let subject = if partial_config.is_empty() {
+            String::from("The model")
+        } else {
+            self.choices(partial_config)
+        };
+        let mut implied = match self.propagate(partial_config) {
+            Some(implied) => implied,
+            None => return format!("{subject} is impossible."),
+        };
+        if !partial_config.is_empty() {
+            // the core and dead features are no consequence of the partial configuration
+            let decided = self.propagate(&[]).unwrap_or_default();
+            implied.retain(|literal| !decided.contains(literal));
+        }
+
+        let names = |positive: bool| -> Vec<String> {
+            implied
+                .iter()
+                .filter(|literal| literal.is_positive() == positive)
+                .map(|literal| self.feature_name(literal.unsigned_abs()))
+                .collect()
+        };
+        let (forced, excluded) = (names(true), names(false));
+        let mut consequences = Vec::new();
+        if !forced.is_empty() {
+            consequences.push(format!("forces {}", enumeration(&forced, "and")));
+        }
+        if !excluded.is_empty() {
+            consequences.push(format!("excludes {}", enumeration(&excluded, "and")));
+        }
+        if consequences.is_empty() {
+            return format!("{subject} has no further consequences.");
+        }
+        format!("{subject} {}.", consequences.join(" and "))
+    }
+
+    /// Describes a relation between features (see [Relation]), e.g. "A requires B".
+    pub fn describe_relation(&self, relation: &Relation) -> String {
+        let name = |feature: u32| self.feature_name(feature);
+        let names =
+            |features: &[u32]| -> Vec<String> { features.iter().map(|&f| name(f)).collect() };
+        match *relation {
+            Relation::Mandatory { child, parent } => {
+                format!("{} is a mandatory child of {}", name(child), name(parent))
+            }
+            Relation::Optional { child, parent } => {
+                format!("{} is an optional child of {}", name(child), name(parent))
+            }
+            Relation::Requires(a, b) => format!("{} requires {}", name(a), name(b)),
+            Relation::Excludes(a, b) => format!("{} excludes {}", name(a), name(b)),
+            Relation::AlternativeGroup {
+                parent,
+                ref children,
+            } => format!(
+                "{} requires exactly one of {}",
+                name(parent),
+                enumeration(&names(children), "or")
+            ),
+            Relation::OrGroup {
+                parent,
+                ref children,
+            } => format!(
+                "{} requires at least one of {}",
+                name(parent),
+                enumeration(&names(children), "or")
+            ),
+        }
+    }
+
+    /// Describes the constraint that a clause of the CNF expresses, e.g. "A requires B" for ¬A ∨ B
+    pub fn describe_clause(&self, clause: &BTreeSet<i32>) -> String {
+        let names = |positive: bool| -> Vec<String> {
+            clause
+                .iter()
+                .filter(|literal| literal.is_positive() == positive)
+                .map(|literal| literal.unsigned_abs())
+                .sorted()
+                .map(|feature| self.feature_name(feature))
+                .collect()
+        };
+        let (premises, conclusions) = (names(false), names(true));
+        match (premises.len(), conclusions.len()) {
+            (0, 0) => String::from("nothing is possible"),
+            (0, 1) => format!("{} is mandatory", conclusions[0]),
+            (0, _) => format!(
+                "at least one of {} has to be selected",
+                enumeration(&conclusions, "or")
+            ),
+            (1, 0) => format!("{} is forbidden", premises[0]),
+            (2, 0) => format!("{} excludes {}", premises[0], premises[1]),
+            (_, 0) => format!(
+                "{} can not be selected together",
+                enumeration(&premises, "and")
+            ),
+            (1, _) => format!(
+                "{} requires {}",
+                premises[0],
+                enumeration(&conclusions, "or")
+            ),
+            (_, _) => format!(
+                "{} together require {}",
+                enumeration(&premises, "and"),
+                enumeration(&conclusions, "or")
+            ),
+        }
+    }
+
+    /// Serializes the explanation together with its sentence (see [Ddnnf::describe_void]) as JSON object
+    pub fn void_explanation_json(&self, explanation: &VoidExplanation) -> String {
+        let clauses: Vec<String> = explanation
+            .clauses
+            .iter()
+            .map(|clause| json_list(clause.iter()))
+            .collect();
+        format!(
+            "{{\"assumptions\":{},\"clauses\":[{}],\"sentence\":{}}}",
+            json_list(explanation.assumptions.iter()),
+            clauses.join(","),
+            json_string(&self.describe_void(explanation))
+        )
+    }
+
+    /// Serializes the propagated decisions together with their sentence (see [Ddnnf::describe_propagation])
+    /// as JSON object. The implied literals are null if the partial configuration is unsatisfiable.
+    pub fn propagation_json(&self, partial_config: &[i32]) -> String {
+        let implied = match self.propagate(partial_config) {
+            Some(implied) => json_list(implied.iter()),
+            None => String::from("null"),
+        };
+        format!(
+            "{{\"assumptions\":{},\"implied\":{},\"sentence\":{}}}",
+            json_list(partial_config.iter()),
+            implied,
+            json_string(&self.describe_propagation(partial_config))
+        )
+    }
+
+    // Describes the selections and deselections of the literals, e.g. "Selecting A and deselecting B"
+    fn choices(&self, literals: &[i32]) -> String {
+        let names = |positive: bool| -> Vec<String> {
+            literals
+                .iter()
+                .filter(|literal| literal.is_positive() == positive)
+                .map(|literal| self.feature_name(literal.unsigned_abs()))
+                .collect()
+        };
+        let (selected, deselected) = (names(true), names(false));
+        match (selected.is_empty(), deselected.is_empty()) {
+            (false, true) => format!("Selecting {}", enumeration(&selected, "and")),
+            (true, false) => format!("Deselecting {}", enumeration(&deselected, "and")),
+            _ => format!(
+                "Selecting {} and deselecting {}",
+                enumeration(&selected, "and"),
+                enumeration(&deselected, "and")
+            ),
+        }
+    }
+}
+
+// Joins the words to an enumeration like "A, B, and C"
+fn enumeration(words: &[String], conjunction: &str) -> String {
+    match words {
+        [] => String::new(),
+        [word] => word.clone(),
+        [first, second] => format!("{first} {conjunction} {second}"),
+        [init @ .., last] => format!("{}, {conjunction} {last}", init.join(", ")),
+    }
+}
+
+fn json_list<'a>(literals: impl Iterator<Item = &'a i32>) -> String {
+    let literals: Vec<String> = literals.map(i32::to_string).collect();
+    format!("[{}]", literals.join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Either;
+
+    use crate::parser::{build_ddnnf, from_cnf::read_cnf};
+
+    use super::*;
+
+    #[test]
+    fn sentences_with_feature_names() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        vp9.load_feature_names("tests/data/VP9.cnf").unwrap();
+        assert_eq!("lagInFrames_16", vp9.feature_name(3));
+
+        assert_eq!(
+            "Selecting lagInFrames_16 and lagInFrames_0 is impossible.",
+            vp9.describe_void(&vp9.explain_void(&[3, 5], false).unwrap())
+        );
+        assert_eq!(
+            "Deselecting root is impossible.",
+            vp9.describe_propagation(&[-1])
+        );
+        assert_eq!(
+            "Selecting lagInFrames_16 excludes lagInFrames_8 and lagInFrames_0.",
+            vp9.describe_propagation(&[3])
+        );
+
+        // the void explanation with clauses names the violated constraints
+        let (variables, clauses) = read_cnf("tests/data/VP9.cnf").unwrap();
+        assert!(vp9.update_cached_state(Either::Right(clauses), Some(variables)));
+        let explanation = vp9.explain_void(&[3, 5], true).unwrap();
+        let sentence = vp9.describe_void(&explanation);
+        assert!(sentence.contains(" is impossible because "));
+        assert!(explanation
+            .clauses
+            .iter()
+            .all(|clause| sentence.contains(&vp9.describe_clause(clause))));
+    }
+
+    #[test]
+    fn sentences_of_clauses_and_relations() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        for (feature, name) in [(1, "A"), (2, "B"), (3, "C")] {
+            ddnnf.feature_names.insert(feature, String::from(name));
+        }
+
+        let clause = |literals: &[i32]| ddnnf.describe_clause(&literals.iter().copied().collect());
+        assert_eq!("A is mandatory", clause(&[1]));
+        assert_eq!("4 is forbidden", clause(&[-4]));
+        assert_eq!("A requires B", clause(&[-1, 2]));
+        assert_eq!("A excludes B", clause(&[-1, -2]));
+        assert_eq!("A requires B, C, or 4", clause(&[-1, 2, 3, 4]));
+        assert_eq!("A and B together require C", clause(&[-1, -2, 3]));
+        assert_eq!("at least one of B or C has to be selected", clause(&[2, 3]));
+        assert_eq!(
+            "A, B, and C can not be selected together",
+            clause(&[-1, -2, -3])
+        );
+
+        assert_eq!(
+            "A requires exactly one of B or C",
+            ddnnf.describe_relation(&Relation::AlternativeGroup {
+                parent: 1,
+                children: vec![2, 3]
+            })
+        );
+        assert_eq!(
+            "B excludes C",
+            ddnnf.describe_relation(&Relation::Excludes(2, 3))
+        );
+        assert_eq!(
+            "Selecting A and 4 has no further consequences.",
+            ddnnf.describe_propagation(&[1, 4])
+        );
+    }
+
+    #[test]
+    fn json_of_explanations() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        ddnnf.feature_names.insert(2, String::from("\"B\""));
+
+        assert_eq!(
+            "{\"assumptions\":[2],\"implied\":[1,-3],\"sentence\":\"Selecting \\\"B\\\" excludes 3.\"}",
+            ddnnf.propagation_json(&[2])
+        );
+        assert_eq!(
+            "{\"assumptions\":[2,3],\"implied\":null,\"sentence\":\"Selecting \\\"B\\\" and 3 is impossible.\"}",
+            ddnnf.propagation_json(&[2, 3])
+        );
+
+        let explanation = ddnnf.explain_void(&[-1, 4], false).unwrap();
+        assert_eq!(
+            "{\"assumptions\":[-1],\"clauses\":[],\"sentence\":\"Deselecting 1 is impossible.\"}",
+            ddnnf.void_explanation_json(&explanation)
+        );
+    }
+}
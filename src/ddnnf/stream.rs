@@ -9,6 +9,9 @@ use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::sync::Arc;
 use std::{io, thread};
 
+use crate::ddnnf::anomalies::config_creation::EnumOptions;
+use crate::ddnnf::counting::bounded::{BoundedCount, CountMode};
+use crate::ddnnf::quantified::Quantified;
 use itertools::{Either, Itertools};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
@@ -21,6 +24,10 @@ use workctl::WorkQueue;
 use crate::parser::persisting::{write_cnf_to_file, write_ddnnf_to_file};
 use crate::{parser::util::*, Ddnnf};
 
+/// The operations of the stream protocol that change the d-DNNF. All other operations only query it,
+/// enumerations merely advance their cursors (see [EnumOptions::start]).
+pub const EDITING_OPERATIONS: [&str; 2] = ["clause-update", "undo-update"];
+
 impl Ddnnf {
     /// Initiate the Stream mode. This enables a commincation channel between stdin and stdout.
     /// Queries from stdin will be computed using max_worker many threads und results will be written
@@ -171,6 +178,8 @@ impl Ddnnf {
     /// E4 Syntax error
     /// E5 Operation was not able to be done, because of wrong input
     /// E6 File or path error
+    // the seed is only used for sampling
+    #[cfg_attr(not(feature = "sampling"), allow(unused_variables, unused_assignments))]
     pub fn handle_stream_msg(&mut self, msg: &str) -> String {
         let mut args: Vec<&str> = msg.split_whitespace().collect();
         if args.is_empty() {
@@ -186,6 +195,7 @@ impl Ddnnf {
         let mut values = Vec::new();
         let mut seed = 42;
         let mut limit = None;
+        let mut mode = CountMode::Exact;
         let mut add_clauses: Vec<BTreeSet<i32>> = Vec::new();
         let mut rmv_clauses: Vec<BTreeSet<i32>> = Vec::new();
         let mut total_features = self.number_of_variables;
@@ -251,7 +261,7 @@ impl Ddnnf {
                         Err(e) => return e,
                     };
                 }
-                "seed" | "s" | "limit" | "l" | "path" | "p" => {
+                "seed" | "s" | "limit" | "l" | "mode" | "m" | "path" | "p" => {
                     if param_index < args.len() {
                         match args[param_index - 1] {
                             "seed" | "s" => {
@@ -268,6 +278,13 @@ impl Ddnnf {
                                 };
                                 param_index += 1;
                             }
+                            "mode" | "m" => {
+                                mode = match args[param_index].parse::<CountMode>() {
+                                    Ok(x) => x,
+                                    Err(e) => return format!("E3 error: {}", e),
+                                };
+                                param_index += 1;
+                            }
                             _ => {
                                 // has to be path because of the outer patter match
                                 // we use a wildcard to satisfy the rust compiler
@@ -360,18 +377,43 @@ impl Ddnnf {
                 &mut params,
                 &values,
             ),
-            "count" => op_with_assumptions_and_vars(
-                |d, x, _| Some(Ddnnf::execute_query(d, x)),
-                self,
-                &mut params,
-                &values,
-            ),
+            "count" => {
+                let operation: fn(&mut Ddnnf, &mut Vec<i32>, bool) -> Option<BoundedCount> =
+                    match mode {
+                        CountMode::Exact => |d, x, _| Some(d.count_with_mode(x, CountMode::Exact)),
+                        CountMode::Bounded => {
+                            |d, x, _| Some(d.count_with_mode(x, CountMode::Bounded))
+                        }
+                        CountMode::Log10 => |d, x, _| Some(d.count_with_mode(x, CountMode::Log10)),
+                    };
+                op_with_assumptions_and_vars(operation, self, &mut params, &values)
+            }
             "sat" => op_with_assumptions_and_vars(
                 |d, x, _| Some(Ddnnf::sat(d, x)),
                 self,
                 &mut params,
                 &values,
             ),
+            "forall-exists" => {
+                if values.iter().any(|&f| f.is_negative()) {
+                    return String::from("E5 error: the group must consist of positive features");
+                }
+                let group = values.iter().map(|&f| f as u32).collect_vec();
+                match self.forall_exists(&group, &params) {
+                    Ok(Quantified::Holds) => String::from("true"),
+                    Ok(Quantified::Counterexample(choice)) => {
+                        format!("false {}", format_vec(choice.iter()))
+                            .trim_end()
+                            .to_string()
+                    }
+                    Err(err) => format!("E5 error: {err}"),
+                }
+            }
+            "explain" => match self.explain_void(&params, true) {
+                Some(explanation) => self.void_explanation_json(&explanation),
+                None => String::from("null"),
+            },
+            "propagate" => self.propagation_json(&params),
             "enum" => {
                 let limit_interpretation = match limit {
                     Some(limit) => limit,
@@ -383,15 +425,26 @@ impl Ddnnf {
                         }
                     }
                 };
-                let configs = self.enumerate(&mut params, limit_interpretation);
+                let options = EnumOptions {
+                    variable_order: self.variable_order.clone(),
+                    ..EnumOptions::with_limit(limit_interpretation)
+                };
+                let configs = self.enumerate(&params, options);
                 match configs {
-                    Some(s) => format_vec_vec(s.iter()),
+                    Some(s) => format_vec_vec(s.collect_vec().iter()),
                     None => String::from("E5 error: with the assumptions, the ddnnf is not satisfiable. Hence, there exist no valid sample configurations"),
                 }
             }
+            #[cfg(feature = "sampling")]
             "random" => {
                 let limit_interpretation = limit.unwrap_or(1);
-                let samples = self.uniform_random_sampling(&params, limit_interpretation, seed);
+                let variable_order = self.variable_order.clone();
+                let samples = self.uniform_random_sampling_ordered(
+                    &params,
+                    limit_interpretation,
+                    seed,
+                    &variable_order,
+                );
                 match samples {
                     Some(s) => format_vec_vec(s.iter()),
                     None => String::from("E5 error: with the assumptions, the ddnnf is not satisfiable. Hence, there exist no valid sample configurations"),
@@ -409,9 +462,11 @@ impl Ddnnf {
                 let cross = args[0] == "atomic-cross";
                 format_vec_vec(self.get_atomic_sets(candidates, &params, cross).iter())
             }
+            #[cfg(feature = "sampling")]
             "t-wise" => {
                 let limit_interpretation = limit.unwrap_or(1);
-                self.sample_t_wise(limit_interpretation).to_string()
+                self.sample_t_wise_with_assumptions(limit_interpretation, &params)
+                    .to_string()
             }
             "clause-update" => {
                 if self.can_save_state() {
@@ -659,6 +714,7 @@ mod test {
     use serial_test::serial;
 
     use super::*;
+    use crate::ddnnf::anomalies::variable_order::VariableOrder;
     use crate::parser::build_ddnnf;
 
     #[test]
@@ -727,6 +783,21 @@ mod test {
         );
 
         assert_eq!(auto1.rc().to_string(), auto1.handle_stream_msg("count"));
+        assert_eq!(
+            auto1.rc().to_string(),
+            auto1.handle_stream_msg("count mode exact")
+        );
+        assert_eq!(
+            format!("10^{:.6}", auto1.log10_count(&[-1])),
+            auto1.handle_stream_msg("count m log10 a -1")
+        );
+        assert_eq!(
+            "10^-inf;10^-inf",
+            auto1.handle_stream_msg("count m log10 a -1469 -1114 939 1551 v 1 1529")
+        );
+        assert!(auto1
+            .handle_stream_msg("count mode float")
+            .starts_with("E3 error"));
         assert_eq!(
             auto1.handle_stream_msg("count v 123 -1111"),
             vec![
@@ -760,6 +831,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn handle_stream_msg_forall_exists() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+
+        assert_eq!("true", ddnnf.handle_stream_msg("forall-exists v 2 3 a 4"));
+        assert_eq!(
+            "false 2 4",
+            ddnnf.handle_stream_msg("forall-exists v 2 4 a 3")
+        );
+        assert_eq!("false", ddnnf.handle_stream_msg("forall-exists a -1"));
+        assert!(ddnnf
+            .handle_stream_msg("forall-exists v -2 a 3")
+            .starts_with("E5"));
+        assert!(ddnnf
+            .handle_stream_msg("forall-exists v 5 a 3")
+            .starts_with("E5"));
+    }
+
+    #[test]
+    fn handle_stream_msg_explain_and_propagate() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        ddnnf.feature_names.insert(2, String::from("Wifi"));
+
+        assert_eq!(
+            "{\"assumptions\":[2,3],\"clauses\":[],\"sentence\":\"Selecting Wifi and 3 is impossible.\"}",
+            ddnnf.handle_stream_msg("explain a 4 2 3")
+        );
+        assert_eq!("null", ddnnf.handle_stream_msg("explain a 2 4"));
+        assert_eq!(
+            "{\"assumptions\":[2],\"implied\":[1,-3],\"sentence\":\"Selecting Wifi excludes 3.\"}",
+            ddnnf.handle_stream_msg("propagate a 2")
+        );
+        assert!(ddnnf.handle_stream_msg("propagate a 5").starts_with("E3"));
+    }
+
     #[test]
     fn handle_stream_msg_enum() {
         let mut _auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
@@ -821,6 +927,33 @@ mod test {
         );
     }
 
+    #[test]
+    fn handle_stream_msg_enum_with_variable_order() {
+        let mut ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        ddnnf.variable_order = VariableOrder::custom(vec![4, 2]).unwrap();
+
+        assert_eq!("4 2 1 -3", ddnnf.handle_stream_msg("enum a 2 4 l 1"));
+        // conditioned copies keep the order
+        let mut conditioned = ddnnf.condition(&[3]);
+        assert_eq!("-4 -2 1 3", conditioned.handle_stream_msg("enum a -4 l 1"));
+    }
+
+    #[cfg(feature = "sampling")]
+    #[test]
+    fn handle_stream_msg_t_wise() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+
+        // the complete configurations follow each other
+        let sample = vp9.handle_stream_msg("t-wise l 2 a 4 -9");
+        let literals: Vec<i32> = sample.split(' ').map(|l| l.parse().unwrap()).collect();
+        assert_eq!(0, literals.len() % 42);
+        for config in literals.chunks(42) {
+            assert!(config.contains(&4) && config.contains(&-9));
+        }
+        assert_eq!(String::new(), vp9.handle_stream_msg("t-wise a 4 -4"));
+    }
+
+    #[cfg(feature = "sampling")]
     #[test]
     fn handle_stream_msg_random() {
         let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
@@ -1048,6 +1181,40 @@ mod test {
         }
     }
 
+    #[test]
+    fn handle_stream_msg_queries_keep_the_model() {
+        let mut vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let ids = vp9.node_ids();
+        let core = vp9.core.clone();
+
+        let dir = tempfile::tempdir().unwrap();
+        let save_ddnnf = format!("save-ddnnf p {}", dir.path().join("vp9.nnf").display());
+        let save_cnf = format!("save-cnf p {}", dir.path().join("vp9.cnf").display());
+        let queries = vec![
+            "count a 1 v 2 3",
+            "core a 4",
+            "sat a -1",
+            "forall-exists v 2 3 a 4",
+            "explain a 3 5",
+            "propagate a 3",
+            "enum a 1 l 3",
+            "atomic v 1 2 3",
+            "atomic-cross",
+            &save_ddnnf,
+            &save_cnf,
+            "exit",
+        ];
+        #[cfg(feature = "sampling")]
+        let queries = [queries, vec!["random l 3", "t-wise l 2"]].concat();
+        for query in queries {
+            assert!(!EDITING_OPERATIONS.contains(&query.split(' ').next().unwrap()));
+            vp9.handle_stream_msg(query);
+            assert_eq!(ids, vp9.node_ids(), "{query} changed the d-DNNF");
+            assert_eq!(core, vp9.core, "{query} changed the core");
+            assert_eq!(216000, vp9.rc(), "{query} changed the count");
+        }
+    }
+
     #[test]
     fn handle_stream_msg_other() {
         let mut auto1: Ddnnf = build_ddnnf("tests/data/auto1_d4.nnf", Some(2513));
@@ -0,0 +1,369 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashSet};
+
+use itertools::Itertools;
+use rug::Integer;
+
+use super::{
+    minimization::Builder,
+    node::{NodeType, NodeType::*},
+};
+use crate::Ddnnf;
+
+impl Ddnnf {
+    /// Synthesizes a small set of clauses whose addition to this d-DNNF yields a model that is equivalent
+    /// to the target, e.g. to replay the evolution between two versions of a feature model with clause
+    /// updates if only the versions themselves are available. The target has to be a restriction of
+    /// this d-DNNF over the same variables, i.e. each of its configurations has to be valid in this d-DNNF.
+    ///
+    /// The candidates are the clauses with at most max_size literals that the target entails but this
+    /// d-DNNF does not, together with the clauses of the CNF of the target if it stems from one. A d-DNNF
+    /// entails a clause iff no configuration violates it, which costs a single query. Clauses whose
+    /// subclauses the target entails already are skipped, because the subclause is the better choice.
+    /// Then, we greedily pick the candidates that exclude the most configurations first and keep each
+    /// one that excludes configurations that the chosen ones do not. For that, we conjoin the chosen
+    /// clauses with this d-DNNF, which keeps it a d-DNNF, and query the conjunction. Once the count
+    /// matches the target, we drop the chosen clauses that the others imply.
+    ///
+    /// There are about (2 * #variables)^max_size candidates, hence small sizes suit bigger models.
+    /// Returns an error if the variables differ, if the candidates do not suffice, i.e. the difference
+    /// needs longer clauses, or if the target is no restriction of this d-DNNF. The latter is checked
+    /// explicitly by comparing the conjunction with the target via [Ddnnf::equivalent].
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ddnnf_lib;
+    /// use std::collections::BTreeSet;
+    /// use ddnnf_lib::Ddnnf;
+    /// use ddnnf_lib::parser::*;
+    ///
+    /// // the model is 1 ∧ (2 ⊕ 3) and 4 is optional
+    /// let ddnnf: Ddnnf = build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    /// // the next version requires 4 and excludes 2
+    /// let next = ddnnf.condition(&[-2, 4]);
+    ///
+    /// let clauses = ddnnf.synthesize_clauses(&next, 2).unwrap();
+    /// assert_eq!(vec![BTreeSet::from([-2]), BTreeSet::from([4])], clauses);
+    /// assert!(next.synthesize_clauses(&ddnnf, 2).is_err());
+    /// ```
+    pub fn synthesize_clauses(
+        &self,
+        target: &Ddnnf,
+        max_size: usize,
+    ) -> Result<Vec<BTreeSet<i32>>, String> {
+        if self.number_of_variables != target.number_of_variables {
+            return Err(format!(
+                "the d-DNNF has {} variables, but the target has {}",
+                self.number_of_variables, target.number_of_variables
+            ));
+        }
+        let expected = target.rc();
+        if expected > self.rc() {
+            return Err(format!(
+                "the target has {expected} configurations, but the d-DNNF only has {}",
+                self.rc()
+            ));
+        }
+        for literal in self.propagate(&[]).unwrap_or_default() {
+            if target.count_adaptive(&[-literal]) > 0 {
+                return Err(format!(
+                    "the target allows {}, which the d-DNNF excludes",
+                    -literal
+                ));
+            }
+        }
+
+        let mut candidates = self.clause_candidates(target, max_size);
+        // the most effective clauses first, ties are broken by the shorter clause and then by the features
+        candidates.sort_by_cached_key(|(clause, excluded)| {
+            let features = clause
+                .iter()
+                .map(|literal| (literal.unsigned_abs(), literal.is_negative()))
+                .sorted()
+                .collect_vec();
+            (Reverse(excluded.clone()), clause.len(), features)
+        });
+
+        let mut restricted = self.conditioned(&[]);
+        let mut chosen: Vec<BTreeSet<i32>> = Vec::new();
+        for (clause, _) in candidates {
+            if restricted.rc() == expected {
+                break;
+            }
+            let violation: Vec<i32> = clause.iter().map(|literal| -literal).collect();
+            if restricted.count_adaptive(&violation) > 0 {
+                restricted = restricted.conjoined(&clause);
+                chosen.push(clause);
+            }
+        }
+        let count = restricted.rc();
+        if count < expected {
+            return Err(String::from(
+                "the clauses exclude configurations of the target, hence it is no restriction of the d-DNNF",
+            ));
+        }
+        if count > expected {
+            return Err(format!(
+                "{} configurations remain that the target excludes; they need clauses with more than {max_size} literals",
+                count - expected
+            ));
+        }
+        // each chosen clause holds in the target, hence the counts only match without equivalence
+        // if the target has configurations that this d-DNNF excludes
+        if !restricted.equivalent(target) {
+            return Err(String::from(
+                "the target allows configurations that the d-DNNF excludes, hence it is no restriction of the d-DNNF",
+            ));
+        }
+
+        // a clause is redundant if the others already exclude each configuration that violates it
+        for index in (0..chosen.len()).rev() {
+            let clause = chosen.remove(index);
+            let violation: Vec<i32> = clause.iter().map(|literal| -literal).collect();
+            if self.restricted(&chosen).count_adaptive(&violation) > 0 {
+                chosen.insert(index, clause);
+            }
+        }
+        Ok(chosen)
+    }
+
+    // Collects the clauses that the target entails but this d-DNNF does not, together with the number of
+    // configurations of this d-DNNF that violate them
+    fn clause_candidates(&self, target: &Ddnnf, max_size: usize) -> Vec<(BTreeSet<i32>, Integer)> {
+        let mut candidates: Vec<(BTreeSet<i32>, Integer)> = Vec::new();
+        let mut add_candidate = |clause: BTreeSet<i32>| {
+            let violation: Vec<i32> = clause.iter().map(|literal| -literal).collect();
+            let excluded = self.count_adaptive(&violation);
+            if excluded > 0 {
+                candidates.push((clause, excluded));
+            }
+        };
+
+        let mut entailed: HashSet<Vec<i32>> = HashSet::new();
+        let variables = 1..=self.number_of_variables as i32;
+        for size in 1..=max_size.min(self.number_of_variables as usize) {
+            for chosen in variables.clone().combinations(size) {
+                for signs in 0..1_usize << size {
+                    let clause: Vec<i32> = chosen
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &variable)| {
+                            if signs & (1 << i) == 0 {
+                                variable
+                            } else {
+                                -variable
+                            }
+                        })
+                        .sorted()
+                        .collect();
+                    let subsumed = size > 1
+                        && (0..size).any(|skipped| {
+                            let mut subclause = clause.clone();
+                            subclause.remove(skipped);
+                            entailed.contains(&subclause)
+                        });
+                    if subsumed {
+                        continue;
+                    }
+
+                    let violation: Vec<i32> = clause.iter().map(|literal| -literal).collect();
+                    if target.count_adaptive(&violation) == 0 {
+                        add_candidate(clause.iter().copied().collect());
+                        entailed.insert(clause);
+                    }
+                }
+            }
+        }
+
+        // the clauses of the target entail it, hence they complete the candidates
+        if let Some(state) = target.cached_state.as_ref() {
+            for clause in state.clauses.iter() {
+                let sorted: Vec<i32> = clause.iter().copied().sorted().collect();
+                if !entailed.contains(&sorted) {
+                    add_candidate(clause.clone());
+                }
+            }
+        }
+        candidates
+    }
+
+    // Conjoins the d-DNNF with the clause. Each configuration that satisfies the clause contains a first
+    // literal of it. Hence, the copies of the d-DNNF that are conditioned on ¬l_1, ..., ¬l_{i-1}, l_i
+    // are disjoint and form a deterministic Or node. The copies share the subgraphs that do not depend
+    // on the literals of the clause, which keeps the conjunction small for short clauses.
+    fn conjoined(&self, clause: &BTreeSet<i32>) -> Ddnnf {
+        let mut builder = Builder::default();
+        let mut roots: Vec<usize> = Vec::new();
+        let mut assumptions: Vec<i32> = Vec::new();
+        for &literal in clause {
+            assumptions.push(literal);
+            let copy = self.conditioned(&assumptions);
+            assumptions.pop();
+            assumptions.push(-literal);
+            if copy.rc() == 0 {
+                continue;
+            }
+
+            let mut new_index: Vec<usize> = Vec::with_capacity(copy.nodes.len());
+            for node in copy.nodes.iter() {
+                let ntype = match &node.ntype {
+                    And { children } => And {
+                        children: children.iter().map(|&c| new_index[c]).collect(),
+                    },
+                    Or { children } => Or {
+                        children: children.iter().map(|&c| new_index[c]).collect(),
+                    },
+                    ntype => ntype.clone(),
+                };
+                new_index.push(builder.push(ntype));
+            }
+            roots.push(new_index[copy.nodes.len() - 1]);
+        }
+        let root = match roots.len() {
+            0 => builder.push(False),
+            1 => roots[0],
+            _ => builder.push(Or { children: roots }),
+        };
+        let mut ntypes: Vec<NodeType> = builder.ntypes;
+        ntypes.truncate(root + 1);
+
+        let mut conjoined = Ddnnf {
+            max_worker: self.max_worker,
+            max_digits: self.max_digits,
+            number_of_variables: self.number_of_variables,
+            ..Default::default()
+        };
+        conjoined.replace_nodes(ntypes);
+        conjoined
+    }
+
+    // Conjoins the d-DNNF with all clauses
+    fn restricted(&self, clauses: &[BTreeSet<i32>]) -> Ddnnf {
+        clauses.iter().fold(self.conditioned(&[]), |ddnnf, clause| {
+            ddnnf.conjoined(clause)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::{build_ddnnf, distribute_building};
+
+    use super::*;
+
+    #[test]
+    fn synthesized_clauses_restrict_to_the_target() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        assert_eq!(Ok(Vec::new()), vp9.synthesize_clauses(&vp9, 2));
+
+        let target = vp9.condition(&[4, -9, 20]);
+        let clauses = vp9.synthesize_clauses(&target, 2).unwrap();
+        assert_eq!(
+            vec![
+                BTreeSet::from([-9]),
+                BTreeSet::from([4]),
+                BTreeSet::from([20])
+            ],
+            clauses.iter().cloned().sorted().collect_vec()
+        );
+        assert!(vp9.restricted(&clauses).equivalent(&target));
+
+        // the target allows 4 and needs more configurations
+        assert!(target.synthesize_clauses(&vp9, 2).is_err());
+        assert!(vp9
+            .condition(&[-4])
+            .synthesize_clauses(&vp9.condition(&[4]), 2)
+            .is_err());
+        assert!(vp9.synthesize_clauses(&target, 0).is_err());
+        let mut wider = target.clone();
+        wider.number_of_variables = 43;
+        assert!(vp9.synthesize_clauses(&wider, 1).is_err());
+
+        // an unsatisfiable target entails all clauses, e.g. the negation of the core feature 1
+        let void = vp9.condition(&[1, -1]);
+        let clauses = vp9.synthesize_clauses(&void, 1).unwrap();
+        assert_eq!(vec![BTreeSet::from([-1])], clauses);
+        assert_eq!(0, vp9.restricted(&clauses).rc());
+    }
+
+    #[test]
+    fn synthesized_clauses_with_several_literals() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        // 1 ∧ (2 ⊕ 3) ∧ (¬2 ∨ 4), i.e. 4 is optional unless 2 is selected
+        let lines = [
+            "nnf 12 15 4",
+            "L 1",
+            "L 2",
+            "L -3",
+            "L 4",
+            "A 3 1 2 3",
+            "L -2",
+            "L 3",
+            "L -4",
+            "O 4 2 3 7",
+            "A 3 5 6 8",
+            "O 2 2 4 9",
+            "A 2 0 10",
+        ];
+        let target = distribute_building(lines.iter().map(|l| l.to_string()).collect(), None, None);
+        assert_eq!(3, target.rc());
+
+        assert!(ddnnf.synthesize_clauses(&target, 1).is_err());
+        let clauses = ddnnf.synthesize_clauses(&target, 2).unwrap();
+        assert_eq!(vec![BTreeSet::from([-2, 4])], clauses);
+        assert!(ddnnf.restricted(&clauses).equivalent(&target));
+    }
+
+    #[test]
+    fn targets_with_other_configurations_get_rejected() {
+        let ddnnf: Ddnnf = build_ddnnf("tests/data/small_ex_c2d.nnf", None);
+        // 4 iff 2 and 4 iff 3 have the same count and core, but no configuration in common
+        let with_two = ddnnf.restricted(&[BTreeSet::from([-2, 4]), BTreeSet::from([2, -4])]);
+        let with_three = ddnnf.restricted(&[BTreeSet::from([-3, 4]), BTreeSet::from([3, -4])]);
+        assert_eq!(2, with_two.rc());
+        assert_eq!(2, with_three.rc());
+
+        let error = with_two.synthesize_clauses(&with_three, 2).unwrap_err();
+        assert!(error.contains("no restriction"));
+        assert!(ddnnf.synthesize_clauses(&with_three, 2).is_ok());
+    }
+
+    #[test]
+    fn conjoined_clauses_keep_the_counts_exact() {
+        let vp9: Ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        let clauses = [
+            BTreeSet::from([4, 9]),
+            BTreeSet::from([-9, 20, -38]),
+            BTreeSet::from([-4, -20, 38]),
+        ];
+
+        // inclusion-exclusion over the sets of violated clauses
+        let mut expected = Integer::new();
+        for violated in clauses.iter().powerset() {
+            let violation: BTreeSet<i32> = violated
+                .iter()
+                .flat_map(|clause| clause.iter().map(|literal| -literal))
+                .collect();
+            if violation
+                .iter()
+                .any(|literal| violation.contains(&-literal))
+            {
+                continue;
+            }
+            let count = vp9.count_adaptive(&violation.into_iter().collect_vec());
+            if violated.len() % 2 == 0 {
+                expected += count;
+            } else {
+                expected -= count;
+            }
+        }
+        let mut restricted = vp9.restricted(&clauses);
+        assert_eq!(expected, restricted.rc());
+        assert!(expected > 0 && expected < vp9.rc());
+        assert_eq!(
+            vp9.count_adaptive(&[4, -9, -20]),
+            restricted.execute_query(&[4, -9, -20])
+        );
+        assert_eq!(0, restricted.execute_query(&[-4, -9]));
+    }
+}
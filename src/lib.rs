@@ -8,6 +8,8 @@
 extern crate test;
 
 pub mod parser;
+pub mod profiling;
+pub mod settings;
 pub use crate::parser::c2d_lexer;
 pub use crate::parser::d4_lexer;
 
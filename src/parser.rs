@@ -8,8 +8,11 @@ pub mod from_cnf;
 use from_cnf::{check_for_cnf_header, CNFToken};
 
 pub mod persisting;
+use persisting::BINARY_MAGIC;
 pub mod util;
 
+pub mod composition;
+
 use core::panic;
 use std::{
     cell::RefCell,
@@ -17,15 +20,21 @@ use std::{
     collections::{BTreeSet, HashMap, HashSet},
     ffi::OsStr,
     fs::{self, File},
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read},
     path::Path,
     process,
     rc::Rc,
+    time::Duration,
 };
 
+#[cfg(feature = "editing")]
+use std::time::Instant;
+
 use rug::{Complete, Integer};
 
-use crate::ddnnf::{node::Node, node::NodeType, Ddnnf};
+use crate::ddnnf::{node::Node, node::NodeId, node::NodeType, Ddnnf};
+use crate::profiling::{self, Phase};
+use crate::settings::Settings;
 
 use petgraph::{
     graph::{EdgeIndex, NodeIndex},
@@ -53,56 +62,230 @@ use petgraph::{
 ///
 /// The function panics for an invalid file path.
 #[inline]
-pub fn build_ddnnf(mut path: &str, mut total_features: Option<u32>) -> Ddnnf {
+pub fn build_ddnnf(path: &str, total_features: Option<u32>) -> Ddnnf {
+    build_ddnnf_with_settings(path, total_features, &Settings::default())
+}
+
+/// Parses a ddnnf, referenced by the file path, like [build_ddnnf].
+/// If the file is a CNF, the settings determine which compiler is used
+/// and where the intermediate d-DNNF is stored.
+///
+/// # Panics
+///
+/// The function panics for an invalid file path or if a CNF can not be compiled.
+/// Further, it panics if the compiler reports a model count that differs from the count of the parsed d-DNNF.
+pub fn build_ddnnf_with_settings(
+    path: &str,
+    mut total_features: Option<u32>,
+    settings: &Settings,
+) -> Ddnnf {
+    let _parsing = profiling::phase(Phase::Parse);
     let mut clauses = BTreeSet::new();
+    let intermediate_path = settings.intermediate_path(".intermediate.nnf");
+    let intermediate_file = intermediate_path
+        .to_str()
+        .expect("Failed to convert the path of the intermediate d-DNNF to string");
+    let mut ddnnf_path = path;
+    let mut reported_count = None;
+    let mut compile_stats = None;
+
     if let Some(extension) = Path::new(path).extension().and_then(OsStr::to_str) {
         if extension == "dimacs" || extension == "cnf" {
-            #[cfg(feature = "d4")]
-            {
-                let file = open_file_savely(path);
-                let lines = BufReader::new(file).lines();
-                for line in lines {
-                    let line = line.expect("Unable to read line");
-                    match check_for_cnf_header(line.as_str()).unwrap().1 {
-                        CNFToken::Header {
-                            total_features: total_features_header,
-                            total_clauses: _,
-                        } => {
-                            let ddnnf_file = ".intermediate.nnf";
-                            d4_oxide::compile_ddnnf(path.to_string(), ddnnf_file.to_string());
-                            path = ddnnf_file;
-                            total_features = Some(total_features_header as u32);
-                        }
-                        CNFToken::Clause { features } => {
-                            clauses.insert(features);
-                        }
-                        CNFToken::Comment => (),
+            let file = open_file_savely(path);
+            let lines = BufReader::new(file).lines();
+            for line in lines {
+                let line = line.expect("Unable to read line");
+                match check_for_cnf_header(line.as_str()).unwrap().1 {
+                    CNFToken::Header {
+                        total_features: total_features_header,
+                        total_clauses: _,
+                    } => {
+                        let (count, stats) = compile_cnf(path, intermediate_file, settings);
+                        reported_count = count;
+                        compile_stats = Some(stats);
+                        ddnnf_path = intermediate_file;
+                        total_features = Some(total_features_header as u32);
+                    }
+                    CNFToken::Clause { features } => {
+                        clauses.insert(features);
                     }
+                    CNFToken::Comment => (),
                 }
             }
-
-            #[cfg(not(feature = "d4"))]
-            {
-                panic!("CNF to d-DNNF compilation is only possible when including d4.");
+            if compile_stats.is_none() {
+                panic!("The CNF {path} has no header 'p cnf <features> <clauses>', hence it can not be compiled.");
             }
         }
     }
 
-    let file = open_file_savely(path);
+    if is_binary_ddnnf(ddnnf_path) {
+        let bytes = fs::read(ddnnf_path).expect("Unable to read the binary d-DNNF");
+        return build_binary_ddnnf(&bytes).expect("The binary d-DNNF is corrupted");
+    }
+
+    let file = open_file_savely(ddnnf_path);
     let lines = BufReader::new(file)
         .lines()
         .map(|line| line.expect("Unable to read line"))
         .collect::<Vec<String>>();
 
-    if path == ".intermediate.nnf" {
-        fs::remove_file(path).unwrap();
+    if ddnnf_path == intermediate_file {
+        fs::remove_file(ddnnf_path).unwrap();
     }
 
-    if clauses.is_empty() {
+    let mut ddnnf = if clauses.is_empty() {
         distribute_building(lines, total_features, None)
     } else {
         distribute_building(lines, total_features, Some(clauses))
+    };
+    if let Some(stats) = compile_stats {
+        ddnnf.record_compilation(stats);
     }
+    ddnnf.record_settings(settings);
+
+    // a deviation hints at a bug in the parser or a format the parser does not support
+    if let Some(count) = reported_count {
+        if count != ddnnf.rc() {
+            panic!(
+                "The compiler reported {} models but the parsed d-DNNF has {} models.",
+                count,
+                ddnnf.rc()
+            );
+        }
+    }
+    ddnnf
+}
+
+/// The GNU time binary that measures the peak memory of an external compiler on Linux
+#[cfg(feature = "editing")]
+const TIME_BINARY: &str = "/usr/bin/time";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Measurements of one compilation of a CNF into a d-DNNF
+pub struct CompileStats {
+    /// The wall clock time of the compilation
+    pub runtime: Duration,
+    /// The maximum resident set size of the compiler in KiB. It is only known for external
+    /// compilers on Linux systems that provide GNU time at /usr/bin/time.
+    pub peak_memory_kib: Option<u64>,
+    /// The size of the compiled d-DNNF file in bytes
+    pub output_size: u64,
+}
+
+impl CompileStats {
+    /// Aggregates the measurements of multiple compilations, e.g. the initial one and the ones of
+    /// all clause updates. Runtimes and output sizes get summed up, while the peak memory is the
+    /// maximum. Returns None if there is no compilation.
+    pub fn aggregate(stats: &[CompileStats]) -> Option<CompileStats> {
+        if stats.is_empty() {
+            return None;
+        }
+        Some(CompileStats {
+            runtime: stats.iter().map(|stats| stats.runtime).sum(),
+            peak_memory_kib: stats.iter().filter_map(|stats| stats.peak_memory_kib).max(),
+            output_size: stats.iter().map(|stats| stats.output_size).sum(),
+        })
+    }
+}
+
+// Compiles the CNF in cnf_path into a d-DNNF that is saved in ddnnf_path.
+// An external compiler supplied via the settings takes precedence over the bundled d4.
+// Returns the model count that the compiler reported, if there is any, and the measurements.
+#[cfg(feature = "editing")]
+fn compile_cnf(
+    cnf_path: &str,
+    ddnnf_path: &str,
+    settings: &Settings,
+) -> (Option<Integer>, CompileStats) {
+    let _compiling = profiling::phase(Phase::Compile);
+    let time = Instant::now();
+    let (reported_count, peak_memory_kib) = if let Some(compiler) = &settings.compiler {
+        // GNU time writes the maximum resident set size to a file to keep the output of the compiler intact
+        let time_file = (cfg!(target_os = "linux") && Path::new(TIME_BINARY).exists())
+            .then(|| tempfile::NamedTempFile::new().ok())
+            .flatten();
+        let mut command = match &time_file {
+            Some(time_file) => {
+                let mut command = process::Command::new(TIME_BINARY);
+                command
+                    .args(["-f", "%M", "-o"])
+                    .arg(time_file.path())
+                    .arg(compiler);
+                command
+            }
+            None => process::Command::new(compiler),
+        };
+        let output = command
+            .args([
+                "-i",
+                cnf_path,
+                "-m",
+                "ddnnf-compiler",
+                "--dump-ddnnf",
+                ddnnf_path,
+            ])
+            .stderr(process::Stdio::inherit())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                let peak_memory_kib = time_file.and_then(|time_file| {
+                    fs::read_to_string(time_file.path())
+                        .ok()?
+                        .lines()
+                        .last()?
+                        .trim()
+                        .parse()
+                        .ok()
+                });
+                (
+                    reported_model_count(&String::from_utf8_lossy(&output.stdout)),
+                    peak_memory_kib,
+                )
+            }
+            Ok(output) => panic!("The compiler {compiler:?} failed with {}.", output.status),
+            Err(err) => panic!("Unable to start the compiler {compiler:?}: {err}"),
+        }
+    } else {
+        #[cfg(feature = "d4")]
+        {
+            // the bundled compiler does not expose its model count
+            d4_oxide::compile_ddnnf(cnf_path.to_string(), ddnnf_path.to_string());
+            (None, None)
+        }
+
+        #[cfg(not(feature = "d4"))]
+        {
+            panic!(
+                "CNF to d-DNNF compilation is only possible when including d4 or supplying a compiler."
+            );
+        }
+    };
+
+    let stats = CompileStats {
+        runtime: time.elapsed(),
+        peak_memory_kib,
+        output_size: fs::metadata(ddnnf_path).map_or(0, |metadata| metadata.len()),
+    };
+    (reported_count, stats)
+}
+
+// Without the editing feature, we neither spawn an external compiler nor bundle d4
+#[cfg(not(feature = "editing"))]
+fn compile_cnf(
+    _cnf_path: &str,
+    _ddnnf_path: &str,
+    _settings: &Settings,
+) -> (Option<Integer>, CompileStats) {
+    panic!("CNF to d-DNNF compilation is only possible when including the editing feature.");
+}
+
+// d4 reports the model count in its solution line, e.g. 's 216000'
+#[cfg(feature = "editing")]
+fn reported_model_count(output: &str) -> Option<Integer> {
+    output
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("s ")?.trim().parse().ok())
 }
 
 /// Chooses, depending on the first read line, which building implmentation to choose.
@@ -116,6 +299,17 @@ pub fn distribute_building(
 ) -> Ddnnf {
     use C2DToken::*;
 
+    // a file without any node describes no model
+    if lines.is_empty() {
+        return Ddnnf::new(
+            Vec::new(),
+            HashMap::new(),
+            Vec::new(),
+            total_features.unwrap_or(0),
+            clauses,
+        );
+    }
+
     match lex_line_c2d(lines[0].trim()) {
         Ok((
             _,
@@ -156,6 +350,8 @@ fn build_c2d_ddnnf(
     clauses: Option<BTreeSet<BTreeSet<i32>>>,
 ) -> Ddnnf {
     use C2DToken::*;
+    // c2d nodes get counted while they are created
+    let building = profiling::phase(Phase::BuildGraph);
 
     let mut parsed_nodes: Vec<Node> = Vec::with_capacity(lines.len());
 
@@ -181,28 +377,136 @@ fn build_c2d_ddnnf(
             _ => panic!("Tried to parse the header of the .nnf at the wrong time"),
         };
 
-        // fill the parent node pointer, save literals
-        match &next.ntype {
-            NodeType::And { children } | NodeType::Or { children } => {
-                let next_indize: usize = parsed_nodes.len();
-                for &i in children {
-                    parsed_nodes[i].parents.push(next_indize);
-                }
+        push_parsed_node(next, &mut parsed_nodes, &mut literals, &mut true_nodes);
+    }
+    drop(building);
+
+    Ddnnf::new(parsed_nodes, literals, true_nodes, variables, clauses)
+}
+
+// Appends a node whose children are already part of parsed_nodes.
+// Further, we fill the parent node pointers and save literals and true nodes.
+#[inline]
+fn push_parsed_node(
+    next: Node,
+    parsed_nodes: &mut Vec<Node>,
+    literals: &mut HashMap<i32, usize>,
+    true_nodes: &mut Vec<usize>,
+) {
+    match &next.ntype {
+        NodeType::And { children } | NodeType::Or { children } => {
+            let next_indize: usize = parsed_nodes.len();
+            for &i in children {
+                parsed_nodes[i].parents.push(next_indize);
             }
-            // fill the FxHashMap with the literals
-            NodeType::Literal { literal } => {
-                literals.insert(*literal, parsed_nodes.len());
+        }
+        // fill the FxHashMap with the literals
+        NodeType::Literal { literal } => {
+            literals.insert(*literal, parsed_nodes.len());
+        }
+        NodeType::True => {
+            true_nodes.push(parsed_nodes.len());
+        }
+        _ => (),
+    }
+
+    parsed_nodes.push(next);
+}
+
+// Checks whether the file starts with the magic bytes of the binary format
+fn is_binary_ddnnf(path: &str) -> bool {
+    let mut magic = [0; BINARY_MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && &magic == BINARY_MAGIC
+}
+
+/// Builds a d-DNNF from its binary representation
+/// (see [persisting::write_binary_ddnnf_to_file] for the layout).
+pub fn build_binary_ddnnf(bytes: &[u8]) -> io::Result<Ddnnf> {
+    // splits off the next bytes of the remaining input
+    fn take<'a>(rest: &mut &'a [u8], amount: usize) -> io::Result<&'a [u8]> {
+        if rest.len() < amount {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected end of the binary d-DNNF",
+            ));
+        }
+        let (chunk, remaining) = rest.split_at(amount);
+        *rest = remaining;
+        Ok(chunk)
+    }
+    let mut rest = bytes;
+
+    if take(&mut rest, BINARY_MAGIC.len())? != BINARY_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the file is not a binary d-DNNF",
+        ));
+    }
+    let variables = u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap());
+    let number_of_nodes = u64::from_le_bytes(take(&mut rest, 8)?.try_into().unwrap()) as usize;
+
+    let building = profiling::phase(Phase::BuildGraph);
+    // the counts stem from the file, hence a corrupted file must not dictate the allocation:
+    // each node takes at least one byte and each child four bytes
+    let mut parsed_nodes: Vec<Node> = Vec::with_capacity(number_of_nodes.min(rest.len()));
+    let mut literals: HashMap<i32, usize> = HashMap::new();
+    let mut true_nodes = Vec::new();
+
+    for _ in 0..number_of_nodes {
+        let tag = take(&mut rest, 1)?[0];
+        let next = match tag {
+            0 | 1 => {
+                let amount = u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap()) as usize;
+                let mut children = Vec::with_capacity(amount.min(rest.len() / 4));
+                for _ in 0..amount {
+                    let child =
+                        u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap()) as usize;
+                    if child >= parsed_nodes.len() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "a child has to be defined before its parent",
+                        ));
+                    }
+                    children.push(child);
+                }
+                if tag == 0 {
+                    Node::new_and(calc_and_count(&mut parsed_nodes, &children), children)
+                } else {
+                    Node::new_or(0, calc_or_count(&mut parsed_nodes, &children), children)
+                }
             }
-            NodeType::True => {
-                true_nodes.push(parsed_nodes.len());
+            2 => Node::new_literal(i32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap())),
+            3 => Node::new_bool(true),
+            4 => Node::new_bool(false),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown node type {tag}"),
+                ))
             }
-            _ => (),
+        };
+        push_parsed_node(next, &mut parsed_nodes, &mut literals, &mut true_nodes);
+    }
+    drop(building);
+
+    let mut ddnnf = Ddnnf::new(parsed_nodes, literals, true_nodes, variables, None);
+
+    // the annotations are optional
+    if !rest.is_empty() {
+        let number_of_annotations = u64::from_le_bytes(take(&mut rest, 8)?.try_into().unwrap());
+        for _ in 0..number_of_annotations {
+            let id = NodeId(u64::from_le_bytes(take(&mut rest, 8)?.try_into().unwrap()));
+            let length = u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap()) as usize;
+            let annotation = String::from_utf8(take(&mut rest, length)?.to_vec())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            ddnnf.annotations.insert(id, annotation);
         }
-
-        parsed_nodes.push(next);
     }
 
-    Ddnnf::new(parsed_nodes, literals, true_nodes, variables, clauses)
+    Ok(ddnnf)
 }
 
 /// Parses a ddnnf, referenced by the file path.
@@ -214,6 +518,7 @@ fn build_d4_ddnnf(
     total_features_opt: Option<u32>,
     clauses: Option<BTreeSet<BTreeSet<i32>>>,
 ) -> Ddnnf {
+    let building = profiling::phase(Phase::BuildGraph);
     let mut ddnnf_graph = StableGraph::<TId, ()>::new();
 
     let mut total_features = total_features_opt.unwrap_or(0);
@@ -433,6 +738,18 @@ fn build_d4_ddnnf(
         }
     }
 
+    // The root got removed together with a False node, i.e. the d-DNNF has no model
+    if !ddnnf_graph.contains_node(root) {
+        drop(building);
+        return Ddnnf::new(
+            vec![Node::new_bool(false)],
+            HashMap::new(),
+            Vec::new(),
+            total_features,
+            clauses,
+        );
+    }
+
     // third dfs:
     // Look at each or node. For each outgoing edge:
     // 1. Compute all literals that occur in the children of that edge
@@ -449,6 +766,8 @@ fn build_d4_ddnnf(
     //                                         /  \  /
     //                                       -Lm   Lm
     //
+    drop(building);
+    let diffing = profiling::phase(Phase::LiteralDiffs);
     let mut safe: HashMap<NodeIndex, HashSet<u32>> = HashMap::new();
     let mut dfs = DfsPostOrder::new(&ddnnf_graph, root);
     while let Some(nx) = dfs.next(&ddnnf_graph) {
@@ -463,6 +782,8 @@ fn build_d4_ddnnf(
     // perform a depth first search to get the nodes ordered such
     // that child nodes are listed before their parents
     // transform that interim representation into a node vector
+    drop(diffing);
+    let counting = profiling::phase(Phase::Count);
     dfs = DfsPostOrder::new(&ddnnf_graph, root);
     let mut nd_to_usize: HashMap<NodeIndex, usize> = HashMap::new();
 
@@ -509,6 +830,7 @@ fn build_d4_ddnnf(
 
         parsed_nodes.push(next);
     }
+    drop(counting);
 
     Ddnnf::new(parsed_nodes, literals, true_nodes, total_features, clauses)
 }
@@ -553,27 +875,44 @@ fn get_literals(
     nx_literals: &HashMap<NodeIndex, i32>,
     or_child: NodeIndex,
 ) -> HashSet<u32> {
-    let lookup = safe.get(&or_child);
-    if let Some(x) = lookup {
-        return x.clone();
-    }
-
-    let mut res = HashSet::new();
     use c2d_lexer::TokenIdentifier::*;
-    match di_graph[or_child] {
-        And | Or => {
-            di_graph
-                .neighbors_directed(or_child, Outgoing)
-                .for_each(|n| res.extend(get_literals(di_graph, safe, nx_literals, n)));
-            safe.insert(or_child, res.clone());
+    let has_literals =
+        |node: NodeIndex| matches!(di_graph[node], And | Or | PositiveLiteral | NegativeLiteral);
+
+    // the literals of a node require the literals of its children, which are computed first
+    let mut stack = vec![or_child];
+    while let Some(&node) = stack.last() {
+        if safe.contains_key(&node) || !has_literals(node) {
+            stack.pop();
+            continue;
         }
-        PositiveLiteral | NegativeLiteral => {
-            res.insert(nx_literals.get(&or_child).unwrap().unsigned_abs());
-            safe.insert(or_child, res.clone());
+        match di_graph[node] {
+            And | Or => {
+                let missing: Vec<NodeIndex> = di_graph
+                    .neighbors_directed(node, Outgoing)
+                    .filter(|&n| has_literals(n) && !safe.contains_key(&n))
+                    .collect();
+                if missing.is_empty() {
+                    let mut res = HashSet::new();
+                    for n in di_graph.neighbors_directed(node, Outgoing) {
+                        if let Some(literals) = safe.get(&n) {
+                            res.extend(literals);
+                        }
+                    }
+                    safe.insert(node, res);
+                    stack.pop();
+                } else {
+                    stack.extend(missing);
+                }
+            }
+            _ => {
+                let literal = nx_literals.get(&node).unwrap().unsigned_abs();
+                safe.insert(node, HashSet::from([literal]));
+                stack.pop();
+            }
         }
-        _ => (),
     }
-    res
+    safe.get(&or_child).cloned().unwrap_or_default()
 }
 
 // multiplies the count of all child Nodes of an And Node
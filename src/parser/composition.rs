@@ -0,0 +1,116 @@
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    time::{Duration, Instant},
+};
+
+use itertools::Either;
+use rug::Integer;
+
+use super::{
+    build_ddnnf_with_settings,
+    from_cnf::{check_for_cnf_header, CNFToken},
+};
+use crate::{ddnnf::clause_cache::normalize_clauses, settings::Settings, Ddnnf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Describes the effect of a single delta file on the d-DNNF
+pub struct DeltaStats {
+    /// The path of the delta file
+    pub path: String,
+    /// The number of clauses that got added to the CNF
+    pub added_clauses: usize,
+    /// The number of clauses that are satisfied by every configuration and got skipped
+    pub skipped_clauses: usize,
+    /// The number of features after applying the delta
+    pub number_of_variables: u32,
+    pub count_before: Integer,
+    pub count_after: Integer,
+    /// The time it took to apply the delta (including the compilation)
+    pub duration: Duration,
+}
+
+/// Builds a d-DNNF from a base CNF and an ordered list of delta files.
+/// Each delta file is a CNF that contains the clauses that get added to the model.
+/// A delta can introduce new features via its header 'p cnf #FEATURES #CLAUSES'.
+/// The base gets compiled once and each delta is applied as clause update. Hence, the resulting
+/// d-DNNF supports undoing the last delta.
+///
+/// Returns an error if a file can not be read, a delta contains invalid clauses, or an update fails.
+///
+/// # Panics
+///
+/// The same as for [build_ddnnf_with_settings] regarding the base CNF.
+pub fn build_ddnnf_with_deltas(
+    base_path: &str,
+    delta_paths: &[&str],
+    settings: &Settings,
+) -> io::Result<(Ddnnf, Vec<DeltaStats>)> {
+    let mut ddnnf = build_ddnnf_with_settings(base_path, None, settings);
+    if !delta_paths.is_empty() && !ddnnf.can_save_state() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the base {base_path} has to be a CNF to apply deltas"),
+        ));
+    }
+
+    let mut stats = Vec::with_capacity(delta_paths.len());
+    for &path in delta_paths {
+        stats.push(apply_delta(&mut ddnnf, path)?);
+    }
+    Ok((ddnnf, stats))
+}
+
+// Adds the clauses of the delta file to the d-DNNF
+fn apply_delta(ddnnf: &mut Ddnnf, path: &str) -> io::Result<DeltaStats> {
+    let invalid =
+        |msg: String| io::Error::new(io::ErrorKind::InvalidData, format!("{path}: {msg}"));
+    let time = Instant::now();
+    let count_before = ddnnf.rc();
+
+    let mut total_features = ddnnf.number_of_variables;
+    let mut clauses = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match check_for_cnf_header(line.trim()) {
+            Ok((
+                _,
+                CNFToken::Header {
+                    total_features: features,
+                    ..
+                },
+            )) => {
+                total_features = total_features.max(features as u32);
+            }
+            Ok((_, CNFToken::Clause { features })) => clauses.push(features),
+            Ok((_, CNFToken::Comment)) => (),
+            Err(_) => return Err(invalid(format!("unable to parse the line '{line}'"))),
+        }
+    }
+
+    let added: Vec<BTreeSet<i32>> = normalize_clauses(&clauses, total_features).map_err(invalid)?;
+    let skipped_clauses = clauses.len() - added.len();
+    let added_clauses = added.len();
+
+    // without any effective clause, we can keep the d-DNNF as it is
+    let changes_model = added_clauses > 0 || total_features != ddnnf.number_of_variables;
+    if changes_model
+        && !ddnnf.update_cached_state(Either::Left((added, Vec::new())), Some(total_features))
+    {
+        return Err(invalid(String::from("unable to apply the clauses")));
+    }
+
+    Ok(DeltaStats {
+        path: path.to_string(),
+        added_clauses,
+        skipped_clauses,
+        number_of_variables: ddnnf.number_of_variables,
+        count_before,
+        count_after: ddnnf.rc(),
+        duration: time.elapsed(),
+    })
+}
@@ -1,4 +1,8 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
 
 use nom::{
     branch::alt,
@@ -38,6 +42,65 @@ pub fn check_for_cnf_header(line: &str) -> IResult<&str, CNFToken> {
     alt((lex_comment, lex_header, lex_clause))(line)
 }
 
+/// Reads the number of variables and the clauses of a CNF file in the DIMACS format.
+/// If the header declares fewer variables than the clauses use, the larger number is returned.
+pub fn read_cnf(path: &str) -> io::Result<(u32, BTreeSet<BTreeSet<i32>>)> {
+    let mut total_features = 0;
+    let mut clauses = BTreeSet::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match check_for_cnf_header(line.trim()) {
+            Ok((
+                _,
+                Header {
+                    total_features: features,
+                    ..
+                },
+            )) => total_features = total_features.max(features as u32),
+            Ok((_, Clause { features })) => {
+                let max_variable = features.iter().map(|l| l.unsigned_abs()).max();
+                total_features = total_features.max(max_variable.unwrap_or(0));
+                clauses.insert(features);
+            }
+            Ok((_, Comment)) => (),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{path}: unable to parse the line '{line}'"),
+                ))
+            }
+        }
+    }
+    Ok((total_features, clauses))
+}
+
+/// Reads the names of the features from the comments of a CNF file. Tools like FeatureIDE
+/// write one comment of the form 'c <variable> <name>' per feature, where the name is the rest
+/// of the line. Other comments are ignored.
+pub fn read_feature_names(path: &str) -> io::Result<HashMap<u32, String>> {
+    let mut names = HashMap::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let comment = match line.trim().strip_prefix("c ") {
+            Some(comment) => comment.trim_start(),
+            None => continue,
+        };
+        // the name is the rest of the line and may contain whitespace
+        if let Some((Ok(variable), name)) = comment
+            .split_once(char::is_whitespace)
+            .map(|(variable, name)| (variable.parse::<u32>(), name.trim()))
+        {
+            if variable > 0 && !name.is_empty() {
+                names.insert(variable, name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
 // lexes the head of a CNF file of the format p cnf #FEATURES #CLAUSES
 fn lex_header(line: &str) -> IResult<&str, CNFToken> {
     map(
@@ -71,6 +134,30 @@ fn lex_comment(line: &str) -> IResult<&str, CNFToken> {
 mod test {
     use super::*;
 
+    #[test]
+    fn feature_names_of_cnf() {
+        let names = read_feature_names("tests/data/VP9.cnf").unwrap();
+        assert_eq!(42, names.len());
+        assert_eq!("root", names[&1]);
+        assert_eq!("lagInFrames_16", names[&3]);
+
+        assert!(read_feature_names("tests/data/small_ex.cnf")
+            .unwrap()
+            .is_empty());
+
+        // names keep their inner whitespace
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            "c 1 Wireless LAN \nc 2\tCable\nc 3\nc x Ignored\np cnf 3 1\n1 2 0\n",
+        )
+        .unwrap();
+        let names = read_feature_names(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(2, names.len());
+        assert_eq!("Wireless LAN", names[&1]);
+        assert_eq!("Cable", names[&2]);
+    }
+
     #[test]
     fn lex_cnf_lines() {
         let comment = "c 1 N_100300__F_100332";
@@ -92,4 +179,15 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn read_cnf_files() {
+        let (variables, clauses) = read_cnf("tests/data/small_ex.cnf").unwrap();
+        assert_eq!(4, variables);
+        // one of the 13 clauses is a duplicate
+        assert_eq!(12, clauses.len());
+        assert!(clauses.contains(&BTreeSet::from([-1, -2, -3, 4])));
+
+        assert!(read_cnf("tests/data/small_ex_c2d.nnf").is_err());
+    }
 }
@@ -1,15 +1,19 @@
 use std::{
     cmp::max,
     collections::BTreeSet,
+    ffi::OsStr,
+    fmt,
     fs::File,
-    io::{LineWriter, Write},
+    io::{BufWriter, LineWriter, Write},
+    path::Path,
+    str::FromStr,
 };
 
 use rug::Assign;
 
 use crate::{Ddnnf, Node, NodeType};
 
-use super::util::format_vec;
+use super::util::{format_vec, log10};
 
 /// Takes a CNF and writes the string representation into a file with the provided name
 pub(crate) fn write_cnf_to_file(
@@ -49,6 +53,245 @@ pub fn write_ddnnf_to_file(ddnnf: &Ddnnf, path_out: &str) -> std::io::Result<()>
     Ok(())
 }
 
+/// The magic bytes (including the format version) that start each d-DNNF in the binary format
+pub(crate) const BINARY_MAGIC: &[u8; 6] = b"DDNNF\x01";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The file formats a d-DNNF can be converted into
+pub enum Format {
+    /// The c2d format including a header. The variable numbers stay the same.
+    C2d,
+    /// The d4 format. Since it contains no header,
+    /// the number of variables has to be supplied again while loading.
+    D4,
+    /// A CNF in DIMACS format that results from the Tseitin transformation of the d-DNNF.
+    /// Variables 1 to n are the original variables, all further variables represent inner nodes.
+    Cnf,
+    /// A compact binary representation that skips lexing while loading
+    Binary,
+}
+
+impl Format {
+    /// Guesses the format based on the file extension of the path.
+    /// `.nnf` files are interpreted as c2d because it is the only format with a header.
+    pub fn from_path(path: &str) -> Option<Format> {
+        match Path::new(path).extension().and_then(OsStr::to_str) {
+            Some("nnf") => Some(Format::C2d),
+            Some("cnf") | Some("dimacs") => Some(Format::Cnf),
+            Some("bin") => Some(Format::Binary),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "c2d" => Ok(Format::C2d),
+            "d4" => Ok(Format::D4),
+            "cnf" | "dimacs" => Ok(Format::Cnf),
+            "bin" | "binary" => Ok(Format::Binary),
+            _ => Err(format!(
+                "unknown format {s}. Possible formats are c2d, d4, cnf, and binary"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::C2d => "c2d",
+            Format::D4 => "d4",
+            Format::Cnf => "cnf",
+            Format::Binary => "binary",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Writes the d-DNNF into a file with the provided name using the requested format
+pub fn write_ddnnf_as(ddnnf: &Ddnnf, format: Format, path_out: &str) -> std::io::Result<()> {
+    match format {
+        Format::C2d => write_ddnnf_to_file(ddnnf, path_out),
+        Format::D4 => write_d4_ddnnf_to_file(ddnnf, path_out),
+        Format::Cnf => write_tseitin_cnf_to_file(ddnnf, path_out),
+        Format::Binary => write_binary_ddnnf_to_file(ddnnf, path_out),
+    }
+}
+
+/// Takes a d-DNNF and writes it in the d4 format into a file with the provided name.
+/// The root is the first node. Literals are placed on the edges to a single true node.
+pub fn write_d4_ddnnf_to_file(ddnnf: &Ddnnf, path_out: &str) -> std::io::Result<()> {
+    let file = File::create(path_out)?;
+    let mut lw = LineWriter::with_capacity(1000, file);
+
+    // assign the d4 ids in reverse order such that the root gets the id 1
+    let mut ids = vec![0; ddnnf.nodes.len()];
+    let mut declarations = Vec::new();
+    for (position, node) in ddnnf.nodes.iter().enumerate().rev() {
+        let declaration = match node.ntype {
+            NodeType::And { .. } => "a",
+            NodeType::Or { .. } => "o",
+            NodeType::False => "f",
+            NodeType::Literal { .. } if position == ddnnf.nodes.len() - 1 => "a",
+            NodeType::Literal { .. } | NodeType::True => continue,
+        };
+        declarations.push(declaration);
+        ids[position] = declarations.len();
+    }
+    declarations.push("t");
+    let true_id = declarations.len();
+
+    for (id, declaration) in declarations.iter().enumerate() {
+        lw.write_all(format!("{} {} 0\n", declaration, id + 1).as_bytes())?;
+    }
+
+    let write_edge =
+        |lw: &mut LineWriter<File>, from: usize, child: usize| match ddnnf.nodes[child].ntype {
+            NodeType::Literal { literal } => {
+                lw.write_all(format!("{} {} {} 0\n", from, true_id, literal).as_bytes())
+            }
+            NodeType::True => lw.write_all(format!("{} {} 0\n", from, true_id).as_bytes()),
+            _ => lw.write_all(format!("{} {} 0\n", from, ids[child]).as_bytes()),
+        };
+
+    for (position, node) in ddnnf.nodes.iter().enumerate().rev() {
+        match &node.ntype {
+            NodeType::And { children } | NodeType::Or { children } => {
+                for &child in children {
+                    write_edge(&mut lw, ids[position], child)?;
+                }
+            }
+            NodeType::Literal { .. } if ids[position] != 0 => {
+                write_edge(&mut lw, ids[position], position)?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Takes a d-DNNF and writes its Tseitin transformation as CNF into a file with the provided name.
+/// Each inner node gets an additional variable that is equivalent to the subformula of the node.
+/// Hence, the CNF has the same number of models as the d-DNNF.
+pub fn write_tseitin_cnf_to_file(ddnnf: &Ddnnf, path_out: &str) -> std::io::Result<()> {
+    let mut clauses: Vec<Vec<i32>> = Vec::new();
+    let mut variables: Vec<i32> = Vec::with_capacity(ddnnf.nodes.len());
+    let mut next_variable = ddnnf.number_of_variables as i32;
+
+    for node in ddnnf.nodes.iter() {
+        let variable = match &node.ntype {
+            NodeType::Literal { literal } => *literal,
+            _ => {
+                next_variable += 1;
+                next_variable
+            }
+        };
+
+        match &node.ntype {
+            NodeType::And { children } => {
+                // variable <=> c_1 & ... & c_n
+                let mut long_clause = vec![variable];
+                for &child in children {
+                    clauses.push(vec![-variable, variables[child]]);
+                    long_clause.push(-variables[child]);
+                }
+                clauses.push(long_clause);
+            }
+            NodeType::Or { children } => {
+                // variable <=> c_1 | ... | c_n
+                let mut long_clause = vec![-variable];
+                for &child in children {
+                    clauses.push(vec![variable, -variables[child]]);
+                    long_clause.push(variables[child]);
+                }
+                clauses.push(long_clause);
+            }
+            NodeType::True => clauses.push(vec![variable]),
+            NodeType::False => clauses.push(vec![-variable]),
+            NodeType::Literal { .. } => (),
+        }
+        variables.push(variable);
+    }
+    if let Some(&root) = variables.last() {
+        clauses.push(vec![root]);
+    }
+
+    let file = File::create(path_out)?;
+    let mut lw = LineWriter::with_capacity(1000, file);
+
+    lw.write_all(
+        format!(
+            "c variables 1 to {} are the features of the d-DNNF\n",
+            ddnnf.number_of_variables
+        )
+        .as_bytes(),
+    )?;
+    lw.write_all(format!("p cnf {} {}\n", next_variable, clauses.len()).as_bytes())?;
+    for clause in clauses {
+        lw.write_all(format!("{} 0\n", format_vec(clause.iter())).as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Takes a d-DNNF and writes it in the binary format into a file with the provided name.
+/// After the magic bytes, the file contains the number of variables (u32), the number of nodes (u64),
+/// and the nodes. Each node starts with its type (u8) followed by either the literal (i32)
+/// or the number of children (u32) and the children (u32 each). Optionally, the annotations follow:
+/// their number (u64) and for each annotation the node id (u64), the length (u32), and the UTF-8 bytes.
+/// All numbers are little endian.
+pub fn write_binary_ddnnf_to_file(ddnnf: &Ddnnf, path_out: &str) -> std::io::Result<()> {
+    let file = File::create(path_out)?;
+    let mut wtr = BufWriter::new(file);
+
+    wtr.write_all(BINARY_MAGIC)?;
+    wtr.write_all(&ddnnf.number_of_variables.to_le_bytes())?;
+    wtr.write_all(&(ddnnf.nodes.len() as u64).to_le_bytes())?;
+
+    for node in &ddnnf.nodes {
+        match &node.ntype {
+            NodeType::And { children } | NodeType::Or { children } => {
+                let tag: u8 = if matches!(node.ntype, NodeType::And { .. }) {
+                    0
+                } else {
+                    1
+                };
+                wtr.write_all(&[tag])?;
+                wtr.write_all(&(children.len() as u32).to_le_bytes())?;
+                for &child in children {
+                    wtr.write_all(&(child as u32).to_le_bytes())?;
+                }
+            }
+            NodeType::Literal { literal } => {
+                wtr.write_all(&[2])?;
+                wtr.write_all(&literal.to_le_bytes())?;
+            }
+            NodeType::True => wtr.write_all(&[3])?,
+            NodeType::False => wtr.write_all(&[4])?,
+        }
+    }
+
+    if !ddnnf.annotations.is_empty() {
+        // sorted by id to get the same file for the same d-DNNF
+        let mut annotations: Vec<_> = ddnnf.annotations.iter().collect();
+        annotations.sort_unstable();
+
+        wtr.write_all(&(annotations.len() as u64).to_le_bytes())?;
+        for (id, annotation) in annotations {
+            wtr.write_all(&id.0.to_le_bytes())?;
+            wtr.write_all(&(annotation.len() as u32).to_le_bytes())?;
+            wtr.write_all(annotation.as_bytes())?;
+        }
+    }
+
+    wtr.flush()
+}
+
 /// Takes a node of the ddnnf which is in the our representation of a flatted DAG
 /// and transforms it into the corresponding String.
 /// We use an adjusted version of the c2d format: Or nodes can have multiple children, there are no decision nodes
@@ -126,6 +369,7 @@ pub fn write_as_mermaid_md(
 /// Adds the nodes its children to the mermaid graph
 fn mermaidify_nodes(ddnnf: &Ddnnf, marking: &[usize]) -> String {
     let mut result = String::new();
+    let depths = compute_depths(ddnnf);
 
     for (position, node) in ddnnf.nodes.iter().enumerate().rev() {
         result = format!(
@@ -140,7 +384,7 @@ fn mermaidify_nodes(ddnnf: &Ddnnf, marking: &[usize]) -> String {
                     );
 
                     let mut children_series = children.clone();
-                    children_series.sort_by_key(|c1| compute_depth(ddnnf, *c1));
+                    children_series.sort_by_key(|&c1| depths[c1]);
 
                     if !children_series.is_empty() {
                         for (i, &child) in children_series.iter().enumerate() {
@@ -205,16 +449,22 @@ fn mermaidify_type(ddnnf: &Ddnnf, position: usize) -> String {
     mm_node
 }
 
-/// Computes the depth of any node in the current graph.
-/// Here, the depth is the length of the deepest path starting from position
-fn compute_depth(ddnnf: &Ddnnf, position: usize) -> usize {
-    match &ddnnf.nodes[position].ntype {
-        NodeType::And { children } | NodeType::Or { children } => children
-            .iter()
-            .fold(0, |acc, &x| max(acc + 1, compute_depth(ddnnf, x) + 1)),
-        NodeType::True => 0,
-        _ => 1,
+/// Computes the depth of each node in the current graph.
+/// Here, the depth is the length of the deepest path starting from the node.
+/// Due to the postorder, the depths of the children are known before the depth of their parent.
+fn compute_depths(ddnnf: &Ddnnf) -> Vec<usize> {
+    let mut depths: Vec<usize> = Vec::with_capacity(ddnnf.nodes.len());
+    for node in ddnnf.nodes.iter() {
+        let depth = match &node.ntype {
+            NodeType::And { children } | NodeType::Or { children } => children
+                .iter()
+                .fold(0, |acc, &x| max(acc + 1, depths[x] + 1)),
+            NodeType::True => 0,
+            _ => 1,
+        };
+        depths.push(depth);
     }
+    depths
 }
 
 fn marking_insert(marking: &[usize], position: usize) -> &str {
@@ -224,3 +474,136 @@ fn marking_insert(marking: &[usize], position: usize) -> &str {
         ""
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How [write_as_dot] and [write_as_json_graph] state the count of each node
+pub enum CountScale {
+    /// The exact number of models of the subgraph
+    #[default]
+    Exact,
+    /// The decadic logarithm of the number of models, which keeps huge counts readable
+    Log10,
+}
+
+/// The count of each node under the scale. Additionally, each node gets a mass between 0 and 1 that
+/// relates the logarithm of its count to the one of the largest count. Visualizers can color the
+/// nodes by the mass to show where the configurations concentrate.
+pub fn node_counts(ddnnf: &Ddnnf, scale: CountScale) -> Vec<(String, f64)> {
+    let logs: Vec<f64> = ddnnf.nodes.iter().map(|node| log10(&node.count)).collect();
+    let max_log = logs.iter().copied().fold(0.0, f64::max);
+    ddnnf
+        .nodes
+        .iter()
+        .zip(logs)
+        .map(|(node, log)| {
+            let count = match scale {
+                CountScale::Exact => node.count.to_string(),
+                CountScale::Log10 => log.to_string(),
+            };
+            let mass = if max_log > 0.0 {
+                log.max(0.0) / max_log
+            } else {
+                0.0
+            };
+            (count, mass)
+        })
+        .collect()
+}
+
+/// Writes the d-DNNF as graph in the DOT language of Graphviz. Each node states its count and is
+/// filled with a shade of red that gets darker with the mass of the node (see [node_counts]).
+pub fn write_as_dot(ddnnf: &Ddnnf, scale: CountScale, path_out: &str) -> std::io::Result<()> {
+    let file = File::create(path_out)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "digraph ddnnf {{")?;
+    writeln!(writer, "\tnode [style=filled];")?;
+    for (index, (node, (count, mass))) in ddnnf
+        .nodes
+        .iter()
+        .zip(node_counts(ddnnf, scale))
+        .enumerate()
+    {
+        let shade = (255.0 * (1.0 - mass)).round() as u8;
+        writeln!(
+            writer,
+            "\t{index} [label=\"{}\\n{count}\", count=\"{count}\", fillcolor=\"#ff{shade:02x}{shade:02x}\"];",
+            graph_label(&node.ntype)
+        )?;
+    }
+    for (index, node) in ddnnf.nodes.iter().enumerate() {
+        if let NodeType::And { children } | NodeType::Or { children } = &node.ntype {
+            for child in children {
+                writeln!(writer, "\t{index} -> {child};")?;
+            }
+        }
+    }
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+/// Writes the d-DNNF as JSON graph with a list of nodes and a list of edges ([parent, child]).
+/// Each node has an id (its index), a type, its literal (if any), its count, and its mass
+/// (see [node_counts]). Exact counts are strings because they easily exceed the range of JSON numbers.
+pub fn write_as_json_graph(
+    ddnnf: &Ddnnf,
+    scale: CountScale,
+    path_out: &str,
+) -> std::io::Result<()> {
+    let nodes: Vec<String> = ddnnf
+        .nodes
+        .iter()
+        .zip(node_counts(ddnnf, scale))
+        .enumerate()
+        .map(|(index, (node, (count, mass)))| {
+            let (node_type, literal) = match node.ntype {
+                NodeType::And { .. } => ("and", String::new()),
+                NodeType::Or { .. } => ("or", String::new()),
+                NodeType::Literal { literal } => ("literal", format!(",\"literal\":{literal}")),
+                NodeType::True => ("true", String::new()),
+                NodeType::False => ("false", String::new()),
+            };
+            let count = match scale {
+                CountScale::Exact => format!("\"{count}\""),
+                // the logarithm of 0 has no JSON representation
+                CountScale::Log10 if node.count == 0 => String::from("null"),
+                CountScale::Log10 => count,
+            };
+            format!("{{\"id\":{index},\"type\":\"{node_type}\"{literal},\"count\":{count},\"mass\":{mass}}}")
+        })
+        .collect();
+    let edges: Vec<String> = ddnnf
+        .nodes
+        .iter()
+        .enumerate()
+        .flat_map(|(index, node)| {
+            match &node.ntype {
+                NodeType::And { children } | NodeType::Or { children } => children.clone(),
+                _ => Vec::new(),
+            }
+            .into_iter()
+            .map(move |child| format!("[{index},{child}]"))
+        })
+        .collect();
+
+    let mut writer = BufWriter::new(File::create(path_out)?);
+    write!(
+        writer,
+        "{{\"nodes\":[{}],\"edges\":[{}]}}",
+        nodes.join(","),
+        edges.join(",")
+    )?;
+    writer.flush()
+}
+
+// The label of a node in graph exports without its count
+fn graph_label(ntype: &NodeType) -> String {
+    match ntype {
+        NodeType::And { .. } => String::from("∧"),
+        NodeType::Or { .. } => String::from("∨"),
+        NodeType::Literal { literal } if literal.is_negative() => format!("¬L{}", literal.abs()),
+        NodeType::Literal { literal } => format!("L{literal}"),
+        NodeType::True => String::from("T"),
+        NodeType::False => String::from("F"),
+    }
+}
@@ -1,3 +1,5 @@
+use rug::Integer;
+
 pub fn format_vec<T: ToString>(vals: impl Iterator<Item = T>) -> String {
     vals.map(|v| v.to_string())
         .collect::<Vec<String>>()
@@ -13,3 +15,114 @@ where
         .collect::<Vec<String>>()
         .join(";")
 }
+
+/// Formats a count in scientific notation with the given number of digits after the decimal point.
+/// The mantissa gets rounded half up. Counts with at most 'precision' + 1 digits are formatted as they are.
+///
+/// # Example
+/// ```
+/// extern crate ddnnf_lib;
+/// use ddnnf_lib::parser::util::format_scientific;
+/// use rug::Integer;
+///
+/// assert_eq!("1.23e5", format_scientific(&Integer::from(123_456), 2));
+/// assert_eq!("1.0e3", format_scientific(&Integer::from(999), 1));
+/// assert_eq!("42", format_scientific(&Integer::from(42), 2));
+/// ```
+pub fn format_scientific(count: &Integer, precision: usize) -> String {
+    let sign = if *count < 0 { "-" } else { "" };
+    let digits = count.as_abs().to_string();
+    if digits.len() <= precision + 1 {
+        return count.to_string();
+    }
+
+    let mut exponent = digits.len() - 1;
+    let mut mantissa: Integer = digits[..=precision]
+        .parse()
+        .expect("digits are a valid number");
+    if digits.as_bytes()[precision + 1] >= b'5' {
+        mantissa += 1;
+    }
+    let mut mantissa = mantissa.to_string();
+    if mantissa.len() > precision + 1 {
+        // rounding carried over to a new digit, e.g. 9.99 became 10.00
+        mantissa.pop();
+        exponent += 1;
+    }
+
+    if precision == 0 {
+        format!("{sign}{mantissa}e{exponent}")
+    } else {
+        format!("{sign}{}.{}e{exponent}", &mantissa[..1], &mantissa[1..])
+    }
+}
+
+/// Computes the decadic logarithm of a count, which is negative infinity for zero.
+/// That also works for counts that exceed the range of f64.
+pub fn log10(count: &Integer) -> f64 {
+    if *count == 0 {
+        return f64::NEG_INFINITY;
+    }
+    // count = mantissa * 2^exponent with 0.5 <= |mantissa| < 1
+    let (mantissa, exponent) = count.as_abs().to_f64_exp();
+    mantissa.log10() + exponent as f64 * std::f64::consts::LOG10_2
+}
+
+/// Quotes the text as JSON string. Quotes, backslashes, and control characters get escaped.
+pub fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scientific_notation() {
+        let huge = Integer::from(Integer::u_pow_u(10, 3000)) * 7 + 5;
+        assert_eq!("7.00e3000", format_scientific(&huge, 2));
+        assert_eq!("7e3000", format_scientific(&huge, 0));
+        assert_eq!("-1.3e4", format_scientific(&Integer::from(-12_500), 1));
+        assert_eq!("9", format_scientific(&Integer::from(9), 0));
+        assert_eq!("1e2", format_scientific(&Integer::from(95), 0));
+        assert_eq!("99", format_scientific(&Integer::from(99), 1));
+        assert_eq!("0", format_scientific(&Integer::ZERO, 3));
+        assert_eq!("216000", format_scientific(&Integer::from(216_000), 5));
+        assert_eq!("2.1600e5", format_scientific(&Integer::from(216_000), 4));
+    }
+
+    #[test]
+    fn logarithm_of_counts() {
+        assert_eq!(f64::NEG_INFINITY, log10(&Integer::ZERO));
+        assert!((log10(&Integer::from(1000)) - 3.0).abs() < 1e-12);
+        assert!((log10(&Integer::from(216_000)) - 216_000f64.log10()).abs() < 1e-12);
+
+        let huge = Integer::from(Integer::u_pow_u(10, 3000)) * 7;
+        assert!((log10(&huge) - (3000.0 + 7f64.log10())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn json_strings() {
+        assert_eq!("\"v1.0\"", json_string("v1.0"));
+        assert_eq!(r#""say \"hi\" \\o/""#, json_string(r#"say "hi" \o/"#));
+        assert_eq!(
+            r#""a\nb\tc\u0000\u001f\r""#,
+            json_string("a\nb\tc\u{0}\u{1f}\r")
+        );
+        assert_eq!("\"ünïcödé\"", json_string("ünïcödé"));
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+/// The coarse phases of ddnnife that get reported to the installed [Profiler].
+/// Phases nest, e.g. building the graph is part of parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    /// Loading a d-DNNF (or CNF) from a file
+    Parse,
+    /// Compiling a CNF into a d-DNNF
+    Compile,
+    /// Creating the nodes (and for d4 files the intermediate graph) out of the lines
+    BuildGraph,
+    /// Smoothing a d4 d-DNNF by computing the differences of the literals below Or nodes
+    LiteralDiffs,
+    /// Computing the counts of the nodes of a parsed d-DNNF
+    Count,
+    /// Answering a counting query
+    Query,
+    /// Computing a uniform random or t-wise sample
+    Sample,
+}
+
+impl Phase {
+    /// The name of the phase as used in the folded stacks of [FoldedStacks]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Compile => "compile",
+            Phase::BuildGraph => "build-graph",
+            Phase::LiteralDiffs => "literal-diffs",
+            Phase::Count => "count",
+            Phase::Query => "query",
+            Phase::Sample => "sample",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Receives the start and the end of each phase. Implementations can forward the phases to a
+/// profiler of their choice. The phases are reported by the thread that performs them.
+/// Both methods do nothing by default.
+pub trait Profiler: Send + Sync {
+    fn enter(&self, _phase: Phase) {}
+    fn exit(&self, _phase: Phase) {}
+}
+
+/// The default profiler that ignores all phases
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProfiler;
+
+impl Profiler for NoProfiler {}
+
+// Without an installed profiler, the phases only cost the check of this flag
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PROFILER: Lazy<RwLock<Arc<dyn Profiler>>> = Lazy::new(|| RwLock::new(Arc::new(NoProfiler)));
+
+/// Installs the profiler for all threads and returns the previously installed one
+pub fn set_profiler(profiler: Arc<dyn Profiler>) -> Arc<dyn Profiler> {
+    let previous = std::mem::replace(&mut *PROFILER.write().unwrap(), profiler);
+    ENABLED.store(true, Ordering::Release);
+    previous
+}
+
+/// Installs the [NoProfiler] again
+pub fn reset_profiler() {
+    ENABLED.store(false, Ordering::Release);
+    *PROFILER.write().unwrap() = Arc::new(NoProfiler);
+}
+
+/// Reports the phase as entered and reports its exit when the guard is dropped
+pub(crate) fn phase(phase: Phase) -> PhaseGuard {
+    let profiler = ENABLED
+        .load(Ordering::Acquire)
+        .then(|| PROFILER.read().unwrap().clone());
+    if let Some(profiler) = &profiler {
+        profiler.enter(phase);
+    }
+    PhaseGuard { phase, profiler }
+}
+
+/// Marks the end of a phase when it goes out of scope
+#[must_use]
+pub(crate) struct PhaseGuard {
+    phase: Phase,
+    profiler: Option<Arc<dyn Profiler>>,
+}
+
+impl Drop for PhaseGuard {
+    fn drop(&mut self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.exit(self.phase);
+        }
+    }
+}
+
+/// A profiler that measures the time spent in each stack of nested phases.
+/// The result is in the folded stack format that flamegraph tools like inferno take as input.
+///
+/// # Example
+/// ```
+/// extern crate ddnnf_lib;
+/// use std::sync::Arc;
+/// use ddnnf_lib::parser::*;
+/// use ddnnf_lib::profiling::{reset_profiler, set_profiler, FoldedStacks};
+///
+/// let stacks = Arc::new(FoldedStacks::default());
+/// set_profiler(stacks.clone());
+/// let mut ddnnf = build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+/// ddnnf.execute_query(&[1, 2]);
+/// reset_profiler();
+///
+/// // e.g. 'parse;build-graph 1532' with the time in microseconds
+/// assert!(stacks.folded().lines().any(|line| line.starts_with("parse;build-graph ")));
+/// assert!(stacks.folded().lines().any(|line| line.starts_with("query ")));
+/// ```
+#[derive(Debug, Default)]
+pub struct FoldedStacks {
+    open: Mutex<HashMap<ThreadId, Vec<OpenPhase>>>,
+    // the time spent in each stack excluding the time of nested phases
+    self_times: Mutex<BTreeMap<Vec<Phase>, Duration>>,
+}
+
+#[derive(Debug)]
+struct OpenPhase {
+    phase: Phase,
+    start: Instant,
+    nested: Duration,
+}
+
+impl FoldedStacks {
+    /// The measured stacks, one per line, in the form 'PHASE;NESTED_PHASE MICROSECONDS'
+    pub fn folded(&self) -> String {
+        self.self_times
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(stack, time)| {
+                let names: Vec<&str> = stack.iter().map(Phase::name).collect();
+                format!("{} {}\n", names.join(";"), time.as_micros())
+            })
+            .collect()
+    }
+}
+
+impl Profiler for FoldedStacks {
+    fn enter(&self, phase: Phase) {
+        self.open
+            .lock()
+            .unwrap()
+            .entry(thread::current().id())
+            .or_default()
+            .push(OpenPhase {
+                phase,
+                start: Instant::now(),
+                nested: Duration::ZERO,
+            });
+    }
+
+    fn exit(&self, phase: Phase) {
+        let mut open = self.open.lock().unwrap();
+        let stack = match open.get_mut(&thread::current().id()) {
+            Some(stack) if stack.last().map(|open| open.phase) == Some(phase) => stack,
+            // the phase was entered before the profiler got installed
+            _ => return,
+        };
+        let finished = stack.pop().unwrap();
+        let elapsed = finished.start.elapsed();
+        if let Some(parent) = stack.last_mut() {
+            parent.nested += elapsed;
+        }
+
+        let mut path: Vec<Phase> = stack.iter().map(|open| open.phase).collect();
+        path.push(phase);
+        *self.self_times.lock().unwrap().entry(path).or_default() +=
+            elapsed.saturating_sub(finished.nested);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::parser::build_ddnnf;
+
+    // Records the phases of the current thread
+    #[derive(Default)]
+    struct Recorder {
+        thread: Option<ThreadId>,
+        events: Mutex<Vec<(bool, Phase)>>,
+    }
+
+    impl Profiler for Recorder {
+        fn enter(&self, phase: Phase) {
+            if self.thread == Some(thread::current().id()) {
+                self.events.lock().unwrap().push((true, phase));
+            }
+        }
+
+        fn exit(&self, phase: Phase) {
+            if self.thread == Some(thread::current().id()) {
+                self.events.lock().unwrap().push((false, phase));
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn phases_get_reported() {
+        let recorder = Arc::new(Recorder {
+            thread: Some(thread::current().id()),
+            ..Default::default()
+        });
+        set_profiler(recorder.clone());
+        let mut ddnnf = build_ddnnf("tests/data/VP9_d4.nnf", Some(42));
+        ddnnf.execute_query(&[1]);
+        reset_profiler();
+        ddnnf.execute_query(&[2]);
+
+        use Phase::*;
+        assert_eq!(
+            vec![
+                (true, Parse),
+                (true, BuildGraph),
+                (false, BuildGraph),
+                (true, LiteralDiffs),
+                (false, LiteralDiffs),
+                (true, Count),
+                (false, Count),
+                (false, Parse),
+                (true, Query),
+                (false, Query)
+            ],
+            *recorder.events.lock().unwrap()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn folded_stacks() {
+        let stacks = FoldedStacks::default();
+        stacks.exit(Phase::Query);
+        stacks.enter(Phase::Parse);
+        stacks.enter(Phase::Count);
+        thread::sleep(Duration::from_millis(5));
+        stacks.exit(Phase::Count);
+        stacks.exit(Phase::Parse);
+
+        let folded = stacks.folded();
+        let lines: Vec<&str> = folded.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("parse "));
+        let count_time: u128 = lines[1]
+            .strip_prefix("parse;count ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(count_time >= 5_000);
+    }
+}
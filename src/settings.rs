@@ -0,0 +1,248 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::ddnnf::counting::bounded::CountMode;
+
+/// The name of the configuration file that is picked up from the working directory
+/// if no other configuration file is supplied.
+pub const DEFAULT_SETTINGS_FILE: &str = "ddnnife.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Holds the defaults of ddnnife that can be configured via a TOML file.
+/// Each value that is `None` falls back to the default of the corresponding operation.
+/// Values supplied via command line flags always take precedence over the settings.
+///
+/// # Example
+/// ```
+/// use ddnnf_lib::settings::Settings;
+/// use ddnnf_lib::ddnnf::counting::bounded::CountMode;
+///
+/// let settings = Settings::from_toml_str("
+///     threads = 8
+///     compiler = \"/opt/d4/d4\"
+///     numeric = \"log10\"
+///
+///     [limits]
+///     samples = 500
+/// ").unwrap();
+///
+/// assert_eq!(Some(8), settings.threads);
+/// assert_eq!(Some(500), settings.limits.samples);
+/// assert_eq!(Some(CountMode::Log10), settings.numeric);
+/// assert_eq!(None, settings.cache_dir);
+/// ```
+pub struct Settings {
+    /// The number of threads used for operations that support multiple threads
+    pub threads: Option<u16>,
+    /// Path to an external d4 binary. If set, CNFs are compiled with that binary
+    /// instead of the bundled d4 compiler.
+    pub compiler: Option<PathBuf>,
+    /// The directory in which intermediate files (e.g. the d-DNNF compiled from a CNF) are stored
+    pub cache_dir: Option<PathBuf>,
+    /// The numeric backend of counts: exact big integers, bounded by the digit limit, or log10 floats
+    pub numeric: Option<CountMode>,
+    /// Limits for the amount of produced results
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Limits for operations that produce multiple results
+pub struct Limits {
+    /// The amount of configurations that get generated by sampling and enumeration
+    pub samples: Option<usize>,
+    /// The maximum number of decimal digits of exact counts. Larger counts are reported in log10 form.
+    pub max_digits: Option<usize>,
+}
+
+impl Settings {
+    /// Loads the settings from a TOML file
+    pub fn from_file(path: &Path) -> io::Result<Settings> {
+        Settings::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Loads the settings from the supplied path or, if there is none, from the
+    /// default settings file in the working directory. If neither exists, the default settings are used.
+    pub fn load(path: Option<&Path>) -> io::Result<Settings> {
+        match path {
+            Some(path) => Settings::from_file(path),
+            None => {
+                let default_path = Path::new(DEFAULT_SETTINGS_FILE);
+                if default_path.exists() {
+                    Settings::from_file(default_path)
+                } else {
+                    Ok(Settings::default())
+                }
+            }
+        }
+    }
+
+    /// Parses the settings from a string in TOML format.
+    /// We support the subset of TOML that is needed for the settings:
+    /// comments, tables, strings, and integers.
+    pub fn from_toml_str(input: &str) -> io::Result<Settings> {
+        let mut settings = Settings::default();
+        let mut table = String::new();
+
+        for (line_number, line) in input.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = |msg: &str| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {}: {msg}", line_number + 1),
+                )
+            };
+
+            if let Some(name) = line.strip_prefix('[') {
+                table = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| invalid("unclosed table header"))?
+                    .trim()
+                    .to_string();
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected a key value pair"))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match (table.as_str(), key) {
+                ("", "threads") => {
+                    settings.threads = Some(parse_integer(value).map_err(|e| invalid(&e))?)
+                }
+                ("", "compiler") => {
+                    settings.compiler = Some(parse_string(value).map_err(|e| invalid(&e))?.into())
+                }
+                ("", "cache_dir") => {
+                    settings.cache_dir = Some(parse_string(value).map_err(|e| invalid(&e))?.into())
+                }
+                ("", "numeric") => {
+                    settings.numeric = Some(
+                        parse_string(value)
+                            .and_then(|mode| mode.parse())
+                            .map_err(|e| invalid(&e))?,
+                    )
+                }
+                ("limits", "samples") => {
+                    settings.limits.samples = Some(parse_integer(value).map_err(|e| invalid(&e))?)
+                }
+                ("limits", "max_digits") => {
+                    settings.limits.max_digits =
+                        Some(parse_integer(value).map_err(|e| invalid(&e))?)
+                }
+                (table, key) => {
+                    let key = if table.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{table}.{key}")
+                    };
+                    return Err(invalid(&format!("unknown setting \"{key}\"")));
+                }
+            }
+        }
+
+        if settings.threads == Some(0) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "threads must be at least 1",
+            ));
+        }
+
+        Ok(settings)
+    }
+
+    /// Returns the path of an intermediate file with the given name
+    /// that is placed in the cache directory (if there is any).
+    pub fn intermediate_path(&self, file_name: &str) -> PathBuf {
+        match &self.cache_dir {
+            Some(dir) => dir.join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+}
+
+// removes a trailing comment while ignoring '#' that are part of a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_string(value: &str) -> Result<String, String> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(String::from)
+        .ok_or_else(|| format!("expected a string but got {value}"))
+}
+
+fn parse_integer<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .replace('_', "")
+        .parse::<T>()
+        .map_err(|_| format!("expected a positive integer but got {value}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_settings() {
+        let settings = Settings::from_toml_str(
+            "# ddnnife settings
+            threads = 16 # all cores
+            compiler = \"/usr/bin/d4#2\"
+            cache_dir = \"/tmp/ddnnife\"
+            numeric = \"bounded\"
+
+            [limits]
+            samples = 10_000
+            max_digits = 500",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Settings {
+                threads: Some(16),
+                compiler: Some(PathBuf::from("/usr/bin/d4#2")),
+                cache_dir: Some(PathBuf::from("/tmp/ddnnife")),
+                numeric: Some(CountMode::Bounded),
+                limits: Limits {
+                    samples: Some(10_000),
+                    max_digits: Some(500),
+                },
+            },
+            settings
+        );
+        assert_eq!(
+            PathBuf::from("/tmp/ddnnife/.intermediate.nnf"),
+            settings.intermediate_path(".intermediate.nnf")
+        );
+        assert_eq!(Settings::default(), Settings::from_toml_str("").unwrap());
+    }
+
+    #[test]
+    fn invalid_settings() {
+        assert!(Settings::from_toml_str("threads = four").is_err());
+        assert!(Settings::from_toml_str("threads = 0").is_err());
+        assert!(Settings::from_toml_str("compiler = /usr/bin/d4").is_err());
+        assert!(Settings::from_toml_str("samples = 10").is_err());
+        assert!(Settings::from_toml_str("[limits\nsamples = 10").is_err());
+        assert!(Settings::from_toml_str("threads").is_err());
+        assert!(Settings::from_toml_str("numeric = \"float\"").is_err());
+    }
+}
@@ -37,3 +37,160 @@ fn ddnnf_parsing_test() {
         _ => panic!("Node isn't an or node"),
     }
 }
+
+#[cfg(all(unix, feature = "editing"))]
+mod external_compiler {
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use ddnnf_lib::ddnnf::Ddnnf;
+    use ddnnf_lib::parser::{
+        build_ddnnf_with_settings, composition::build_ddnnf_with_deltas, CompileStats,
+    };
+    use ddnnf_lib::settings::Settings;
+
+    // Creates a fake compiler that dumps the small example and reports the supplied count.
+    // It keeps a copy of the last CNF it got in compiled.cnf.
+    fn fake_compiler(dir: &tempfile::TempDir, reported_count: &str) -> Settings {
+        let ddnnf = fs::canonicalize("tests/data/small_ex_d4.nnf").unwrap();
+        let compiler = dir.path().join("d4");
+        fs::write(
+            &compiler,
+            format!(
+                "#!/bin/sh\ncp \"$2\" {}\ncp {} \"$6\"\necho \"c some output\"\necho \"s {}\"\n",
+                dir.path().join("compiled.cnf").display(),
+                ddnnf.display(),
+                reported_count
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&compiler, fs::Permissions::from_mode(0o755)).unwrap();
+
+        Settings {
+            compiler: Some(compiler),
+            cache_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_matching_model_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = fake_compiler(&dir, "4");
+        let ddnnf: Ddnnf = build_ddnnf_with_settings("tests/data/small_ex.cnf", None, &settings);
+        assert_eq!(4, ddnnf.rc());
+
+        let stats = ddnnf.compile_stats();
+        assert_eq!(1, stats.len());
+        assert_eq!(
+            fs::metadata("tests/data/small_ex_d4.nnf").unwrap().len(),
+            stats[0].output_size
+        );
+        assert_eq!(Some(stats[0]), CompileStats::aggregate(stats));
+        assert!(CompileStats::aggregate(&[]).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "The compiler reported 5 models")]
+    fn rejects_deviating_model_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = fake_compiler(&dir, "5");
+        build_ddnnf_with_settings("tests/data/small_ex.cnf", None, &settings);
+    }
+
+    #[test]
+    #[should_panic(expected = "has no header")]
+    fn rejects_cnf_without_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = fake_compiler(&dir, "4");
+        let headless = dir.path().join("headless.cnf");
+        fs::write(
+            &headless,
+            "1 0
+-2 3 0
+",
+        )
+        .unwrap();
+        build_ddnnf_with_settings(headless.to_str().unwrap(), None, &settings);
+    }
+
+    #[test]
+    fn deltas_get_recompiled_with_the_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = fake_compiler(&dir, "4");
+        let tautologies = dir.path().join("tautologies.cnf");
+        fs::write(&tautologies, "c only tautologies\n1 -1 0\n\n2 3 -2 0\n").unwrap();
+        let tautologies = tautologies.to_str().unwrap();
+
+        let (ddnnf, stats) =
+            build_ddnnf_with_deltas("tests/data/small_ex.cnf", &[tautologies], &settings).unwrap();
+        assert_eq!(4, ddnnf.rc());
+        assert_eq!(1, stats.len());
+        assert_eq!(0, stats[0].added_clauses);
+        assert_eq!(2, stats[0].skipped_clauses);
+        assert_eq!(stats[0].count_before, stats[0].count_after);
+
+        // an effective delta gets recompiled with the configured compiler
+        let delta = dir.path().join("delta.cnf");
+        fs::write(&delta, "-1 -2 0\n").unwrap();
+        let (ddnnf, stats) = build_ddnnf_with_deltas(
+            "tests/data/small_ex.cnf",
+            &[delta.to_str().unwrap()],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(1, stats[0].added_clauses);
+        assert_eq!(2, ddnnf.compile_stats().len());
+        let compiled = fs::read_to_string(dir.path().join("compiled.cnf")).unwrap();
+        assert!(compiled.lines().any(|line| line == "-2 -1 0"));
+
+        // so does the CNF without a clause for its sensitivity
+        let clause = BTreeSet::from([-2, -1]);
+        assert!(ddnnf.sensitivity(&[clause]).unwrap()[0].removed);
+        let compiled = fs::read_to_string(dir.path().join("compiled.cnf")).unwrap();
+        assert!(compiled.lines().all(|line| line != "-2 -1 0"));
+
+        let invalid = dir.path().join("invalid.cnf");
+        fs::write(&invalid, "p cnf 4 1\n1 5 0\n").unwrap();
+        let result = build_ddnnf_with_deltas(
+            "tests/data/small_ex.cnf",
+            &[tautologies, invalid.to_str().unwrap()],
+            &settings,
+        );
+        assert!(result.unwrap_err().to_string().contains("invalid.cnf"));
+
+        assert!(
+            build_ddnnf_with_deltas("tests/data/small_ex_c2d.nnf", &[tautologies], &settings)
+                .is_err()
+        );
+    }
+}
+
+#[cfg(feature = "d4")]
+#[test]
+fn compose_cnf_with_deltas() {
+    use ddnnf_lib::parser::composition::build_ddnnf_with_deltas;
+    use ddnnf_lib::settings::Settings;
+
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first.cnf");
+    std::fs::write(&first, "c select feature 3\n3 0\n").unwrap();
+    let second = dir.path().join("second.cnf");
+    std::fs::write(&second, "p cnf 5 1\n-4 5 0\n").unwrap();
+
+    let base: Ddnnf = parser::build_ddnnf("tests/data/small_ex.cnf", None);
+    let (mut ddnnf, stats) = build_ddnnf_with_deltas(
+        "tests/data/small_ex.cnf",
+        &[first.to_str().unwrap(), second.to_str().unwrap()],
+        &Settings::default(),
+    )
+    .unwrap();
+
+    assert_eq!(2, stats.len());
+    assert_eq!(base.rc(), stats[0].count_before);
+    assert_eq!(5, ddnnf.number_of_variables);
+    assert_eq!(stats[0].count_after, stats[1].count_before);
+    assert_eq!(ddnnf.rc(), stats[1].count_after);
+    assert_eq!(0, ddnnf.execute_query(&[-3]));
+}
@@ -1,7 +1,13 @@
 extern crate ddnnf_lib;
 
 use ddnnf_lib::ddnnf::Ddnnf;
-use ddnnf_lib::parser::{self, persisting::write_ddnnf_to_file};
+use ddnnf_lib::parser::{
+    self,
+    persisting::{
+        node_counts, write_as_dot, write_as_json_graph, write_ddnnf_as, write_ddnnf_to_file,
+        CountScale, Format,
+    },
+};
 
 use file_diff::diff_files;
 use std::fs;
@@ -33,3 +39,119 @@ fn card_of_features_normal_and_reloaded_test() {
     fs::remove_file(saved_nnf).unwrap();
     fs::remove_file(saved_out).unwrap();
 }
+
+#[test]
+fn convert_to_d4_and_binary() {
+    let mut ddnnf: Ddnnf = parser::build_ddnnf("./tests/data/VP9_d4.nnf", Some(42));
+
+    let d4_out = "./tests/data/VP9_converted_d4.nnf";
+    write_ddnnf_as(&ddnnf, Format::D4, d4_out).unwrap();
+    let mut d4_ddnnf: Ddnnf = parser::build_ddnnf(d4_out, Some(42));
+
+    let binary_out = "./tests/data/VP9_converted.bin";
+    write_ddnnf_as(&ddnnf, Format::Binary, binary_out).unwrap();
+    let mut binary_ddnnf: Ddnnf = parser::build_ddnnf(binary_out, None);
+
+    assert_eq!(ddnnf.number_of_variables, binary_ddnnf.number_of_variables);
+    assert_eq!(ddnnf.nodes.len(), binary_ddnnf.nodes.len());
+    assert_eq!(ddnnf.rc(), d4_ddnnf.rc());
+    assert_eq!(ddnnf.rc(), binary_ddnnf.rc());
+    for feature in 1..=42 {
+        for literal in [feature, -feature] {
+            let expected = ddnnf.execute_query(&[literal]);
+            assert_eq!(expected, d4_ddnnf.execute_query(&[literal]));
+            assert_eq!(expected, binary_ddnnf.execute_query(&[literal]));
+        }
+    }
+
+    fs::remove_file(d4_out).unwrap();
+    fs::remove_file(binary_out).unwrap();
+}
+
+#[test]
+fn corrupted_binary_files() {
+    let header = |nodes: u64| {
+        let mut bytes = b"DDNNF\x01".to_vec();
+        bytes.extend(4_u32.to_le_bytes());
+        bytes.extend(nodes.to_le_bytes());
+        bytes
+    };
+
+    // huge counts of nodes and children must not be preallocated
+    assert!(parser::build_binary_ddnnf(&header(u64::MAX)).is_err());
+    let mut bytes = header(1);
+    bytes.push(0);
+    bytes.extend(u32::MAX.to_le_bytes());
+    assert!(parser::build_binary_ddnnf(&bytes).is_err());
+
+    let mut bytes = header(1);
+    bytes.push(3);
+    assert_eq!(1, parser::build_binary_ddnnf(&bytes).unwrap().rc());
+}
+
+#[test]
+fn convert_to_tseitin_cnf() {
+    let ddnnf: Ddnnf = parser::build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    let cnf_out = "./tests/data/small_ex_tseitin.cnf";
+    write_ddnnf_as(&ddnnf, Format::Cnf, cnf_out).unwrap();
+
+    let content = fs::read_to_string(cnf_out).unwrap();
+    let mut variables = 0;
+    let mut clauses: Vec<Vec<i32>> = Vec::new();
+    for line in content.lines() {
+        if let Some(header) = line.strip_prefix("p cnf ") {
+            variables = header.split_whitespace().next().unwrap().parse().unwrap();
+        } else if !line.starts_with('c') {
+            let clause = line.split_whitespace().map(|l| l.parse().unwrap());
+            clauses.push(clause.take_while(|&l| l != 0).collect());
+        }
+    }
+    assert!(variables > ddnnf.number_of_variables as usize);
+
+    // the auxiliary variables are determined by the original ones, hence the counts are equal
+    let models = (0..1_u32 << variables)
+        .filter(|assignment| {
+            clauses.iter().all(|clause| {
+                clause.iter().any(|&l| {
+                    let value = assignment >> (l.unsigned_abs() - 1) & 1 == 1;
+                    value == l.is_positive()
+                })
+            })
+        })
+        .count();
+    assert_eq!(4, models);
+
+    fs::remove_file(cnf_out).unwrap();
+}
+
+#[test]
+fn export_graphs_with_counts() {
+    let ddnnf: Ddnnf = parser::build_ddnnf("./tests/data/small_ex_c2d.nnf", None);
+    let counts = node_counts(&ddnnf, CountScale::Exact);
+    assert_eq!(ddnnf.nodes.len(), counts.len());
+    assert_eq!(("4".to_string(), 1.0), counts[counts.len() - 1]);
+    assert_eq!(("1".to_string(), 0.0), counts[0]);
+    let (log, mass) = &node_counts(&ddnnf, CountScale::Log10)[9];
+    assert!((log.parse::<f64>().unwrap() - 2_f64.log10()).abs() < 1e-9);
+    assert!((mass - 0.5).abs() < 1e-9);
+
+    let dot_out = "./tests/data/small_ex_graph.dot";
+    write_as_dot(&ddnnf, CountScale::Exact, dot_out).unwrap();
+    let dot = fs::read_to_string(dot_out).unwrap();
+    assert!(dot.starts_with("digraph ddnnf {"));
+    assert!(dot.contains("\t11 [label=\"∧\\n4\", count=\"4\", fillcolor=\"#ff0000\"];"));
+    assert!(dot.contains("\t0 [label=\"L1\\n1\", count=\"1\", fillcolor=\"#ffffff\"];"));
+    assert_eq!(11, dot.matches(" -> ").count());
+
+    let json_out = "./tests/data/small_ex_graph.json";
+    write_as_json_graph(&ddnnf, CountScale::Exact, json_out).unwrap();
+    let json = fs::read_to_string(json_out).unwrap();
+    assert!(
+        json.contains("{\"id\":2,\"type\":\"literal\",\"literal\":-3,\"count\":\"1\",\"mass\":0}")
+    );
+    assert!(json.contains("{\"id\":11,\"type\":\"and\",\"count\":\"4\",\"mass\":1}"));
+    assert!(json.ends_with("[11,0],[11,9],[11,10]]}"));
+
+    fs::remove_file(dot_out).unwrap();
+    fs::remove_file(json_out).unwrap();
+}